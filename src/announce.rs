@@ -0,0 +1,43 @@
+use std::net::IpAddr;
+
+use anyhow::Result;
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+/// The mDNS service type under which the SSE endpoint is advertised
+const SERVICE_TYPE: &str = "_mcp-sse._tcp.local.";
+
+/// Advertise the SSE endpoint on the local network. The announcement ends when the
+/// returned daemon is dropped
+pub fn announce(host: &str, port: u16) -> Result<ServiceDaemon> {
+    let daemon = ServiceDaemon::new()?;
+    let instance_name = format!("cursor-rust-tools-{}", std::process::id());
+    let host_name = format!("{instance_name}.local.");
+    let properties = [
+        ("path", "/sse"),
+        ("server", "cursor_rust_tools"),
+        ("version", env!("CARGO_PKG_VERSION")),
+    ];
+    let service = match host.parse::<IpAddr>() {
+        // Bound to a single interface, only announce that one
+        Ok(ip) if !ip.is_unspecified() => ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance_name,
+            &host_name,
+            ip,
+            port,
+            &properties[..],
+        )?,
+        _ => ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance_name,
+            &host_name,
+            (),
+            port,
+            &properties[..],
+        )?
+        .enable_addr_auto(),
+    };
+    daemon.register(service)?;
+    tracing::info!("Announcing {SERVICE_TYPE} {instance_name} on port {port}");
+    Ok(daemon)
+}
@@ -1,6 +1,15 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use anyhow::Result;
+use flume::Sender;
 use serde::{Deserialize, Serialize};
 use serde_json as json;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 use tokio::process::Command;
 
 use crate::project::Project;
@@ -10,8 +19,14 @@ use crate::project::Project;
 pub enum CargoMessage {
     CompilerArtifact(json::Value),
     BuildScriptExecuted(json::Value),
-    CompilerMessage { message: CompilerMessage },
-    BuildFinished { success: bool },
+    CompilerMessage {
+        message: CompilerMessage,
+        #[serde(default)]
+        package_id: String,
+    },
+    BuildFinished {
+        success: bool,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -31,75 +46,1476 @@ pub struct CompilerMessageSpan {
     pub file_name: String,
     pub line_start: usize,
     pub line_end: usize,
+    pub label: Option<String>,
+}
+
+impl CompilerMessage {
+    /// The error code of the message, e.g. `E0502`
+    pub fn error_code(&self) -> Option<&str> {
+        self.code.as_ref()?.get("code")?.as_str()
+    }
+}
+
+/// The part of the workspace a cargo command builds, and with which features and target
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildSelection {
+    /// A workspace member, the whole workspace if `None`
+    pub package: Option<String>,
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    pub target: Option<String>,
+}
+
+impl BuildSelection {
+    /// The arguments for `cargo check`, `cargo test` and `cargo nextest run`
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(package) = &self.package {
+            args.push("--package".to_string());
+            args.push(package.clone());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if let Some(target) = &self.target {
+            args.push("--target".to_string());
+            args.push(target.clone());
+        }
+        args
+    }
+}
+
+/// The outcome of running a binary of the project
+#[derive(Clone, Debug)]
+pub struct RunOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` if the binary was killed or terminated by a signal
+    pub exit_code: Option<i32>,
+    /// The binary was killed because it didn't exit within the timeout
+    pub timed_out: bool,
+}
+
+/// How long the output of a binary is still read once it exited, its own children
+/// may keep the pipes open
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// How much of stdout and stderr each is kept of a run, the middle of longer output
+/// is dropped while it is read
+const MAX_CAPTURED_OUTPUT: usize = 1024 * 1024;
+
+/// The outcome of checking the project for a specific target
+#[derive(Clone, Debug)]
+pub struct TargetCheck {
+    pub success: bool,
+    /// Compiler errors together with the package that emitted them
+    pub errors: Vec<(PackageId, CompilerMessage)>,
+    /// Cargo's own output, e.g. if the target is not installed
+    pub stderr: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PackageId {
+    pub name: String,
+    /// Whether this is a path dependency (usually a workspace member)
+    pub is_local: bool,
+}
+
+impl PackageId {
+    /// Parse both the old (`name 0.1.0 (registry+...)`) and the new
+    /// (`registry+...#name@0.1.0`) package id format
+    pub fn parse(package_id: &str) -> Self {
+        let is_local = package_id.contains("path+file://");
+        let name = match package_id.split_once('#') {
+            Some((_, fragment)) => match fragment.split_once('@') {
+                Some((name, _)) => name.to_string(),
+                // `path+file:///foo/bar#0.1.0` omits the name if it matches the directory
+                None => package_id
+                    .split('#')
+                    .next()
+                    .and_then(|url| url.rsplit('/').next())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+            None => package_id
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        Self { name, is_local }
+    }
+}
+
+/// The parts of `cargo metadata --format-version 1` we care about
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Metadata {
+    pub workspace_root: String,
+    pub target_directory: String,
+    pub workspace_members: Vec<String>,
+    pub packages: Vec<MetadataPackage>,
+    /// The resolved dependency graph, `None` with `--no-deps`
+    #[serde(default)]
+    pub resolve: Option<MetadataResolve>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataPackage {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    pub manifest_path: String,
+    /// `None` for workspace members and path dependencies
+    #[serde(default)]
+    pub source: Option<String>,
+    pub features: BTreeMap<String, Vec<String>>,
+    pub targets: Vec<MetadataTarget>,
+    pub dependencies: Vec<MetadataDependency>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataTarget {
+    pub name: String,
+    pub kind: Vec<String>,
+    #[serde(default)]
+    pub src_path: String,
+    #[serde(default, rename = "required-features")]
+    pub required_features: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataDependency {
+    pub name: String,
+    pub req: String,
+    pub kind: Option<String>,
+    pub optional: bool,
+    #[serde(default)]
+    pub rename: Option<String>,
+    /// `None` for path dependencies
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataResolve {
+    pub nodes: Vec<MetadataNode>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataNode {
+    /// The package id
+    pub id: String,
+    pub deps: Vec<MetadataNodeDep>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataNodeDep {
+    /// The package id of the dependency
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<MetadataDepKind>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MetadataDepKind {
+    /// `None` for normal dependencies, otherwise `dev` or `build`
+    pub kind: Option<String>,
+}
+
+impl MetadataDependency {
+    pub fn is_crates_io(&self) -> bool {
+        self.source.as_deref().is_some_and(|source| {
+            source.contains("crates.io-index") || source.contains("index.crates.io")
+        })
+    }
+}
+
+impl Metadata {
+    pub fn workspace_packages(&self) -> impl Iterator<Item = &MetadataPackage> {
+        self.packages
+            .iter()
+            .filter(|package| self.workspace_members.contains(&package.id))
+    }
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+}
+
+/// The versions of every package in the `Cargo.lock` of the workspace
+pub fn locked_versions(
+    workspace_root: impl AsRef<Path>,
+) -> Result<HashMap<String, Vec<semver::Version>>> {
+    let content = std::fs::read_to_string(workspace_root.as_ref().join("Cargo.lock"))?;
+    let lockfile: Lockfile = toml::from_str(&content)?;
+    let mut versions: HashMap<String, Vec<semver::Version>> = HashMap::new();
+    for package in lockfile.package {
+        if let Ok(version) = semver::Version::parse(&package.version) {
+            versions.entry(package.name).or_default().push(version);
+        }
+    }
+    Ok(versions)
+}
+
+/// A unit from the `UNIT_DATA` of cargo's `--timings` report. Times are in seconds
+#[derive(Clone, Debug, Deserialize)]
+pub struct UnitTiming {
+    pub i: usize,
+    pub name: String,
+    pub version: String,
+    pub mode: String,
+    pub target: String,
+    pub start: f64,
+    pub duration: f64,
+    pub rmeta_time: Option<f64>,
+    #[serde(default)]
+    pub unlocked_units: Vec<usize>,
+    #[serde(default)]
+    pub unlocked_rmeta_units: Vec<usize>,
+}
+
+impl UnitTiming {
+    pub fn end(&self) -> f64 {
+        self.start + self.duration
+    }
+
+    /// When this unit allowed `unit` to start, if it was blocking it
+    fn unlock_time(&self, unit: usize) -> Option<f64> {
+        if self.unlocked_units.contains(&unit) {
+            Some(self.end())
+        } else if self.unlocked_rmeta_units.contains(&unit) {
+            Some(self.start + self.rmeta_time.unwrap_or(self.duration))
+        } else {
+            None
+        }
+    }
+}
+
+/// Follow the units which were the last to unblock the next one, starting from the
+/// unit that finished last
+pub fn critical_path(units: &[UnitTiming]) -> Vec<&UnitTiming> {
+    let Some(mut current) = units.iter().max_by(|a, b| a.end().total_cmp(&b.end())) else {
+        return Vec::new();
+    };
+    let mut path = vec![current];
+    while let Some(blocker) = units
+        .iter()
+        .filter_map(|unit| Some((unit, unit.unlock_time(current.i)?)))
+        .filter(|(unit, _)| unit.start < current.start)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(unit, _)| unit)
+    {
+        path.push(blocker);
+        current = blocker;
+    }
+    path.reverse();
+    path
+}
+
+/// The result of a single test from nextest's libtest compatible output
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TestResult {
+    pub name: String,
+    /// `ok`, `failed` or `ignored`
+    pub event: String,
+    pub exec_time: Option<f64>,
+    /// The captured output of failed tests
+    pub stdout: Option<String>,
+}
+
+/// The timing of a benchmark in nanoseconds per iteration
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct BenchResult {
+    pub name: String,
+    /// libtest's median or criterion's point estimate
+    pub time_ns: f64,
+    /// Criterion's confidence interval, or the time -/+ libtest's deviation
+    pub low_ns: f64,
+    pub high_ns: f64,
+}
+
+/// The benchmark timings in the output of `cargo bench`, from libtest
+/// (`test fib ... bench:   1,234 ns/iter (+/- 56)`) and from criterion
+/// (`fib 20   time:   [26.029 µs 26.251 µs 26.505 µs]`)
+pub fn parse_bench_output(lines: &[String]) -> Vec<BenchResult> {
+    let mut results = Vec::new();
+    let mut previous = "";
+    for line in lines {
+        let line = line.trim();
+        if let Some((name, timing)) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.split_once(" ... bench:"))
+        {
+            results.extend(libtest_timing(name.trim(), timing));
+        } else if let Some((name, timing)) = line.split_once("time:") {
+            // Criterion puts long names on a line of their own
+            let name = match name.trim() {
+                "" => previous,
+                name => name,
+            };
+            if !name.is_empty() {
+                results.extend(criterion_timing(name, timing));
+            }
+        }
+        if !line.is_empty() {
+            previous = line;
+        }
+    }
+    results
+}
+
+fn libtest_timing(name: &str, timing: &str) -> Option<BenchResult> {
+    let (time, deviation) = timing.trim().split_once(" ns/iter")?;
+    let number = |text: &str| text.trim().replace(',', "").parse::<f64>().ok();
+    let time = number(time)?;
+    let deviation = number(deviation.trim().strip_prefix("(+/-")?.trim_end_matches(')'))?;
+    Some(BenchResult {
+        name: name.to_string(),
+        time_ns: time,
+        low_ns: (time - deviation).max(0.0),
+        high_ns: time + deviation,
+    })
+}
+
+fn criterion_timing(name: &str, timing: &str) -> Option<BenchResult> {
+    let values = timing
+        .trim()
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    let [low, low_unit, time, time_unit, high, high_unit] = values.as_slice() else {
+        return None;
+    };
+    let nanos = |value: &str, unit: &str| {
+        let factor = match unit {
+            "ps" => 0.001,
+            "ns" => 1.0,
+            "µs" | "us" => 1_000.0,
+            "ms" => 1_000_000.0,
+            "s" => 1_000_000_000.0,
+            _ => return None,
+        };
+        Some(value.parse::<f64>().ok()? * factor)
+    };
+    Some(BenchResult {
+        name: name.to_string(),
+        time_ns: nanos(time, time_unit)?,
+        low_ns: nanos(low, low_unit)?,
+        high_ns: nanos(high, high_unit)?,
+    })
+}
+
+/// The checks of `cargo deny` the `cargo_deny` tool runs by default
+pub const DENY_CHECKS: &[&str] = &["advisories", "bans", "licenses"];
+
+/// An error or warning of a `cargo deny` check
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DenyViolation {
+    /// `advisories`, `bans`, `licenses` or `sources`
+    pub check: String,
+    /// `error` or `warning`
+    pub severity: String,
+    /// e.g. `rejected`, `banned` or `vulnerability`
+    pub code: String,
+    pub message: String,
+    /// The affected crates as `name@version`
+    pub crates: Vec<String>,
+    /// The advisory, the offending license or the config entry
+    pub details: Vec<String>,
+}
+
+/// The violations in the `--format json` output of `cargo deny check <check>`.
+/// `None` if the output has no summary, i.e. the check didn't run.
+pub fn parse_deny_output(check: &str, output: &str) -> Option<Vec<DenyViolation>> {
+    let mut violations = Vec::new();
+    let mut finished = false;
+    for line in output.lines() {
+        let Ok(message) = json::from_str::<json::Value>(line) else {
+            continue;
+        };
+        let fields = &message["fields"];
+        match message["type"].as_str() {
+            Some("summary") => finished = true,
+            Some("diagnostic") => {
+                let severity = fields["severity"].as_str().unwrap_or_default();
+                if severity != "error" && severity != "warning" {
+                    continue;
+                }
+                let crates = fields["graphs"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|graph| {
+                        let krate = &graph["Krate"];
+                        Some(format!(
+                            "{}@{}",
+                            krate["name"].as_str()?,
+                            krate["version"].as_str()?
+                        ))
+                    })
+                    .collect();
+                let mut details = Vec::new();
+                if let Some(id) = fields["advisory"]["id"].as_str() {
+                    let title = fields["advisory"]["title"].as_str().unwrap_or_default();
+                    details.push(format!("{id}: {title}"));
+                }
+                for label in fields["labels"].as_array().into_iter().flatten() {
+                    let span = label["span"].as_str().unwrap_or_default();
+                    match label["message"].as_str() {
+                        Some(message) if !message.is_empty() => {
+                            details.push(format!("{message}: {span}"))
+                        }
+                        _ => details.push(span.to_string()),
+                    }
+                }
+                violations.push(DenyViolation {
+                    check: check.to_string(),
+                    severity: severity.to_string(),
+                    code: fields["code"].as_str().unwrap_or_default().to_string(),
+                    message: fields["message"].as_str().unwrap_or_default().to_string(),
+                    crates,
+                    details,
+                });
+            }
+            _ => {}
+        }
+    }
+    finished.then_some(violations)
+}
+
+#[derive(Deserialize)]
+struct LibtestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+    exec_time: Option<f64>,
+    stdout: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct CargoRemote {
     repository: Project,
+    /// Receives the lines cargo prints while a command runs
+    output: Option<Sender<String>>,
+    /// The target directory once cargo resolved it
+    target_dir: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl CargoRemote {
     pub fn new(repository: Project) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            output: None,
+            target_dir: Arc::default(),
+        }
+    }
+
+    /// A copy that sends the human readable output of its commands to `output`
+    /// line by line while they run, so that long builds and test runs show progress
+    pub fn with_output(&self, output: Sender<String>) -> Self {
+        Self {
+            repository: self.repository.clone(),
+            output: Some(output),
+            target_dir: self.target_dir.clone(),
+        }
+    }
+
+    /// The target directory as cargo resolves it, e.g. from `CARGO_TARGET_DIR`,
+    /// `build.target-dir` in `.cargo/config.toml` or the workspace root. It is only
+    /// resolved once per project.
+    pub async fn target_dir(&self) -> Result<PathBuf> {
+        if let Some(target_dir) = self.target_dir.lock().ok().and_then(|dir| dir.clone()) {
+            return Ok(target_dir);
+        }
+        let target_dir = PathBuf::from(self.metadata(true).await?.target_directory);
+        if let Ok(mut cached) = self.target_dir.lock() {
+            *cached = Some(target_dir.clone());
+        }
+        Ok(target_dir)
     }
 
     async fn run_cargo_command(
         &self,
         args: &[&str],
         backtrace: bool,
-    ) -> Result<(Vec<CargoMessage>, Vec<String>)> {
-        let output = Command::new("cargo")
+    ) -> Result<(Vec<CargoMessage>, Vec<String>, String)> {
+        let backtrace = if backtrace { "full" } else { "0" };
+        self.run_cargo_command_with_env(args, &[("RUST_BACKTRACE", backtrace)])
+            .await
+    }
+
+    async fn run_cargo_command_with_env(
+        &self,
+        args: &[&str],
+        env: &[(&str, &str)],
+    ) -> Result<(Vec<CargoMessage>, Vec<String>, String)> {
+        let mut child = Command::new("cargo")
             .current_dir(self.repository.root())
             .args(args)
-            .env("RUST_BACKTRACE", if backtrace { "full" } else { "0" })
-            .output()
-            .await?;
-
-        let stdout = String::from_utf8(output.stdout)?;
+            .envs(env.iter().copied())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Aborted tool calls should not leave cargo running
+            .kill_on_drop(true)
+            .spawn()?;
+        let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            anyhow::bail!("Failed to capture the output of cargo");
+        };
+        let mut stdout = BufReader::new(stdout).split(b'\n');
+        let mut stderr = BufReader::new(stderr).split(b'\n');
 
+        // Both pipes are read as the lines come in, cargo blocks once one of them is full
         let mut messages = Vec::new();
         let mut test_messages = Vec::new();
-        for line in stdout.lines().filter(|line| !line.is_empty()) {
-            match json::from_str::<CargoMessage>(line) {
-                Ok(message) => {
-                    messages.push(message);
+        let mut stderr_output = String::new();
+        let (mut stdout_open, mut stderr_open) = (true, true);
+        while stdout_open || stderr_open {
+            tokio::select! {
+                line = stdout.next_segment(), if stdout_open => {
+                    let Some(line) = line? else {
+                        stdout_open = false;
+                        continue;
+                    };
+                    let line = String::from_utf8_lossy(&line);
+                    let line = line.trim_end_matches('\r');
+                    if line.is_empty() {
+                        continue;
+                    }
+                    match json::from_str::<CargoMessage>(line) {
+                        Ok(message) => {
+                            messages.push(message);
+                        }
+                        Err(_) => {
+                            // Cargo test doesn't respect `message-format=json`
+                            if !line.starts_with('{') {
+                                self.report(line);
+                            }
+                            test_messages.push(line.to_string());
+                        }
+                    }
                 }
-                Err(_) => {
-                    // Cargo test doesn't respect `message-format=json`
-                    test_messages.push(line.to_string());
+                line = stderr.next_segment(), if stderr_open => {
+                    let Some(line) = line? else {
+                        stderr_open = false;
+                        continue;
+                    };
+                    let line = String::from_utf8_lossy(&line);
+                    self.report(line.trim_end_matches('\r'));
+                    stderr_output.push_str(&line);
+                    stderr_output.push('\n');
                 }
             }
         }
+        child.wait().await?;
 
-        Ok((messages, test_messages))
+        Ok((messages, test_messages, stderr_output))
+    }
+
+    fn report(&self, line: &str) {
+        if let Some(output) = &self.output
+            && !line.trim().is_empty()
+        {
+            // The receiver is gone once the tool call returned
+            let _ = output.send(line.to_string());
+        }
     }
 
     pub async fn check(&self, only_errors: bool) -> Result<Vec<String>> {
-        let (messages, _) = self
-            .run_cargo_command(&["check", "--message-format=json"], false)
-            .await?;
+        Ok(self
+            .check_messages(only_errors, &BuildSelection::default())
+            .await?
+            .into_iter()
+            .map(|message| message.rendered)
+            .collect())
+    }
+
+    /// Like `check` but returns the structured compiler messages
+    pub async fn check_messages(
+        &self,
+        only_errors: bool,
+        selection: &BuildSelection,
+    ) -> Result<Vec<CompilerMessage>> {
+        let selection_args = selection.cargo_args();
+        let mut args = vec!["check", "--message-format=json"];
+        args.extend(selection_args.iter().map(String::as_str));
+        let (messages, _, _) = self.run_cargo_command(&args, false).await?;
         Ok(messages
             .into_iter()
             .filter_map(|message| match message {
-                CargoMessage::CompilerMessage { message } => {
+                CargoMessage::CompilerMessage { message, .. } => {
                     if only_errors && message.level != "error" {
                         return None;
                     }
-                    Some(message.rendered)
+                    Some(message)
                 }
                 _ => None,
             })
             .collect::<Vec<_>>())
     }
 
-    pub async fn test(&self, test_name: Option<String>, backtrace: bool) -> Result<Vec<String>> {
+    /// Check the project for another target, continuing past crates that fail to build
+    pub async fn check_target(&self, target: &str, extra_args: &[&str]) -> Result<TargetCheck> {
+        let mut args = vec![
+            "check",
+            "--message-format=json",
+            "--keep-going",
+            "--target",
+            target,
+        ];
+        args.extend_from_slice(extra_args);
+        let (messages, _, stderr) = self.run_cargo_command(&args, false).await?;
+        let mut success = false;
+        let mut errors = Vec::new();
+        for message in messages {
+            match message {
+                CargoMessage::CompilerMessage {
+                    message,
+                    package_id,
+                } if message.level == "error" => {
+                    errors.push((PackageId::parse(&package_id), message));
+                }
+                CargoMessage::BuildFinished { success: finished } => success = finished,
+                _ => {}
+            }
+        }
+        Ok(TargetCheck {
+            success,
+            errors,
+            stderr,
+        })
+    }
+
+    /// The inverted dependency tree of a package, showing how it gets pulled in
+    pub async fn inverse_tree(&self, package: &str, extra_args: &[&str]) -> Result<String> {
+        let mut args = vec!["tree", "--invert", package, "--edges", "normal"];
+        args.extend_from_slice(extra_args);
+        let (_, lines, _) = self.run_cargo_command(&args, false).await?;
+        Ok(lines.join("\n"))
+    }
+
+    /// Run `cargo add` on the manifest and return cargo's output
+    pub async fn add_dependency(
+        &self,
+        manifest: &str,
+        dependency: &str,
+        features: &[String],
+        dev: bool,
+    ) -> Result<String> {
+        let mut args = vec!["add", "--manifest-path", manifest, dependency];
+        if dev {
+            args.push("--dev");
+        }
+        let features = features.join(",");
+        if !features.is_empty() {
+            args.extend(["--features", &features]);
+        }
+        let (_, _, stderr) = self.run_cargo_command(&args, false).await?;
+        Ok(stderr)
+    }
+
+    /// Run `cargo remove` on the manifest and return cargo's output
+    pub async fn remove_dependency(
+        &self,
+        manifest: &str,
+        dependency: &str,
+        dev: bool,
+    ) -> Result<String> {
+        let mut args = vec!["remove", "--manifest-path", manifest, dependency];
+        if dev {
+            args.push("--dev");
+        }
+        let (_, _, stderr) = self.run_cargo_command(&args, false).await?;
+        Ok(stderr)
+    }
+
+    /// Run `cargo clean` and return cargo's summary of what was (or would be) removed
+    pub async fn clean(
+        &self,
+        package: Option<&str>,
+        profile: Option<&str>,
+        dry_run: bool,
+    ) -> Result<String> {
+        let mut args = vec!["clean"];
+        if let Some(package) = package {
+            args.extend(["--package", package]);
+        }
+        if let Some(profile) = profile {
+            args.extend(["--profile", profile]);
+        }
+        if dry_run {
+            args.push("--dry-run");
+        }
+        // The dry run lists every file on stdout, cargo's summary is on stderr
+        let (_, _, stderr) = self.run_cargo_command(&args, false).await?;
+        Ok(stderr)
+    }
+
+    /// Run `cargo metadata`, optionally without resolving the dependencies
+    pub async fn metadata(&self, no_deps: bool) -> Result<Metadata> {
+        let mut args = vec!["metadata", "--format-version", "1"];
+        if no_deps {
+            args.push("--no-deps");
+        }
+        let (_, lines, stderr) = self.run_cargo_command(&args, false).await?;
+        if lines.is_empty() {
+            anyhow::bail!("cargo metadata failed: {stderr}");
+        }
+        Ok(json::from_str(&lines.join("\n"))?)
+    }
+
+    /// Build with `--timings` and parse the unit data from the generated report
+    pub async fn build_timings(&self) -> Result<Vec<UnitTiming>> {
+        let (_, _, stderr) = self
+            .run_cargo_command(&["build", "--timings"], false)
+            .await?;
+        let report = self
+            .target_dir()
+            .await?
+            .join("cargo-timings")
+            .join("cargo-timing.html");
+        let html = std::fs::read_to_string(&report)
+            .map_err(|e| anyhow::anyhow!("Failed to read {report:?}: {e}\n{stderr}"))?;
+        let Some((_, data)) = html.split_once("const UNIT_DATA = ") else {
+            anyhow::bail!("No unit data found in {report:?}");
+        };
+        // The data is followed by a `;` and more javascript
+        let units = json::Deserializer::from_str(data)
+            .into_iter::<Vec<UnitTiming>>()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No unit data found in {report:?}"))??;
+        Ok(units)
+    }
+
+    /// Build a binary like `cargo run --bin` does and run it with `args`. The binary
+    /// is started directly instead of through cargo, so that killing it after
+    /// `timeout` doesn't leave it running.
+    pub async fn run(
+        &self,
+        bin: Option<&str>,
+        args: &[String],
+        timeout: Duration,
+    ) -> Result<RunOutput> {
+        let mut build_args = vec!["build", "--message-format=json"];
+        match bin {
+            Some(bin) => build_args.extend(["--bin", bin]),
+            None => build_args.push("--bins"),
+        }
+        let (messages, _, stderr) = self.run_cargo_command(&build_args, false).await?;
+        let mut errors = Vec::new();
+        let mut executables = Vec::new();
+        for message in messages {
+            match message {
+                CargoMessage::CompilerMessage { message, .. } if message.level == "error" => {
+                    errors.push(message.rendered);
+                }
+                CargoMessage::CompilerArtifact(artifact) => {
+                    let is_bin = artifact["target"]["kind"]
+                        .as_array()
+                        .is_some_and(|kinds| kinds.iter().any(|kind| kind == "bin"));
+                    if let Some(executable) = artifact["executable"].as_str()
+                        && is_bin
+                    {
+                        let name = artifact["target"]["name"].as_str().unwrap_or_default();
+                        executables.push((name.to_string(), PathBuf::from(executable)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("The build failed:\n{}", errors.join("\n"));
+        }
+        let executable = match (bin, executables.as_slice()) {
+            (Some(bin), _) => executables
+                .iter()
+                .find(|(name, _)| name == bin)
+                .map(|(_, path)| path.clone()),
+            (None, [(_, path)]) => Some(path.clone()),
+            (None, []) => None,
+            (None, _) => {
+                let names = executables
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>();
+                anyhow::bail!(
+                    "The project has several binaries, pass one of them as `bin`: {}",
+                    names.join(", ")
+                );
+            }
+        };
+        let Some(executable) = executable else {
+            anyhow::bail!("No binary was built:\n{stderr}");
+        };
+
+        let mut child = Command::new(&executable)
+            .current_dir(self.repository.root())
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdout = tokio::spawn(read_pipe_capped(child.stdout.take(), MAX_CAPTURED_OUTPUT));
+        let stderr = tokio::spawn(read_pipe_capped(child.stderr.take(), MAX_CAPTURED_OUTPUT));
+        let (exit_code, timed_out) = match tokio::time::timeout(timeout, child.wait()).await {
+            Ok(status) => (status?.code(), false),
+            Err(_) => {
+                child.kill().await?;
+                (None, true)
+            }
+        };
+        let collect = |reader: tokio::task::JoinHandle<String>| async move {
+            tokio::time::timeout(OUTPUT_GRACE, reader)
+                .await
+                .ok()
+                .and_then(|output| output.ok())
+                .unwrap_or_default()
+        };
+        Ok(RunOutput {
+            stdout: collect(stdout).await,
+            stderr: collect(stderr).await,
+            exit_code,
+            timed_out,
+        })
+    }
+
+    /// Run the benches, optionally only those matching `filter`
+    pub async fn bench(
+        &self,
+        filter: Option<&str>,
+        selection: &BuildSelection,
+    ) -> Result<Vec<BenchResult>> {
+        let selection_args = selection.cargo_args();
+        let mut args = vec!["bench"];
+        args.extend(selection_args.iter().map(String::as_str));
+        if let Some(filter) = filter {
+            args.extend(["--", filter]);
+        }
+        let (_, lines, stderr) = self.run_cargo_command(&args, false).await?;
+        let results = parse_bench_output(&lines);
+        if results.is_empty() {
+            anyhow::bail!("No benchmark results found in the output of cargo bench:\n{stderr}");
+        }
+        Ok(results)
+    }
+
+    /// Where the results of a baseline are stored. Next to criterion's own data in
+    /// the target dir, so that `cargo clean` removes them as well.
+    async fn bench_baseline_path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("Baseline names may only contain letters, digits, `-` and `_`");
+        }
+        Ok(self
+            .target_dir()
+            .await?
+            .join("bench-baselines")
+            .join(format!("{name}.json")))
+    }
+
+    pub async fn save_bench_baseline(&self, name: &str, results: &[BenchResult]) -> Result<()> {
+        let path = self.bench_baseline_path(name).await?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, json::to_string_pretty(results)?)?;
+        Ok(())
+    }
+
+    pub async fn bench_baseline(&self, name: &str) -> Result<Vec<BenchResult>> {
+        let path = self.bench_baseline_path(name).await?;
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            anyhow::bail!(
+                "There is no baseline named `{name}`, save one with `save_baseline` first"
+            );
+        };
+        Ok(json::from_str(&content)?)
+    }
+
+    pub async fn test(
+        &self,
+        test_name: Option<String>,
+        selection: &BuildSelection,
+        backtrace: bool,
+    ) -> Result<Vec<String>> {
+        let selection_args = selection.cargo_args();
         let mut args = vec!["test", "--message-format=json"];
+        args.extend(selection_args.iter().map(String::as_str));
         if let Some(ref test_name) = test_name {
             args.push("--");
             args.push("--nocapture");
             args.push(test_name);
         }
-        let (_, messages) = self.run_cargo_command(&args, backtrace).await?;
+        let (_, messages, _) = self.run_cargo_command(&args, backtrace).await?;
         Ok(messages)
     }
+
+    /// The names of the tests (including doc tests) of a package
+    pub async fn list_tests(&self, package: &str) -> Result<Vec<String>> {
+        let (_, lines, stderr) = self
+            .run_cargo_command(
+                &[
+                    "test",
+                    "--package",
+                    package,
+                    "--",
+                    "--list",
+                    "--format",
+                    "terse",
+                ],
+                false,
+            )
+            .await?;
+        let tests = lines
+            .iter()
+            .filter_map(|line| line.strip_suffix(": test"))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        if tests.is_empty() && stderr.contains("error") {
+            anyhow::bail!("Failed to list the tests of {package}:\n{stderr}");
+        }
+        Ok(tests)
+    }
+
+    /// Run `cargo audit` and summarize the found advisories
+    pub async fn audit(&self) -> Result<String> {
+        let (_, lines, stderr) = self.run_cargo_command(&["audit", "--json"], false).await?;
+        let Some(report) = lines
+            .iter()
+            .find_map(|line| json::from_str::<json::Value>(line).ok())
+        else {
+            anyhow::bail!("cargo audit failed (is cargo-audit installed?):\n{stderr}");
+        };
+        let advisories = report["vulnerabilities"]["list"]
+            .as_array()
+            .map(|list| {
+                list.iter()
+                    .map(|vulnerability| {
+                        format!(
+                            "{} {}: {} ({})",
+                            vulnerability["package"]["name"]
+                                .as_str()
+                                .unwrap_or_default(),
+                            vulnerability["package"]["version"]
+                                .as_str()
+                                .unwrap_or_default(),
+                            vulnerability["advisory"]["title"]
+                                .as_str()
+                                .unwrap_or_default(),
+                            vulnerability["advisory"]["id"].as_str().unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        Ok(if advisories.is_empty() {
+            "No vulnerabilities found".to_string()
+        } else {
+            format!(
+                "{} vulnerabilities found:\n{}",
+                advisories.len(),
+                advisories.join("\n")
+            )
+        })
+    }
+
+    /// Expand all macros of a crate with `cargo expand` (requires `cargo-expand`).
+    /// `item` limits the output to a module or item, e.g. `parser::Token`, and `target`
+    /// selects the crate, e.g. `["--bin", "server"]`. The library if there is one otherwise.
+    pub async fn expand(
+        &self,
+        item: Option<&str>,
+        target: &[&str],
+        selection: &BuildSelection,
+    ) -> Result<String> {
+        let selection_args = selection.cargo_args();
+        let mut args = vec!["expand", "--color", "never"];
+        args.extend(target);
+        args.extend(selection_args.iter().map(String::as_str));
+        if let Some(item) = item {
+            args.push(item);
+        }
+        let mut child = Command::new("cargo")
+            .current_dir(self.repository.root())
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let expanded = tokio::spawn(read_pipe(child.stdout.take()));
+
+        // The expansion is only complete once the crate compiled, the progress is on stderr
+        let mut stderr_output = String::new();
+        if let Some(stderr) = child.stderr.take() {
+            let mut stderr = BufReader::new(stderr).split(b'\n');
+            while let Some(line) = stderr.next_segment().await? {
+                let line = String::from_utf8_lossy(&line);
+                self.report(line.trim_end_matches('\r'));
+                stderr_output.push_str(&line);
+                stderr_output.push('\n');
+            }
+        }
+        let status = child.wait().await?;
+        let expanded = expanded.await?;
+        if stderr_output.contains("no such command") {
+            anyhow::bail!(
+                "cargo-expand is not installed, install it with `cargo install cargo-expand`"
+            );
+        }
+        if !status.success() {
+            anyhow::bail!("cargo expand failed:\n{stderr_output}");
+        }
+        if expanded.trim().is_empty() {
+            anyhow::bail!("cargo expand returned no code:\n{stderr_output}");
+        }
+        Ok(expanded)
+    }
+
+    /// Run `cargo deny check` (requires `cargo-deny`) for each of the `checks`, e.g.
+    /// `licenses`, with the `deny.toml` of the project
+    pub async fn deny(&self, checks: &[&str]) -> Result<Vec<DenyViolation>> {
+        let mut violations = Vec::new();
+        for check in checks {
+            let (_, _, stderr) = self
+                .run_cargo_command(&["deny", "--format", "json", "check", check], false)
+                .await?;
+            if stderr.contains("no such command") {
+                anyhow::bail!(
+                    "cargo-deny is not installed, install it with `cargo install cargo-deny`"
+                );
+            }
+            let Some(found) = parse_deny_output(check, &stderr) else {
+                anyhow::bail!("cargo deny check {check} failed:\n{stderr}");
+            };
+            violations.extend(found);
+        }
+        Ok(violations)
+    }
+
+    /// The long form explanation of an error code, e.g. `E0382`, as markdown
+    pub async fn explain(&self, code: &str) -> Result<String> {
+        let output = Command::new("rustc")
+            .current_dir(self.repository.root())
+            .args(["--explain", code])
+            .output()
+            .await?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    /// Whether `cargo nextest` is installed
+    pub async fn has_nextest(&self) -> bool {
+        Command::new("cargo")
+            .args(["nextest", "--version"])
+            .output()
+            .await
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// Run the tests with nextest and return the per test results
+    pub async fn nextest(
+        &self,
+        test_name: Option<String>,
+        selection: &BuildSelection,
+    ) -> Result<Vec<TestResult>> {
+        let selection_args = selection.cargo_args();
+        let mut args = vec![
+            "nextest",
+            "run",
+            "--no-fail-fast",
+            "--message-format",
+            "libtest-json",
+        ];
+        args.extend(selection_args.iter().map(String::as_str));
+        if let Some(ref test_name) = test_name {
+            args.push(test_name);
+        }
+        let (_, lines, stderr) = self
+            .run_cargo_command_with_env(
+                &args,
+                &[
+                    ("NEXTEST_EXPERIMENTAL_LIBTEST_JSON", "1"),
+                    ("RUST_BACKTRACE", "0"),
+                ],
+            )
+            .await?;
+        let results = lines
+            .iter()
+            .filter_map(|line| json::from_str::<LibtestEvent>(line).ok())
+            .filter(|event| event.kind == "test" && event.event != "started")
+            .filter_map(|event| {
+                Some(TestResult {
+                    name: event.name?,
+                    event: event.event,
+                    exec_time: event.exec_time,
+                    stdout: event.stdout,
+                })
+            })
+            .collect::<Vec<_>>();
+        if results.is_empty() && !stderr.contains("Starting 0 tests") {
+            anyhow::bail!("cargo nextest failed:\n{stderr}");
+        }
+        Ok(results)
+    }
+
+    /// Compile the target containing `file` with `-Zprint-type-sizes` on nightly and
+    /// return the layout lines for the type. The nightly artifacts go to their own
+    /// target dir, where the package is cleaned before every build: rustc only prints
+    /// the sizes when it compiles the crate, and cargo skips fresh crates.
+    pub async fn print_type_sizes(&self, file: &Path, type_name: &str) -> Result<Vec<String>> {
+        let metadata = self.metadata(true).await?;
+        let Some((package, target)) = target_of_file(&metadata, file) else {
+            anyhow::bail!("No target of the workspace contains {file:?}");
+        };
+        let target_dir = Path::new(&metadata.target_directory).join("type-sizes");
+        let target_dir = target_dir.to_string_lossy();
+        self.run_cargo_command(
+            &[
+                "+nightly",
+                "clean",
+                "--package",
+                &package.name,
+                "--target-dir",
+                &target_dir,
+            ],
+            false,
+        )
+        .await?;
+        let mut args = vec![
+            "+nightly",
+            "rustc",
+            "--package",
+            &package.name,
+            "--target-dir",
+            &target_dir,
+        ];
+        // `cargo rustc` refuses to guess the target if the package has more than one
+        match target.kind.first().map(String::as_str) {
+            Some("bin") => args.extend(["--bin", &target.name]),
+            Some("example") => args.extend(["--example", &target.name]),
+            Some("test") => args.extend(["--test", &target.name]),
+            Some("bench") => args.extend(["--bench", &target.name]),
+            _ => args.push("--lib"),
+        }
+        args.extend(["--", "-Zprint-type-sizes"]);
+        let (_, lines, _) = self.run_cargo_command(&args, false).await?;
+        let mut output = Vec::new();
+        let mut in_type = false;
+        for line in lines {
+            let Some(rest) = line.strip_prefix("print-type-size ") else {
+                in_type = false;
+                continue;
+            };
+            if let Some(declaration) = rest.strip_prefix("type: `") {
+                let name = declaration.split('`').next().unwrap_or_default();
+                let name = name.split('<').next().unwrap_or_default();
+                in_type = name == type_name || name.ends_with(&format!("::{type_name}"));
+            }
+            if in_type {
+                output.push(rest.to_string());
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// The package and target whose sources contain `file`: the target with the deepest
+/// source directory, the library if several share one
+fn target_of_file<'a>(
+    metadata: &'a Metadata,
+    file: &Path,
+) -> Option<(&'a MetadataPackage, &'a MetadataTarget)> {
+    metadata
+        .packages
+        .iter()
+        .flat_map(|package| package.targets.iter().map(move |target| (package, target)))
+        .filter(|(_, target)| !target.kind.iter().any(|kind| kind == "custom-build"))
+        .filter_map(|(package, target)| {
+            let directory = Path::new(&target.src_path).parent()?;
+            let is_lib = !target
+                .kind
+                .iter()
+                .any(|kind| matches!(kind.as_str(), "bin" | "example" | "test" | "bench"));
+            file.starts_with(directory)
+                .then(|| ((directory.components().count(), is_lib), (package, target)))
+        })
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, found)| found)
+}
+
+/// Everything written to a pipe until it is closed
+async fn read_pipe(pipe: Option<impl AsyncRead + Unpin>) -> String {
+    let mut buffer = Vec::new();
+    if let Some(mut pipe) = pipe
+        && let Err(e) = pipe.read_to_end(&mut buffer).await
+    {
+        tracing::warn!("Failed to read the output of the binary: {}", e);
+    }
+    String::from_utf8_lossy(&buffer).to_string()
+}
+
+/// The start and the end of everything written to a pipe until it is closed, at most
+/// `max_bytes` of them. The pipe is read to the end, so the writer doesn't block.
+async fn read_pipe_capped(pipe: Option<impl AsyncRead + Unpin>, max_bytes: usize) -> String {
+    let Some(mut pipe) = pipe else {
+        return String::new();
+    };
+    let (head_bytes, tail_bytes) = (max_bytes / 2, max_bytes - max_bytes / 2);
+    let mut buffer = Vec::new();
+    let mut dropped = 0;
+    let mut chunk = [0; 8192];
+    loop {
+        match pipe.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(e) => {
+                tracing::warn!("Failed to read the output of the binary: {}", e);
+                break;
+            }
+        }
+        // Dropped in batches, so that the tail isn't moved for every chunk
+        if buffer.len() > 2 * max_bytes {
+            let end = buffer.len() - tail_bytes;
+            dropped += end - head_bytes;
+            buffer.drain(head_bytes..end);
+        }
+    }
+    if buffer.len() > max_bytes {
+        let end = buffer.len() - tail_bytes;
+        dropped += end - head_bytes;
+        buffer.drain(head_bytes..end);
+    }
+    if dropped == 0 {
+        return String::from_utf8_lossy(&buffer).to_string();
+    }
+    format!(
+        "{}\n... ({dropped} bytes dropped) ...\n{}",
+        String::from_utf8_lossy(&buffer[..head_bytes]),
+        String::from_utf8_lossy(&buffer[head_bytes..])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_id() {
+        let id = PackageId::parse(
+            "registry+https://github.com/rust-lang/crates.io-index#getrandom@0.2.15",
+        );
+        assert_eq!(id.name, "getrandom");
+        assert!(!id.is_local);
+
+        let id = PackageId::parse("path+file:///home/user/my-app#0.1.0");
+        assert_eq!(id.name, "my-app");
+        assert!(id.is_local);
+
+        let id = PackageId::parse("path+file:///home/user/workspace/core#app-core@0.1.0");
+        assert_eq!(id.name, "app-core");
+        assert!(id.is_local);
+
+        let id =
+            PackageId::parse("ring 0.17.8 (registry+https://github.com/rust-lang/crates.io-index)");
+        assert_eq!(id.name, "ring");
+        assert!(!id.is_local);
+    }
+
+    fn unit(i: usize, start: f64, duration: f64, unlocked: &[usize]) -> UnitTiming {
+        UnitTiming {
+            i,
+            name: format!("unit{i}"),
+            version: "0.1.0".to_string(),
+            mode: "todo".to_string(),
+            target: "".to_string(),
+            start,
+            duration,
+            rmeta_time: None,
+            unlocked_units: unlocked.to_vec(),
+            unlocked_rmeta_units: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_bench_output() {
+        let lines = [
+            "running 2 tests",
+            "test bench_add ... bench:       1,234 ns/iter (+/- 56)",
+            "test bench_mul ... bench:          12.50 ns/iter (+/- 0.25)",
+            "fib 20                  time:   [26.029 µs 26.251 µs 26.505 µs]",
+            "                        change: [-2.8352% -1.2005% +0.4182%] (p = 0.15 > 0.05)",
+            "parsing/a_rather_long_benchmark_name",
+            "                        time:   [1.5000 ms 2.0000 ms 2.5000 ms]",
+        ]
+        .map(String::from);
+        let results = parse_bench_output(&lines);
+        let timings = results
+            .iter()
+            .map(|result| {
+                (
+                    result.name.as_str(),
+                    result.low_ns,
+                    result.time_ns,
+                    result.high_ns,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            timings,
+            [
+                ("bench_add", 1178.0, 1234.0, 1290.0),
+                ("bench_mul", 12.25, 12.5, 12.75),
+                ("fib 20", 26029.0, 26251.0, 26505.0),
+                (
+                    "parsing/a_rather_long_benchmark_name",
+                    1_500_000.0,
+                    2_000_000.0,
+                    2_500_000.0
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_deny_output() {
+        let output = [
+            r#"{"type":"log","fields":{"level":"WARN","message":"license was not encountered"}}"#,
+            r#"{"type":"diagnostic","fields":{"severity":"error","code":"rejected","message":"failed to satisfy license requirements","labels":[{"message":"rejected: license is not explicitly allowed","span":"GPL-3.0"}],"graphs":[{"Krate":{"name":"gpl-crate","version":"1.0.0"}}]}}"#,
+            r#"{"type":"diagnostic","fields":{"severity":"note","code":"accepted","message":"license requirements satisfied","labels":[],"graphs":[]}}"#,
+            r#"{"type":"diagnostic","fields":{"severity":"warning","code":"unmaintained","message":"unmaintained advisory detected","advisory":{"id":"RUSTSEC-2024-0001","title":"old is unmaintained"},"labels":[],"graphs":[{"Krate":{"name":"old","version":"0.2.1"}}]}}"#,
+            r#"{"type":"summary","fields":{"licenses":{"errors":1,"warnings":0,"notes":1,"helps":0}}}"#,
+        ]
+        .join("\n");
+        let violations = parse_deny_output("licenses", &output).unwrap();
+        assert_eq!(
+            violations,
+            [
+                DenyViolation {
+                    check: "licenses".to_string(),
+                    severity: "error".to_string(),
+                    code: "rejected".to_string(),
+                    message: "failed to satisfy license requirements".to_string(),
+                    crates: vec!["gpl-crate@1.0.0".to_string()],
+                    details: vec![
+                        "rejected: license is not explicitly allowed: GPL-3.0".to_string()
+                    ],
+                },
+                DenyViolation {
+                    check: "licenses".to_string(),
+                    severity: "warning".to_string(),
+                    code: "unmaintained".to_string(),
+                    message: "unmaintained advisory detected".to_string(),
+                    crates: vec!["old@0.2.1".to_string()],
+                    details: vec!["RUSTSEC-2024-0001: old is unmaintained".to_string()],
+                },
+            ]
+        );
+        assert_eq!(
+            parse_deny_output("bans", "error: no such command: `deny`"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_build_selection_args() {
+        assert!(BuildSelection::default().cargo_args().is_empty());
+        let selection = BuildSelection {
+            package: Some("core".to_string()),
+            features: vec!["serde".to_string(), "tokio".to_string()],
+            no_default_features: true,
+            target: Some("wasm32-unknown-unknown".to_string()),
+        };
+        assert_eq!(
+            selection.cargo_args(),
+            [
+                "--package",
+                "core",
+                "--features",
+                "serde,tokio",
+                "--no-default-features",
+                "--target",
+                "wasm32-unknown-unknown"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_of_file() {
+        let metadata: Metadata = json::from_value(json::json!({
+            "workspace_root": "/app",
+            "target_directory": "/app/target",
+            "workspace_members": [],
+            "packages": [{
+                "id": "path+file:///app#0.1.0",
+                "name": "app",
+                "version": "0.1.0",
+                "edition": "2024",
+                "manifest_path": "/app/Cargo.toml",
+                "features": {},
+                "dependencies": [],
+                "targets": [
+                    { "name": "app", "kind": ["bin"], "src_path": "/app/src/main.rs" },
+                    { "name": "app", "kind": ["lib"], "src_path": "/app/src/lib.rs" },
+                    { "name": "tool", "kind": ["bin"], "src_path": "/app/src/bin/tool.rs" },
+                    { "name": "build-script-build", "kind": ["custom-build"], "src_path": "/app/build.rs" }
+                ]
+            }]
+        }))
+        .unwrap();
+        let target = |file: &str| {
+            target_of_file(&metadata, Path::new(file)).map(|(_, target)| target.kind[0].clone())
+        };
+        assert_eq!(target("/app/src/model/user.rs").as_deref(), Some("lib"));
+        assert_eq!(target("/app/src/bin/tool.rs").as_deref(), Some("bin"));
+        assert_eq!(target("/app/build.rs"), None);
+        assert_eq!(target("/other/src/lib.rs"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_pipe_capped() {
+        let output = "a".repeat(10) + &"b".repeat(100_000) + &"c".repeat(10);
+        let capped = read_pipe_capped(Some(output.as_bytes()), 20).await;
+        assert_eq!(
+            capped,
+            "aaaaaaaaaa\n... (100000 bytes dropped) ...\ncccccccccc"
+        );
+        assert_eq!(read_pipe_capped(Some(&b"short"[..]), 20).await, "short");
+        assert_eq!(read_pipe_capped(None::<&[u8]>, 20).await, "");
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let units = vec![
+            unit(0, 0.0, 1.0, &[2]),
+            unit(1, 0.0, 3.0, &[2]),
+            unit(2, 3.0, 2.0, &[3]),
+            unit(3, 5.0, 1.0, &[]),
+            unit(4, 0.0, 0.5, &[]),
+        ];
+        let path = critical_path(&units)
+            .into_iter()
+            .map(|unit| unit.i)
+            .collect::<Vec<_>>();
+        assert_eq!(path, vec![1, 2, 3]);
+    }
 }
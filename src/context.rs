@@ -1,14 +1,21 @@
 use std::collections::HashMap;
 use std::fs;
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
-use tokio::sync::{RwLock, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::{RwLock, RwLockReadGuard};
 
 use crate::cargo_remote::CargoRemote;
-use crate::docs::{Docs, DocsNotification};
-use crate::lsp::LspNotification;
-use crate::mcp::McpNotification;
+use crate::docs::cache::CacheStats;
+use crate::docs::{Docs, DocsConfig, DocsNotification, DocsProgress};
+use crate::lsp::{LspNotification, RustAnalyzerConfig, ServerStatusParams};
+use crate::maintenance::{MaintenanceConfig, MaintenanceStatus, spawn_scheduler};
+use crate::mcp::{
+    ClientSession, InFlightCalls, McpNotification, Metrics, Sessions, current_session,
+};
+use crate::notify::WebhookConfig;
 use crate::ui::ProjectDescription;
 use crate::{
     lsp::RustAnalyzerLsp,
@@ -22,7 +29,8 @@ use serde::{Deserialize, Serialize};
 pub enum ContextNotification {
     Lsp(LspNotification),
     Docs(DocsNotification),
-    Mcp(McpNotification),
+    /// With the client that made the tool call, for the SSE transport
+    Mcp(McpNotification, Option<ClientSession>),
     ProjectAdded(PathBuf),
     ProjectRemoved(PathBuf),
     ProjectDescriptions(Vec<ProjectDescription>),
@@ -32,11 +40,20 @@ impl ContextNotification {
     pub fn notification_path(&self) -> PathBuf {
         match self {
             ContextNotification::Lsp(LspNotification::Indexing { project, .. }) => project.clone(),
-            ContextNotification::Docs(DocsNotification::Indexing { project, .. }) => {
+            ContextNotification::Lsp(LspNotification::Crashed { project, .. }) => project.clone(),
+            ContextNotification::Lsp(LspNotification::Status { project, .. }) => project.clone(),
+            ContextNotification::Lsp(LspNotification::ManifestChanged { project }) => {
+                project.clone()
+            }
+            ContextNotification::Lsp(LspNotification::Stderr { project, .. }) => project.clone(),
+            ContextNotification::Docs(notification) => notification.project().clone(),
+            ContextNotification::Mcp(McpNotification::Request { project, .. }, _) => {
+                project.clone()
+            }
+            ContextNotification::Mcp(McpNotification::Response { project, .. }, _) => {
                 project.clone()
             }
-            ContextNotification::Mcp(McpNotification::Request { project, .. }) => project.clone(),
-            ContextNotification::Mcp(McpNotification::Response { project, .. }) => project.clone(),
+            ContextNotification::Mcp(McpNotification::Output { project, .. }, _) => project.clone(),
             ContextNotification::ProjectAdded(project) => project.clone(),
             ContextNotification::ProjectRemoved(project) => project.clone(),
             ContextNotification::ProjectDescriptions(_) => PathBuf::from("project_descriptions"),
@@ -45,23 +62,87 @@ impl ContextNotification {
 
     pub fn description(&self) -> String {
         match self {
-            ContextNotification::Lsp(LspNotification::Indexing { is_indexing, .. }) => {
+            ContextNotification::Lsp(LspNotification::Indexing {
+                is_indexing,
+                percentage,
+                ..
+            }) => match (is_indexing, percentage) {
+                (true, Some(percentage)) => format!("LSP Indexing: {percentage}%"),
+                (true, None) => "LSP Indexing: Started".to_string(),
+                (false, _) => "LSP Indexing: Finished".to_string(),
+            },
+            ContextNotification::Lsp(LspNotification::Crashed { stderr, .. }) => {
                 format!(
-                    "LSP Indexing: {}",
-                    if *is_indexing { "Started" } else { "Finished" }
+                    "LSP Crashed: {}",
+                    stderr.lines().last().unwrap_or("no output")
+                )
+            }
+            ContextNotification::Lsp(LspNotification::Status { status, .. }) => {
+                format!(
+                    "LSP Status: {:?}{}",
+                    status.health,
+                    status
+                        .message
+                        .as_ref()
+                        .map(|message| format!(", {message}"))
+                        .unwrap_or_default()
                 )
             }
+            ContextNotification::Lsp(LspNotification::ManifestChanged { .. }) => {
+                "Dependencies changed, reloading the workspace".to_string()
+            }
+            ContextNotification::Lsp(LspNotification::Stderr { line, .. }) => {
+                format!("rust-analyzer: {line}")
+            }
             ContextNotification::Docs(DocsNotification::Indexing { is_indexing, .. }) => {
                 format!(
                     "Docs Indexing: {}",
                     if *is_indexing { "Started" } else { "Finished" }
                 )
             }
-            ContextNotification::Mcp(McpNotification::Request { content, .. }) => {
-                format!("MCP Request: {:?}", content)
+            ContextNotification::Docs(DocsNotification::CrateStarted {
+                name,
+                index,
+                total,
+                ..
+            }) => {
+                format!(
+                    "Docs Indexing {}",
+                    DocsProgress {
+                        name: name.clone(),
+                        index: *index,
+                        total: *total,
+                        pages: None,
+                    }
+                )
+            }
+            ContextNotification::Docs(DocsNotification::CrateFinished {
+                name,
+                index,
+                total,
+                ..
+            }) => {
+                format!("Docs Indexed {index}/{total}: {name}")
+            }
+            ContextNotification::Docs(DocsNotification::Progress {
+                name, done, total, ..
+            }) => {
+                format!("Docs Indexing {name}: {done}/{total} pages")
+            }
+            ContextNotification::Docs(DocsNotification::Failed { error, .. }) => {
+                format!(
+                    "Docs Generation Failed: {}",
+                    error.lines().last().unwrap_or_default()
+                )
+            }
+            ContextNotification::Mcp(McpNotification::Request { content, .. }, session) => {
+                format!("MCP Request{}: {:?}", client_label(session), content)
+            }
+            ContextNotification::Mcp(McpNotification::Response { content, .. }, session) => {
+                format!("MCP Response{}: {:?}", client_label(session), content)
             }
-            ContextNotification::Mcp(McpNotification::Response { content, .. }) => {
-                format!("MCP Response: {:?}", content)
+            ContextNotification::Mcp(McpNotification::Output { line, .. }, session) => {
+                format!("Cargo{}: {line}", client_label(session))
             }
             ContextNotification::ProjectAdded(project) => {
                 format!("Project Added: {:?}", project)
@@ -80,11 +161,66 @@ const CONFIGURATION_FILE: &str = ".cursor-rust-tools";
 #[derive(Debug)]
 pub struct ProjectContext {
     pub project: Project,
+    /// The root of the cargo workspace the project belongs to, if cargo knows it
+    pub workspace_root: Option<PathBuf>,
     pub lsp: RustAnalyzerLsp,
     pub docs: Docs,
     pub cargo_remote: CargoRemote,
     pub is_indexing_lsp: AtomicBool,
+    /// How far the LSP indexing got in percent, while `is_indexing_lsp`
+    pub lsp_percentage: AtomicU32,
     pub is_indexing_docs: AtomicBool,
+    /// The crate the docs indexing is working on, if it is running
+    pub docs_progress: std::sync::Mutex<Option<DocsProgress>>,
+    /// The health rust-analyzer reported last
+    pub lsp_status: std::sync::Mutex<Option<ServerStatusParams>>,
+    /// The memory usage report of rust-analyzer, once it was requested
+    pub lsp_memory: std::sync::Mutex<Option<String>>,
+    pub maintenance: tokio::sync::Mutex<MaintenanceStatus>,
+}
+
+impl ProjectContext {
+    pub fn docs_progress(&self) -> Option<DocsProgress> {
+        self.docs_progress
+            .lock()
+            .ok()
+            .and_then(|progress| progress.clone())
+    }
+
+    fn set_docs_progress(&self, progress: Option<DocsProgress>) {
+        if let Ok(mut current) = self.docs_progress.lock() {
+            *current = progress;
+        }
+    }
+
+    pub fn lsp_status(&self) -> Option<ServerStatusParams> {
+        self.lsp_status
+            .lock()
+            .ok()
+            .and_then(|status| status.clone())
+    }
+
+    fn set_lsp_status(&self, status: Option<ServerStatusParams>) {
+        if let Ok(mut current) = self.lsp_status.lock() {
+            *current = status;
+        }
+    }
+
+    pub fn lsp_memory(&self) -> Option<String> {
+        self.lsp_memory
+            .lock()
+            .ok()
+            .and_then(|memory| memory.clone())
+    }
+}
+
+/// Server settings from the command line or the environment. They take precedence
+/// over the configuration file and aren't written to it.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOverrides {
+    pub port: Option<u16>,
+    pub bind_addresses: Vec<String>,
+    pub allow_remote: bool,
 }
 
 #[derive(Clone)]
@@ -93,44 +229,132 @@ pub struct Context {
     transport: TransportType,
     lsp_sender: Sender<LspNotification>,
     docs_sender: Sender<DocsNotification>,
-    mcp_sender: Sender<McpNotification>,
+    mcp_sender: Sender<(McpNotification, Option<ClientSession>)>,
     notifier: Sender<ContextNotification>,
+    webhooks: Arc<std::sync::RwLock<Vec<WebhookConfig>>>,
+    announce_mdns: Arc<AtomicBool>,
+    audit_log: Arc<AtomicBool>,
+    bind_addresses: Arc<std::sync::RwLock<Vec<String>>>,
+    /// The port from the configuration file, 0 if it has none
+    port: Arc<AtomicU16>,
+    /// Whether the configuration file allows listening on non-loopback addresses
+    allow_remote: Arc<AtomicBool>,
+    overrides: Arc<std::sync::RwLock<ServerOverrides>>,
+    /// The bearer token SSE clients have to send, if any
+    api_key: Arc<std::sync::RwLock<Option<String>>>,
+    in_flight: InFlightCalls,
+    sessions: Sessions,
+    metrics: Metrics,
+    shutdown_grace_seconds: Arc<AtomicU64>,
 }
 
 impl Context {
     pub async fn new(port: u16, notifier: Sender<ContextNotification>) -> Self {
-        let (lsp_sender, lsp_receiver) = flume::unbounded();
-        let (docs_sender, docs_receiver) = flume::unbounded();
+        let (lsp_sender, lsp_receiver) = flume::unbounded::<LspNotification>();
+        let (docs_sender, docs_receiver) = flume::unbounded::<DocsNotification>();
         let (mcp_sender, mcp_receiver) = flume::unbounded();
 
         let projects = Arc::new(RwLock::new(HashMap::new()));
 
+        let webhooks = Arc::new(std::sync::RwLock::new(Vec::new()));
+
         let cloned_projects = projects.clone();
         let cloned_notifier = notifier.clone();
         tokio::spawn(async move {
             loop {
                 tokio::select! {
-                    Ok(notification) = mcp_receiver.recv_async() => {
-                        if let Err(e) = cloned_notifier.send(ContextNotification::Mcp(notification)) {
+                    Ok((notification, session)) = mcp_receiver.recv_async() => {
+                        if let Err(e) = cloned_notifier.send(ContextNotification::Mcp(notification, session)) {
                             tracing::error!("Failed to send MCP notification: {}", e);
                         }
                     }
-                    Ok(ref notification @ DocsNotification::Indexing { ref project, is_indexing }) = docs_receiver.recv_async() => {
-                        if let Err(e) = cloned_notifier.send(ContextNotification::Docs(notification.clone())) {
-                            tracing::error!("Failed to send docs notification: {}", e);
+                    Ok(notification) = docs_receiver.recv_async() => {
+                        // Store the progress first, the UI reloads the descriptions on every notification
+                        let projects: RwLockReadGuard<'_, HashMap<PathBuf, Arc<ProjectContext>>> = cloned_projects.read().await;
+                        if let Some(project) = projects.get(notification.project()) {
+                            match &notification {
+                                DocsNotification::Indexing { is_indexing, .. } => {
+                                    project.is_indexing_docs.store(*is_indexing, std::sync::atomic::Ordering::Relaxed);
+                                    if !is_indexing {
+                                        project.set_docs_progress(None);
+                                    }
+                                }
+                                DocsNotification::CrateStarted { name, index, total, .. } => {
+                                    project.set_docs_progress(Some(DocsProgress {
+                                        name: name.clone(),
+                                        index: *index,
+                                        total: *total,
+                                        pages: None,
+                                    }));
+                                }
+                                DocsNotification::Progress { name, done, total, .. } => {
+                                    if let Some(mut progress) = project.docs_progress()
+                                        && progress.name == *name
+                                    {
+                                        progress.pages = Some((*done, *total));
+                                        project.set_docs_progress(Some(progress));
+                                    }
+                                }
+                                DocsNotification::CrateFinished { .. } | DocsNotification::Failed { .. } => {}
+                            }
                         }
-                        let mut projects: RwLockWriteGuard<'_, HashMap<PathBuf, Arc<ProjectContext>>> = cloned_projects.write().await;
-                        if let Some(project) = projects.get_mut(project) {
-                            project.is_indexing_docs.store(is_indexing, std::sync::atomic::Ordering::Relaxed);
+                        drop(projects);
+                        if let Err(e) = cloned_notifier.send(ContextNotification::Docs(notification)) {
+                            tracing::error!("Failed to send docs notification: {}", e);
                         }
                     }
-                    Ok(ref notification @ LspNotification::Indexing { ref project, is_indexing }) = lsp_receiver.recv_async() => {
+                    Ok(notification) = lsp_receiver.recv_async() => {
                         if let Err(e) = cloned_notifier.send(ContextNotification::Lsp(notification.clone())) {
                             tracing::error!("Failed to send LSP notification: {}", e);
                         }
-                        let mut projects: RwLockWriteGuard<'_, HashMap<PathBuf, Arc<ProjectContext>>> = cloned_projects.write().await;
-                        if let Some(project) = projects.get_mut(project) {
-                            project.is_indexing_lsp.store(is_indexing, std::sync::atomic::Ordering::Relaxed);
+                        match notification {
+                            // Notifications are about the root rust-analyzer was started for
+                            // and apply to all projects sharing it
+                            LspNotification::Indexing { ref project, is_indexing, percentage } => {
+                                for project in lsp_projects(&cloned_projects, project).await {
+                                    project.is_indexing_lsp.store(is_indexing, std::sync::atomic::Ordering::Relaxed);
+                                    project.lsp_percentage.store(percentage.unwrap_or_default(), std::sync::atomic::Ordering::Relaxed);
+                                }
+                            }
+                            LspNotification::Crashed { ref project, ref stderr } => {
+                                tracing::error!("rust-analyzer crashed for {:?}:\n{}", project, stderr);
+                                let projects = lsp_projects(&cloned_projects, project).await;
+                                for project in &projects {
+                                    project.is_indexing_lsp.store(false, std::sync::atomic::Ordering::Relaxed);
+                                    project.set_lsp_status(None);
+                                }
+                                if let Some(project) = projects.into_iter().next() {
+                                    // Restarting waits for the backoff, the other notifications must not
+                                    tokio::spawn(async move {
+                                        if let Err(e) = project.lsp.recover().await {
+                                            tracing::error!("Failed to restart rust-analyzer: {:?}", e);
+                                        }
+                                    });
+                                }
+                            }
+                            LspNotification::Status { ref project, ref status } => {
+                                for project in lsp_projects(&cloned_projects, project).await {
+                                    project.set_lsp_status(Some(status.clone()));
+                                }
+                            }
+                            LspNotification::ManifestChanged { ref project } => {
+                                let projects = lsp_projects(&cloned_projects, project).await;
+                                if !projects.is_empty() {
+                                    tokio::spawn(async move {
+                                        if let Err(e) = projects[0].lsp.reload_workspace().await {
+                                            tracing::error!("Failed to reload the workspace: {:?}", e);
+                                        }
+                                        for project in projects {
+                                            if project.project.docs_config().update_on_dependency_change
+                                                && let Err(e) = project.docs.update_index().await
+                                            {
+                                                tracing::error!("Failed to update the docs index: {:?}", e);
+                                            }
+                                        }
+                                    });
+                                }
+                            }
+                            LspNotification::Stderr { .. } => {}
                         }
                     }
                 }
@@ -147,21 +371,145 @@ impl Context {
             docs_sender,
             mcp_sender,
             notifier,
+            webhooks,
+            announce_mdns: Arc::new(AtomicBool::new(false)),
+            audit_log: Arc::new(AtomicBool::new(false)),
+            bind_addresses: Arc::default(),
+            port: Arc::default(),
+            allow_remote: Arc::default(),
+            overrides: Arc::default(),
+            api_key: Arc::default(),
+            in_flight: InFlightCalls::default(),
+            sessions: Sessions::default(),
+            metrics: Metrics::default(),
+            shutdown_grace_seconds: Arc::new(AtomicU64::new(DEFAULT_SHUTDOWN_GRACE_SECONDS)),
         }
     }
 
     pub fn address_information(&self) -> (String, u16) {
         match &self.transport {
             TransportType::Stdio => ("stdio".to_string(), 0),
-            TransportType::Sse { host, port } => (host.clone(), *port),
+            TransportType::Sse { .. } => {
+                let host = self.bind_addresses().into_iter().next().unwrap_or_default();
+                (advertised_host(&host), self.port())
+            }
+        }
+    }
+
+    pub fn set_server_overrides(&self, overrides: ServerOverrides) {
+        if let Ok(mut current) = self.overrides.write() {
+            *current = overrides;
+        }
+    }
+
+    fn overrides(&self) -> ServerOverrides {
+        self.overrides
+            .read()
+            .map(|overrides| overrides.clone())
+            .unwrap_or_default()
+    }
+
+    /// The port of the SSE server: from the command line, the environment, the
+    /// configuration file or the default, in this order
+    pub fn port(&self) -> u16 {
+        if let Some(port) = self.overrides().port {
+            return port;
+        }
+        match (self.port.load(Ordering::Relaxed), &self.transport) {
+            (0, TransportType::Sse { port, .. }) => *port,
+            (port, _) => port,
         }
     }
 
+    /// Whether the SSE server may listen on addresses other networks can reach
+    pub fn allow_remote(&self) -> bool {
+        self.overrides().allow_remote || self.allow_remote.load(Ordering::Relaxed)
+    }
+
+    /// The addresses the SSE server should listen on, before checking `allow_remote`
+    fn requested_bind_addresses(&self) -> Vec<String> {
+        let overridden = self.overrides().bind_addresses;
+        if !overridden.is_empty() {
+            return overridden;
+        }
+        let configured = self
+            .bind_addresses
+            .read()
+            .map(|addresses| addresses.clone())
+            .unwrap_or_default();
+        match (&self.transport, configured.is_empty()) {
+            (TransportType::Sse { host, .. }, true) => vec![host.clone()],
+            _ => configured,
+        }
+    }
+
+    /// The addresses the SSE server listens on, the default host if none are configured.
+    /// Without `allow_remote` only loopback addresses are used.
+    pub fn bind_addresses(&self) -> Vec<String> {
+        let allow_remote = self.allow_remote();
+        let addresses = self
+            .requested_bind_addresses()
+            .into_iter()
+            .filter(|address| allow_remote || is_loopback(address))
+            .collect::<Vec<_>>();
+        if addresses.is_empty() {
+            return vec![HOSTNAME.to_string()];
+        }
+        addresses
+    }
+
+    /// The configured addresses that aren't used because `allow_remote` isn't set
+    pub fn rejected_bind_addresses(&self) -> Vec<String> {
+        if self.allow_remote() {
+            return Vec::new();
+        }
+        self.requested_bind_addresses()
+            .into_iter()
+            .filter(|address| !is_loopback(address))
+            .collect()
+    }
+
+    /// The addresses the SSE server listens on that other machines can reach
+    pub fn remote_bind_addresses(&self) -> Vec<String> {
+        self.bind_addresses()
+            .into_iter()
+            .filter(|address| !is_loopback(address))
+            .collect()
+    }
+
+    /// The bearer token SSE clients have to send, if one is configured
+    pub fn api_key(&self) -> Option<String> {
+        self.api_key
+            .read()
+            .ok()
+            .and_then(|api_key| api_key.clone())
+            .filter(|api_key| !api_key.is_empty())
+    }
+
     pub fn mcp_configuration(&self) -> String {
         let (host, port) = self.address_information();
+        // IPv6 addresses have to be bracketed in URLs
+        let host = if host.contains(':') {
+            format!("[{host}]")
+        } else {
+            host
+        };
+        let api_key = self.api_key().unwrap_or_default();
+        let headers = if api_key.is_empty() {
+            String::new()
+        } else {
+            format!(
+                r#",
+            "headers": {{
+                "Authorization": "Bearer {api_key}"
+            }}"#
+            )
+        };
         CONFIG_TEMPLATE
             .replace("{{HOST}}", &host)
             .replace("{{PORT}}", &port.to_string())
+            .replace("{{API_KEY}}", &api_key)
+            .replace("{{HEADERS}}", &headers)
     }
 
     pub fn configuration_file(&self) -> String {
@@ -177,11 +525,65 @@ impl Context {
         &self.transport
     }
 
+    /// Whether the SSE endpoint should be advertised via mDNS
+    pub fn announce_mdns(&self) -> bool {
+        self.announce_mdns.load(Ordering::Relaxed)
+    }
+
+    /// Whether the tool calls are written to the audit log of their project
+    pub fn audit_log(&self) -> bool {
+        self.audit_log.load(Ordering::Relaxed)
+    }
+
+    pub fn webhooks(&self) -> Vec<WebhookConfig> {
+        self.webhooks
+            .read()
+            .map(|webhooks| webhooks.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn in_flight(&self) -> InFlightCalls {
+        self.in_flight.clone()
+    }
+
+    /// The MCP clients that connected to the SSE server
+    pub fn sessions(&self) -> Sessions {
+        self.sessions.clone()
+    }
+
+    /// The calls per project and tool since the start
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+
+    /// Send a notification about a tool call, tagged with the client of the call
     pub async fn send_mcp_notification(&self, notification: McpNotification) -> Result<()> {
-        self.mcp_sender.send(notification)?;
+        self.mcp_sender.send((notification, current_session()))?;
         Ok(())
     }
 
+    /// The lock file of the instance which serves this configuration
+    pub fn instance_lock_path(&self) -> PathBuf {
+        self.config_path().with_extension("lock")
+    }
+
+    pub fn crash_reports_path(&self) -> PathBuf {
+        self.config_path().with_extension("crashes")
+    }
+
+    /// The roots of all projects, unless the project list is currently locked
+    pub fn try_project_roots(&self) -> Option<Vec<PathBuf>> {
+        let projects = self.projects.try_read().ok()?;
+        Some(projects.keys().cloned().collect())
+    }
+
+    /// The API key in the configuration file, read without loading the projects
+    pub fn read_api_key(&self) -> Option<String> {
+        let toml_string = std::fs::read_to_string(self.config_path()).ok()?;
+        let config: SerConfig = toml::from_str(&toml_string).ok()?;
+        config.api_key.filter(|api_key| !api_key.is_empty())
+    }
+
     fn config_path(&self) -> PathBuf {
         let parsed = shellexpand::tilde(&self.configuration_file()).to_string();
         PathBuf::from(parsed)
@@ -195,10 +597,31 @@ impl Context {
             .map(|p| SerProject {
                 root: p.root().to_string_lossy().to_string(),
                 ignore_crates: p.ignore_crates().to_vec(),
+                no_std_target: p.no_std_target().map(|t| t.to_string()),
+                use_nextest: p.use_nextest(),
+                max_output_bytes: p.max_output_bytes,
+                maintenance: p.maintenance().clone(),
+                docs: p.docs_config().clone(),
+                rust_analyzer: p.rust_analyzer_config().clone(),
             })
             .collect();
         let config = SerConfig {
             projects: projects_to_save,
+            webhooks: self.webhooks(),
+            announce_mdns: self.announce_mdns(),
+            audit_log: self.audit_log(),
+            shutdown_grace_seconds: self.shutdown_grace_seconds.load(Ordering::Relaxed),
+            bind_addresses: self
+                .bind_addresses
+                .read()
+                .map(|addresses| addresses.clone())
+                .unwrap_or_default(),
+            api_key: self.api_key(),
+            port: match self.port.load(Ordering::Relaxed) {
+                0 => None,
+                port => Some(port),
+            },
+            allow_remote: self.allow_remote.load(Ordering::Relaxed),
         };
 
         let config_path = self.config_path();
@@ -212,7 +635,10 @@ impl Context {
         Ok(())
     }
 
-    pub async fn load_config(&self) -> Result<()> {
+    /// Load the server settings of the configuration file, e.g. the port and the
+    /// bind addresses. They are needed before the instance lock is taken, the
+    /// projects are loaded later by [`Self::load_projects`].
+    pub fn load_settings(&self) -> Result<()> {
         let config_path = self.config_path();
 
         if !config_path.exists() {
@@ -252,10 +678,54 @@ impl Context {
             }
         };
 
+        if let Ok(mut webhooks) = self.webhooks.write() {
+            *webhooks = loaded_config.webhooks;
+        }
+        self.announce_mdns
+            .store(loaded_config.announce_mdns, Ordering::Relaxed);
+        self.audit_log
+            .store(loaded_config.audit_log, Ordering::Relaxed);
+        self.shutdown_grace_seconds
+            .store(loaded_config.shutdown_grace_seconds, Ordering::Relaxed);
+        if let Ok(mut bind_addresses) = self.bind_addresses.write() {
+            // Brackets are only needed in URLs
+            *bind_addresses = loaded_config
+                .bind_addresses
+                .iter()
+                .map(|address| address.trim_matches(['[', ']']).to_string())
+                .collect();
+        }
+
+        self.port
+            .store(loaded_config.port.unwrap_or_default(), Ordering::Relaxed);
+        self.allow_remote
+            .store(loaded_config.allow_remote, Ordering::Relaxed);
+        if let Ok(mut api_key) = self.api_key.write() {
+            *api_key = loaded_config.api_key;
+        }
+        Ok(())
+    }
+
+    /// Load the projects of the configuration file and start their language servers
+    pub async fn load_projects(&self) -> Result<()> {
+        // Problems with the file were already reported by `load_settings`
+        let Some(loaded_config) = fs::read_to_string(self.config_path())
+            .ok()
+            .and_then(|toml_string| toml::from_str::<SerConfig>(&toml_string).ok())
+        else {
+            return Ok(());
+        };
+
         for project in loaded_config.projects {
             let project = Project {
                 root: PathBuf::from(&project.root),
                 ignore_crates: project.ignore_crates,
+                no_std_target: project.no_std_target,
+                use_nextest: project.use_nextest,
+                max_output_bytes: project.max_output_bytes,
+                maintenance: project.maintenance,
+                docs: project.docs,
+                rust_analyzer: project.rust_analyzer,
             };
             // Validate project root before adding
             if !project.root().exists() || !project.root().is_dir() {
@@ -267,7 +737,13 @@ impl Context {
             }
             // We need to canonicalize again as the stored path might be relative or different
             match Project::new(project.root()) {
-                Ok(new_project) => {
+                Ok(mut new_project) => {
+                    new_project.no_std_target = project.no_std_target.clone();
+                    new_project.use_nextest = project.use_nextest;
+                    new_project.max_output_bytes = project.max_output_bytes;
+                    new_project.maintenance = project.maintenance.clone();
+                    new_project.docs = project.docs.clone();
+                    new_project.rust_analyzer = project.rust_analyzer.clone();
                     if let Err(e) = self.add_project(new_project).await {
                         tracing::error!(
                             "Failed to add project {:?} from config: {}",
@@ -292,18 +768,46 @@ impl Context {
     /// Add a new project to the context
     pub async fn add_project(&self, project: Project) -> Result<()> {
         let root = project.root().clone();
-        let lsp = RustAnalyzerLsp::new(&project, self.lsp_sender.clone()).await?;
+        let cargo_remote = CargoRemote::new(project.clone());
+        let workspace_root = match cargo_remote.metadata(true).await {
+            Ok(metadata) => Some(PathBuf::from(metadata.workspace_root)),
+            Err(e) => {
+                tracing::warn!("Failed to find the workspace of {:?}: {:?}", root, e);
+                None
+            }
+        };
+        // Projects of the same cargo workspace share one rust-analyzer
+        let shared = match &workspace_root {
+            Some(workspace_root) => self
+                .projects
+                .read()
+                .await
+                .values()
+                .find(|other| other.workspace_root.as_ref() == Some(workspace_root))
+                .cloned(),
+            None => None,
+        };
+        let lsp = match shared {
+            Some(other) => other.lsp.share(&project).await?,
+            None => RustAnalyzerLsp::new(&project, self.lsp_sender.clone()).await?,
+        };
         let docs = Docs::new(project.clone(), self.docs_sender.clone())?;
         docs.update_index().await?;
-        let cargo_remote = CargoRemote::new(project.clone());
         let project_context = Arc::new(ProjectContext {
             project,
+            workspace_root,
             lsp,
             docs,
             cargo_remote,
             is_indexing_lsp: AtomicBool::new(true),
+            lsp_percentage: AtomicU32::new(0),
             is_indexing_docs: AtomicBool::new(true),
+            docs_progress: Default::default(),
+            lsp_status: Default::default(),
+            lsp_memory: Default::default(),
+            maintenance: Default::default(),
         });
+        spawn_scheduler(&project_context);
 
         let mut projects_map = self.projects.write().await;
         projects_map.insert(root.clone(), project_context);
@@ -330,7 +834,10 @@ impl Context {
             projects_map.remove(root)
         };
 
-        if project.is_some() {
+        if let Some(project) = &project {
+            if let Err(e) = project.lsp.shutdown().await {
+                tracing::error!("Failed to shutdown LSP for project {:?}: {}", root, e);
+            }
             if let Err(e) = self
                 .notifier
                 .send(ContextNotification::ProjectRemoved(root.clone()))
@@ -390,15 +897,74 @@ impl Context {
         let Some(project_context) = self.get_project(project).await else {
             return Err(anyhow::anyhow!("Project not found"));
         };
-        let oldval = project_context
-            .is_indexing_docs
-            .load(std::sync::atomic::Ordering::Relaxed);
+        // Clicking again while this runs only queues one more run
+        project_context.docs.update_index().await?.finished().await;
+        tracing::info!("Docs index updated for {:?}", project);
+        Ok(())
+    }
+
+    /// The disk usage of the docs cache of a project per crate
+    pub async fn docs_cache_stats(&self, project: &PathBuf) -> Result<CacheStats> {
+        let Some(project_context) = self.get_project(project).await else {
+            return Err(anyhow::anyhow!("Project not found"));
+        };
+        project_context.docs.cache_stats().await
+    }
+
+    /// Evict the docs of all crates of a project, they're generated again on demand
+    pub async fn clear_docs_cache(&self, project: &PathBuf) -> Result<()> {
+        let Some(project_context) = self.get_project(project).await else {
+            return Err(anyhow::anyhow!("Project not found"));
+        };
+        project_context.docs.clear_cache().await
+    }
+
+    /// Replace the rust-analyzer of a project with a fresh process
+    pub async fn restart_lsp(&self, project: &PathBuf) -> Result<()> {
+        let Some(project_context) = self.get_project(project).await else {
+            return Err(anyhow::anyhow!("Project not found"));
+        };
         project_context
-            .is_indexing_docs
-            .store(!oldval, std::sync::atomic::Ordering::Relaxed);
+            .is_indexing_lsp
+            .store(true, Ordering::Relaxed);
+        self.request_project_descriptions();
+        project_context.set_lsp_status(None);
+        project_context.lsp.restart().await?;
+        tracing::info!("rust-analyzer restarted for {:?}", project);
+        Ok(())
+    }
+
+    /// Ask rust-analyzer how much memory it uses and store the report for the project descriptions
+    pub async fn load_lsp_memory(&self, project: &PathBuf) -> Result<()> {
+        let Some(project_context) = self.get_project(project).await else {
+            return Err(anyhow::anyhow!("Project not found"));
+        };
+        let memory = project_context.lsp.memory_usage().await?;
+        if let Ok(mut current) = project_context.lsp_memory.lock() {
+            *current = Some(memory);
+        }
+        self.request_project_descriptions();
         Ok(())
     }
 
+    /// Stop accepting tool calls, give the running ones the configured grace
+    /// period to finish, abort the rest and shut down all language servers.
+    pub async fn drain_and_shutdown(&self) {
+        let grace = Duration::from_secs(self.shutdown_grace_seconds.load(Ordering::Relaxed));
+        let aborted = self.in_flight.drain(grace).await;
+        if aborted.is_empty() {
+            tracing::info!("All tool calls finished");
+        }
+        for call in aborted {
+            tracing::warn!(
+                "Aborted `{}` after running for {:.1}s",
+                call.tool,
+                call.running_for.as_secs_f64()
+            );
+        }
+        self.shutdown_all().await;
+    }
+
     pub async fn shutdown_all(&self) {
         let projects = self.projects.write().await;
         for p in projects.values() {
@@ -419,22 +985,118 @@ const CONFIG_TEMPLATE: &str = r#"
         "cursor_rust_tools": {
             "url": "http://{{HOST}}:{{PORT}}/sse",
             "env": {
-                "API_KEY": ""
-            }
+                "API_KEY": "{{API_KEY}}"
+            }{{HEADERS}}
         }
     }
 }
 "#;
 
+const DEFAULT_SHUTDOWN_GRACE_SECONDS: u64 = 10;
+
+fn default_shutdown_grace_seconds() -> u64 {
+    DEFAULT_SHUTDOWN_GRACE_SECONDS
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct SerConfig {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    announce_mdns: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    audit_log: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bind_addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_remote: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_key: Option<String>,
+    #[serde(default = "default_shutdown_grace_seconds")]
+    shutdown_grace_seconds: u64,
     projects: Vec<SerProject>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    webhooks: Vec<WebhookConfig>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct SerProject {
     root: String,
     ignore_crates: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_std_target: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    use_nextest: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_output_bytes: Option<usize>,
+    #[serde(default, skip_serializing_if = "MaintenanceConfig::is_empty")]
+    maintenance: MaintenanceConfig,
+    #[serde(default, skip_serializing_if = "DocsConfig::is_empty")]
+    docs: DocsConfig,
+    #[serde(default, skip_serializing_if = "RustAnalyzerConfig::is_empty")]
+    rust_analyzer: RustAnalyzerConfig,
+}
+
+/// The client of a notification for its description, e.g. ` (Cursor #2)`
+fn client_label(session: &Option<ClientSession>) -> String {
+    session
+        .as_ref()
+        .map(|session| format!(" ({session})"))
+        .unwrap_or_default()
+}
+
+/// The address clients connect to for the bind address. Clients can't connect to a
+/// wildcard address (`0.0.0.0`, `::`), it is replaced by the address of the interface
+/// other machines reach this one on, or the loopback address without a network.
+fn advertised_host(address: &str) -> String {
+    let Ok(ip) = address.parse::<IpAddr>() else {
+        return address.to_string();
+    };
+    if !ip.is_unspecified() {
+        return address.to_string();
+    }
+    // Connecting a UDP socket sends nothing, it only picks the interface of the route.
+    // The targets are documentation addresses.
+    let (local, remote) = match ip {
+        IpAddr::V4(_) => ("0.0.0.0:0", "192.0.2.1:9"),
+        IpAddr::V6(_) => ("[::]:0", "[2001:db8::1]:9"),
+    };
+    std::net::UdpSocket::bind(local)
+        .and_then(|socket| {
+            socket.connect(remote)?;
+            socket.local_addr()
+        })
+        .map(|address| address.ip())
+        .ok()
+        .filter(|ip| !ip.is_unspecified())
+        .unwrap_or(match ip {
+            IpAddr::V4(_) => IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
+            IpAddr::V6(_) => IpAddr::V6(std::net::Ipv6Addr::LOCALHOST),
+        })
+        .to_string()
+}
+
+/// Whether only this machine can connect to the address
+fn is_loopback(address: &str) -> bool {
+    address == "localhost"
+        || address
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// The projects using the rust-analyzer started for `root`
+async fn lsp_projects(
+    projects: &RwLock<HashMap<PathBuf, Arc<ProjectContext>>>,
+    root: &PathBuf,
+) -> Vec<Arc<ProjectContext>> {
+    projects
+        .read()
+        .await
+        .values()
+        .filter(|project| project.lsp.server_root() == root)
+        .cloned()
+        .collect()
 }
 
 async fn project_descriptions(
@@ -444,6 +1106,7 @@ async fn project_descriptions(
         .values()
         .map(|project| ProjectDescription {
             root: project.project.root().clone(),
+            lsp_root: project.lsp.server_root().clone(),
             name: project
                 .project
                 .root()
@@ -454,9 +1117,15 @@ async fn project_descriptions(
             is_indexing_lsp: project
                 .is_indexing_lsp
                 .load(std::sync::atomic::Ordering::Relaxed),
+            lsp_percentage: project
+                .lsp_percentage
+                .load(std::sync::atomic::Ordering::Relaxed),
             is_indexing_docs: project
                 .is_indexing_docs
                 .load(std::sync::atomic::Ordering::Relaxed),
+            docs_progress: project.docs_progress(),
+            lsp_status: project.lsp_status(),
+            lsp_memory: project.lsp_memory(),
         })
         .collect()
 }
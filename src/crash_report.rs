@@ -0,0 +1,120 @@
+use std::backtrace::Backtrace;
+use std::fmt::Write;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::context::Context;
+
+/// Write a crash report into `directory` whenever a panic ends the process, i.e. on
+/// the main thread. The runtime catches the panics of tokio tasks (a tool call that
+/// panics is reported as aborted) and other threads only end themselves. Reports are
+/// only stored locally, they are never uploaded anywhere.
+pub fn install(directory: PathBuf, context: Context) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() == Some("main") {
+            match write_report(&directory, &context, info) {
+                Ok(path) => tracing::error!("Wrote crash report to {:?}", path),
+                Err(e) => tracing::error!("Failed to write crash report: {}", e),
+            }
+        }
+        default_hook(info);
+    }));
+}
+
+/// The newest report that the user hasn't been told about yet
+pub fn unseen_report(directory: &Path) -> Option<PathBuf> {
+    unseen_reports(directory).into_iter().max()
+}
+
+/// Don't prompt for the current reports again on the next start
+pub fn mark_seen(directory: &Path) {
+    for report in unseen_reports(directory) {
+        let seen = report.with_extension("seen.txt");
+        if let Err(e) = std::fs::rename(&report, &seen) {
+            tracing::error!("Failed to mark crash report {:?} as seen: {}", report, e);
+        }
+    }
+}
+
+fn unseen_reports(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with("crash-")
+                        && name.ends_with(".txt")
+                        && !name.ends_with(".seen.txt")
+                })
+        })
+        .collect()
+}
+
+fn write_report(
+    directory: &Path,
+    context: &Context,
+    info: &PanicHookInfo<'_>,
+) -> std::io::Result<PathBuf> {
+    let now = Local::now();
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_else(|| "(unknown)".to_string());
+    let thread = std::thread::current();
+
+    // Formatting into a String can't fail
+    let mut report = String::new();
+    let _ = writeln!(report, "cursor-rust-tools {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "Time: {}", now.to_rfc3339());
+    let _ = writeln!(
+        report,
+        "OS: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "Thread: {}", thread.name().unwrap_or("(unnamed)"));
+    let _ = writeln!(report, "Location: {location}");
+    let _ = writeln!(report, "Message: {message}");
+
+    let _ = writeln!(report, "\nRunning tool calls:");
+    let calls = context.in_flight().describe();
+    if calls.is_empty() {
+        let _ = writeln!(report, "- none");
+    }
+    for call in calls {
+        let _ = writeln!(report, "- {call}");
+    }
+
+    let _ = writeln!(report, "\nProjects:");
+    match context.try_project_roots() {
+        Some(roots) => {
+            for root in roots {
+                let _ = writeln!(report, "- {}", root.display());
+            }
+        }
+        None => {
+            let _ = writeln!(report, "- (the project list is locked)");
+        }
+    }
+
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    std::fs::create_dir_all(directory)?;
+    let path = directory.join(format!("crash-{}.txt", now.format("%Y%m%d-%H%M%S%.3f")));
+    std::fs::write(&path, report)?;
+    Ok(path)
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+
+const API_URL: &str = "https://crates.io/api/v1/crates";
+
+/// crates.io requires a user agent which identifies the client
+const USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/terhechte/cursor-rust-tools)"
+);
+
+/// The crawler policy of crates.io allows one request per second
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// When the next request may be sent, shared by all clients of the process
+static NEXT_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    versions: Vec<CrateVersion>,
+}
+
+#[derive(Deserialize)]
+struct CrateVersion {
+    num: String,
+    yanked: bool,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+}
+
+/// A published version of a crate with its features
+#[derive(Clone, Debug)]
+pub struct PublishedVersion {
+    pub version: Version,
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CratesIo {
+    client: reqwest::Client,
+}
+
+impl CratesIo {
+    pub fn new() -> Result<Self> {
+        let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+        Ok(Self { client })
+    }
+
+    /// All published versions of a crate which are not yanked
+    pub async fn versions(&self, name: &str) -> Result<Vec<Version>> {
+        Ok(self
+            .published_versions(name)
+            .await?
+            .into_iter()
+            .map(|published| published.version)
+            .collect())
+    }
+
+    /// Like `versions`, but including the features of every version
+    pub async fn published_versions(&self, name: &str) -> Result<Vec<PublishedVersion>> {
+        wait_for_turn().await;
+        let response = self
+            .client
+            .get(format!("{API_URL}/{name}"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<CrateResponse>()
+            .await?;
+        Ok(response
+            .versions
+            .into_iter()
+            .filter(|version| !version.yanked)
+            .filter_map(|version| {
+                Some(PublishedVersion {
+                    version: Version::parse(&version.num).ok()?,
+                    features: version.features,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Wait until the request may be sent, each request takes the next free slot
+async fn wait_for_turn() {
+    let wait = {
+        let mut next = NEXT_REQUEST.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + REQUEST_INTERVAL);
+        slot - now
+    };
+    tokio::time::sleep(wait).await;
+}
+
+/// The newest stable version, optionally only considering versions matching `req`
+pub fn latest<'a>(versions: &'a [Version], req: Option<&VersionReq>) -> Option<&'a Version> {
+    versions
+        .iter()
+        .filter(|version| version.pre.is_empty())
+        .filter(|version| req.is_none_or(|req| req.matches(version)))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest() {
+        let versions = ["0.9.1", "1.0.0", "1.2.3", "1.10.0", "2.0.0", "2.1.0-beta.1"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(latest(&versions, None).unwrap().to_string(), "2.0.0");
+        let req = VersionReq::parse("1.2").unwrap();
+        assert_eq!(latest(&versions, Some(&req)).unwrap().to_string(), "1.10.0");
+        let req = VersionReq::parse("=0.9.1").unwrap();
+        assert_eq!(latest(&versions, Some(&req)).unwrap().to_string(), "0.9.1");
+        let req = VersionReq::parse("3").unwrap();
+        assert!(latest(&versions, Some(&req)).is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+
+use super::store::DocsStore;
+use super::utils::dir_size;
+use crate::project::Project;
+
+/// The disk usage of the docs of a crate
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrateUsage {
+    pub name: String,
+    pub version: String,
+    /// Seconds since the epoch when the docs were last looked up or indexed
+    pub last_used: i64,
+    /// The docs were dropped to keep the cache small and are indexed again on the next lookup
+    pub evicted: bool,
+    /// The indexed pages and examples
+    pub index_bytes: u64,
+    /// The docs `cargo doc` generated
+    pub file_bytes: u64,
+}
+
+impl CrateUsage {
+    pub fn bytes(&self) -> u64 {
+        self.index_bytes + self.file_bytes
+    }
+}
+
+/// The disk usage of the docs cache of a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    /// Sorted by size, the largest first
+    pub crates: Vec<CrateUsage>,
+    /// Everything in the cache dir, including the artifacts `cargo doc` builds
+    pub total_bytes: u64,
+}
+
+/// The size of the docs of every crate and of the whole cache dir
+pub fn cache_stats(project: &Project, store: &DocsStore) -> Result<CacheStats> {
+    let mut crates = crate_usage(project, store)?;
+    crates.sort_by(|a, b| b.bytes().cmp(&a.bytes()).then(a.name.cmp(&b.name)));
+    Ok(CacheStats {
+        crates,
+        total_bytes: dir_size(&project.cache_dir()),
+    })
+}
+
+/// Evict the least recently used crates until the docs of the others fit into the
+/// `max_cache_mb` of the project
+pub fn collect_garbage(project: &Project, store: &mut DocsStore) -> Result<()> {
+    let Some(max_megabytes) = project.docs_config().max_cache_mb else {
+        return Ok(());
+    };
+    let max_bytes = max_megabytes * 1024 * 1024;
+    let mut crates = crate_usage(project, store)?;
+    crates.retain(|usage| !usage.evicted);
+    let mut bytes: u64 = crates.iter().map(CrateUsage::bytes).sum();
+    if bytes <= max_bytes {
+        return Ok(());
+    }
+
+    crates.sort_by_key(|usage| usage.last_used);
+    for usage in crates {
+        if bytes <= max_bytes {
+            break;
+        }
+        tracing::info!(
+            "Evicting the docs of {} {} to stay within {max_megabytes} MB",
+            usage.name,
+            usage.version
+        );
+        evict_crate(project, store, &usage.name)?;
+        bytes = bytes.saturating_sub(usage.bytes());
+    }
+    Ok(())
+}
+
+/// Drop the indexed and the generated docs of a crate. They're generated again when
+/// the crate is looked up.
+pub fn evict_crate(project: &Project, store: &mut DocsStore, name: &str) -> Result<()> {
+    store.evict(name)?;
+    for path in generated_files(project, name) {
+        let result = match path.is_dir() {
+            true => fs::remove_dir_all(&path),
+            false => fs::remove_file(&path),
+        };
+        if let Err(e) = result
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+fn crate_usage(project: &Project, store: &DocsStore) -> Result<Vec<CrateUsage>> {
+    let mut crates = store.crate_usage()?;
+    for usage in &mut crates {
+        usage.file_bytes = generated_files(project, &usage.name)
+            .iter()
+            .map(|path| dir_size(path))
+            .sum();
+    }
+    Ok(crates)
+}
+
+/// The HTML pages, the rustdoc JSON and the rendered sources of a crate
+fn generated_files(project: &Project, name: &str) -> [PathBuf; 3] {
+    let docs_dir = project.docs_dir();
+    [
+        docs_dir.join(name),
+        docs_dir.join(format!("{name}.json")),
+        docs_dir.join("src").join(name),
+    ]
+}
@@ -1,6 +1,117 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// The title and description of a module or crate page
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ModuleDocs {
+    /// e.g. `Crate tokio` or `Module sync`
+    pub title: String,
+    pub overview: String,
+}
+
+/// Extract the module docs from the markdown of an `index.html` page.
+/// The overview is everything between the title and the list of items.
+pub fn module_docs(markdown: &str) -> Option<ModuleDocs> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let title_index = lines
+        .iter()
+        .position(|line| line.ends_with("Copy item path"))?;
+    let title = lines[title_index]
+        .trim_end_matches("Copy item path")
+        .trim()
+        .to_string();
+    let overview = lines[title_index + 1..]
+        .iter()
+        .skip_while(|line| {
+            line.starts_with("==") || line.contains("Source") || line.trim().is_empty()
+        })
+        .skip_while(|line| line.trim() == "Expand description")
+        // Item sections look like `Structs§` followed by a `---` underline
+        .take_while(|line| !is_item_section(line))
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+    Some(ModuleDocs { title, overview })
+}
+
+fn is_item_section(line: &str) -> bool {
+    const SECTIONS: [&str; 14] = [
+        "Re-exports",
+        "Modules",
+        "Macros",
+        "Structs",
+        "Enums",
+        "Unions",
+        "Constants",
+        "Statics",
+        "Traits",
+        "Functions",
+        "Type Aliases",
+        "Attribute Macros",
+        "Derive Macros",
+        "Primitive Types",
+    ];
+    line.strip_suffix('§')
+        .is_some_and(|section| SECTIONS.contains(&section))
+}
+
+/// The Rust code examples in the markdown of a docs page. Skips the declaration of
+/// the item, which directly follows the title (JSON) or the `Source` link (HTML),
+/// and code blocks in other languages. Lines rustdoc hides (`# ...`) are removed.
+pub fn code_examples(markdown: &str) -> Vec<String> {
+    let mut examples = Vec::new();
+    let mut previous = "";
+    let mut is_first_block = true;
+    let mut lines = markdown.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        let Some(info) = trimmed.strip_prefix("```") else {
+            if !trimmed.is_empty() {
+                previous = trimmed;
+            }
+            continue;
+        };
+        // Docs can have headings like `# Examples` as well, the title comes first
+        let is_declaration = std::mem::take(&mut is_first_block)
+            && (previous == "Source" || (!previous.is_empty() && markdown.starts_with(previous)));
+        let mut code = Vec::new();
+        for line in lines.by_ref() {
+            if line.trim() == "```" {
+                break;
+            }
+            match line.trim_start().strip_prefix('#') {
+                Some(rest) if rest.is_empty() || rest.starts_with(' ') => {}
+                // `##` escapes a line that starts with `#`
+                Some(rest) if rest.starts_with('#') => code.push(line.replacen("##", "#", 1)),
+                _ => code.push(line.to_string()),
+            }
+        }
+        if !is_declaration && is_rust_block(info) && !code.is_empty() {
+            examples.push(code.join("\n"));
+        }
+    }
+    examples
+}
+
+/// Rust code blocks have no info string, `rust` or only rustdoc attributes like `no_run`
+fn is_rust_block(info: &str) -> bool {
+    const ATTRIBUTES: [&str; 6] = [
+        "rust",
+        "no_run",
+        "ignore",
+        "should_panic",
+        "compile_fail",
+        "test_harness",
+    ];
+    info.split([',', ' '])
+        .map(str::trim)
+        .filter(|attribute| !attribute.is_empty())
+        .all(|attribute| ATTRIBUTES.contains(&attribute) || attribute.starts_with("edition"))
+}
+
 pub fn extract_md(html: &str) -> String {
     // Remove head section before processing
     let re = regex::Regex::new(r"<head>.*?</head>").unwrap();
@@ -101,3 +212,38 @@ fn extract_lines_after_package(input: &str) -> String {
     }
     resulting_lines.join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_docs() {
+        let markdown = "Skip to main content\nCrate dd\n----------\n### Crate Items ###\n* Modules\nCrate dd Copy item path\n==========\nSource\nExpand description\n§DD crate\n----------\nThis crate does things.\n\nModules§\n----------\nsyncThe sync module";
+        assert_eq!(
+            module_docs(markdown),
+            Some(ModuleDocs {
+                title: "Crate dd".to_string(),
+                overview: "§DD crate\n----------\nThis crate does things.".to_string(),
+            })
+        );
+        assert_eq!(module_docs("No title"), None);
+    }
+
+    #[test]
+    fn test_code_examples() {
+        let markdown = "# Function unbounded\n\n```rust\npub fn unbounded<T>()\n```\n\nCreate a channel.\n\n# Examples\n\n```\n# use flume;\nlet (tx, rx) = flume::unbounded();\n#[derive(Debug)]\n## struct Hidden;\n```\n\n```toml\nflume = \"0.11\"\n```\n\n```no_run\nrx.recv();\n```";
+        assert_eq!(
+            code_examples(markdown),
+            [
+                "let (tx, rx) = flume::unbounded();\n#[derive(Debug)]\n# struct Hidden;",
+                "rx.recv();"
+            ]
+        );
+        let html_markdown = "Function unbounded Copy item path\n==========\nSource\n```\npub fn unbounded<T>()\n```\nExpand description\n§Examples\n----------\n```\nlet (tx, rx) = flume::unbounded();\n```";
+        assert_eq!(
+            code_examples(html_markdown),
+            ["let (tx, rx) = flume::unbounded();"]
+        );
+    }
+}
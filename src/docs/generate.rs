@@ -1,17 +1,194 @@
+use crate::cargo_remote::Metadata;
 use crate::project::Project;
 use anyhow::Result;
-use std::process::Command;
+use flume::Sender;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
 
-pub fn generate_docs(project: &Project) -> Result<()> {
+use super::DocsNotification;
+use super::store::DocsStore;
+use super::utils::get_cargo_dependencies;
+
+/// The number of lines of `cargo doc` output that are kept for the error of a failed run
+const MAX_ERROR_LINES: usize = 30;
+
+/// Generate the docs of the project and its dependencies. Uses rustdoc's JSON
+/// output if a nightly toolchain is installed and HTML otherwise.
+/// Once the docs cache exists, only the dependencies whose version changed are documented.
+pub fn generate_docs(project: &Project, notifier: &Sender<DocsNotification>) -> Result<()> {
+    let packages = match changed_packages(project) {
+        Some(packages) if packages.is_empty() => {
+            tracing::info!("The docs of all dependencies are up to date");
+            return Ok(());
+        }
+        Some(packages) => {
+            tracing::info!("Documenting changed dependencies: {:?}", packages);
+            packages
+        }
+        None => Vec::new(),
+    };
+    let total = match packages.len() {
+        0 => package_count(project).unwrap_or(0),
+        count => count,
+    };
+
+    if has_nightly(project) {
+        match run_cargo_doc(project, notifier, &packages, total, true) {
+            Ok(()) => return Ok(()),
+            Err(e) => tracing::warn!("Failed to generate JSON docs, using HTML: {:?}", e),
+        }
+    }
+    run_cargo_doc(project, notifier, &packages, total, false)
+}
+
+/// The package specs (`name@version`) of the dependencies that are missing from
+/// the docs cache or have a different version there. `None` if everything has to
+/// be documented because there is no usable cache.
+fn changed_packages(project: &Project) -> Option<Vec<String>> {
+    let store = DocsStore::open(project).ok()?;
+    let crate_versions = store.crate_versions().ok()?;
+    // The shared cache is usable even if the project has never been indexed
+    let is_shared = project.shared_cache_dir().is_some();
+    // With `-p` the features would apply to the dependency instead of the project
+    let feature_args = project.docs_config().cargo_args();
+    if (!is_shared && (store.is_outdated().unwrap_or(true) || crate_versions.is_empty()))
+        || !feature_args.is_empty()
+        || store.feature_args().ok()? != feature_args
+    {
+        return None;
+    }
+    let dependencies = get_cargo_dependencies(project).ok()?;
+    let packages = dependencies
+        .into_iter()
+        .filter(|dependency| {
+            let library_name = dependency.name.replace('-', "_");
+            !project.ignore_crates().contains(&library_name)
+                && crate_versions.get(&library_name) != Some(&dependency.version)
+                && !store
+                    .has_pages(&library_name, &dependency.version)
+                    .unwrap_or(false)
+        })
+        .map(
+            |dependency| match semver::Version::parse(&dependency.version) {
+                Ok(version) => format!("{}@{version}", dependency.name),
+                // Git and path dependencies
+                Err(_) => dependency.name,
+            },
+        )
+        .collect();
+    Some(packages)
+}
+
+/// Run `cargo doc`, for all crates if `packages` is empty and only for those otherwise
+fn run_cargo_doc(
+    project: &Project,
+    notifier: &Sender<DocsNotification>,
+    packages: &[String],
+    total: usize,
+    json: bool,
+) -> Result<()> {
     // Run cargo doc with custom output directory
-    let output = Command::new("cargo")
+    let mut command = Command::new("cargo");
+    command.current_dir(project.root());
+    if json {
+        command
+            .arg("+nightly")
+            .env("RUSTDOCFLAGS", "-Z unstable-options --output-format json");
+    }
+    let mut child = command
+        .arg("doc")
+        .arg("--target-dir")
+        .arg(project.cache_dir())
+        .args(packages.iter().flat_map(|package| ["-p", package]))
+        .args(if packages.is_empty() {
+            project.docs_config().cargo_args()
+        } else {
+            Vec::new()
+        })
+        // Only direct dependencies are indexed, their own dependencies don't need docs
+        .args(if packages.is_empty() {
+            None
+        } else {
+            Some("--no-deps")
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Cargo reports `Documenting <name> v<version>` for every crate it documents.
+    // Crates are documented in parallel, so only the start of each one is known.
+    let mut index = 0;
+    let mut output = VecDeque::new();
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines() {
+            let line = line?;
+            let Some(name) = documented_crate(&line) else {
+                // Kept to explain a failure, the errors are at the end
+                if output.len() == MAX_ERROR_LINES {
+                    output.pop_front();
+                }
+                output.push_back(line);
+                continue;
+            };
+            index += 1;
+            if let Err(e) = notifier.send(DocsNotification::CrateStarted {
+                project: project.root().to_path_buf(),
+                name: name.to_string(),
+                index,
+                total: total.max(index),
+            }) {
+                tracing::error!("Failed to send docs progress notification: {:?}", e);
+            }
+        }
+    }
+
+    if !child.wait()?.success() {
+        let output = Vec::from(output).join("\n");
+        return Err(anyhow::anyhow!("`cargo doc` failed:\n{output}"));
+    }
+
+    Ok(())
+}
+
+fn has_nightly(project: &Project) -> bool {
+    Command::new("cargo")
         .current_dir(project.root())
-        .args(["doc", "--target-dir", project.cache_folder()])
-        .output()?;
+        .args(["+nightly", "--version"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
 
+/// The number of packages in the dependency graph, an upper bound for the
+/// crates `cargo doc` documents
+fn package_count(project: &Project) -> Option<usize> {
+    let output = Command::new("cargo")
+        .current_dir(project.root())
+        .args(["metadata", "--format-version", "1", "--offline"])
+        .output()
+        .ok()?;
     if !output.status.success() {
-        return Err(anyhow::anyhow!("Failed to generate documentation"));
+        return None;
     }
+    let metadata: Metadata = serde_json::from_slice(&output.stdout).ok()?;
+    Some(metadata.packages.len())
+}
 
-    Ok(())
+fn documented_crate(line: &str) -> Option<&str> {
+    let rest = line.trim_start().strip_prefix("Documenting ")?;
+    rest.split_whitespace().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_documented_crate() {
+        assert_eq!(
+            documented_crate(" Documenting serde_json v1.0.140"),
+            Some("serde_json")
+        );
+        assert_eq!(documented_crate("    Checking memchr v2.7.4"), None);
+    }
 }
@@ -1,67 +1,240 @@
-use super::{utils::get_cargo_dependencies, walk::DocsCache};
+use super::{
+    store::DocsStore,
+    utils::{CargoDependency, get_cargo_dependencies, parse_rust_symbol},
+};
 use anyhow::Result;
-use std::fs;
+use std::collections::BTreeMap;
 
 #[derive(Debug)]
 pub struct DocsIndex {
-    dependencies: Vec<(String, String)>,
-    cache: DocsCache,
+    dependencies: Vec<CargoDependency>,
+    store: DocsStore,
 }
 
 impl DocsIndex {
     pub fn new(repository: &crate::project::Project) -> Result<Self> {
         let dependencies = get_cargo_dependencies(repository)?;
-
-        if !repository.cache_dir().exists() {
-            fs::create_dir_all(repository.cache_dir())?;
-        }
-
-        // Read cache file
-        let cache_path = repository.cache_dir().join("docs_cache.json");
-        if !cache_path.exists() {
-            let cache = DocsCache::default();
-            let cache_content = serde_json::to_string(&cache)?;
-            fs::write(cache_path.clone(), cache_content)?;
-        }
-        let cache_content = fs::read_to_string(cache_path)?;
-        let cache: DocsCache = serde_json::from_str(&cache_content)?;
+        let store = DocsStore::open(repository)?;
 
         Ok(DocsIndex {
             dependencies,
-            cache,
+            store,
         })
     }
 
-    pub fn dependencies(&self) -> &[(String, String)] {
+    /// The hash of `Cargo.lock` when the docs were indexed
+    pub fn lockfile_hash(&self) -> Result<String> {
+        self.store.hash()
+    }
+
+    pub fn dependencies(&self) -> &[CargoDependency] {
         &self.dependencies
     }
 
-    pub fn symbols(&self, dependency: &str) -> Option<Vec<String>> {
-        self.cache
-            .deps
-            .get(dependency)
-            .map(|symbols| symbols.keys().cloned().collect())
+    pub fn store(&self) -> &DocsStore {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut DocsStore {
+        &mut self.store
+    }
+
+    /// The name under which the docs of a dependency are stored, which marks them as used
+    fn crate_key(&self, dependency: &str) -> Result<Option<String>> {
+        let Some(key) = self.store.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        self.store.touch(&key)?;
+        Ok(Some(key))
+    }
+
+    /// The name of the dependency if its docs were evicted
+    pub fn evicted_crate(&self, dependency: &str) -> Result<Option<String>> {
+        self.store.evicted_crate(dependency)
+    }
+
+    /// Index an evicted crate again on the next run. `false` if that was requested already.
+    pub fn restore(&self, name: &str) -> Result<bool> {
+        self.store.restore(name)
     }
 
-    pub fn docs(&self, dependency: &str, symbols: &[String]) -> Option<Vec<(String, String)>> {
-        let dep_docs = self.cache.deps.get(dependency)?;
-        Some(
-            symbols
-                .iter()
-                .filter_map(|symbol| {
-                    let doc = dep_docs.get(symbol)?;
-                    Some((symbol.clone(), doc.clone()))
-                })
-                .collect(),
-        )
+    pub fn symbols(&self, dependency: &str) -> Result<Option<Vec<String>>> {
+        let Some(key) = self.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        Ok(Some(self.store.page_keys(&key)?))
     }
 
-    pub fn markdown_docs(&self, dependency: &str) -> Option<String> {
+    /// The documented items of a dependency grouped by kind (`struct`, `fn`, ...),
+    /// optionally only those whose path contains `filter` (case insensitive)
+    pub fn symbols_by_kind(
+        &self,
+        dependency: &str,
+        filter: Option<&str>,
+    ) -> Result<Option<BTreeMap<String, Vec<String>>>> {
+        let Some(keys) = self.symbols(dependency)? else {
+            return Ok(None);
+        };
+        let filter = filter.map(|filter| filter.to_lowercase());
+        let mut symbols: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for key in &keys {
+            let (module, file) = key.rsplit_once('/').unwrap_or(("", key));
+            // Top level items are stored as `struct Mutex`, nested ones as `sync/struct.Mutex.html`
+            let symbol = match file.split_once(' ') {
+                Some(_) => file.to_string(),
+                None => match parse_rust_symbol(file) {
+                    Some(symbol) => symbol.to_string(),
+                    None => continue,
+                },
+            };
+            let Some((kind, name)) = symbol.split_once(' ') else {
+                continue;
+            };
+            let path = if module.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}::{name}", module.replace('/', "::"))
+            };
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !path.to_lowercase().contains(filter))
+            {
+                continue;
+            }
+            symbols.entry(kind.to_string()).or_default().push(path);
+        }
+        for paths in symbols.values_mut() {
+            paths.sort();
+        }
+        Ok(Some(symbols))
+    }
+
+    /// The page key of `symbol`, which is a key or the path of an item. Paths can
+    /// start with the crate name and be the path a re-exported item is defined at,
+    /// e.g. `tokio::sync::mpsc::bounded::Sender` for `sync/mpsc/struct.Sender.html`.
+    pub fn resolve(&self, dependency: &str, symbol: &str) -> Result<Option<String>> {
+        let Some(crate_key) = self.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        if self.store.page(&crate_key, symbol)?.is_some() {
+            return Ok(Some(symbol.to_string()));
+        }
+        let library_name = dependency.replace('-', "_");
+        let path = [dependency, &library_name, &crate_key, "crate"]
+            .iter()
+            .find_map(|prefix| symbol.strip_prefix(prefix)?.strip_prefix("::"))
+            .unwrap_or(symbol);
+        self.store.resolve_path(&crate_key, path)
+    }
+
+    pub fn docs(
+        &self,
+        dependency: &str,
+        symbols: &[String],
+    ) -> Result<Option<Vec<(String, String)>>> {
+        let Some(key) = self.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        let mut docs = Vec::new();
+        for symbol in symbols {
+            if let Some(doc) = self.store.page(&key, symbol)? {
+                docs.push((symbol.clone(), doc));
+            }
+        }
+        Ok(Some(docs))
+    }
+
+    /// All public paths under which an item named `name` has documentation in the dependency.
+    /// Re-exported items are documented at every public location they're reachable from.
+    pub fn item_paths(&self, dependency: &str, name: &str) -> Result<Option<Vec<String>>> {
+        let Some(crate_key) = self.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        let crate_name = dependency.replace('-', "_");
+        let mut paths = Vec::new();
+        self.store.for_each_page(&crate_key, |key, content| {
+            if let Some((module, item)) = key_to_module_and_item(key)
+                && item == name
+            {
+                let path = if module.is_empty() {
+                    format!("{crate_name}::{item}")
+                } else {
+                    format!("{crate_name}::{}::{item}", module.replace('/', "::"))
+                };
+                paths.push(path);
+            }
+            // Module pages list re-exports as `pub use path::to::Item;`
+            for line in content.lines() {
+                let line = line.trim();
+                let Some(reexport) = line.strip_prefix("pub use ") else {
+                    continue;
+                };
+                let reexport = reexport.trim_end_matches(';');
+                let exported_name = reexport
+                    .rsplit_once(" as ")
+                    .map(|(_, alias)| alias)
+                    .unwrap_or_else(|| reexport.rsplit("::").next().unwrap_or(reexport));
+                if exported_name != name {
+                    continue;
+                }
+                let module = key
+                    .rsplit_once('/')
+                    .map(|(module, _)| format!("{crate_name}::{}", module.replace('/', "::")))
+                    .unwrap_or_else(|| crate_name.clone());
+                paths.push(format!("{module}::{name}"));
+            }
+        })?;
+        paths.sort_by_key(|path| (path.matches("::").count(), path.clone()));
+        paths.dedup();
+        Ok(Some(paths))
+    }
+
+    /// The crate documentation followed by a table of contents of its modules
+    pub fn crate_overview(&self, dependency: &str) -> Result<Option<String>> {
+        let Some(key) = self.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        let modules = self.store.modules(&key)?;
+        let Some(root) = modules.get("") else {
+            return Ok(None);
+        };
+        let mut output = format!("# {}\n{}\n", root.title, root.overview);
+        if modules.len() > 1 {
+            output.push_str("\n## Modules\n");
+        }
+        for (path, module) in modules.iter().filter(|(path, _)| !path.is_empty()) {
+            let summary = module.overview.lines().next().unwrap_or_default();
+            output.push_str(&format!("- `{path}`: {summary}\n"));
+        }
+        Ok(Some(output))
+    }
+
+    /// The code examples of a dependency, or of its items that match `symbol`
+    /// (`Sender`, `sync::Mutex`, `tokio::sync::Mutex` or a page like `struct Sender`)
+    pub fn examples(
+        &self,
+        dependency: &str,
+        symbol: Option<&str>,
+    ) -> Result<Option<Vec<(String, String)>>> {
+        let Some(key) = self.crate_key(dependency)? else {
+            return Ok(None);
+        };
+        let crate_name = dependency.replace('-', "_");
+        let mut examples = self.store.examples(&key)?;
+        if let Some(symbol) = symbol {
+            examples.retain(|(page, _)| matches_symbol(&crate_name, page, symbol));
+        }
+        Ok(Some(examples))
+    }
+
+    pub fn markdown_docs(&self, dependency: &str) -> Result<Option<String>> {
         let mut output = String::new();
 
-        let symbols = self.symbols(dependency)?;
+        let Some(symbols) = self.symbols(dependency)? else {
+            return Ok(None);
+        };
         for symbol in symbols {
-            if let Some(docs) = self.docs(dependency, &[symbol.clone()]) {
+            if let Some(docs) = self.docs(dependency, &[symbol.clone()])? {
                 output.push_str(&symbol);
                 output.push('\n');
                 for (doc, content) in docs {
@@ -72,6 +245,91 @@ impl DocsIndex {
                 }
             }
         }
-        Some(output)
+        Ok(Some(output))
+    }
+}
+
+/// Whether the page `key` documents `symbol`, which is a page key or an item path
+fn matches_symbol(crate_name: &str, key: &str, symbol: &str) -> bool {
+    if key == symbol {
+        return true;
+    }
+    let Some((module, item)) = key_to_module_and_item(key) else {
+        return false;
+    };
+    let (path, name) = symbol.rsplit_once("::").unwrap_or(("", symbol));
+    let module_path = match module.is_empty() {
+        true => crate_name.to_string(),
+        false => format!("{crate_name}::{}", module.replace('/', "::")),
+    };
+    item == name
+        && (path.is_empty() || module_path == path || module_path.ends_with(&format!("::{path}")))
+}
+
+/// The path of the item or module a page documents without the crate name
+/// (`sync/struct.Mutex.html` → `sync::Mutex`, `sync/index.html` → `sync`)
+pub fn page_path(key: &str) -> Option<String> {
+    if let Some(module) = key.strip_suffix("index.html") {
+        let module = module.trim_end_matches('/');
+        return (!module.is_empty()).then(|| module.replace('/', "::"));
+    }
+    let (module, item) = key_to_module_and_item(key)?;
+    match module.is_empty() {
+        true => Some(item),
+        false => Some(format!("{}::{item}", module.replace('/', "::"))),
+    }
+}
+
+/// Splits a cache key like `sync/struct.Mutex.html` or `struct Mutex` into module and item name
+fn key_to_module_and_item(key: &str) -> Option<(String, String)> {
+    let (module, file) = key.rsplit_once('/').unwrap_or(("", key));
+    let item = match file.split_once(' ') {
+        // Top level items are stored as `struct Mutex` / `macro join!`
+        Some((_, name)) => name.trim_end_matches('!'),
+        None => {
+            let file = file.strip_suffix(".html")?;
+            let (_, name) = file.split_once('.')?;
+            name.trim_end_matches('!')
+        }
+    };
+    Some((module.to_string(), item.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_path() {
+        assert_eq!(
+            page_path("sync/struct.Mutex.html").as_deref(),
+            Some("sync::Mutex")
+        );
+        assert_eq!(page_path("struct Sender").as_deref(), Some("Sender"));
+        assert_eq!(page_path("sync/index.html").as_deref(), Some("sync"));
+        assert_eq!(page_path("index.html"), None);
+    }
+
+    #[test]
+    fn test_matches_symbol() {
+        assert!(matches_symbol("tokio", "sync/struct.Mutex.html", "Mutex"));
+        assert!(matches_symbol(
+            "tokio",
+            "sync/struct.Mutex.html",
+            "sync::Mutex"
+        ));
+        assert!(matches_symbol(
+            "tokio",
+            "sync/struct.Mutex.html",
+            "tokio::sync::Mutex"
+        ));
+        assert!(!matches_symbol(
+            "tokio",
+            "sync/struct.Mutex.html",
+            "fs::Mutex"
+        ));
+        assert!(matches_symbol("flume", "struct Sender", "flume::Sender"));
+        assert!(matches_symbol("flume", "struct Sender", "struct Sender"));
+        assert!(!matches_symbol("flume", "struct Sender", "Receiver"));
     }
 }
@@ -1,22 +1,164 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, fmt, path::PathBuf, sync::Arc};
+
+use cache::CacheStats;
 
 use flume::Sender;
 use generate::generate_docs;
-use tokio::sync::Mutex;
+use page::{DocsPage, PageCache, page_at};
+use search::{SearchHit, SearchIndex};
+use std_docs::StdDocs;
+use tokio::sync::{Mutex, OnceCell, watch};
 use walk::walk_docs;
 
 use crate::project::Project;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use utils::lockfile_hash;
 
+pub mod cache;
 pub mod extract_md;
 pub mod generate;
 pub mod index;
+pub mod page;
+pub mod rustdoc_json;
+pub mod search;
+pub mod std_docs;
+pub mod store;
 pub mod utils;
 pub mod walk;
 
+/// The features the docs of a project are generated with and where they're cached
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocsConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub all_features: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_default_features: bool,
+    /// Cache the docs in the user's cache dir, where every crate version is indexed
+    /// once for all projects, instead of in `.docs-cache` in the project
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shared_cache: bool,
+    /// Evict the docs of the least recently used crates once the cache is larger
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cache_mb: Option<u64>,
+    /// Update the docs index whenever a `Cargo.toml` or the `Cargo.lock` changes
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub update_on_dependency_change: bool,
+}
+
+impl DocsConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The feature arguments for `cargo doc`
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        args
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum DocsNotification {
-    Indexing { project: PathBuf, is_indexing: bool },
+    Indexing {
+        project: PathBuf,
+        is_indexing: bool,
+    },
+    /// `cargo doc` or the cache walk started on a crate. `index` counts from 1,
+    /// `total` is 0 if the number of crates isn't known up front.
+    CrateStarted {
+        project: PathBuf,
+        name: String,
+        index: usize,
+        total: usize,
+    },
+    CrateFinished {
+        project: PathBuf,
+        name: String,
+        index: usize,
+        total: usize,
+    },
+    /// `done` of the `total` pages of the crate that is being indexed were walked.
+    /// Sent about every 10 percent.
+    Progress {
+        project: PathBuf,
+        name: String,
+        done: usize,
+        total: usize,
+    },
+    /// `cargo doc` failed, the index keeps the docs from before
+    Failed {
+        project: PathBuf,
+        error: String,
+    },
+}
+
+impl DocsNotification {
+    pub fn project(&self) -> &PathBuf {
+        match self {
+            DocsNotification::Indexing { project, .. }
+            | DocsNotification::CrateStarted { project, .. }
+            | DocsNotification::CrateFinished { project, .. }
+            | DocsNotification::Progress { project, .. }
+            | DocsNotification::Failed { project, .. } => project,
+        }
+    }
+}
+
+/// The crate the docs indexing is currently working on
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocsProgress {
+    pub name: String,
+    pub index: usize,
+    pub total: usize,
+    /// The walked and the total pages of the crate, once the walk started
+    pub pages: Option<(usize, usize)>,
+}
+
+impl fmt::Display for DocsProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.total == 0 {
+            write!(f, "{}: {}", self.index, self.name)?;
+        } else {
+            write!(f, "{}/{}: {}", self.index, self.total, self.name)?;
+        }
+        match self.pages {
+            Some((done, total)) => write!(f, " ({done}/{total} pages)"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A running docs index run, resolves once no run is queued behind it either
+#[derive(Debug, Clone)]
+pub struct IndexRun(watch::Receiver<bool>);
+
+impl IndexRun {
+    pub async fn finished(mut self) {
+        let _ = self.0.wait_for(|finished| *finished).await;
+    }
+}
+
+#[derive(Debug, Default)]
+struct IndexingState {
+    /// Set to `true` by the run once it is done
+    run: Option<watch::Receiver<bool>>,
+    /// Another run was requested while one was in progress
+    rerun: bool,
+    /// The `Cargo.lock` hash the last reindex was started for because the docs were stale
+    reindexed_for: Option<String>,
 }
 
 #[derive(Debug)]
@@ -24,6 +166,13 @@ pub struct Docs {
     project: Project,
     index: Arc<Mutex<index::DocsIndex>>,
     notifier: Sender<DocsNotification>,
+    indexing: Arc<std::sync::Mutex<IndexingState>>,
+    /// The error of the last docs generation, if it failed
+    last_failure: Arc<std::sync::Mutex<Option<String>>>,
+    /// Built on first use, walking the standard library docs takes a moment
+    std_docs: OnceCell<StdDocs>,
+    /// The page boundaries of recently paginated docs
+    pages: PageCache,
 }
 
 impl Docs {
@@ -33,10 +182,36 @@ impl Docs {
             project,
             index: Arc::new(index),
             notifier,
+            indexing: Default::default(),
+            last_failure: Default::default(),
+            std_docs: OnceCell::new(),
+            pages: PageCache::default(),
         })
     }
 
-    pub async fn update_index(&self) -> Result<()> {
+    /// Regenerate the docs and rebuild the index in the background. If a run is
+    /// in progress, another run is queued behind it and its handle is returned,
+    /// so any number of requests cause at most one more run.
+    pub async fn update_index(&self) -> Result<IndexRun> {
+        let sender = {
+            let mut state = self
+                .indexing
+                .lock()
+                .map_err(|_| anyhow::anyhow!("The docs indexing state is poisoned"))?;
+            if let Some(run) = state.run.clone()
+                && !*run.borrow()
+            {
+                tracing::info!("Docs indexing is running already, queueing another run");
+                state.rerun = true;
+                return Ok(IndexRun(run));
+            }
+            let (sender, receiver) = watch::channel(false);
+            state.run = Some(receiver);
+            state.rerun = false;
+            sender
+        };
+        let run = IndexRun(sender.subscribe());
+
         self.notifier.send(DocsNotification::Indexing {
             project: self.project.root().to_path_buf(),
             is_indexing: true,
@@ -44,30 +219,34 @@ impl Docs {
         let cloned_project = self.project.clone();
         let cloned_index = self.index.clone();
         let cloned_notifier = self.notifier.clone();
+        let cloned_indexing = self.indexing.clone();
+        let cloned_failure = self.last_failure.clone();
         tokio::spawn(async move {
-            if let Err(e) = generate_docs(&cloned_project) {
-                tracing::error!("Failed to generate docs: {:?}", e);
-            }
-            if let Err(e) = walk_docs(&cloned_project) {
-                tracing::error!("Failed to update docs cache: {:?}", e);
-            }
-
-            tracing::info!("Updating docs cache...");
-
-            let index = match index::DocsIndex::new(&cloned_project) {
-                Ok(index) => index,
-                Err(e) => {
-                    tracing::error!("Failed to update docs cache: {:?}", e);
-                    if let Err(e) = cloned_notifier.send(DocsNotification::Indexing {
+            loop {
+                let result = rebuild_index(&cloned_project, &cloned_index, &cloned_notifier).await;
+                let failure = result.as_ref().err().map(|e| format!("{e:#}"));
+                if let Some(error) = &failure {
+                    tracing::error!("Failed to generate docs: {error}");
+                    if let Err(e) = cloned_notifier.send(DocsNotification::Failed {
                         project: cloned_project.root().to_path_buf(),
-                        is_indexing: false,
+                        error: error.clone(),
                     }) {
-                        tracing::error!("Failed to send docs indexing notification: {:?}", e);
+                        tracing::error!("Failed to send docs failure notification: {:?}", e);
                     }
-                    return;
                 }
-            };
-            *cloned_index.lock().await = index;
+                if let Ok(mut last_failure) = cloned_failure.lock() {
+                    *last_failure = failure;
+                }
+                // Decided under the lock, so that a request can't queue a run after the last check
+                let Ok(mut state) = cloned_indexing.lock() else {
+                    let _ = sender.send(true);
+                    break;
+                };
+                if !std::mem::take(&mut state.rerun) {
+                    let _ = sender.send(true);
+                    break;
+                }
+            }
 
             if let Err(e) = cloned_notifier.send(DocsNotification::Indexing {
                 project: cloned_project.root().to_path_buf(),
@@ -76,34 +255,278 @@ impl Docs {
                 tracing::error!("Failed to send docs indexing notification: {:?}", e);
             }
         });
-        Ok(())
+        Ok(run)
+    }
+
+    /// The error of the last docs generation if it failed, the docs may be stale then
+    pub fn last_failure(&self) -> Option<String> {
+        self.last_failure
+            .lock()
+            .ok()
+            .and_then(|failure| failure.clone())
+    }
+
+    /// Fails and starts a reindex if `Cargo.lock` changed since the docs were indexed,
+    /// so that the docs of the previous dependency versions are never served
+    async fn ensure_current(&self) -> Result<()> {
+        let Some(hash) = lockfile_hash(self.project.root()) else {
+            return Ok(());
+        };
+        if self.index.lock().await.lockfile_hash()? == hash {
+            return Ok(());
+        }
+        {
+            let mut state = self
+                .indexing
+                .lock()
+                .map_err(|_| anyhow::anyhow!("The docs indexing state is poisoned"))?;
+            if state.run.as_ref().is_some_and(|run| !*run.borrow()) {
+                anyhow::bail!(
+                    "The docs are being regenerated because Cargo.lock changed. Please try again shortly"
+                );
+            }
+            // Don't retry forever if reindexing didn't pick up the new lockfile
+            if state.reindexed_for.as_ref() == Some(&hash) {
+                tracing::warn!("Serving docs that may not match Cargo.lock");
+                return Ok(());
+            }
+            state.reindexed_for = Some(hash);
+        }
+        self.update_index().await?;
+        anyhow::bail!(
+            "Cargo.lock changed since the docs were indexed, they are being regenerated. Please try again shortly"
+        )
     }
 
-    pub async fn crate_docs(&self, crate_name: &str) -> Result<String> {
+    /// Fails and indexes the crate again if its docs were evicted to keep the cache small
+    async fn ensure_indexed(&self, crate_name: &str) -> Result<()> {
+        let restored = {
+            let index = self.index.lock().await;
+            let Some(name) = index.evicted_crate(crate_name)? else {
+                return Ok(());
+            };
+            index.restore(&name)?
+        };
+        // Lookups while it is being indexed don't start another run
+        if restored {
+            self.update_index().await?;
+        }
+        anyhow::bail!(
+            "The docs of {crate_name} were evicted from the cache and are being regenerated. Please try again shortly"
+        )
+    }
+
+    /// The disk usage of the docs cache per crate
+    pub async fn cache_stats(&self) -> Result<CacheStats> {
+        let index = self.index.lock().await;
+        cache::cache_stats(&self.project, index.store())
+    }
+
+    /// Evict the docs of every crate, they're indexed again when they're looked up
+    pub async fn clear_cache(&self) -> Result<()> {
+        let mut index = self.index.lock().await;
+        let store = index.store_mut();
+        for usage in store.crate_usage()? {
+            cache::evict_crate(&self.project, store, &usage.name)?;
+        }
+        SearchIndex::build(&self.project, store)
+    }
+
+    fn page_of(&self, markdown: &str, max_tokens: usize, page: usize) -> Result<DocsPage> {
+        let boundaries = self.pages.boundaries(markdown, max_tokens);
+        page_at(markdown, &boundaries, page).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Page {page} doesn't exist, the docs have {} pages",
+                boundaries.len()
+            )
+        })
+    }
+
+    /// The crate overview, split into pages of at most `max_tokens`
+    pub async fn crate_docs(
+        &self,
+        crate_name: &str,
+        max_tokens: usize,
+        page: usize,
+    ) -> Result<DocsPage> {
+        self.ensure_current().await?;
+        self.ensure_indexed(crate_name).await?;
         let index = self.index.lock().await;
         if index.dependencies().is_empty() {
             return Err(anyhow::anyhow!(
                 "No dependencies found. Please update the docs cache first"
             ));
         }
-        let markdown = index.markdown_docs(crate_name).unwrap();
-        Ok(markdown)
+        // Caches from before module overviews were indexed only have the pages
+        let markdown = match index.crate_overview(crate_name)? {
+            Some(overview) => Some(overview),
+            None => index.markdown_docs(crate_name)?,
+        };
+        let Some(markdown) = markdown else {
+            return Err(anyhow::anyhow!("No docs found for crate: {}", crate_name));
+        };
+        self.page_of(&markdown, max_tokens, page)
     }
 
+    /// The docs of a symbol of the crate, split into pages of at most `max_tokens`
     pub async fn crate_symbol_docs(
         &self,
         crate_name: &str,
         symbol: &str,
-    ) -> Result<Vec<(String, String)>> {
+        max_tokens: usize,
+        page: usize,
+    ) -> Result<DocsPage> {
+        self.ensure_current().await?;
+        self.ensure_indexed(crate_name).await?;
+        let index = self.index.lock().await;
+        if index.dependencies().is_empty() {
+            return Err(anyhow::anyhow!(
+                "No dependencies found. Please update the docs cache first"
+            ));
+        }
+        let key = index
+            .resolve(crate_name, symbol)?
+            .unwrap_or_else(|| symbol.to_string());
+        let Some(docs) = index.docs(crate_name, &[key])? else {
+            return Err(anyhow::anyhow!("No docs found for crate: {}", crate_name));
+        };
+        if docs.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No docs found for {symbol} in {crate_name}. Use `crate_symbols` to list its symbols"
+            ));
+        }
+        let markdown = docs
+            .into_iter()
+            .map(|(symbol, docs)| format!("{symbol}: {docs}"))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        self.page_of(&markdown, max_tokens, page)
+    }
+
+    /// The documented items of a dependency grouped by kind
+    pub async fn crate_symbols(
+        &self,
+        crate_name: &str,
+        filter: Option<&str>,
+    ) -> Result<BTreeMap<String, Vec<String>>> {
+        self.ensure_current().await?;
+        self.ensure_indexed(crate_name).await?;
         let index = self.index.lock().await;
         if index.dependencies().is_empty() {
             return Err(anyhow::anyhow!(
                 "No dependencies found. Please update the docs cache first"
             ));
         }
-        let Some(docs) = index.docs(crate_name, &[symbol.to_string()]) else {
+        let Some(symbols) = index.symbols_by_kind(crate_name, filter)? else {
             return Err(anyhow::anyhow!("No docs found for crate: {}", crate_name));
         };
-        Ok(docs)
+        Ok(symbols)
+    }
+
+    /// The code examples of the crate or of one of its symbols, split into pages of at most `max_tokens`
+    pub async fn crate_examples(
+        &self,
+        crate_name: &str,
+        symbol: Option<&str>,
+        max_tokens: usize,
+        page: usize,
+    ) -> Result<DocsPage> {
+        self.ensure_current().await?;
+        self.ensure_indexed(crate_name).await?;
+        let index = self.index.lock().await;
+        if index.dependencies().is_empty() {
+            return Err(anyhow::anyhow!(
+                "No dependencies found. Please update the docs cache first"
+            ));
+        }
+        let Some(examples) = index.examples(crate_name, symbol)? else {
+            return Err(anyhow::anyhow!("No docs found for crate: {}", crate_name));
+        };
+        if examples.is_empty() {
+            return Err(match symbol {
+                Some(symbol) => anyhow::anyhow!("No examples found for {symbol} in {crate_name}"),
+                None => anyhow::anyhow!("No examples found for crate: {crate_name}"),
+            });
+        }
+        let mut markdown = String::new();
+        let mut previous_key = "";
+        for (key, code) in &examples {
+            if key != previous_key {
+                markdown.push_str(&format!("## {key}\n\n"));
+                previous_key = key;
+            }
+            markdown.push_str(&format!("```rust\n{code}\n```\n\n"));
+        }
+        self.page_of(&markdown, max_tokens, page)
+    }
+
+    /// The public paths under which an item of the crate is reachable
+    pub async fn item_paths(&self, crate_name: &str, name: &str) -> Result<Vec<String>> {
+        self.ensure_current().await?;
+        self.ensure_indexed(crate_name).await?;
+        let index = self.index.lock().await;
+        if index.dependencies().is_empty() {
+            return Err(anyhow::anyhow!(
+                "No dependencies found. Please update the docs cache first"
+            ));
+        }
+        let Some(paths) = index.item_paths(crate_name, name)? else {
+            return Err(anyhow::anyhow!("No docs found for crate: {}", crate_name));
+        };
+        Ok(paths)
+    }
+
+    /// The docs of an item of the standard library, e.g. `Vec::drain`
+    pub async fn std_docs(&self, query: &str) -> Result<Vec<(String, String)>> {
+        let std_docs = self
+            .std_docs
+            .get_or_try_init(|| async {
+                let root = StdDocs::locate(self.project.root()).await?;
+                anyhow::Ok(tokio::task::spawn_blocking(move || StdDocs::new(root)).await?)
+            })
+            .await?;
+        std_docs.lookup(query)
+    }
+
+    /// Full-text search over the docs of all dependencies
+    pub async fn search(
+        &self,
+        query: &str,
+        crate_name: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        self.ensure_current().await?;
+        let project = self.project.clone();
+        let query = query.to_string();
+        let crate_name = crate_name.map(|name| name.replace('-', "_"));
+        tokio::task::spawn_blocking(move || {
+            SearchIndex::open(&project)?.search(&query, crate_name.as_deref(), limit)
+        })
+        .await?
+    }
+}
+
+/// Generate the docs, update the cache and swap in the new index
+async fn rebuild_index(
+    project: &Project,
+    index: &Mutex<index::DocsIndex>,
+    notifier: &Sender<DocsNotification>,
+) -> Result<()> {
+    let (cloned_project, cloned_notifier) = (project.clone(), notifier.clone());
+    let generated = tokio::task::spawn_blocking(move || {
+        // The docs that were generated are indexed even if `cargo doc` failed
+        let generated = generate_docs(&cloned_project, &cloned_notifier);
+        if let Err(e) = walk_docs(&cloned_project, &cloned_notifier) {
+            tracing::error!("Failed to update docs cache: {:?}", e);
+        }
+        generated
+    })
+    .await?;
+
+    tracing::info!("Updating docs cache...");
+    match index::DocsIndex::new(project) {
+        Ok(new_index) => *index.lock().await = new_index,
+        Err(e) => tracing::error!("Failed to update docs cache: {:?}", e),
     }
+    generated
 }
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// Rough number of characters per token of markdown, used to turn a token budget into a length
+const CHARS_PER_TOKEN: usize = 4;
+
+/// The token budget of a docs response if the client doesn't ask for one
+pub const DEFAULT_MAX_TOKENS: usize = 8000;
+
+/// The page cache is cleared once it holds the boundaries of this many docs
+const MAX_CACHED_DOCS: usize = 64;
+
+/// One page of a docs response that was split to stay within a token budget
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocsPage {
+    pub text: String,
+    /// Counts from 1
+    pub page: usize,
+    pub pages: usize,
+}
+
+impl DocsPage {
+    /// The text with a hint on how to get the next page, if there is one
+    pub fn with_continuation(&self) -> String {
+        if self.page >= self.pages {
+            return self.text.clone();
+        }
+        format!(
+            "{}\n\n[Page {} of {}. Call again with `page: {}` for more.]",
+            self.text,
+            self.page,
+            self.pages,
+            self.page + 1
+        )
+    }
+}
+
+/// Page `page` of `text` with the given page boundaries
+pub fn page_at(text: &str, boundaries: &[Range<usize>], page: usize) -> Option<DocsPage> {
+    let range = boundaries.get(page.checked_sub(1)?)?.clone();
+    Some(DocsPage {
+        text: text[range].to_string(),
+        page,
+        pages: boundaries.len(),
+    })
+}
+
+/// Split `text` at line boundaries into pages of at most `max_tokens` and return
+/// their byte ranges. Lines longer than a page are split as well.
+pub fn page_boundaries(text: &str, max_tokens: usize) -> Vec<Range<usize>> {
+    let max_chars = max_tokens.max(1) * CHARS_PER_TOKEN;
+    let mut pages = Vec::new();
+    // The start of the current page and the characters on it so far
+    let (mut start, mut chars) = (0, 0);
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let mut rest_start = offset;
+        offset += line.len();
+        let mut rest = line;
+        let mut length = line.chars().count();
+        while !rest.is_empty() {
+            let room = max_chars.saturating_sub(chars);
+            if length <= room {
+                chars += length;
+                break;
+            }
+            if chars == 0 || length > max_chars {
+                // Fill the page with as much of the line as fits
+                let split = rest
+                    .char_indices()
+                    .nth(room)
+                    .map(|(index, _)| index)
+                    .unwrap_or(rest.len());
+                rest = &rest[split..];
+                rest_start += split;
+                length -= room;
+            }
+            pages.push(start..rest_start);
+            (start, chars) = (rest_start, 0);
+        }
+    }
+    if start < text.len() || pages.is_empty() {
+        pages.push(start..text.len());
+    }
+    pages
+}
+
+/// The page boundaries of recently paginated docs, so that getting the next page
+/// doesn't split the whole doc again
+#[derive(Debug, Clone, Default)]
+pub struct PageCache(Arc<Mutex<HashMap<(u64, usize), Vec<Range<usize>>>>>);
+
+impl PageCache {
+    /// The page boundaries of `text` for the token budget
+    pub fn boundaries(&self, text: &str, max_tokens: usize) -> Vec<Range<usize>> {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let key = (hasher.finish(), max_tokens);
+        if let Some(boundaries) = self
+            .0
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&key).cloned())
+        {
+            return boundaries;
+        }
+        let boundaries = page_boundaries(text, max_tokens);
+        if let Ok(mut cache) = self.0.lock() {
+            if cache.len() >= MAX_CACHED_DOCS {
+                cache.clear();
+            }
+            cache.insert(key, boundaries.clone());
+        }
+        boundaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paginate(text: &str, max_tokens: usize, page: usize) -> Option<DocsPage> {
+        page_at(text, &page_boundaries(text, max_tokens), page)
+    }
+
+    #[test]
+    fn test_paginate() {
+        let text = "aaaa\nbbbb\ncccc\n";
+        // 2 tokens are 8 characters, one line with its newline is 5
+        let first = paginate(text, 2, 1).unwrap();
+        assert_eq!(first.text, "aaaa\n");
+        assert_eq!(first.pages, 3);
+        assert_eq!(paginate(text, 2, 3).unwrap().text, "cccc\n");
+        assert!(paginate(text, 2, 4).is_none());
+        assert!(paginate(text, 2, 0).is_none());
+
+        let whole = paginate(text, 100, 1).unwrap();
+        assert_eq!(whole.text, text);
+        assert_eq!(whole.with_continuation(), text);
+
+        let long = "x".repeat(10);
+        let pages: Vec<_> = (1..=3)
+            .map(|page| paginate(&long, 1, page).unwrap().text)
+            .collect();
+        assert_eq!(pages, ["xxxx", "xxxx", "xx"]);
+
+        // A line that doesn't fit the rest of the page starts the next one
+        let text = "ab\näöüäöüä\nc";
+        assert_eq!(page_boundaries(text, 2), [0..3, 3..18, 18..19]);
+        assert_eq!(page_boundaries("", 2), [0..0]);
+        let cache = PageCache::default();
+        assert_eq!(cache.boundaries(text, 2), page_boundaries(text, 2));
+        assert_eq!(cache.boundaries(text, 2), page_boundaries(text, 2));
+    }
+}
@@ -0,0 +1,990 @@
+//! Indexes the JSON output of rustdoc (`--output-format json`, nightly only).
+//! The JSON format is unstable, so it's read as plain `serde_json::Value`s and
+//! the field names that changed between format versions are all accepted.
+
+use anyhow::{Context as _, Result};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use super::extract_md::ModuleDocs;
+use super::store::CrateDocs;
+use super::utils::parse_rust_symbol;
+
+/// Read the `<crate>.json` file rustdoc wrote for a crate
+pub fn json_docs(path: &Path) -> Result<CrateDocs> {
+    let krate: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let index = krate
+        .get("index")
+        .and_then(Value::as_object)
+        .context("The rustdoc JSON has no index")?;
+    let root = krate
+        .get("root")
+        .and_then(|root| index.get(&id_key(root)?))
+        .context("The rustdoc JSON has no root module")?;
+    let crate_name = root
+        .get("name")
+        .and_then(Value::as_str)
+        .context("The root module has no name")?
+        .to_string();
+
+    let mut walker = Walker {
+        index,
+        paths: krate.get("paths").and_then(Value::as_object),
+        crate_name,
+        visited: HashSet::new(),
+        docs: CrateDocs::default(),
+    };
+    walker.module(root, &[]);
+    Ok(walker.docs)
+}
+
+struct Walker<'a> {
+    index: &'a Map<String, Value>,
+    /// The path where each item is defined, which may be private
+    paths: Option<&'a Map<String, Value>>,
+    crate_name: String,
+    /// Modules that were indexed already, glob re-exports can form cycles
+    visited: HashSet<String>,
+    docs: CrateDocs,
+}
+
+impl Walker<'_> {
+    fn item(&self, id: &Value) -> Option<&Value> {
+        self.index.get(&id_key(id)?)
+    }
+
+    fn module(&mut self, module: &Value, path: &[String]) {
+        if let Some(id) = module.get("id").and_then(id_key)
+            && !self.visited.insert(id)
+        {
+            return;
+        }
+        let mut reexports = Vec::new();
+        let mut listing = Vec::new();
+        let mut globbed = HashSet::new();
+        self.members(module, path, &mut reexports, &mut listing, &mut globbed);
+
+        let title = match path.last() {
+            Some(_) => format!("Module {}", path.join("::")),
+            None => format!("Crate {}", self.crate_name),
+        };
+        let overview = docs(module).to_string();
+        let mut markdown = format!("# {title}\n\n{overview}\n");
+        if !reexports.is_empty() {
+            markdown.push_str("\n## Re-exports\n\n");
+            for reexport in &reexports {
+                markdown.push_str(&format!("{reexport}\n"));
+            }
+        }
+        listing.sort();
+        let mut section = "";
+        for (heading, name, summary) in &listing {
+            if *heading != section {
+                section = heading;
+                markdown.push_str(&format!("\n## {heading}\n\n"));
+            }
+            markdown.push_str(&format!("- `{name}`: {summary}\n"));
+        }
+
+        let key = match path.is_empty() {
+            true => "index.html".to_string(),
+            false => format!("{}/index.html", path.join("/")),
+        };
+        self.docs.pages.push((key, markdown));
+        self.docs
+            .modules
+            .push((path.join("::"), ModuleDocs { title, overview }));
+    }
+
+    /// Document the items of `module` as members of the module at `path`.
+    /// Called for the items of modules that are re-exported with a glob as well.
+    fn members(
+        &mut self,
+        module: &Value,
+        path: &[String],
+        reexports: &mut Vec<String>,
+        listing: &mut Vec<(&'static str, String, String)>,
+        globbed: &mut HashSet<String>,
+    ) {
+        let items = module
+            .pointer("/inner/module/items")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for id in &items {
+            let Some(item) = self.item(id).cloned() else {
+                continue;
+            };
+            if !is_public(&item) {
+                continue;
+            }
+            let Some((kind, inner)) = inner(&item) else {
+                continue;
+            };
+            match kind {
+                "impl" => {}
+                "use" => {
+                    let source = inner.get("source").and_then(Value::as_str).unwrap_or("");
+                    let name = inner.get("name").and_then(Value::as_str).unwrap_or("");
+                    let is_glob = inner.get("is_glob").and_then(Value::as_bool) == Some(true);
+                    let target_id = inner.get("id").and_then(id_key);
+                    let target = inner.get("id").and_then(|id| self.item(id)).cloned();
+                    if is_glob {
+                        reexports.push(format!("pub use {source}::*;"));
+                        if let Some(target) = target
+                            && let Some(target_id) = target_id
+                            && matches!(self::inner(&target), Some(("module", _)))
+                            && globbed.insert(target_id)
+                        {
+                            self.members(&target, path, reexports, listing, globbed);
+                        }
+                        continue;
+                    }
+                    if source.rsplit("::").next() == Some(name) {
+                        reexports.push(format!("pub use {source};"));
+                    } else {
+                        reexports.push(format!("pub use {source} as {name};"));
+                    }
+                    // Items of this crate are documented where they're re-exported as well
+                    if let Some(target) = target
+                        && let Some(entry) = self.document(&target, name, path)
+                    {
+                        listing.push(entry);
+                    }
+                }
+                _ => {
+                    let name = item.get("name").and_then(Value::as_str).unwrap_or("");
+                    if let Some(entry) = self.document(&item, name, path) {
+                        listing.push(entry);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Store the page of an item under `name` in the module at `path` and return
+    /// its entry for the module listing
+    fn document(
+        &mut self,
+        item: &Value,
+        name: &str,
+        path: &[String],
+    ) -> Option<(&'static str, String, String)> {
+        let (kind, _) = inner(item)?;
+        let summary = docs(item).lines().next().unwrap_or_default().to_string();
+        if kind == "module" {
+            let mut module_path = path.to_vec();
+            module_path.push(name.to_string());
+            self.module(item, &module_path);
+            return Some(("Modules", name.to_string(), summary));
+        }
+        let (prefix, heading) = page_kind(item)?;
+
+        let file = format!("{prefix}.{name}.html");
+        let key = match path.is_empty() {
+            true => parse_rust_symbol(&file)?.to_string(),
+            false => format!("{}/{file}", path.join("/")),
+        };
+        let full_path = std::iter::once(self.crate_name.as_str())
+            .chain(path.iter().map(String::as_str))
+            .chain(std::iter::once(name))
+            .collect::<Vec<_>>()
+            .join("::");
+        // Compiler messages use the path where a re-exported item is defined
+        if let Some(definition) = self.definition_path(item)
+            && format!("{}::{definition}", self.crate_name) != full_path
+        {
+            self.docs.aliases.push((definition, key.clone()));
+        }
+        let markdown = self.page(item, name, &full_path);
+        self.docs.pages.push((key, markdown));
+        Some((heading, name.to_string(), summary))
+    }
+
+    /// The path of an item of this crate where it's defined, without the crate name
+    fn definition_path(&self, item: &Value) -> Option<String> {
+        let summary = self.paths?.get(&id_key(item.get("id")?)?)?;
+        if summary.get("crate_id").and_then(Value::as_u64) != Some(0) {
+            return None;
+        }
+        let path = summary.get("path")?.as_array()?;
+        let segments = path
+            .iter()
+            .skip(1)
+            .map(|segment| segment.as_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(segments.join("::"))
+    }
+
+    fn page(&self, item: &Value, name: &str, full_path: &str) -> String {
+        let (kind, inner) = inner(item).unwrap_or(("", &Value::Null));
+        let title = match page_kind(item) {
+            Some((_, heading)) => heading.trim_end_matches('s').replace("Aliase", "Alias"),
+            None => kind.to_string(),
+        };
+        let mut markdown = format!(
+            "# {title} {full_path}\n\n```rust\n{}\n```\n\n{}\n",
+            self.declaration(item, name),
+            docs(item)
+        );
+
+        match kind {
+            "enum" => {
+                let variants = self.items(inner.get("variants"));
+                if !variants.is_empty() {
+                    markdown.push_str("\n## Variants\n");
+                }
+                for variant in variants {
+                    markdown.push_str(&format!(
+                        "\n### `{}`\n\n{}\n",
+                        self.variant(variant),
+                        docs(variant)
+                    ));
+                }
+            }
+            "struct" | "union" => {
+                let fields = self.fields(kind, inner);
+                if fields.iter().any(|field| !docs(field).is_empty()) {
+                    markdown.push_str("\n## Fields\n");
+                    for field in fields {
+                        markdown.push_str(&format!(
+                            "\n### `{}`\n\n{}\n",
+                            self.field(field),
+                            docs(field)
+                        ));
+                    }
+                }
+            }
+            "trait" => {
+                let methods = self.items(inner.get("items"));
+                let (provided, required): (Vec<_>, Vec<_>) =
+                    methods.into_iter().partition(|method| {
+                        method.pointer("/inner/function/has_body") == Some(&true.into())
+                    });
+                for (heading, methods) in [
+                    ("Required Methods", required),
+                    ("Provided Methods", provided),
+                ] {
+                    if methods.is_empty() {
+                        continue;
+                    }
+                    markdown.push_str(&format!("\n## {heading}\n"));
+                    for method in methods {
+                        let name = method.get("name").and_then(Value::as_str).unwrap_or("");
+                        markdown.push_str(&format!(
+                            "\n### `{}`\n\n{}\n",
+                            self.declaration(method, name),
+                            docs(method)
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Inherent methods and the implemented traits of types
+        let impls = self.items(inner.get("impls"));
+        let mut traits = Vec::new();
+        let mut methods = String::new();
+        for implementation in impls {
+            let Some(("impl", inner)) = self::inner(implementation) else {
+                continue;
+            };
+            let synthetic = inner.get("is_synthetic").and_then(Value::as_bool) == Some(true);
+            let blanket = inner.get("blanket_impl").is_some_and(|b| !b.is_null());
+            match inner.get("trait").filter(|t| !t.is_null()) {
+                Some(tr) if !synthetic && !blanket => traits.push(render_path(tr)),
+                Some(_) => {}
+                None => {
+                    for method in self.items(inner.get("items")) {
+                        if !is_public(method) {
+                            continue;
+                        }
+                        let name = method.get("name").and_then(Value::as_str).unwrap_or("");
+                        methods.push_str(&format!(
+                            "\n### `{}`\n\n{}\n",
+                            self.declaration(method, name),
+                            docs(method)
+                        ));
+                    }
+                }
+            }
+        }
+        if !methods.is_empty() {
+            markdown.push_str("\n## Methods\n");
+            markdown.push_str(&methods);
+        }
+        if !traits.is_empty() {
+            traits.sort();
+            traits.dedup();
+            markdown.push_str("\n## Trait Implementations\n\n");
+            for tr in traits {
+                markdown.push_str(&format!("- `{tr}`\n"));
+            }
+        }
+        markdown
+    }
+
+    fn items<'v>(&'v self, ids: Option<&Value>) -> Vec<&'v Value> {
+        ids.and_then(Value::as_array)
+            .map(|ids| ids.iter().filter_map(|id| self.item(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// The source-like declaration of an item, without its body
+    fn declaration(&self, item: &Value, name: &str) -> String {
+        let (kind, inner) = self::inner(item).unwrap_or(("", &Value::Null));
+        let generics = inner.get("generics");
+        let (params, where_clause) = render_generics(generics);
+        match kind {
+            // Trait methods have no visibility of their own
+            "function" if is_public(item) => format!("pub {}", render_fn(name, inner)),
+            "function" => render_fn(name, inner),
+            "struct" | "union" => {
+                let fields = self.fields(kind, inner);
+                let stripped = inner
+                    .pointer("/kind/plain/has_stripped_fields")
+                    .or_else(|| inner.get("has_stripped_fields"))
+                    .and_then(Value::as_bool)
+                    == Some(true);
+                let is_tuple = inner.pointer("/kind/tuple").is_some();
+                let is_unit = inner.get("kind").and_then(Value::as_str) == Some("unit");
+                let mut fields: Vec<String> = fields
+                    .iter()
+                    .map(|field| match is_tuple {
+                        true => format!("pub {}", render_type(field_type(field))),
+                        false => format!("pub {}", self.field(field)),
+                    })
+                    .collect();
+                if stripped || (is_tuple && fields.len() < self.tuple_len(inner)) {
+                    fields.push("/* private fields */".to_string());
+                }
+                match (is_unit, is_tuple) {
+                    (true, _) => format!("pub {kind} {name}{params}{where_clause};"),
+                    (_, true) => format!(
+                        "pub {kind} {name}{params}({}){where_clause};",
+                        fields.join(", ")
+                    ),
+                    _ if fields.is_empty() => {
+                        format!("pub {kind} {name}{params}{where_clause} {{}}")
+                    }
+                    _ => {
+                        let fields: String = fields
+                            .iter()
+                            .map(|field| match field.starts_with("/*") {
+                                true => format!("    {field}\n"),
+                                false => format!("    {field},\n"),
+                            })
+                            .collect();
+                        format!("pub {kind} {name}{params}{where_clause} {{\n{fields}}}")
+                    }
+                }
+            }
+            "enum" => {
+                let variants: Vec<String> = self
+                    .items(inner.get("variants"))
+                    .into_iter()
+                    .map(|variant| self.variant(variant))
+                    .collect();
+                format!(
+                    "pub enum {name}{params}{where_clause} {{\n    {},\n}}",
+                    variants.join(",\n    ")
+                )
+            }
+            "trait" => {
+                let bounds = render_bounds(inner.get("bounds"));
+                let supertraits = match bounds.is_empty() {
+                    true => String::new(),
+                    false => format!(": {bounds}"),
+                };
+                let unsafety = match inner.get("is_unsafe").and_then(Value::as_bool) {
+                    Some(true) => "unsafe ",
+                    _ => "",
+                };
+                format!("pub {unsafety}trait {name}{params}{supertraits}{where_clause} {{ ... }}")
+            }
+            "type_alias" | "typedef" => format!(
+                "pub type {name}{params}{where_clause} = {};",
+                render_type(inner.get("type").unwrap_or(&Value::Null))
+            ),
+            "constant" => {
+                let value = inner
+                    .pointer("/const/value")
+                    .and_then(Value::as_str)
+                    .or_else(|| inner.pointer("/const/expr").and_then(Value::as_str))
+                    .unwrap_or("_");
+                format!(
+                    "pub const {name}: {} = {value};",
+                    render_type(inner.get("type").unwrap_or(&Value::Null))
+                )
+            }
+            "static" => {
+                let mutability = match flag(inner, &["is_mutable", "mutable"]) {
+                    true => "mut ",
+                    false => "",
+                };
+                format!(
+                    "pub static {mutability}{name}: {};",
+                    render_type(inner.get("type").unwrap_or(&Value::Null))
+                )
+            }
+            "macro" => inner.as_str().unwrap_or(name).to_string(),
+            "proc_macro" => match inner.get("kind").and_then(Value::as_str) {
+                Some("derive") => format!("#[derive({name})]"),
+                Some("attr") => format!("#[{name}]"),
+                _ => format!("{name}!() {{ ... }}"),
+            },
+            "assoc_type" => format!("type {name};"),
+            "assoc_const" => format!(
+                "const {name}: {};",
+                render_type(inner.get("type").unwrap_or(&Value::Null))
+            ),
+            _ => name.to_string(),
+        }
+    }
+
+    fn fields<'v>(&'v self, kind: &str, inner: &'v Value) -> Vec<&'v Value> {
+        let ids = match kind {
+            "union" => inner.get("fields"),
+            _ => inner
+                .pointer("/kind/plain/fields")
+                .or_else(|| inner.pointer("/kind/tuple"))
+                .or_else(|| inner.get("fields")),
+        };
+        // Tuple structs have `null` for the fields that aren't public
+        self.items(ids)
+    }
+
+    fn tuple_len(&self, inner: &Value) -> usize {
+        inner
+            .pointer("/kind/tuple")
+            .and_then(Value::as_array)
+            .map_or(0, |fields| fields.len())
+    }
+
+    fn field(&self, field: &Value) -> String {
+        let name = field.get("name").and_then(Value::as_str).unwrap_or("_");
+        format!("{name}: {}", render_type(field_type(field)))
+    }
+
+    fn variant(&self, variant: &Value) -> String {
+        let name = variant.get("name").and_then(Value::as_str).unwrap_or("");
+        let kind = variant
+            .pointer("/inner/variant/kind")
+            .unwrap_or(&Value::Null);
+        if let Some(fields) = kind.get("tuple") {
+            let types: Vec<String> = self
+                .items(Some(fields))
+                .into_iter()
+                .map(|field| render_type(field_type(field)))
+                .collect();
+            format!("{name}({})", types.join(", "))
+        } else if let Some(fields) = kind.pointer("/struct/fields") {
+            let fields: Vec<String> = self
+                .items(Some(fields))
+                .into_iter()
+                .map(|field| self.field(field))
+                .collect();
+            format!("{name} {{ {} }}", fields.join(", "))
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// The file name prefix of the HTML page and the module section of an item
+fn page_kind(item: &Value) -> Option<(&'static str, &'static str)> {
+    let (kind, inner) = inner(item)?;
+    Some(match kind {
+        "function" => ("fn", "Functions"),
+        "struct" => ("struct", "Structs"),
+        "enum" => ("enum", "Enums"),
+        "union" => ("union", "Unions"),
+        "trait" => ("trait", "Traits"),
+        "type_alias" | "typedef" => ("type", "Type Aliases"),
+        "constant" => ("constant", "Constants"),
+        "static" => ("static", "Statics"),
+        "macro" => ("macro", "Macros"),
+        "primitive" => ("primitive", "Primitive Types"),
+        "proc_macro" => match inner.get("kind").and_then(Value::as_str) {
+            Some("derive") => ("derive", "Derive Macros"),
+            Some("attr") => ("attr", "Attribute Macros"),
+            _ => ("macro", "Macros"),
+        },
+        _ => return None,
+    })
+}
+
+/// Item ids are numbers in newer format versions and strings in older ones
+fn id_key(id: &Value) -> Option<String> {
+    match id {
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(string) => Some(string.clone()),
+        _ => None,
+    }
+}
+
+/// The kind of an item (`struct`, `function`, ...) and its kind specific data
+fn inner(item: &Value) -> Option<(&str, &Value)> {
+    match item.get("inner")? {
+        Value::Object(inner) => inner.iter().next().map(|(kind, v)| (kind.as_str(), v)),
+        Value::String(kind) => Some((kind.as_str(), &Value::Null)),
+        _ => None,
+    }
+}
+
+fn is_public(item: &Value) -> bool {
+    item.get("visibility").and_then(Value::as_str) == Some("public")
+}
+
+fn docs(item: &Value) -> &str {
+    item.get("docs")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .trim()
+}
+
+fn field_type(field: &Value) -> &Value {
+    field.pointer("/inner/struct_field").unwrap_or(&Value::Null)
+}
+
+/// Boolean fields were renamed from `const` to `is_const` etc.
+fn flag(value: &Value, names: &[&str]) -> bool {
+    names
+        .iter()
+        .any(|name| value.get(*name).and_then(Value::as_bool) == Some(true))
+}
+
+fn render_fn(name: &str, function: &Value) -> String {
+    let (params, where_clause) = render_generics(function.get("generics"));
+    let header = function.get("header").unwrap_or(&Value::Null);
+    let mut qualifiers = String::new();
+    for (names, qualifier) in [
+        (["is_const", "const"], "const "),
+        (["is_async", "async"], "async "),
+        (["is_unsafe", "unsafe"], "unsafe "),
+    ] {
+        if flag(header, &names) {
+            qualifiers.push_str(qualifier);
+        }
+    }
+    let signature = function
+        .get("sig")
+        .or_else(|| function.get("decl"))
+        .unwrap_or(&Value::Null);
+    format!(
+        "{qualifiers}fn {name}{params}{}{where_clause}",
+        render_signature(signature)
+    )
+}
+
+/// `(inputs) -> output` of a function signature
+fn render_signature(signature: &Value) -> String {
+    let inputs: Vec<String> = signature
+        .get("inputs")
+        .and_then(Value::as_array)
+        .map(|inputs| {
+            inputs
+                .iter()
+                .map(|input| {
+                    let name = input.get(0).and_then(Value::as_str).unwrap_or("_");
+                    let ty = input.get(1).unwrap_or(&Value::Null);
+                    if name == "self" {
+                        return render_self(ty);
+                    }
+                    format!("{name}: {}", render_type(ty))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let output = match signature.get("output").filter(|output| !output.is_null()) {
+        Some(output) => format!(" -> {}", render_type(output)),
+        None => String::new(),
+    };
+    format!("({}){output}", inputs.join(", "))
+}
+
+/// `self`, `&self`, `&mut self` or `self: Type`
+fn render_self(ty: &Value) -> String {
+    if ty.get("generic").and_then(Value::as_str) == Some("Self") {
+        return "self".to_string();
+    }
+    if let Some(reference) = ty.get("borrowed_ref")
+        && reference.pointer("/type/generic").and_then(Value::as_str) == Some("Self")
+    {
+        let lifetime = match reference.get("lifetime").and_then(Value::as_str) {
+            Some(lifetime) => format!("{lifetime} "),
+            None => String::new(),
+        };
+        let mutability = match flag(reference, &["is_mutable", "mutable"]) {
+            true => "mut ",
+            false => "",
+        };
+        return format!("&{lifetime}{mutability}self");
+    }
+    format!("self: {}", render_type(ty))
+}
+
+fn render_type(ty: &Value) -> String {
+    let (kind, inner) = match ty {
+        Value::Object(object) => match object.iter().next() {
+            Some((kind, inner)) => (kind.as_str(), inner),
+            None => return "_".to_string(),
+        },
+        Value::String(kind) if kind == "infer" => return "_".to_string(),
+        _ => return "_".to_string(),
+    };
+    match kind {
+        "resolved_path" => render_path(inner),
+        "primitive" | "generic" => inner.as_str().unwrap_or("_").to_string(),
+        "borrowed_ref" => {
+            let lifetime = match inner.get("lifetime").and_then(Value::as_str) {
+                Some(lifetime) => format!("{lifetime} "),
+                None => String::new(),
+            };
+            let mutability = match flag(inner, &["is_mutable", "mutable"]) {
+                true => "mut ",
+                false => "",
+            };
+            format!(
+                "&{lifetime}{mutability}{}",
+                render_type(inner.get("type").unwrap_or(&Value::Null))
+            )
+        }
+        "raw_pointer" => {
+            let mutability = match flag(inner, &["is_mutable", "mutable"]) {
+                true => "mut",
+                false => "const",
+            };
+            format!(
+                "*{mutability} {}",
+                render_type(inner.get("type").unwrap_or(&Value::Null))
+            )
+        }
+        "tuple" => {
+            let types: Vec<String> = inner
+                .as_array()
+                .map(|types| types.iter().map(render_type).collect())
+                .unwrap_or_default();
+            match types.len() {
+                1 => format!("({},)", types[0]),
+                _ => format!("({})", types.join(", ")),
+            }
+        }
+        "slice" => format!("[{}]", render_type(inner)),
+        "array" => format!(
+            "[{}; {}]",
+            render_type(inner.get("type").unwrap_or(&Value::Null)),
+            inner.get("len").and_then(Value::as_str).unwrap_or("_")
+        ),
+        "impl_trait" => format!("impl {}", render_bounds(Some(inner))),
+        "dyn_trait" => {
+            let mut bounds: Vec<String> = inner
+                .get("traits")
+                .and_then(Value::as_array)
+                .map(|traits| {
+                    traits
+                        .iter()
+                        .filter_map(|tr| tr.get("trait"))
+                        .map(render_path)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if let Some(lifetime) = inner.get("lifetime").and_then(Value::as_str) {
+                bounds.push(lifetime.to_string());
+            }
+            format!("dyn {}", bounds.join(" + "))
+        }
+        "qualified_path" => {
+            let name = inner.get("name").and_then(Value::as_str).unwrap_or("_");
+            let self_type = render_type(inner.get("self_type").unwrap_or(&Value::Null));
+            match inner.get("trait").filter(|tr| !tr.is_null()) {
+                // `Self::Item` in a trait has a trait with an empty path
+                Some(tr) if !render_path(tr).is_empty() => {
+                    format!("<{self_type} as {}>::{name}", render_path(tr))
+                }
+                _ => format!("{self_type}::{name}"),
+            }
+        }
+        "function_pointer" => {
+            let signature = inner
+                .get("sig")
+                .or_else(|| inner.get("decl"))
+                .unwrap_or(&Value::Null);
+            format!("fn{}", render_signature(signature))
+        }
+        "pat" => render_type(inner.get("type").unwrap_or(&Value::Null)),
+        _ => kind.to_string(),
+    }
+}
+
+/// A path like `Result<T, E>` or `Fn(u8) -> bool`
+fn render_path(path: &Value) -> String {
+    let name = path
+        .get("path")
+        .or_else(|| path.get("name"))
+        .and_then(Value::as_str)
+        .unwrap_or("_");
+    format!("{name}{}", render_args(path.get("args")))
+}
+
+fn render_args(args: Option<&Value>) -> String {
+    let Some(args) = args.filter(|args| !args.is_null()) else {
+        return String::new();
+    };
+    if let Some(angle) = args.get("angle_bracketed") {
+        let mut rendered: Vec<String> = angle
+            .get("args")
+            .and_then(Value::as_array)
+            .map(|args| {
+                args.iter()
+                    .map(|arg| {
+                        if let Some(ty) = arg.get("type") {
+                            render_type(ty)
+                        } else if let Some(lifetime) = arg.get("lifetime").and_then(Value::as_str) {
+                            lifetime.to_string()
+                        } else if let Some(constant) = arg.get("const") {
+                            constant
+                                .get("expr")
+                                .and_then(Value::as_str)
+                                .unwrap_or("_")
+                                .to_string()
+                        } else {
+                            "_".to_string()
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let constraints = angle
+            .get("constraints")
+            .or_else(|| angle.get("bindings"))
+            .and_then(Value::as_array);
+        for constraint in constraints.into_iter().flatten() {
+            let name = constraint
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("_");
+            let binding = constraint.get("binding").unwrap_or(&Value::Null);
+            if let Some(equality) = binding.get("equality") {
+                let ty = equality.get("type").unwrap_or(equality);
+                rendered.push(format!("{name} = {}", render_type(ty)));
+            } else if let Some(bounds) = binding.get("constraint") {
+                rendered.push(format!("{name}: {}", render_bounds(Some(bounds))));
+            }
+        }
+        return match rendered.is_empty() {
+            true => String::new(),
+            false => format!("<{}>", rendered.join(", ")),
+        };
+    }
+    if let Some(parenthesized) = args.get("parenthesized") {
+        let inputs: Vec<String> = parenthesized
+            .get("inputs")
+            .and_then(Value::as_array)
+            .map(|inputs| inputs.iter().map(render_type).collect())
+            .unwrap_or_default();
+        let output = match parenthesized
+            .get("output")
+            .filter(|output| !output.is_null())
+        {
+            Some(output) => format!(" -> {}", render_type(output)),
+            None => String::new(),
+        };
+        return format!("({}){output}", inputs.join(", "));
+    }
+    String::new()
+}
+
+/// Trait and lifetime bounds joined with `+`
+fn render_bounds(bounds: Option<&Value>) -> String {
+    bounds
+        .and_then(Value::as_array)
+        .map(|bounds| {
+            bounds
+                .iter()
+                .filter_map(|bound| {
+                    if let Some(tr) = bound.get("trait_bound") {
+                        let modifier = match tr.get("modifier").and_then(Value::as_str) {
+                            Some("maybe") => "?",
+                            _ => "",
+                        };
+                        return Some(format!(
+                            "{modifier}{}",
+                            render_path(tr.get("trait").unwrap_or(&Value::Null))
+                        ));
+                    }
+                    bound
+                        .get("outlives")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                })
+                .collect::<Vec<_>>()
+                .join(" + ")
+        })
+        .unwrap_or_default()
+}
+
+/// The `<T: Bound>` parameters and the `where` clause of an item
+fn render_generics(generics: Option<&Value>) -> (String, String) {
+    let Some(generics) = generics else {
+        return (String::new(), String::new());
+    };
+    let params: Vec<String> = generics
+        .get("params")
+        .and_then(Value::as_array)
+        .map(|params| {
+            params
+                .iter()
+                .filter_map(|param| {
+                    let name = param.get("name").and_then(Value::as_str)?;
+                    let kind = param.get("kind")?;
+                    if let Some(ty) = kind.get("type") {
+                        // `impl Trait` arguments show up as synthetic parameters
+                        if flag(ty, &["is_synthetic", "synthetic"]) {
+                            return None;
+                        }
+                        let bounds = render_bounds(ty.get("bounds"));
+                        return Some(match bounds.is_empty() {
+                            true => name.to_string(),
+                            false => format!("{name}: {bounds}"),
+                        });
+                    }
+                    if let Some(constant) = kind.get("const") {
+                        return Some(format!(
+                            "const {name}: {}",
+                            render_type(constant.get("type").unwrap_or(&Value::Null))
+                        ));
+                    }
+                    Some(name.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let predicates: Vec<String> = generics
+        .get("where_predicates")
+        .and_then(Value::as_array)
+        .map(|predicates| {
+            predicates
+                .iter()
+                .filter_map(|predicate| {
+                    let bound = predicate.get("bound_predicate")?;
+                    Some(format!(
+                        "{}: {}",
+                        render_type(bound.get("type").unwrap_or(&Value::Null)),
+                        render_bounds(bound.get("bounds"))
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let params = match params.is_empty() {
+        true => String::new(),
+        false => format!("<{}>", params.join(", ")),
+    };
+    let where_clause = match predicates.is_empty() {
+        true => String::new(),
+        false => format!(" where {}", predicates.join(", ")),
+    };
+    (params, where_clause)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The JSON rustdoc (format version 57) wrote for a small crate, without the
+    /// auto trait and blanket impls and the paths of other crates
+    fn fixture() -> CrateDocs {
+        json_docs(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rustdoc_json.json"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_json_docs() {
+        let docs = fixture();
+        let page = |key: &str| {
+            docs.pages
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, markdown)| markdown.as_str())
+                .unwrap_or_else(|| panic!("no page {key}"))
+        };
+        let mut keys = docs
+            .pages
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "enum Greeting",
+                "fn hello",
+                "index.html",
+                "struct Greeter",
+                "util/constant.DEFAULT_NAME.html",
+                "util/index.html"
+            ]
+        );
+
+        let index = page("index.html");
+        assert!(index.starts_with("# Crate fixture\n\nA small crate to test"));
+        assert!(index.contains("## Re-exports\n\npub use inner::Greeter;\n"));
+        assert!(index.contains("## Functions\n\n- `hello`: Greets the world.\n"));
+        assert!(index.contains("## Modules\n\n- `util`: Helpers for greetings.\n"));
+
+        assert!(page("fn hello").starts_with(
+            "# Function fixture::hello\n\n```rust\npub fn hello(name: &str) -> String\n```"
+        ));
+        let greeter = page("struct Greeter");
+        assert!(greeter.contains("pub struct Greeter {\n    pub name: String,\n}"));
+        assert!(greeter.contains("## Methods\n\n### `pub fn new(name: &str) -> Self`"));
+        assert!(
+            page("enum Greeting")
+                .contains("pub enum Greeting {\n    Short,\n    Named(String),\n}")
+        );
+        assert!(
+            page("util/constant.DEFAULT_NAME.html")
+                .contains("pub const DEFAULT_NAME: &str = \"world\";")
+        );
+
+        // Re-exported from a private module
+        assert_eq!(
+            docs.aliases,
+            [("inner::Greeter".to_string(), "struct Greeter".to_string())]
+        );
+        let mut modules = docs
+            .modules
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        modules.sort();
+        assert_eq!(modules, ["", "util"]);
+    }
+
+    #[test]
+    fn test_render_fn() {
+        let function = json!({
+            "sig": {
+                "inputs": [
+                    ["self", {"borrowed_ref": {"lifetime": null, "is_mutable": false, "type": {"generic": "Self"}}}],
+                    ["msg", {"generic": "T"}]
+                ],
+                "output": {"resolved_path": {"path": "Result", "id": 1, "args": {"angle_bracketed": {
+                    "args": [{"type": {"tuple": []}}, {"type": {"resolved_path": {"path": "SendError", "id": 2, "args": {"angle_bracketed": {"args": [{"type": {"generic": "T"}}], "constraints": []}}}}}],
+                    "constraints": []
+                }}}}
+            },
+            "generics": {"params": [], "where_predicates": []},
+            "header": {"is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust"}
+        });
+        assert_eq!(
+            render_fn("send", &function),
+            "fn send(&self, msg: T) -> Result<(), SendError<T>>"
+        );
+    }
+}
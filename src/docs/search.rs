@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, STORED, STRING, Schema, TEXT, Value};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{Index, IndexWriter, TantivyDocument, Term, doc};
+
+use super::store::DocsStore;
+
+/// Memory budget of the index writer
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// A full-text index over the markdown docs of all dependencies
+pub struct SearchIndex {
+    index: Index,
+    krate: Field,
+    symbol: Field,
+    body: Field,
+}
+
+/// A search result with a highlighted excerpt of the docs
+#[derive(Debug)]
+pub struct SearchHit {
+    pub krate: String,
+    pub symbol: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+fn index_dir(project: &crate::project::Project) -> PathBuf {
+    project.cache_dir().join("search_index")
+}
+
+fn schema() -> (Schema, Field, Field, Field) {
+    let mut builder = Schema::builder();
+    let krate = builder.add_text_field("crate", STRING | STORED);
+    let symbol = builder.add_text_field("symbol", TEXT | STORED);
+    let body = builder.add_text_field("body", TEXT | STORED);
+    (builder.build(), krate, symbol, body)
+}
+
+impl SearchIndex {
+    /// Rebuild the index from the docs store
+    pub fn build(project: &crate::project::Project, store: &DocsStore) -> Result<()> {
+        let dir = index_dir(project);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        let (schema, krate, symbol, body) = schema();
+        let index = Index::create_in_dir(&dir, schema)?;
+        let mut writer: IndexWriter = index.writer(WRITER_MEMORY)?;
+        store.for_each_crate_page(|crate_name, symbol_name, markdown| {
+            writer.add_document(doc!(
+                krate => crate_name,
+                symbol => symbol_name,
+                body => markdown,
+            ))?;
+            Ok(())
+        })?;
+        writer.commit()?;
+        Ok(())
+    }
+
+    pub fn open(project: &crate::project::Project) -> Result<Self> {
+        let dir = index_dir(project);
+        if !dir.join("meta.json").exists() {
+            anyhow::bail!("The docs search index has not been built yet");
+        }
+        Self::open_dir(&dir)
+    }
+
+    fn open_dir(dir: &Path) -> Result<Self> {
+        let index = Index::open_in_dir(dir)?;
+        let schema = index.schema();
+        Ok(Self {
+            krate: schema.get_field("crate")?,
+            symbol: schema.get_field("symbol")?,
+            body: schema.get_field("body")?,
+            index,
+        })
+    }
+
+    /// Search the docs of all dependencies, or only of `krate`
+    pub fn search(&self, query: &str, krate: Option<&str>, limit: usize) -> Result<Vec<SearchHit>> {
+        let searcher = self.index.reader()?.searcher();
+        let parser = QueryParser::for_index(&self.index, vec![self.symbol, self.body]);
+        // Queries are written by agents, don't fail on stray syntax
+        let (text_query, _) = parser.parse_query_lenient(query);
+        let query: Box<dyn Query> = match krate {
+            Some(krate) => Box::new(BooleanQuery::new(vec![
+                (Occur::Must, text_query),
+                (
+                    Occur::Must,
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(self.krate, krate),
+                        IndexRecordOption::Basic,
+                    )),
+                ),
+            ])),
+            None => text_query,
+        };
+
+        // `TopDocs` panics with a limit of 0
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit.max(1)))?;
+        let snippets = SnippetGenerator::create(&searcher, &*query, self.body)?;
+        let mut hits = Vec::new();
+        for (score, address) in top_docs {
+            let doc = searcher.doc::<TantivyDocument>(address)?;
+            let text = |field| {
+                doc.get_first(field)
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let snippet = snippets.snippet_from_doc(&doc);
+            let mut highlighted = String::new();
+            let mut start = 0;
+            for range in snippet.highlighted() {
+                highlighted.push_str(&snippet.fragment()[start..range.start]);
+                highlighted.push_str(&format!("**{}**", &snippet.fragment()[range.clone()]));
+                start = range.end;
+            }
+            highlighted.push_str(&snippet.fragment()[start..]);
+            hits.push(SearchHit {
+                krate: text(self.krate),
+                symbol: text(self.symbol),
+                snippet: highlighted,
+                score,
+            });
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let dir =
+            std::env::temp_dir().join(format!("cursor-rust-tools-search-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (schema, krate, symbol, body) = schema();
+        let index = Index::create_in_dir(&dir, schema).unwrap();
+        let mut writer: IndexWriter = index.writer(WRITER_MEMORY).unwrap();
+        let docs = [
+            (
+                "tokio",
+                "struct Sender",
+                "Sends values, applies backpressure when full",
+            ),
+            ("tokio", "fn spawn", "Spawns a new asynchronous task"),
+            (
+                "flume",
+                "struct Sender",
+                "A bounded channel with backpressure",
+            ),
+        ];
+        for (crate_name, symbol_name, markdown) in docs {
+            writer
+                .add_document(doc!(krate => crate_name, symbol => symbol_name, body => markdown))
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let index = SearchIndex::open_dir(&dir).unwrap();
+        let hits = index.search("backpressure", None, 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[0].snippet.contains("**backpressure**"));
+        let hits = index.search("backpressure", Some("flume"), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].symbol, "struct Sender");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
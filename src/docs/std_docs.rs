@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+
+use super::extract_md::extract_md;
+use super::utils::parse_rust_symbol;
+
+/// The crates of the standard library. `std` comes first because it re-exports
+/// most of `core` and `alloc` and its pages are what users usually mean.
+const STD_CRATES: [&str; 4] = ["std", "core", "alloc", "proc_macro"];
+
+/// The maximum number of pages returned for a query
+const MAX_PAGES: usize = 3;
+
+/// The maximum number of lines returned for a single method / field
+const MEMBER_LINES: usize = 60;
+
+/// The HTML docs of the standard library, installed with `rustup component add rust-docs`
+#[derive(Debug)]
+pub struct StdDocs {
+    /// Item name to the pages documenting it (e.g. `Vec` -> `std/vec/struct.Vec.html`)
+    items: HashMap<String, Vec<PathBuf>>,
+    root: PathBuf,
+}
+
+impl StdDocs {
+    /// The docs of the toolchain that is active for the project
+    pub async fn locate(project_root: &Path) -> Result<PathBuf> {
+        let output = tokio::process::Command::new("rustc")
+            .current_dir(project_root)
+            .args(["--print", "sysroot"])
+            .output()
+            .await?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr));
+        }
+        let sysroot = String::from_utf8(output.stdout)?;
+        let root = PathBuf::from(sysroot.trim()).join("share/doc/rust/html");
+        if !root.join("std").exists() {
+            anyhow::bail!(
+                "No standard library docs found in {:?}. Install them with `rustup component add rust-docs`",
+                root
+            );
+        }
+        Ok(root)
+    }
+
+    pub fn new(root: PathBuf) -> Self {
+        let mut items: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for krate in STD_CRATES {
+            let walker = WalkBuilder::new(root.join(krate)).hidden(false).build();
+            for entry in walker.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                let Some(symbol) = parse_rust_symbol(file_name) else {
+                    continue;
+                };
+                let Ok(relative) = path.strip_prefix(&root) else {
+                    continue;
+                };
+                items
+                    .entry(symbol.name().to_string())
+                    .or_default()
+                    .push(relative.to_path_buf());
+            }
+        }
+        for pages in items.values_mut() {
+            pages.sort_by_key(|page| page_rank(page));
+        }
+        Self { items, root }
+    }
+
+    /// The docs for a path like `Vec`, `Vec::drain`, `collections::HashMap` or
+    /// `core::iter::Iterator::scan` as (page, markdown) pairs
+    pub fn lookup(&self, query: &str) -> Result<Vec<(String, String)>> {
+        let segments: Vec<&str> = query
+            .trim()
+            .trim_end_matches("()")
+            .split("::")
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let Some((last, modules)) = segments.split_last() else {
+            anyhow::bail!("Empty query");
+        };
+
+        // `Vec::drain` is a method on `Vec`, `collections::HashMap` is the item `HashMap`
+        let (item, modules, member) = if self.items.contains_key(*last) {
+            (*last, modules, None)
+        } else if let Some((item, modules)) = modules.split_last() {
+            (*item, modules, Some(*last))
+        } else {
+            anyhow::bail!("No documentation found for `{query}`");
+        };
+
+        let Some(pages) = self.items.get(item) else {
+            anyhow::bail!("No documentation found for `{query}`");
+        };
+        let pages = pages
+            .iter()
+            .filter(|page| {
+                let page_modules = page
+                    .parent()
+                    .map(|parent| {
+                        parent
+                            .iter()
+                            .filter_map(|component| component.to_str())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                modules.iter().all(|module| page_modules.contains(module))
+            })
+            .collect::<Vec<_>>();
+        if pages.is_empty() {
+            anyhow::bail!("No documentation found for `{query}`");
+        }
+
+        let mut docs = Vec::new();
+        let mut seen_paths = Vec::new();
+        for page in pages {
+            if docs.len() == MAX_PAGES {
+                break;
+            }
+            // `std` re-exports most of `core` and `alloc` under the same path
+            let path_in_crate = page.iter().skip(1).collect::<PathBuf>();
+            if seen_paths.contains(&path_in_crate) {
+                continue;
+            }
+            seen_paths.push(path_in_crate);
+            let html = std::fs::read_to_string(self.root.join(page))?;
+            // Moved items leave a page redirecting to the new location
+            if html.contains("http-equiv=\"refresh\"") {
+                continue;
+            }
+            let markdown = strip_sidebar(&extract_md(&html));
+            let content = match member {
+                Some(member) => match member_section(&markdown, member) {
+                    Some(section) => section,
+                    None => continue,
+                },
+                None => markdown,
+            };
+            docs.push((page.display().to_string(), content));
+        }
+        if docs.is_empty() {
+            anyhow::bail!("No documentation found for `{query}`");
+        }
+        Ok(docs)
+    }
+}
+
+/// `std` pages first, then the shortest path
+fn page_rank(page: &Path) -> (usize, usize) {
+    let krate = page
+        .iter()
+        .next()
+        .and_then(|krate| krate.to_str())
+        .and_then(|krate| STD_CRATES.iter().position(|c| *c == krate))
+        .unwrap_or(STD_CRATES.len());
+    (krate, page.iter().count())
+}
+
+/// Item pages start with the sidebar listing all sections, methods and trait
+/// implementations. The content starts at the title (`Struct HashMap Copy item path`).
+fn strip_sidebar(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let Some(title) = lines
+        .iter()
+        .position(|line| line.ends_with("Copy item path"))
+    else {
+        return markdown.to_string();
+    };
+    let mut content = vec![lines[title].trim_end_matches("Copy item path").trim_end()];
+    content.extend(&lines[title + 1..]);
+    content.join("\n")
+}
+
+/// The part of an item page documenting a method, associated item or field.
+/// Members are documented under `####` headings, the declaration summary at
+/// the top of trait pages is only used if there is no heading.
+fn member_section(markdown: &str, member: &str) -> Option<String> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let is_declaration = |line: &str| {
+        [
+            format!("fn {member}("),
+            format!("fn {member}<"),
+            format!("const {member}:"),
+            format!("type {member}"),
+            format!("{member}: "),
+        ]
+        .iter()
+        .any(|pattern| line.contains(pattern.as_str()))
+    };
+    let heading = lines
+        .iter()
+        .position(|line| line.starts_with("#### ") && is_declaration(line));
+    let is_heading = heading.is_some();
+    let start = match heading {
+        Some(start) => start,
+        None => lines.iter().position(|line| is_declaration(line))?,
+    };
+    let is_next_member = |line: &str| {
+        if is_heading {
+            line.starts_with("#### ")
+        } else {
+            line.contains("fn ") && line.contains('(') && !line.trim().starts_with("//")
+        }
+    };
+    let end = lines
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, line)| is_next_member(line))
+        .map(|(index, _)| index)
+        .unwrap_or(lines.len())
+        .min(start + MEMBER_LINES);
+    Some(lines[start..end].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_member_section() {
+        let markdown = "pub fn len(&self) -> usize\nReturns the number of elements\npub fn drain<R>(&mut self, range: R) -> Drain<'_, T, A>\nRemoves the subslice\nExamples\npub fn clear(&mut self)\nClears the vector";
+        assert_eq!(
+            member_section(markdown, "drain").unwrap(),
+            "pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, A>\nRemoves the subslice\nExamples"
+        );
+        assert!(member_section(markdown, "scan").is_none());
+
+        let markdown = "    fn scan<St>(self, initial_state: St) { ... }\n    fn map<B>(self) { ... }\n#### fn scan<St>(self, initial_state: St) ####\nAn iterator adapter\n##### §Examples #####\n#### fn map<B>(self) ####";
+        assert_eq!(
+            member_section(markdown, "scan").unwrap(),
+            "#### fn scan<St>(self, initial_state: St) ####\nAn iterator adapter\n##### §Examples #####"
+        );
+    }
+
+    #[test]
+    fn test_page_rank() {
+        let mut pages = [
+            PathBuf::from("core/iter/traits/iterator/trait.Iterator.html"),
+            PathBuf::from("std/iter/trait.Iterator.html"),
+            PathBuf::from("std/iter/traits/trait.Iterator.html"),
+        ];
+        pages.sort_by_key(|page| page_rank(page));
+        assert_eq!(pages[0], PathBuf::from("std/iter/trait.Iterator.html"));
+    }
+}
@@ -0,0 +1,743 @@
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::cache::CrateUsage;
+use super::extract_md::{ModuleDocs, code_examples};
+use super::index::page_path;
+
+/// Bumped whenever the way pages are stored changes, which re-indexes all crates
+pub const CACHE_FORMAT: u32 = 7;
+
+/// The format of the `docs_cache.json` files that can be imported
+const JSON_CACHE_FORMAT: u32 = 3;
+
+/// The tables of a project: which crate versions it uses, when they were last
+/// looked up and how they're named
+const PROJECT_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+    CREATE TABLE IF NOT EXISTS crates (
+        name TEXT PRIMARY KEY,
+        version TEXT NOT NULL,
+        last_used INTEGER NOT NULL DEFAULT 0,
+        evicted INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS aliases (alias TEXT PRIMARY KEY, name TEXT NOT NULL);
+";
+
+/// The tables of [`pages_schema`], which hold the docs of a crate version
+const PAGE_TABLES: [&str; 4] = ["pages", "modules", "examples", "paths"];
+
+/// The docs of every crate version, in the project's database or in the shared one
+fn pages_schema(database: &str) -> String {
+    format!(
+        "
+        CREATE TABLE IF NOT EXISTS {database}.pages (
+            crate TEXT NOT NULL,
+            version TEXT NOT NULL,
+            key TEXT NOT NULL,
+            markdown TEXT NOT NULL,
+            PRIMARY KEY (crate, version, key)
+        );
+        CREATE TABLE IF NOT EXISTS {database}.modules (
+            crate TEXT NOT NULL,
+            version TEXT NOT NULL,
+            path TEXT NOT NULL,
+            title TEXT NOT NULL,
+            overview TEXT NOT NULL,
+            PRIMARY KEY (crate, version, path)
+        );
+        CREATE TABLE IF NOT EXISTS {database}.examples (
+            crate TEXT NOT NULL,
+            version TEXT NOT NULL,
+            key TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            code TEXT NOT NULL,
+            PRIMARY KEY (crate, version, key, position)
+        );
+        CREATE TABLE IF NOT EXISTS {database}.paths (
+            crate TEXT NOT NULL,
+            version TEXT NOT NULL,
+            path TEXT NOT NULL,
+            key TEXT NOT NULL,
+            PRIMARY KEY (crate, version, path)
+        );
+        "
+    )
+}
+
+/// The indexed docs of all dependencies of a project, stored in `docs.sqlite`
+/// in the cache dir. Pages are read when they're asked for instead of loading
+/// the docs of every crate into memory.
+///
+/// With a shared cache the pages are stored in a database in the shared cache dir
+/// that is attached as `shared`, keyed by crate and version, so that every version
+/// is only indexed once for all projects.
+#[derive(Debug)]
+pub struct DocsStore {
+    connection: Connection,
+    /// Where the pages are stored, to notice when a project switches between caches
+    location: String,
+    is_shared: bool,
+}
+
+/// The docs of a crate version as they're indexed
+#[derive(Debug, Default)]
+pub struct CrateDocs {
+    /// Keyed like the pages of the HTML docs (`sync/struct.Mutex.html`, `struct Mutex` at the top level)
+    pub pages: Vec<(String, String)>,
+    /// Module overviews keyed by the module path (empty for the crate root)
+    pub modules: Vec<(String, ModuleDocs)>,
+    /// Other paths of items than the one of their page, like the private module a
+    /// re-exported item is defined in, to the key of their page
+    pub aliases: Vec<(String, String)>,
+}
+
+/// The cache of earlier versions, a single JSON file that is imported once
+#[derive(Deserialize)]
+struct JsonCache {
+    #[serde(default)]
+    format: u32,
+    hash: String,
+    deps: HashMap<String, HashMap<String, String>>,
+    crate_versions: HashMap<String, String>,
+    #[serde(default)]
+    aliases: HashMap<String, String>,
+    #[serde(default)]
+    feature_args: Vec<String>,
+    #[serde(default)]
+    modules: HashMap<String, BTreeMap<String, ModuleDocs>>,
+}
+
+impl DocsStore {
+    pub fn open(project: &crate::project::Project) -> Result<Self> {
+        fs::create_dir_all(project.cache_dir())?;
+        let shared = match project.shared_cache_dir() {
+            Some(dir) => {
+                fs::create_dir_all(&dir)?;
+                // A new format starts a new database, other projects may still use the old one
+                Some(dir.join(format!("docs-v{CACHE_FORMAT}.sqlite")))
+            }
+            None => None,
+        };
+        let mut store =
+            Self::open_path(&project.cache_dir().join("docs.sqlite"), shared.as_deref())?;
+        let json_path = project.cache_dir().join("docs_cache.json");
+        if json_path.exists() {
+            store.import_json(&json_path)?;
+            fs::remove_file(json_path)?;
+        }
+        Ok(store)
+    }
+
+    fn open_path(path: &Path, shared: Option<&Path>) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        // Readers keep working while the docs are re-indexed
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        // The shared database may be written by another project at the same time
+        connection.busy_timeout(Duration::from_secs(30))?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            [],
+        )?;
+
+        let mut store = Self {
+            connection,
+            location: String::new(),
+            is_shared: shared.is_some(),
+        };
+        // Tables of an older format have different columns, and with a shared cache
+        // the tables of the project would shadow the shared ones
+        let is_outdated = store.is_outdated()?;
+        if shared.is_some() || is_outdated {
+            for table in PAGE_TABLES {
+                store
+                    .connection
+                    .execute(&format!("DROP TABLE IF EXISTS main.{table}"), [])?;
+            }
+        }
+        if is_outdated {
+            store
+                .connection
+                .execute("DROP TABLE IF EXISTS crates", [])?;
+        }
+        store.connection.execute_batch(PROJECT_SCHEMA)?;
+        match shared {
+            Some(shared) => {
+                store
+                    .connection
+                    .execute("ATTACH DATABASE ?1 AS shared", [shared.to_string_lossy()])?;
+                store
+                    .connection
+                    .pragma_update(Some("shared"), "journal_mode", "WAL")?;
+                store.connection.execute_batch(&pages_schema("shared"))?;
+                store.location = shared.to_string_lossy().to_string();
+            }
+            None => store.connection.execute_batch(&pages_schema("main"))?,
+        }
+
+        // The versions are only indexed if the pages are where they're looked up
+        if store.meta("location")?.unwrap_or_default() != store.location {
+            store.clear_versions()?;
+            store.set_meta("location", &store.location)?;
+        }
+        Ok(store)
+    }
+
+    fn import_json(&mut self, path: &Path) -> Result<()> {
+        let mut cache: JsonCache = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if cache.format != JSON_CACHE_FORMAT || !self.is_outdated()? {
+            return Ok(());
+        }
+        tracing::info!("Importing the docs cache from {}", path.display());
+        self.reset()?;
+        for (name, pages) in cache.deps {
+            let Some(version) = cache.crate_versions.get(&name) else {
+                continue;
+            };
+            let docs = CrateDocs {
+                pages: pages.into_iter().collect(),
+                modules: cache
+                    .modules
+                    .remove(&name)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect(),
+                aliases: Vec::new(),
+            };
+            self.replace_crate(&name, version, docs)?;
+        }
+        for (name, version) in &cache.crate_versions {
+            self.set_crate_version(name, version)?;
+        }
+        self.set_aliases(&cache.aliases)?;
+        self.set_feature_args(&cache.feature_args)?;
+        self.set_hash(&cache.hash)?;
+        Ok(())
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            [key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Stores in an older format are re-indexed from scratch
+    pub fn is_outdated(&self) -> Result<bool> {
+        Ok(self.meta("format")? != Some(CACHE_FORMAT.to_string()))
+    }
+
+    /// Remove all docs of the project and mark the store as being in the current
+    /// format. The pages of a shared cache stay, other projects use them.
+    pub fn reset(&mut self) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute_batch("DELETE FROM meta; DELETE FROM crates; DELETE FROM aliases;")?;
+        if !self.is_shared {
+            for table in PAGE_TABLES {
+                transaction.execute(&format!("DELETE FROM {table}"), [])?;
+            }
+        }
+        transaction.execute(
+            "INSERT INTO meta (key, value) VALUES ('format', ?1), ('location', ?2)",
+            [CACHE_FORMAT.to_string(), self.location.clone()],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The hash of `Cargo.lock` when the docs were indexed
+    pub fn hash(&self) -> Result<String> {
+        Ok(self.meta("hash")?.unwrap_or_default())
+    }
+
+    pub fn set_hash(&self, hash: &str) -> Result<()> {
+        self.set_meta("hash", hash)
+    }
+
+    /// The feature arguments `cargo doc` ran with
+    pub fn feature_args(&self) -> Result<Vec<String>> {
+        match self.meta("feature_args")? {
+            Some(args) => Ok(serde_json::from_str(&args)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn set_feature_args(&self, args: &[String]) -> Result<()> {
+        self.set_meta("feature_args", &serde_json::to_string(args)?)
+    }
+
+    /// The version of every indexed crate
+    pub fn crate_versions(&self) -> Result<HashMap<String, String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name, version FROM crates")?;
+        let versions = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(versions)
+    }
+
+    /// Indexing a crate counts as using it and brings it back if it was evicted
+    pub fn set_crate_version(&self, name: &str, version: &str) -> Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO crates (name, version, last_used) VALUES (?1, ?2, ?3)",
+            params![name, version, now()],
+        )?;
+        Ok(())
+    }
+
+    /// Remember that the docs of a crate were looked up, the least recently used
+    /// crates are evicted first
+    pub fn touch(&self, name: &str) -> Result<()> {
+        self.connection.execute(
+            "UPDATE crates SET last_used = ?2 WHERE name = ?1",
+            params![name, now()],
+        )?;
+        Ok(())
+    }
+
+    /// Drop the docs of a crate to save space. The crate keeps its version, so that it
+    /// isn't documented again until [`Self::restore`] is called. Only the project stops
+    /// using the docs with a shared cache.
+    pub fn evict(&mut self, name: &str) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("UPDATE crates SET evicted = 1 WHERE name = ?1", [name])?;
+        if !self.is_shared {
+            for table in PAGE_TABLES {
+                transaction.execute(&format!("DELETE FROM {table} WHERE crate = ?1"), [name])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Forget the version of an evicted crate, so that the next run documents and
+    /// indexes it again. `false` if that was requested already.
+    pub fn restore(&self, name: &str) -> Result<bool> {
+        let changed = self.connection.execute(
+            "UPDATE crates SET version = '' WHERE name = ?1 AND evicted = 1 AND version != ''",
+            [name],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// The size of the indexed docs of every crate, without the generated docs on disk
+    pub fn crate_usage(&self) -> Result<Vec<CrateUsage>> {
+        let mut statement = self.connection.prepare(
+            "SELECT name, crates.version, last_used, evicted,
+                 (SELECT COALESCE(SUM(length(CAST(markdown AS BLOB))), 0) FROM pages
+                  WHERE crate = name AND pages.version = crates.version)
+                 + (SELECT COALESCE(SUM(length(CAST(code AS BLOB))), 0) FROM examples
+                  WHERE crate = name AND examples.version = crates.version)
+             FROM crates ORDER BY name",
+        )?;
+        let usage = statement
+            .query_map([], |row| {
+                Ok(CrateUsage {
+                    name: row.get(0)?,
+                    version: row.get(1)?,
+                    last_used: row.get(2)?,
+                    evicted: row.get(3)?,
+                    index_bytes: row.get(4)?,
+                    file_bytes: 0,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(usage)
+    }
+
+    /// Forget the versions, so that every crate is indexed again. The pages stay until then.
+    pub fn clear_versions(&self) -> Result<()> {
+        self.connection.execute("DELETE FROM crates", [])?;
+        Ok(())
+    }
+
+    /// Drop the docs of every crate for which `keep` returns `false`. Only the
+    /// project stops using them with a shared cache.
+    pub fn retain_crates(&mut self, keep: impl Fn(&str) -> bool) -> Result<()> {
+        let mut names = self.crate_versions()?.into_keys().collect::<Vec<_>>();
+        if !self.is_shared {
+            let mut statement = self
+                .connection
+                .prepare("SELECT DISTINCT crate FROM pages")?;
+            for name in statement.query_map([], |row| row.get::<_, String>(0))? {
+                names.push(name?);
+            }
+        }
+
+        let transaction = self.connection.transaction()?;
+        for name in names.iter().filter(|name| !keep(name)) {
+            transaction.execute("DELETE FROM crates WHERE name = ?1", [name])?;
+            if !self.is_shared {
+                for table in PAGE_TABLES {
+                    transaction
+                        .execute(&format!("DELETE FROM {table} WHERE crate = ?1"), [name])?;
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Renamed dependencies (`alias = { package = "name" }`) to their crate name
+    pub fn set_aliases(&mut self, aliases: &HashMap<String, String>) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM aliases", [])?;
+        for (alias, name) in aliases {
+            transaction.execute(
+                "INSERT INTO aliases (alias, name) VALUES (?1, ?2)",
+                [alias, name],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Replace the docs of a crate version in one transaction, so that readers see
+    /// either the old or the new docs. The code examples of the pages and the paths
+    /// of the items are stored separately. Without a shared cache the docs of the
+    /// other versions are dropped as well.
+    pub fn replace_crate(&mut self, name: &str, version: &str, docs: CrateDocs) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        for table in PAGE_TABLES {
+            if self.is_shared {
+                transaction.execute(
+                    &format!("DELETE FROM {table} WHERE crate = ?1 AND version = ?2"),
+                    [name, version],
+                )?;
+            } else {
+                transaction.execute(&format!("DELETE FROM {table} WHERE crate = ?1"), [name])?;
+            }
+        }
+        {
+            let mut insert_page = transaction.prepare(
+                "INSERT OR REPLACE INTO pages (crate, version, key, markdown) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut insert_example = transaction.prepare(
+                "INSERT OR REPLACE INTO examples (crate, version, key, position, code) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            // The first page or alias of a path wins
+            let mut insert_path = transaction.prepare(
+                "INSERT OR IGNORE INTO paths (crate, version, path, key) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (key, markdown) in docs.pages {
+                for (position, code) in code_examples(&markdown).into_iter().enumerate() {
+                    insert_example.execute(params![name, version, key, position, code])?;
+                }
+                if let Some(path) = page_path(&key) {
+                    insert_path.execute(params![name, version, path, key])?;
+                }
+                insert_page.execute(params![name, version, key, markdown])?;
+            }
+            for (path, key) in docs.aliases {
+                insert_path.execute(params![name, version, path, key])?;
+            }
+            let mut insert_module = transaction.prepare(
+                "INSERT OR REPLACE INTO modules (crate, version, path, title, overview) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (path, module) in docs.modules {
+                insert_module.execute(params![
+                    name,
+                    version,
+                    path,
+                    module.title,
+                    module.overview
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Whether the docs of the crate version are stored, e.g. by another project
+    pub fn has_pages(&self, name: &str, version: &str) -> Result<bool> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT 1 FROM pages WHERE crate = ?1 AND version = ?2 LIMIT 1",
+                [name, version],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// The name under which the docs of a dependency are stored. Accepts the package
+    /// name, the name it was renamed to with `package = ...` and the library name (`-` → `_`).
+    pub fn crate_key(&self, dependency: &str) -> Result<Option<String>> {
+        self.find_crate(dependency, false)
+    }
+
+    /// The name of the dependency if its docs were evicted
+    pub fn evicted_crate(&self, dependency: &str) -> Result<Option<String>> {
+        self.find_crate(dependency, true)
+    }
+
+    fn find_crate(&self, dependency: &str, evicted: bool) -> Result<Option<String>> {
+        let is_indexed = |name: &str| -> Result<bool> {
+            Ok(self
+                .connection
+                .query_row(
+                    "SELECT 1 FROM crates WHERE name = ?1 AND evicted = ?2",
+                    params![name, evicted],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some())
+        };
+        if is_indexed(dependency)? {
+            return Ok(Some(dependency.to_string()));
+        }
+        let alias: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT name FROM aliases WHERE alias = ?1",
+                [dependency],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(name) = alias {
+            return Ok(is_indexed(&name)?.then_some(name));
+        }
+        let library_name = dependency.replace('-', "_");
+        Ok(is_indexed(&library_name)?.then_some(library_name))
+    }
+
+    /// The keys of all pages of a crate, sorted
+    pub fn page_keys(&self, krate: &str) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            // Joined with the crates, so that only the version the project uses is found
+            .prepare(
+                "SELECT key FROM pages
+                 JOIN crates ON crate = name AND pages.version = crates.version
+                 WHERE crate = ?1 ORDER BY key",
+            )?;
+        let keys = statement
+            .query_map([krate], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(keys)
+    }
+
+    pub fn page(&self, krate: &str, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT markdown FROM pages
+                 JOIN crates ON crate = name AND pages.version = crates.version
+                 WHERE crate = ?1 AND key = ?2",
+                [krate, key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Call `f` with the key and markdown of every page of a crate
+    pub fn for_each_page(&self, krate: &str, mut f: impl FnMut(&str, &str)) -> Result<()> {
+        let mut statement = self.connection.prepare(
+            "SELECT key, markdown FROM pages
+                 JOIN crates ON crate = name AND pages.version = crates.version
+                 WHERE crate = ?1",
+        )?;
+        let mut rows = statement.query([krate])?;
+        while let Some(row) = rows.next()? {
+            f(row.get_ref(0)?.as_str()?, row.get_ref(1)?.as_str()?);
+        }
+        Ok(())
+    }
+
+    /// Call `f` with the crate, key and markdown of every page of the project
+    pub fn for_each_crate_page(
+        &self,
+        mut f: impl FnMut(&str, &str, &str) -> Result<()>,
+    ) -> Result<()> {
+        let mut statement = self.connection.prepare(
+            "SELECT crate, key, markdown FROM pages
+                 JOIN crates ON crate = name AND pages.version = crates.version
+                 WHERE evicted = 0",
+        )?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            f(
+                row.get_ref(0)?.as_str()?,
+                row.get_ref(1)?.as_str()?,
+                row.get_ref(2)?.as_str()?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The key of the page of the item at `path` (without the crate name), which
+    /// can also be the path it was re-exported from
+    pub fn resolve_path(&self, krate: &str, path: &str) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT key FROM paths
+                 JOIN crates ON crate = name AND paths.version = crates.version
+                 WHERE crate = ?1 AND path = ?2",
+                [krate, path],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// The code examples of a crate as the key of their page and the code, in page order
+    pub fn examples(&self, krate: &str) -> Result<Vec<(String, String)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT key, code FROM examples
+             JOIN crates ON crate = name AND examples.version = crates.version
+             WHERE crate = ?1 ORDER BY key, position",
+        )?;
+        let examples = statement
+            .query_map([krate], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(examples)
+    }
+
+    /// The module overviews of a crate keyed by the module path (empty for the crate root)
+    pub fn modules(&self, krate: &str) -> Result<BTreeMap<String, ModuleDocs>> {
+        let mut statement = self.connection.prepare(
+            "SELECT path, title, overview FROM modules
+                 JOIN crates ON crate = name AND modules.version = crates.version
+                 WHERE crate = ?1",
+        )?;
+        let modules = statement
+            .query_map([krate], |row| {
+                Ok((
+                    row.get(0)?,
+                    ModuleDocs {
+                        title: row.get(1)?,
+                        overview: row.get(2)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(modules)
+    }
+}
+
+/// Seconds since the epoch
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store() {
+        let dir =
+            std::env::temp_dir().join(format!("cursor-rust-tools-store-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut store = DocsStore::open_path(&dir.join("project.sqlite"), None).unwrap();
+        assert!(store.is_outdated().unwrap());
+        store.reset().unwrap();
+        assert!(!store.is_outdated().unwrap());
+
+        let pages = vec![(
+            "struct Sender".to_string(),
+            "# Struct Sender\n\n```rust\npub struct Sender\n```\n\nA sender\n\n```\ntx.send(1);\n```".to_string(),
+        )];
+        let modules = vec![(
+            String::new(),
+            ModuleDocs {
+                title: "Crate flume".to_string(),
+                overview: "Channels".to_string(),
+            },
+        )];
+        let docs = || CrateDocs {
+            pages: pages.clone(),
+            modules: modules.clone(),
+            aliases: vec![("sender::Sender".to_string(), "struct Sender".to_string())],
+        };
+        store.replace_crate("flume", "0.11.1", docs()).unwrap();
+        // Pages are only visible once the version is indexed
+        assert_eq!(store.crate_key("flume").unwrap(), None);
+        store.set_crate_version("flume", "0.11.1").unwrap();
+        store
+            .set_aliases(&HashMap::from([(
+                "channels".to_string(),
+                "flume".to_string(),
+            )]))
+            .unwrap();
+
+        assert_eq!(
+            store.crate_key("channels").unwrap().as_deref(),
+            Some("flume")
+        );
+        assert_eq!(store.crate_key("tokio").unwrap(), None);
+        assert_eq!(store.page_keys("flume").unwrap(), ["struct Sender"]);
+        assert_eq!(
+            store.page("flume", "struct Sender").unwrap(),
+            Some(pages[0].1.clone())
+        );
+        assert_eq!(
+            store.examples("flume").unwrap(),
+            [("struct Sender".to_string(), "tx.send(1);".to_string())]
+        );
+        assert_eq!(
+            store.modules("flume").unwrap(),
+            modules.clone().into_iter().collect::<BTreeMap<_, _>>()
+        );
+        // Both the path of the page and the alias resolve
+        for path in ["Sender", "sender::Sender"] {
+            assert_eq!(
+                store.resolve_path("flume", path).unwrap().as_deref(),
+                Some("struct Sender")
+            );
+        }
+
+        // Evicted crates aren't found until they're indexed again
+        assert!(store.crate_usage().unwrap()[0].index_bytes > 0);
+        store.evict("flume").unwrap();
+        assert_eq!(store.crate_key("flume").unwrap(), None);
+        assert_eq!(
+            store.evicted_crate("channels").unwrap().as_deref(),
+            Some("flume")
+        );
+        assert_eq!(store.crate_usage().unwrap()[0].index_bytes, 0);
+        assert!(store.restore("flume").unwrap());
+        assert!(!store.restore("flume").unwrap());
+        assert_eq!(store.crate_versions().unwrap()["flume"], "");
+        store.replace_crate("flume", "0.11.1", docs()).unwrap();
+        store.set_crate_version("flume", "0.11.1").unwrap();
+        assert_eq!(store.crate_key("flume").unwrap().as_deref(), Some("flume"));
+
+        store.retain_crates(|name| name != "flume").unwrap();
+        assert!(!store.has_pages("flume", "0.11.1").unwrap());
+        assert!(store.crate_versions().unwrap().is_empty());
+
+        // Projects with a shared cache see the crate versions indexed by the others
+        let shared = dir.join("shared.sqlite");
+        let mut first = DocsStore::open_path(&dir.join("first.sqlite"), Some(&shared)).unwrap();
+        let second = DocsStore::open_path(&dir.join("second.sqlite"), Some(&shared)).unwrap();
+        first.replace_crate("flume", "0.11.1", docs()).unwrap();
+        first.set_crate_version("flume", "0.11.1").unwrap();
+        assert!(second.has_pages("flume", "0.11.1").unwrap());
+        first.retain_crates(|name| name != "flume").unwrap();
+        assert!(second.has_pages("flume", "0.11.1").unwrap());
+
+        drop((store, first, second));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,8 +1,15 @@
 use anyhow::Result;
+use ignore::WalkBuilder;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
 use toml::Value;
 
+use crate::cargo_remote::{Metadata, MetadataPackage};
+
 #[derive(Debug, PartialEq)]
 pub enum RustSymbol<'a> {
     Function(&'a str),
@@ -11,9 +18,35 @@ pub enum RustSymbol<'a> {
     Trait(&'a str),
     Type(&'a str),
     Enum(&'a str),
+    Union(&'a str),
+    Constant(&'a str),
+    Static(&'a str),
+    /// Attribute proc macro, `#[name]`
+    Attribute(&'a str),
+    /// Derive macro, `#[derive(Name)]`
+    Derive(&'a str),
+    /// Primitive type, only documented by `std` and `core`
+    Primitive(&'a str),
 }
 
 impl RustSymbol<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            RustSymbol::Function(name)
+            | RustSymbol::Macro(name)
+            | RustSymbol::Struct(name)
+            | RustSymbol::Trait(name)
+            | RustSymbol::Type(name)
+            | RustSymbol::Enum(name)
+            | RustSymbol::Union(name)
+            | RustSymbol::Constant(name)
+            | RustSymbol::Static(name)
+            | RustSymbol::Attribute(name)
+            | RustSymbol::Derive(name)
+            | RustSymbol::Primitive(name) => name,
+        }
+    }
+
     #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         match self {
@@ -23,6 +56,12 @@ impl RustSymbol<'_> {
             RustSymbol::Trait(name) => format!("trait {}", name),
             RustSymbol::Type(name) => format!("type {}", name),
             RustSymbol::Enum(name) => format!("enum {}", name),
+            RustSymbol::Union(name) => format!("union {}", name),
+            RustSymbol::Constant(name) => format!("const {}", name),
+            RustSymbol::Static(name) => format!("static {}", name),
+            RustSymbol::Attribute(name) => format!("attr {}", name),
+            RustSymbol::Derive(name) => format!("derive {}", name),
+            RustSymbol::Primitive(name) => format!("primitive {}", name),
         }
     }
 }
@@ -49,42 +88,221 @@ pub fn parse_rust_symbol(filename: &str) -> Option<RustSymbol> {
         "trait" => Some(RustSymbol::Trait(name)),
         "type" => Some(RustSymbol::Type(name)),
         "enum" => Some(RustSymbol::Enum(name)),
+        "union" => Some(RustSymbol::Union(name)),
+        "constant" => Some(RustSymbol::Constant(name)),
+        "static" => Some(RustSymbol::Static(name)),
+        "attr" => Some(RustSymbol::Attribute(name)),
+        "derive" => Some(RustSymbol::Derive(name)),
+        "primitive" => Some(RustSymbol::Primitive(name)),
         _ => None,
     }
 }
 
+/// A dependency from a `Cargo.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CargoDependency {
+    /// The name of the package
+    pub name: String,
+    /// The name the dependency is imported as, if it was renamed with `package = ...`
+    pub alias: Option<String>,
+    pub version: String,
+}
+
+/// The SHA-256 of the `Cargo.lock` of the project, which is in the workspace root
+pub fn lockfile_hash(root: &Path) -> Option<String> {
+    let lockfile = root
+        .ancestors()
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lockfile| lockfile.exists())?;
+    let content = fs::read(lockfile).ok()?;
+    Some(
+        Sha256::digest(content)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect(),
+    )
+}
+
+/// The cache dir of the user: `$XDG_CACHE_HOME`, `~/Library/Caches` on macOS and `~/.cache` otherwise
+fn user_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    if cfg!(target_os = "macos") {
+        Some(home.join("Library").join("Caches"))
+    } else {
+        Some(home.join(".cache"))
+    }
+}
+
+/// The docs cache that all projects with `shared_cache` use
+pub fn shared_cache_dir() -> Option<PathBuf> {
+    Some(user_cache_dir()?.join("cursor-rust-tools"))
+}
+
 /// Get all dependencies from a Rust project. Supports workspaces as well.
-/// Returns a list of tuples with the dependency name and version.
-pub fn get_cargo_dependencies(project: &crate::project::Project) -> Result<Vec<(String, String)>> {
-    let mut dependencies = Vec::new();
-    let cargo_path = project.root().join("Cargo.toml");
-    let cargo_content = fs::read_to_string(&cargo_path)?;
-    let cargo_toml: Value = toml::from_str(&cargo_content)?;
+/// Uses `cargo metadata` and falls back to reading the manifests if that fails
+/// (e.g. because the dependencies haven't been downloaded yet).
+pub fn get_cargo_dependencies(project: &crate::project::Project) -> Result<Vec<CargoDependency>> {
+    match metadata_dependencies(project.root()) {
+        Ok(dependencies) => Ok(dependencies),
+        Err(e) => {
+            tracing::debug!("cargo metadata failed, reading Cargo.toml instead: {:?}", e);
+            manifest_dependencies(project.root())
+        }
+    }
+}
 
-    // Helper function to extract dependencies and versions
-    fn extract_deps(table: &Value) -> Vec<(String, String)> {
-        table
-            .as_table()
-            .map(|t| {
-                t.iter()
-                    .filter_map(|(name, val)| {
-                        let version = match val {
-                            Value::String(v) => Some(v.clone()),
-                            Value::Table(t) => t.get("version")?.as_str()?.to_string().into(),
-                            _ => None,
-                        }?;
-                        Some((name.clone(), version))
-                    })
-                    .collect()
-            })
-            .unwrap_or_default()
+/// The direct dependencies of all workspace members according to the resolved graph
+/// of `cargo metadata`, so that optional dependencies which aren't enabled and
+/// dependencies of other platforms are left out.
+/// The version key is the resolved version, the git commit or the path of the package
+/// with a fingerprint of its files, so that local crates are re-indexed when they change.
+fn metadata_dependencies(root: &Path) -> Result<Vec<CargoDependency>> {
+    let mut command = Command::new("cargo");
+    command
+        .current_dir(root)
+        .args(["metadata", "--format-version", "1", "--offline"]);
+    if let Some(host) = host_target(root) {
+        command.args(["--filter-platform", &host]);
+    }
+    let output = command.output()?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr));
     }
+    let metadata: Metadata = serde_json::from_slice(&output.stdout)?;
+    let Some(resolve) = &metadata.resolve else {
+        anyhow::bail!("cargo metadata returned no dependency graph");
+    };
+    let packages = metadata
+        .packages
+        .iter()
+        .map(|package| (package.id.as_str(), package))
+        .collect::<HashMap<_, _>>();
 
-    // Parse workspace dependencies if they exist
-    if let Some(workspace) = cargo_toml.get("workspace") {
-        if let Some(workspace_deps) = workspace.get("dependencies") {
-            dependencies.extend(extract_deps(workspace_deps));
+    let mut dependencies = Vec::new();
+    for node in resolve
+        .nodes
+        .iter()
+        .filter(|node| metadata.workspace_members.contains(&node.id))
+    {
+        let Some(member) = packages.get(node.id.as_str()) else {
+            continue;
+        };
+        for package in node
+            .deps
+            .iter()
+            .filter_map(|dependency| packages.get(dependency.pkg.as_str()))
+        {
+            let version = match &package.source {
+                Some(source) if source.starts_with("git+") => source.clone(),
+                Some(_) => package.version.clone(),
+                None => {
+                    let path = Path::new(&package.manifest_path)
+                        .parent()
+                        .unwrap_or(Path::new(&package.manifest_path));
+                    match source_fingerprint(path) {
+                        Some(fingerprint) => format!("path+{}#{fingerprint}", path.display()),
+                        None => format!("path+{}", path.display()),
+                    }
+                }
+            };
+            dependencies.push(CargoDependency {
+                name: package.name.clone(),
+                alias: dependency_alias(member, package),
+                version,
+            });
+        }
+    }
+
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+    dependencies.dedup_by(|a, b| a.name == b.name);
+    Ok(dependencies)
+}
+
+/// The name `member` imports `package` as, if it renamed it in its manifest
+fn dependency_alias(member: &MetadataPackage, package: &MetadataPackage) -> Option<String> {
+    let version = semver::Version::parse(&package.version).ok();
+    member
+        .dependencies
+        .iter()
+        .filter(|dependency| dependency.name == package.name)
+        .find(|dependency| {
+            let req = semver::VersionReq::parse(&dependency.req).ok();
+            match (req, &version) {
+                (Some(req), Some(version)) => req.matches(version),
+                _ => true,
+            }
+        })
+        .and_then(|dependency| dependency.rename.clone())
+}
+
+/// The target triple of the toolchain the project uses, e.g. `x86_64-unknown-linux-gnu`
+fn host_target(root: &Path) -> Option<String> {
+    let output = Command::new("rustc")
+        .current_dir(root)
+        .arg("-vV")
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|host| host.trim().to_string())
+}
+
+/// A hash over the paths, sizes and modification times of the files of a local
+/// package. Ignored files (like `target`) don't count.
+fn source_fingerprint(directory: &Path) -> Option<String> {
+    let mut files = Vec::new();
+    for entry in WalkBuilder::new(directory)
+        .filter_entry(|entry| entry.file_name() != "target")
+        .build()
+        .flatten()
+    {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
         }
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_nanos())
+            .unwrap_or_default();
+        files.push(format!(
+            "{} {} {modified}",
+            entry.path().display(),
+            metadata.len()
+        ));
+    }
+    if files.is_empty() {
+        return None;
+    }
+    files.sort();
+    Some(
+        Sha256::digest(files.join("\n"))
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect(),
+    )
+}
+
+/// The dependencies from the `Cargo.toml` files of the project and its members
+fn manifest_dependencies(root: &Path) -> Result<Vec<CargoDependency>> {
+    let mut dependencies = Vec::new();
+    let cargo_path = root.join("Cargo.toml");
+    let cargo_content = fs::read_to_string(&cargo_path)?;
+    let cargo_toml: Value = toml::from_str(&cargo_content)?;
+
+    // Parse workspace dependencies if they exist. The project can also be a member
+    // of a workspace further up, which members inherit dependencies from
+    let workspace_deps = workspace_dependencies(root);
+    if cargo_toml.get("workspace").is_some() {
+        dependencies.extend(workspace_deps.values().cloned());
     }
 
     // Get workspace members
@@ -97,7 +315,7 @@ pub fn get_cargo_dependencies(project: &crate::project::Project) -> Result<Vec<(
                     .iter()
                     .filter_map(|p| p.as_str())
                     .flat_map(|pattern| {
-                        let p = format!("{}/{}", project.root().display(), pattern);
+                        let p = format!("{}/{}", root.display(), pattern);
                         glob::glob(&p)
                             .map(|paths| paths.collect::<Vec<_>>())
                             .unwrap_or_else(|_| vec![Ok(PathBuf::from(p))])
@@ -107,7 +325,7 @@ pub fn get_cargo_dependencies(project: &crate::project::Project) -> Result<Vec<(
             .unwrap_or_default()
     } else {
         // If not a workspace, treat as single package
-        vec![Ok(project.root().to_path_buf())]
+        vec![Ok(root.to_path_buf())]
     };
 
     // Parse dependencies from each member
@@ -124,16 +342,16 @@ pub fn get_cargo_dependencies(project: &crate::project::Project) -> Result<Vec<(
 
             // Get dependencies from different sections
             if let Some(deps) = member_toml.get("dependencies") {
-                dependencies.extend(extract_deps(deps));
+                dependencies.extend(extract_deps(deps, &workspace_deps));
             }
             if let Some(dev_deps) = member_toml.get("dev-dependencies") {
-                dependencies.extend(extract_deps(dev_deps));
+                dependencies.extend(extract_deps(dev_deps, &workspace_deps));
             }
             if let Some(target) = cargo_toml.get("target") {
                 if let Some(target_table) = target.as_table() {
                     for target_cfg in target_table.values() {
                         if let Some(target_deps) = target_cfg.get("dependencies") {
-                            dependencies.extend(extract_deps(target_deps));
+                            dependencies.extend(extract_deps(target_deps, &workspace_deps));
                         }
                     }
                 }
@@ -142,11 +360,133 @@ pub fn get_cargo_dependencies(project: &crate::project::Project) -> Result<Vec<(
     }
 
     // Deduplicate dependencies (keep last occurrence)
-    dependencies.sort_by(|a, b| a.0.cmp(&b.0));
-    dependencies.dedup_by(|a, b| a.0 == b.0);
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+    dependencies.dedup_by(|a, b| a.name == b.name);
     Ok(dependencies)
 }
 
+/// Extract the dependencies and their versions from a dependency table.
+/// `dep.workspace = true` entries are resolved against `workspace_deps`.
+fn extract_deps(
+    table: &Value,
+    workspace_deps: &HashMap<String, CargoDependency>,
+) -> Vec<CargoDependency> {
+    table
+        .as_table()
+        .map(|t| {
+            t.iter()
+                .filter_map(|(name, val)| extract_dep(name, val, workspace_deps))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn extract_dep(
+    name: &str,
+    val: &Value,
+    workspace_deps: &HashMap<String, CargoDependency>,
+) -> Option<CargoDependency> {
+    if let Value::Table(t) = val
+        && t.get("workspace").and_then(|w| w.as_bool()) == Some(true)
+    {
+        return workspace_deps.get(name).cloned();
+    }
+    let version = match val {
+        Value::String(v) => Some(v.clone()),
+        Value::Table(t) => dependency_version(t),
+        _ => None,
+    }?;
+    let package = match val {
+        Value::Table(t) => t.get("package").and_then(|p| p.as_str()),
+        _ => None,
+    };
+    Some(match package {
+        Some(package) => CargoDependency {
+            name: package.to_string(),
+            alias: Some(name.to_string()),
+            version,
+        },
+        None => CargoDependency {
+            name: name.to_string(),
+            alias: None,
+            version,
+        },
+    })
+}
+
+/// The `[workspace.dependencies]` of the closest workspace containing `path`,
+/// keyed by the name members use to inherit them
+fn workspace_dependencies(path: &Path) -> HashMap<String, CargoDependency> {
+    for directory in path.ancestors() {
+        let Ok(content) = fs::read_to_string(directory.join("Cargo.toml")) else {
+            continue;
+        };
+        let Ok(cargo_toml) = toml::from_str::<Value>(&content) else {
+            continue;
+        };
+        let Some(workspace) = cargo_toml.get("workspace") else {
+            continue;
+        };
+        let Some(table) = workspace.get("dependencies").and_then(|d| d.as_table()) else {
+            return HashMap::new();
+        };
+        return table
+            .iter()
+            .filter_map(|(name, val)| {
+                let dependency = extract_dep(name, val, &HashMap::new())?;
+                Some((name.clone(), dependency))
+            })
+            .collect();
+    }
+    HashMap::new()
+}
+
+/// The key that decides whether the docs of a dependency have to be re-indexed.
+/// Registry dependencies use their version, git dependencies the pinned
+/// `rev` / `tag` / `branch` and path dependencies their path.
+fn dependency_version(table: &toml::Table) -> Option<String> {
+    if let Some(version) = table.get("version").and_then(|v| v.as_str()) {
+        return Some(version.to_string());
+    }
+    if let Some(git) = table.get("git").and_then(|v| v.as_str()) {
+        let reference = ["rev", "tag", "branch"]
+            .iter()
+            .find_map(|key| table.get(*key).and_then(|v| v.as_str()))
+            .unwrap_or("HEAD");
+        return Some(format!("git+{git}#{reference}"));
+    }
+    let path = table.get("path").and_then(|v| v.as_str())?;
+    Some(format!("path+{path}"))
+}
+
+/// The size of a file or of everything in a directory, without following symlinks
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| dir_size(&entry.path()))
+        .sum()
+}
+
+/// A size in bytes for humans, e.g. `1.5 MiB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,9 +509,143 @@ mod tests {
             parse_rust_symbol("trait.Context.html"),
             Some(RustSymbol::Trait("Context"))
         );
+        assert_eq!(
+            parse_rust_symbol("constant.MAX.html"),
+            Some(RustSymbol::Constant("MAX"))
+        );
+        assert_eq!(
+            parse_rust_symbol("derive.Serialize.html"),
+            Some(RustSymbol::Derive("Serialize"))
+        );
+        assert_eq!(
+            parse_rust_symbol("primitive.str.html"),
+            Some(RustSymbol::Primitive("str"))
+        );
         assert_eq!(parse_rust_symbol("invalid"), None);
     }
 
+    #[test]
+    fn test_dependency_version() {
+        let version = |toml: &str| dependency_version(&toml::from_str(toml).unwrap());
+        assert_eq!(version(r#"version = "1.0""#), Some("1.0".to_string()));
+        assert_eq!(
+            version("git = \"https://github.com/a/b\"\nrev = \"abc123\""),
+            Some("git+https://github.com/a/b#abc123".to_string())
+        );
+        assert_eq!(
+            version(r#"git = "https://github.com/a/b""#),
+            Some("git+https://github.com/a/b#HEAD".to_string())
+        );
+        assert_eq!(version(r#"path = "../b""#), Some("path+../b".to_string()));
+        assert_eq!(version(r#"workspace = true"#), None);
+    }
+
+    #[test]
+    fn test_extract_inherited_deps() {
+        let workspace_deps = HashMap::from([(
+            "serde".to_string(),
+            CargoDependency {
+                name: "serde".to_string(),
+                alias: None,
+                version: "1.0".to_string(),
+            },
+        )]);
+        let table: Value =
+            toml::from_str("serde.workspace = true\nmissing = { workspace = true }\nlog = \"0.4\"")
+                .unwrap();
+        let mut deps = extract_deps(&table, &workspace_deps);
+        deps.sort_by(|a, b| a.name.cmp(&b.name));
+        let versions = deps
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.version.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec![("log", "0.4"), ("serde", "1.0")]);
+    }
+
+    #[test]
+    fn test_source_fingerprint() {
+        let root =
+            std::env::temp_dir().join(format!("cursor-rust-tools-source-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let before = source_fingerprint(&root).unwrap();
+        fs::write(root.join("target/build.log"), "ignored").unwrap();
+        assert_eq!(source_fingerprint(&root).unwrap(), before);
+        fs::write(root.join("src/lib.rs"), "pub fn f() {}").unwrap();
+        assert_ne!(source_fingerprint(&root).unwrap(), before);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Writes a workspace with a member inheriting a dependency from the
+    /// workspace, a renamed path dependency, an optional dependency which isn't
+    /// enabled and a dependency of another platform
+    fn write_fixture_workspace() -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("cursor-rust-tools-fixture-{}", std::process::id()));
+        let files = [
+            (
+                "Cargo.toml",
+                "[workspace]\nmembers = [\"a\", \"b\"]\nresolver = \"2\"\n\n[workspace.dependencies]\nb = { path = \"b\" }\n",
+            ),
+            (
+                "a/Cargo.toml",
+                "[package]\nname = \"a\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nb.workspace = true\nrenamed = { path = \"../c\", package = \"c\" }\nd = { path = \"../d\", optional = true }\n\n[target.'cfg(target_os = \"none\")'.dependencies]\ne = { path = \"../e\" }\n",
+            ),
+            (
+                "b/Cargo.toml",
+                "[package]\nname = \"b\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            (
+                "c/Cargo.toml",
+                "[package]\nname = \"c\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            (
+                "d/Cargo.toml",
+                "[package]\nname = \"d\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            (
+                "e/Cargo.toml",
+                "[package]\nname = \"e\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("a/src/lib.rs", ""),
+            ("b/src/lib.rs", ""),
+            ("c/src/lib.rs", ""),
+            ("d/src/lib.rs", ""),
+            ("e/src/lib.rs", ""),
+        ];
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_metadata_and_manifest_dependencies() {
+        let root = write_fixture_workspace();
+        let names = |dependencies: Vec<CargoDependency>| {
+            dependencies
+                .into_iter()
+                .map(|dep| (dep.name, dep.alias))
+                .collect::<Vec<_>>()
+        };
+        let from_metadata = names(metadata_dependencies(&root).unwrap());
+        let from_manifest = names(manifest_dependencies(&root).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+
+        let expected = vec![
+            ("b".to_string(), None),
+            ("c".to_string(), Some("renamed".to_string())),
+        ];
+        assert_eq!(from_metadata, expected);
+        // The manifests don't tell which features are enabled
+        let mut expected = expected;
+        expected.push(("d".to_string(), None));
+        assert_eq!(from_manifest, expected);
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(RustSymbol::Function("Ok").to_string(), "fn Ok");
@@ -180,5 +654,11 @@ mod tests {
         assert_eq!(RustSymbol::Trait("Context").to_string(), "trait Context");
         assert_eq!(RustSymbol::Type("Result").to_string(), "type Result");
         assert_eq!(RustSymbol::Enum("Option").to_string(), "enum Option");
+        assert_eq!(RustSymbol::Static("GLOBAL").to_string(), "static GLOBAL");
+        assert_eq!(RustSymbol::Attribute("main").to_string(), "attr main");
+        assert_eq!(
+            RustSymbol::Union("MaybeUninit").to_string(),
+            "union MaybeUninit"
+        );
     }
 }
@@ -1,112 +1,284 @@
 use anyhow::Result;
+use flume::Sender;
 use ignore::WalkBuilder;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self};
 use std::path::{Path, PathBuf};
 
-use super::extract_md::extract_md;
-use super::utils::{get_cargo_dependencies, parse_rust_symbol};
+use super::DocsNotification;
+use super::cache::collect_garbage;
+use super::extract_md::{extract_md, module_docs};
+use super::index::page_path;
+use super::rustdoc_json::json_docs;
+use super::search::SearchIndex;
+use super::store::{CrateDocs, DocsStore};
+use super::utils::{get_cargo_dependencies, lockfile_hash, parse_rust_symbol};
 
-#[derive(Serialize, Deserialize, Default, Debug)]
-pub struct DocsCache {
-    pub hash: String,
-    pub deps: HashMap<String, HashMap<String, String>>,
-    pub crate_versions: HashMap<String, String>,
-}
-
-impl DocsCache {
-    pub fn new(project: &crate::project::Project) -> Result<Self> {
-        let cache_path = project.cache_dir().join("docs_cache.json");
-        if cache_path.exists() {
-            let content = fs::read_to_string(cache_path)?;
-            Ok(serde_json::from_str(&content)?)
-        } else {
-            Ok(Self::default())
-        }
+pub fn walk_docs(
+    project: &crate::project::Project,
+    notifier: &Sender<DocsNotification>,
+) -> Result<()> {
+    let mut store = DocsStore::open(project)?;
+    if store.is_outdated()? {
+        tracing::info!("Docs cache format changed, re-indexing all crates");
+        store.reset()?;
     }
 
-    pub fn save(&self, project: &crate::project::Project) -> Result<()> {
-        let cache_path = project.cache_dir().join("docs_cache.json");
-        fs::create_dir_all(project.cache_dir())?;
-        fs::write(cache_path, serde_json::to_string_pretty(self)?)?;
-        Ok(())
+    // Different features document different items
+    let feature_args = project.docs_config().cargo_args();
+    if store.feature_args()? != feature_args {
+        tracing::info!("Docs features changed, re-indexing all crates");
+        store.clear_versions()?;
+        store.set_feature_args(&feature_args)?;
     }
-}
-
-pub fn walk_docs(project: &crate::project::Project) -> Result<()> {
-    let mut cache = DocsCache::new(project)?;
 
     let dependencies = get_cargo_dependencies(project)?;
     tracing::info!("dependencies: {:?}", dependencies);
 
+    // rustdoc uses the library name of the package, where `-` becomes `_`
+    store.set_aliases(
+        &dependencies
+            .iter()
+            .filter_map(|dependency| {
+                let alias = dependency.alias.clone()?;
+                Some((alias, dependency.name.replace('-', "_")))
+            })
+            .collect(),
+    )?;
+
     // Convert dependencies to a HashMap for easier lookup
-    let dep_versions: HashMap<String, String> = dependencies.into_iter().collect();
+    let dep_versions: HashMap<String, String> = dependencies
+        .into_iter()
+        .map(|dependency| (dependency.name, dependency.version))
+        .collect();
 
-    // Walk the docs directory
-    let walker = WalkBuilder::new(project.docs_dir()).hidden(false).build();
+    // Drop the crates that are no longer dependencies, the others are updated in place
+    store.retain_crates(|crate_name| {
+        dep_versions.contains_key(crate_name)
+            || dep_versions.contains_key(&crate_name.replace('_', "-"))
+    })?;
 
-    for result in walker {
-        let entry = result?;
+    // Crate versions that another project indexed into the shared cache are used as they are
+    let mut previous_versions = store.crate_versions()?;
+    for (name, version) in &dep_versions {
+        let crate_name = name.replace('-', "_");
+        if previous_versions.get(&crate_name) != Some(version)
+            && !project.ignore_crates().contains(&crate_name)
+            && store.has_pages(&crate_name, version)?
+        {
+            tracing::debug!("Using the shared docs of {crate_name} {version}");
+            store.set_crate_version(&crate_name, version)?;
+            previous_versions.insert(crate_name, version.clone());
+        }
+    }
+
+    // Collect the crates to index first, so that the progress has a total
+    let mut crates = Vec::new();
+    for entry in fs::read_dir(project.docs_dir())? {
+        let entry = entry?;
         let path = entry.path();
+        // HTML docs are a directory per crate, JSON docs a `<crate>.json` file
+        let crate_name = if entry.file_type()?.is_dir() {
+            entry.file_name().to_string_lossy().to_string()
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("json")
+            && let Some(stem) = path.file_stem()
+        {
+            stem.to_string_lossy().to_string()
+        } else {
+            continue;
+        };
+        if crates.iter().any(|(name, _)| *name == crate_name) {
+            continue;
+        }
+
+        // Skip if crate is not in dependencies
+        let Some(version) = dep_versions
+            .get(&crate_name)
+            .or_else(|| dep_versions.get(&crate_name.replace('_', "-")))
+        else {
+            tracing::debug!("Skipping {crate_name} because it's not in dependencies");
+            continue;
+        };
+
+        // Skip if crate is in ignore list
+        if project.ignore_crates().contains(&crate_name) {
+            tracing::debug!("Skipping {crate_name} because it's in ignore list");
+            continue;
+        }
+
+        // Skip if version hasn't changed
+        if previous_versions.get(&crate_name) == Some(version) {
+            tracing::debug!("Skipping {crate_name} because the version has not changed");
+            continue;
+        }
+
+        crates.push((crate_name, version.clone()));
+    }
+    crates.sort();
 
-        if path.extension().and_then(|ext| ext.to_str()) == Some("html") {
-            if let Some(relative_path) = path_to_cache_key(path, project.docs_dir()) {
-                if let Some((crate_name, file_path)) = extract_crate_and_path(&relative_path) {
-                    // Skip if crate is not in dependencies
-                    let Some(version) = dep_versions.get(crate_name) else {
-                        tracing::debug!(
-                            "Skipping {crate_name}: {file_path} because it's not in dependencies"
-                        );
-                        continue;
-                    };
-
-                    // Skip if crate is in ignore list
-                    if project.ignore_crates().contains(&crate_name.to_string()) {
-                        tracing::debug!("Skipping {crate_name} because it's in ignore list");
-                        continue;
-                    }
-
-                    // Skip if version hasn't changed
-                    if let Some(cached_version) = cache.crate_versions.get(crate_name) {
-                        if cached_version == version {
-                            tracing::debug!(
-                                "Skipping {crate_name} because the version has not changed"
-                            );
-                            continue;
-                        }
-                    }
-
-                    // Process the file since it's either new or updated
-                    let html_content = fs::read_to_string(path)?;
-                    let markdown = extract_md(&html_content);
-                    tracing::debug!("Indexing {crate_name}: {file_path}");
-
-                    let symbol = parse_rust_symbol(file_path)
-                        .map(|s| s.to_string())
-                        .unwrap_or(file_path.to_string());
-
-                    cache
-                        .deps
-                        .entry(crate_name.to_string())
-                        .or_default()
-                        .insert(symbol, markdown);
-
-                    // Store the version number
-                    cache
-                        .crate_versions
-                        .insert(crate_name.to_string(), version.clone());
+    let total = crates.len();
+    for (index, (crate_name, version)) in crates.into_iter().enumerate() {
+        let progress = |started: bool| {
+            let project = project.root().to_path_buf();
+            let (name, index) = (crate_name.clone(), index + 1);
+            let notification = if started {
+                DocsNotification::CrateStarted {
+                    project,
+                    name,
+                    index,
+                    total,
+                }
+            } else {
+                DocsNotification::CrateFinished {
+                    project,
+                    name,
+                    index,
+                    total,
                 }
+            };
+            if let Err(e) = notifier.send(notification) {
+                tracing::error!("Failed to send docs progress notification: {:?}", e);
             }
-        }
+        };
+        progress(true);
+        index_crate(project, notifier, &mut store, &crate_name, &version)?;
+        // Store the version number
+        store.set_crate_version(&crate_name, &version)?;
+        progress(false);
     }
 
-    // Create and save cache
-    cache.save(project)?;
+    store.set_hash(&lockfile_hash(project.root()).unwrap_or_default())?;
+    collect_garbage(project, &mut store)?;
+    SearchIndex::build(project, &store)?;
 
     Ok(())
 }
 
+/// Store the pages of a crate, from its rustdoc JSON if there is one and from
+/// the html pages otherwise. Replaces the pages from an earlier run, which may
+/// be in a different format.
+fn index_crate(
+    project: &crate::project::Project,
+    notifier: &Sender<DocsNotification>,
+    store: &mut DocsStore,
+    crate_name: &str,
+    version: &str,
+) -> Result<()> {
+    let json_path = project.docs_dir().join(format!("{crate_name}.json"));
+    if json_path.exists() {
+        let docs = json_docs(&json_path)?;
+        return store.replace_crate(crate_name, version, docs);
+    }
+
+    let mut pages = HashMap::new();
+    let mut modules = BTreeMap::new();
+    let mut aliases = Vec::new();
+
+    // Collected first, so that the progress has a total
+    let mut files = Vec::new();
+    for result in WalkBuilder::new(project.docs_dir().join(crate_name))
+        .hidden(false)
+        .build()
+    {
+        let entry = result?;
+        if entry.path().extension().and_then(|ext| ext.to_str()) == Some("html") {
+            files.push(entry.into_path());
+        }
+    }
+
+    let total = files.len();
+    let mut reported = 0;
+    for (done, path) in files.iter().enumerate() {
+        // Large crates have thousands of pages, report every 10 percent
+        let step = done * 10 / total;
+        if step > reported {
+            reported = step;
+            if let Err(e) = notifier.send(DocsNotification::Progress {
+                project: project.root().to_path_buf(),
+                name: crate_name.to_string(),
+                done,
+                total,
+            }) {
+                tracing::error!("Failed to send docs progress notification: {:?}", e);
+            }
+        }
+
+        let Some(relative_path) = path_to_cache_key(path, project.docs_dir()) else {
+            continue;
+        };
+        let Some((_, file_path)) = extract_crate_and_path(&relative_path) else {
+            continue;
+        };
+
+        let html_content = fs::read_to_string(path)?;
+        let symbol = page_key(file_path);
+
+        // rustdoc puts redirects where re-exported items are defined
+        if let Some(url) = redirect_url(&html_content) {
+            if let Some(target) = resolve_url(&relative_path, url)
+                && let Some((target_crate, target_path)) = extract_crate_and_path(&target)
+                && target_crate == crate_name
+                && let Some(alias) = page_path(&symbol)
+            {
+                aliases.push((alias, page_key(target_path)));
+            }
+            continue;
+        }
+
+        let markdown = extract_md(&html_content);
+        tracing::debug!("Indexing {crate_name}: {file_path}");
+
+        if let Some(module) = module_path(file_path)
+            && let Some(docs) = module_docs(&markdown)
+        {
+            modules.insert(module, docs);
+        }
+
+        pages.insert(symbol, markdown);
+    }
+    let docs = CrateDocs {
+        pages: pages.into_iter().collect(),
+        modules: modules.into_iter().collect(),
+        aliases,
+    };
+    store.replace_crate(crate_name, version, docs)
+}
+
+/// Top level items are stored as `struct Mutex`, nested ones by their file (`sync/struct.Mutex.html`)
+fn page_key(file_path: &str) -> String {
+    parse_rust_symbol(file_path)
+        .map(|s| s.to_string())
+        .unwrap_or(file_path.to_string())
+}
+
+/// The target of a redirect page
+fn redirect_url(html: &str) -> Option<&str> {
+    let (_, rest) = html.split_once("http-equiv=\"refresh\" content=\"0;URL=")?;
+    rest.split('"').next()
+}
+
+/// Resolve `url` relative to the page at `from` (both relative to the docs dir)
+fn resolve_url(from: &str, url: &str) -> Option<String> {
+    let mut segments: Vec<&str> = from.split('/').collect();
+    segments.pop();
+    for segment in url.split('#').next()?.split('/') {
+        match segment {
+            ".." => {
+                segments.pop()?;
+            }
+            "." | "" => {}
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// `sync/index.html` → `sync::`, `index.html` → the crate root
+fn module_path(file_path: &str) -> Option<String> {
+    let module = file_path.strip_suffix("index.html")?;
+    Some(module.trim_end_matches('/').replace('/', "::"))
+}
+
 fn path_to_cache_key(path: &Path, docs_dir: PathBuf) -> Option<String> {
     path.strip_prefix(docs_dir)
         .ok()
@@ -121,3 +293,22 @@ fn extract_crate_and_path(path: &str) -> Option<(&str, &str)> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        let html = r#"<meta http-equiv="refresh" content="0;URL=../../../../tokio/sync/mpsc/struct.Sender.html"><title>Redirection</title>"#;
+        let url = redirect_url(html).unwrap();
+        assert_eq!(
+            resolve_url("tokio/sync/mpsc/bounded/struct.Sender.html", url).as_deref(),
+            Some("tokio/sync/mpsc/struct.Sender.html")
+        );
+        assert_eq!(
+            resolve_url("tokio/struct.Sender.html", "../../struct.Sender.html"),
+            None
+        );
+    }
+}
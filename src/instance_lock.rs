@@ -0,0 +1,213 @@
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io::ErrorKind,
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// How long a taken over instance gets to release its lock
+const TAKE_OVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the instance which just took the lock gets to record itself
+const RECORD_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Recorded next to the lock file by the instance which holds the lock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunningInstance {
+    pub pid: u32,
+    pub host: String,
+    pub port: u16,
+}
+
+impl RunningInstance {
+    /// The instance which holds the lock, if one is running
+    pub fn find(lock_path: &Path) -> Option<Self> {
+        match is_locked(lock_path) {
+            Ok(true) => {
+                // The instance may have just taken the lock and not recorded itself yet
+                let start = Instant::now();
+                loop {
+                    if let Some(instance) = read_instance(lock_path) {
+                        return Some(instance);
+                    }
+                    if start.elapsed() > RECORD_TIMEOUT {
+                        return None;
+                    }
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+            Ok(false) => None,
+            Err(e) => {
+                tracing::debug!("Can't lock {:?}, checking the process: {}", lock_path, e);
+                read_instance(lock_path).filter(|instance| instance.is_alive())
+            }
+        }
+    }
+
+    pub fn address(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Whether the process is still running. Stdio instances don't listen, so only
+    /// SSE instances can be reached on their address.
+    fn is_alive(&self) -> bool {
+        if self.pid == std::process::id() {
+            return false;
+        }
+        if self.host != "stdio" && self.is_serving() {
+            return true;
+        }
+        is_process_alive(self.pid)
+    }
+
+    fn is_serving(&self) -> bool {
+        let Some(address) = self
+            .address()
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+        else {
+            return false;
+        };
+        TcpStream::connect_timeout(&address, Duration::from_millis(500)).is_ok()
+    }
+
+    /// Terminate the instance and take the lock once it released it
+    pub fn take_over(&self, lock_path: &Path, host: &str, port: u16) -> Result<InstanceLock> {
+        #[cfg(unix)]
+        let status = std::process::Command::new("kill")
+            .arg(self.pid.to_string())
+            .status()?;
+        #[cfg(windows)]
+        let status = std::process::Command::new("taskkill")
+            .args(["/PID", &self.pid.to_string()])
+            .status()?;
+        if !status.success() {
+            anyhow::bail!(
+                "Failed to terminate the running instance (pid {})",
+                self.pid
+            );
+        }
+        let start = Instant::now();
+        loop {
+            if let Some(lock) = InstanceLock::try_acquire(lock_path, host, port)? {
+                return Ok(lock);
+            }
+            if start.elapsed() > TAKE_OVER_TIMEOUT {
+                anyhow::bail!("The running instance (pid {}) did not shut down", self.pid);
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+/// Held for the lifetime of the process. The operating system releases the lock
+/// when the process exits, even if it crashed.
+#[derive(Debug)]
+pub struct InstanceLock {
+    /// Locked while the lock is held
+    file: File,
+    instance_path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Take the lock and record this instance with its address, `None` if another
+    /// instance holds the lock
+    pub fn try_acquire(lock_path: &Path, host: &str, port: u16) -> Result<Option<Self>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)
+            .with_context(|| format!("Failed to open the instance lock {lock_path:?}"))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(e)) => {
+                // Some network file systems don't support locks, fall back to the
+                // recorded process
+                tracing::warn!("Failed to lock {:?}: {}", lock_path, e);
+                if RunningInstance::find(lock_path).is_some() {
+                    return Ok(None);
+                }
+            }
+        }
+        let instance = RunningInstance {
+            pid: std::process::id(),
+            host: host.to_string(),
+            port,
+        };
+        let instance_path = instance_path(lock_path);
+        std::fs::write(&instance_path, toml::to_string(&instance)?)
+            .with_context(|| format!("Failed to record the instance in {instance_path:?}"))?;
+        Ok(Some(Self {
+            file,
+            instance_path,
+        }))
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // Only removed while the lock is still held, so it can't be the record of
+        // the next instance. The lock file stays, the next instance locks the same
+        // file.
+        if let Err(e) = std::fs::remove_file(&self.instance_path) {
+            tracing::error!(
+                "Failed to remove the instance record {:?}: {}",
+                self.instance_path,
+                e
+            );
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+/// The file the instance which holds the lock is recorded in. The lock file itself
+/// can't be read while it is locked on Windows.
+fn instance_path(lock_path: &Path) -> PathBuf {
+    lock_path.with_extension("instance")
+}
+
+fn read_instance(lock_path: &Path) -> Option<RunningInstance> {
+    let content = std::fs::read_to_string(instance_path(lock_path)).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Whether another process holds the lock
+fn is_locked(lock_path: &Path) -> std::io::Result<bool> {
+    let file = match File::open(lock_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    match file.try_lock_shared() {
+        // Released when the file is closed
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+fn is_process_alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    let alive = std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    #[cfg(windows)]
+    let alive = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()));
+    alive
+}
@@ -1,7 +1,15 @@
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 use anyhow::Result;
 use async_lsp::{LanguageServer, ServerSocket};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use lsp_types::{DidChangeWatchedFilesParams, FileChangeType, FileEvent};
 use notify_debouncer_mini::{
     DebounceEventResult, DebouncedEvent, Debouncer, new_debouncer, notify::*,
@@ -9,12 +17,21 @@ use notify_debouncer_mini::{
 use tokio::{runtime::Handle, sync::Mutex};
 use url::Url;
 
+use super::documents::Documents;
+use crate::lsp::LspNotification;
 use crate::project::Project;
 
+/// How many files changed since the notifier was started, used to invalidate caches
+type ChangeCounter = Arc<AtomicU64>;
+
+/// The ignore filter of each watched project, rooted at the project root
+type Filters = Arc<std::sync::Mutex<Vec<Gitignore>>>;
+
 #[derive(Debug)]
 pub struct ChangeNotifier {
-    #[allow(dead_code)] // Keep the handle to ensure the change notifier runs
     debouncer: Debouncer<RecommendedWatcher>,
+    changes: ChangeCounter,
+    filters: Filters,
 }
 
 impl ChangeNotifier {
@@ -22,15 +39,44 @@ impl ChangeNotifier {
         server: Arc<Mutex<ServerSocket>>,
         project: &Project,
         handle: Handle,
+        notifier: flume::Sender<LspNotification>,
+        documents: Documents,
     ) -> Result<Self> {
+        let root = project.root().to_path_buf();
         let handle_clone = handle.clone();
-        let target_path = project.root().join("target");
+        let filters = Filters::new(std::sync::Mutex::new(vec![project_filter(project)]));
+        let cloned_filters = filters.clone();
+        let changes = ChangeCounter::default();
+        let cloned_changes = changes.clone();
         let mut debouncer = new_debouncer(
             Duration::from_secs(2),
             move |res: DebounceEventResult| match res {
-                Ok(events) => events.iter().for_each(|e| {
-                    handle_event(e, server.clone(), handle_clone.clone(), target_path.clone())
-                }),
+                Ok(events) => {
+                    let Ok(filters) = cloned_filters.lock() else {
+                        tracing::error!("The watch filters are poisoned");
+                        return;
+                    };
+                    events.iter().for_each(|e| {
+                        handle_event(
+                            e,
+                            server.clone(),
+                            handle_clone.clone(),
+                            &documents,
+                            &filters,
+                            &cloned_changes,
+                        )
+                    });
+                    // One notification for all manifests that changed together
+                    if events
+                        .iter()
+                        .any(|e| is_manifest(&e.path) && !is_ignored(&filters, &e.path))
+                        && let Err(e) = notifier.send(LspNotification::ManifestChanged {
+                            project: root.clone(),
+                        })
+                    {
+                        tracing::error!("Failed to send manifest notification: {}", e);
+                    }
+                }
                 Err(e) => tracing::error!("Error {:?}", e),
             },
         )?;
@@ -39,21 +85,124 @@ impl ChangeNotifier {
         debouncer
             .watcher()
             .watch(project.root(), RecursiveMode::Recursive)?;
-        Ok(Self { debouncer })
+        Ok(Self {
+            debouncer,
+            changes,
+            filters,
+        })
+    }
+
+    /// Also watch the root of a project sharing the rust-analyzer, with the ignore
+    /// rules of that project. `watched` are the roots which are watched already.
+    pub fn watch(&mut self, project: &Project, watched: &[PathBuf]) -> Result<()> {
+        let root = project.root();
+        // A root within a watched root is watched recursively already
+        if !watched.iter().any(|other| root.starts_with(other)) {
+            self.debouncer
+                .watcher()
+                .watch(root, RecursiveMode::Recursive)?;
+        }
+        if let Ok(mut filters) = self.filters.lock() {
+            filters.push(project_filter(project));
+        }
+        Ok(())
+    }
+
+    /// Stop watching the root of a project which no longer uses the rust-analyzer.
+    /// The `remaining` roots stay watched, also those within the removed root.
+    pub fn unwatch(&mut self, root: &Path, remaining: &[PathBuf]) {
+        if let Ok(mut filters) = self.filters.lock()
+            && let Some(index) = filters.iter().position(|filter| filter.path() == root)
+        {
+            filters.remove(index);
+        }
+        if remaining.iter().any(|other| root.starts_with(other)) {
+            return;
+        }
+        if let Err(e) = self.debouncer.watcher().unwatch(root) {
+            tracing::warn!("Failed to unwatch {:?}: {}", root, e);
+        }
+        // Unwatching is recursive, so the roots within it have to be watched again
+        for other in remaining.iter().filter(|other| other.starts_with(root)) {
+            if let Err(e) = self
+                .debouncer
+                .watcher()
+                .watch(other, RecursiveMode::Recursive)
+            {
+                tracing::warn!("Failed to watch {:?}: {}", other, e);
+            }
+        }
+    }
+
+    /// The number of file changes since the notifier was started
+    pub fn change_count(&self) -> u64 {
+        self.changes.load(Ordering::Relaxed)
+    }
+}
+
+fn project_filter(project: &Project) -> Gitignore {
+    watch_filter(
+        project.root(),
+        &project.cache_dir(),
+        &project.rust_analyzer_config().watch_ignore,
+    )
+}
+
+/// The files whose changes aren't sent to rust-analyzer: `target` (otherwise it would
+/// trigger itself), `.git`, the docs cache, everything in the `.gitignore` of the project
+/// and the `extra` patterns. Changes to the `.gitignore` apply after a restart.
+fn watch_filter(root: &Path, cache_dir: &Path, extra: &[String]) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for file in [root.join(".gitignore"), root.join(".git/info/exclude")] {
+        if file.exists()
+            && let Some(e) = builder.add(&file)
+        {
+            tracing::warn!("Failed to read {:?}: {}", file, e);
+        }
+    }
+    let mut lines = vec!["/target/".to_string(), "/.git/".to_string()];
+    if let Ok(cache_dir) = cache_dir.strip_prefix(root) {
+        lines.push(format!("/{}/", cache_dir.display()));
     }
+    lines.extend(extra.iter().cloned());
+    for line in lines {
+        if let Err(e) = builder.add_line(None, &line) {
+            tracing::warn!("Invalid watch ignore pattern `{}`: {}", line, e);
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        tracing::error!("Failed to build the watch ignore patterns: {}", e);
+        Gitignore::empty()
+    })
+}
+
+/// Whether the innermost project containing the path ignores it
+fn is_ignored(filters: &[Gitignore], path: &Path) -> bool {
+    filters
+        .iter()
+        .filter(|filter| path.starts_with(filter.path()))
+        .max_by_key(|filter| filter.path().components().count())
+        .is_some_and(|filter| {
+            filter
+                .matched_path_or_any_parents(path, path.is_dir())
+                .is_ignore()
+        })
+}
+
+/// Changes to these files change the dependencies
+fn is_manifest(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == "Cargo.toml" || name == "Cargo.lock")
 }
 
 fn handle_event(
     event: &DebouncedEvent,
     server: Arc<Mutex<ServerSocket>>,
     handle: Handle,
-    target_path: PathBuf,
+    documents: &Documents,
+    filters: &[Gitignore],
+    changes: &ChangeCounter,
 ) {
-    // Don't trigger lsp on target files. Otherwise it will trigger itself.
-    if event.path.starts_with(&target_path) {
-        return;
-    }
-    tracing::trace!("Event {:?} for {:?}", event.kind, event.path);
     let url = match Url::from_file_path(event.path.clone()) {
         Ok(url) => url,
         Err(e) => {
@@ -61,15 +210,77 @@ fn handle_event(
             return;
         }
     };
+    // rust-analyzer uses the text of open documents, so they're synced even if ignored
+    let ignored = is_ignored(filters, &event.path);
+    if ignored && documents.version(&url).is_none() {
+        return;
+    }
+    changes.fetch_add(1, Ordering::Relaxed);
+    tracing::trace!("Event {:?} for {:?}", event.kind, event.path);
+    let documents = documents.clone();
+    let path = event.path.clone();
     handle.spawn(async move {
-        match server
-            .lock()
-            .await
-            .did_change_watched_files(DidChangeWatchedFilesParams {
-                changes: vec![FileEvent::new(url, FileChangeType::CHANGED)],
-            }) {
+        let mut server = server.lock().await;
+        // Open documents only change through `didChange`
+        match documents.sync(&server, &url, &path) {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to sync {:?}: {:?}", path, e),
+        }
+        if ignored {
+            return;
+        }
+        match server.did_change_watched_files(DidChangeWatchedFilesParams {
+            changes: vec![FileEvent::new(url, FileChangeType::CHANGED)],
+        }) {
             Ok(_) => (),
             Err(e) => tracing::error!("Failed to send DidChangeWatchedFiles notification: {:?}", e),
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_filter() {
+        let root = Path::new("/nonexistent/project");
+        let filters = [watch_filter(
+            root,
+            &root.join(".docs-cache"),
+            &["*.log".to_string(), "/node_modules/".to_string()],
+        )];
+        for ignored in [
+            "target/debug/build.rs",
+            ".git/index",
+            ".docs-cache/index.json",
+            "node_modules/pkg/index.js",
+            "logs/server.log",
+        ] {
+            assert!(is_ignored(&filters, &root.join(ignored)), "{ignored}");
+        }
+        for watched in ["src/main.rs", "Cargo.toml", "crates/target.rs"] {
+            assert!(!is_ignored(&filters, &root.join(watched)), "{watched}");
+        }
+        // The shared docs cache isn't in the project
+        let filter = watch_filter(root, Path::new("/nonexistent/cache"), &[]);
+        assert!(!is_ignored(&[filter], Path::new("/nonexistent/cache/a")));
+    }
+
+    #[test]
+    fn test_nested_project_filters() {
+        let root = Path::new("/nonexistent/workspace");
+        let member = root.join("member");
+        let filters = [
+            watch_filter(root, &root.join(".docs-cache"), &["*.log".to_string()]),
+            watch_filter(&member, &member.join(".docs-cache"), &[]),
+        ];
+        // The rules of the innermost project apply
+        assert!(is_ignored(&filters, &root.join("server.log")));
+        assert!(!is_ignored(&filters, &member.join("server.log")));
+        assert!(is_ignored(&filters, &member.join("target/debug/build.rs")));
+        assert!(!is_ignored(&filters, &member.join("src/lib.rs")));
+        assert!(!is_ignored(&filters, Path::new("/nonexistent/other/a.log")));
+    }
+}
@@ -1,14 +1,20 @@
+use std::collections::HashMap;
 use std::ops::ControlFlow;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use super::Stop;
+use super::ext::{ServerStatusNotification, ServerStatusParams};
 use crate::lsp::LspNotification;
 use async_lsp::router::Router;
 use async_lsp::{LanguageClient, ResponseError};
 use lsp_types::{
-    NumberOrString, ProgressParams, ProgressParamsValue, PublishDiagnosticsParams,
+    Diagnostic, NumberOrString, ProgressParams, ProgressParamsValue, PublishDiagnosticsParams,
     ShowMessageParams, WorkDoneProgress,
 };
+use tokio::sync::watch;
+use url::Url;
 
 // Old and new token names.
 const RA_INDEXING_TOKENS: &[&str] = &[
@@ -17,10 +23,67 @@ const RA_INDEXING_TOKENS: &[&str] = &[
     "rustAnalyzer/Building",
 ];
 
+/// The steps after which requests return complete results, building the build scripts
+/// can finish later
+const RA_PRIMING_TOKENS: &[&str] = &["rustAnalyzer/Indexing", "rustAnalyzer/cachePriming"];
+
+/// The latest diagnostics rust-analyzer published for each file, empty for the checked
+/// files without diagnostics
+pub type DiagnosticsCache = Arc<Mutex<HashMap<Url, Vec<Diagnostic>>>>;
+
+/// How far rust-analyzer got with indexing the project
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IndexStatus {
+    /// The initial indexing finished, the results of requests are complete from now on
+    pub indexed: bool,
+    /// The progress of all running indexing steps in percent
+    pub percentage: Option<u32>,
+}
+
+/// The progress of the indexing steps rust-analyzer reported since it started indexing
+#[derive(Debug, Default)]
+struct IndexingProgress {
+    /// The percentage of every step by token, 100 once the step ended
+    steps: HashMap<String, u32>,
+    started: Option<Instant>,
+}
+
+impl IndexingProgress {
+    /// Record the progress of a step. A step that begins after all steps ended starts
+    /// a new run.
+    fn update(&mut self, token: &str, progress: &WorkDoneProgress) {
+        if matches!(progress, WorkDoneProgress::Begin(_)) && self.is_finished() {
+            self.steps.clear();
+        }
+        self.started.get_or_insert_with(Instant::now);
+        let previous = self.steps.get(token).copied().unwrap_or_default();
+        let percentage = match progress {
+            WorkDoneProgress::Begin(begin) => begin.percentage.unwrap_or_default(),
+            WorkDoneProgress::Report(report) => report.percentage.unwrap_or(previous),
+            WorkDoneProgress::End(_) => 100,
+        };
+        self.steps.insert(token.to_string(), percentage.min(100));
+    }
+
+    /// The average progress of the steps of the run
+    fn percentage(&self) -> u32 {
+        if self.steps.is_empty() {
+            return 0;
+        }
+        self.steps.values().sum::<u32>() / self.steps.len() as u32
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.steps.is_empty() && self.steps.values().all(|percentage| *percentage == 100)
+    }
+}
+
 pub struct ClientState {
     project: PathBuf,
-    indexed_tx: Option<flume::Sender<()>>,
+    index_status: watch::Sender<IndexStatus>,
     notifier: flume::Sender<LspNotification>,
+    diagnostics: DiagnosticsCache,
+    progress: IndexingProgress,
 }
 
 impl LanguageClient for ClientState {
@@ -29,38 +92,57 @@ impl LanguageClient for ClientState {
 
     fn progress(&mut self, params: ProgressParams) -> Self::NotifyResult {
         tracing::trace!("{:?} {:?}", params.token, params.value);
-        let is_indexing =
-            matches!(params.token, NumberOrString::String(s) if RA_INDEXING_TOKENS.contains(&&*s));
-        let is_work_done = matches!(
-            params.value,
-            ProgressParamsValue::WorkDone(WorkDoneProgress::End(_))
-        );
-        if is_indexing && !is_work_done {
-            if let Err(e) = self.notifier.send(LspNotification::Indexing {
-                project: self.project.clone(),
-                is_indexing: true,
-            }) {
-                tracing::error!("Failed to send indexing notification: {}", e);
-            }
+        let NumberOrString::String(token) = &params.token else {
+            return ControlFlow::Continue(());
+        };
+        if !RA_INDEXING_TOKENS.contains(&token.as_str()) {
+            return ControlFlow::Continue(());
         }
-        if is_indexing && is_work_done {
+        let ProgressParamsValue::WorkDone(progress) = &params.value;
+        let was_indexing = !self.progress.is_finished();
+        let previous = self.progress.percentage();
+        self.progress.update(token, progress);
+        let is_indexing = !self.progress.is_finished();
+        let percentage = self.progress.percentage();
+
+        let is_primed = RA_PRIMING_TOKENS.contains(&token.as_str())
+            && matches!(progress, WorkDoneProgress::End(_));
+        self.index_status.send_modify(|status| {
+            status.percentage = is_indexing.then_some(percentage);
+            status.indexed |= is_primed;
+        });
+        if !is_indexing && let Some(started) = self.progress.started.take() {
+            tracing::info!(
+                "rust-analyzer indexed {} in {:.1}s",
+                self.project.display(),
+                started.elapsed().as_secs_f64()
+            );
+        }
+        if is_indexing != was_indexing || percentage != previous {
             if let Err(e) = self.notifier.send(LspNotification::Indexing {
                 project: self.project.clone(),
-                is_indexing: false,
+                is_indexing,
+                percentage: is_indexing.then_some(percentage),
             }) {
                 tracing::error!("Failed to send indexing notification: {}", e);
             }
-
-            if let Some(tx) = &self.indexed_tx {
-                if let Err(e) = tx.try_send(()) {
-                    tracing::error!("Failed to send indexing completion signal: {}", e);
-                }
-            }
         }
         ControlFlow::Continue(())
     }
 
-    fn publish_diagnostics(&mut self, _: PublishDiagnosticsParams) -> Self::NotifyResult {
+    fn publish_diagnostics(&mut self, params: PublishDiagnosticsParams) -> Self::NotifyResult {
+        tracing::trace!(
+            "{} diagnostics for {}",
+            params.diagnostics.len(),
+            params.uri
+        );
+        match self.diagnostics.lock() {
+            // Empty lists are kept, they tell that the file was checked
+            Ok(mut diagnostics) => {
+                diagnostics.insert(params.uri, params.diagnostics);
+            }
+            Err(e) => tracing::error!("Failed to lock diagnostics cache: {}", e),
+        }
         ControlFlow::Continue(())
     }
 
@@ -72,20 +154,88 @@ impl LanguageClient for ClientState {
 
 impl ClientState {
     pub fn new_router(
-        indexed_tx: flume::Sender<()>,
+        index_status: watch::Sender<IndexStatus>,
         notifier: flume::Sender<LspNotification>,
         project: PathBuf,
+        diagnostics: DiagnosticsCache,
     ) -> Router<Self> {
         let mut router = Router::from_language_client(ClientState {
-            indexed_tx: Some(indexed_tx),
+            index_status,
             notifier,
             project,
+            diagnostics,
+            progress: IndexingProgress::default(),
         });
         router.event(Self::on_stop);
+        router.notification::<ServerStatusNotification>(Self::on_server_status);
         router
     }
 
+    fn on_server_status(
+        &mut self,
+        status: ServerStatusParams,
+    ) -> ControlFlow<async_lsp::Result<()>> {
+        tracing::debug!("Server status {:?}", status);
+        // Without cache priming (`cachePriming.enable = false`) no priming step ends,
+        // rust-analyzer is ready once it finished loading the workspace
+        if status.quiescent {
+            self.index_status.send_modify(|index| index.indexed = true);
+        }
+        if let Err(e) = self.notifier.send(LspNotification::Status {
+            project: self.project.clone(),
+            status,
+        }) {
+            tracing::error!("Failed to send status notification: {}", e);
+        }
+        ControlFlow::Continue(())
+    }
+
     pub fn on_stop(&mut self, _: Stop) -> ControlFlow<async_lsp::Result<()>> {
         ControlFlow::Break(Ok(()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressReport};
+
+    fn begin() -> WorkDoneProgress {
+        WorkDoneProgress::Begin(WorkDoneProgressBegin::default())
+    }
+
+    fn report(percentage: Option<u32>) -> WorkDoneProgress {
+        WorkDoneProgress::Report(WorkDoneProgressReport {
+            percentage,
+            ..WorkDoneProgressReport::default()
+        })
+    }
+
+    fn end() -> WorkDoneProgress {
+        WorkDoneProgress::End(WorkDoneProgressEnd::default())
+    }
+
+    #[test]
+    fn test_indexing_progress() {
+        let mut progress = IndexingProgress::default();
+        progress.update("rustAnalyzer/Building", &begin());
+        progress.update("rustAnalyzer/cachePriming", &begin());
+        progress.update("rustAnalyzer/Building", &report(Some(60)));
+        assert_eq!(progress.percentage(), 30);
+        // Reports without a percentage keep the previous one
+        progress.update("rustAnalyzer/Building", &report(None));
+        assert_eq!(progress.percentage(), 30);
+        progress.update("rustAnalyzer/Building", &end());
+        progress.update("rustAnalyzer/cachePriming", &report(Some(50)));
+        assert_eq!(progress.percentage(), 75);
+        assert!(!progress.is_finished());
+        progress.update("rustAnalyzer/cachePriming", &end());
+        assert!(progress.is_finished());
+        assert_eq!(progress.percentage(), 100);
+
+        // A new run starts from scratch
+        progress.update("rustAnalyzer/cachePriming", &begin());
+        assert_eq!(progress.percentage(), 0);
+        assert!(!progress.is_finished());
+    }
+}
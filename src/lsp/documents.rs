@@ -0,0 +1,226 @@
+//! The documents opened in rust-analyzer. While a document is open, rust-analyzer uses
+//! the text it was sent instead of the file on disk, so every change on disk is sent
+//! as a `didChange` with the next version of the document. Only the recently used
+//! documents stay open, so that rust-analyzer doesn't keep the text of every file a
+//! tool ever looked at.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use async_lsp::ServerSocket;
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument};
+use lsp_types::{
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Position,
+    Range, TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    VersionedTextDocumentIdentifier,
+};
+use url::Url;
+
+use super::PositionEncoding;
+
+/// Opening another document closes the least recently used one
+const MAX_OPEN_DOCUMENTS: usize = 64;
+
+#[derive(Debug)]
+struct Document {
+    version: i32,
+    text: String,
+    used: Instant,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    encoding: PositionEncoding,
+    open: HashMap<Url, Document>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Documents(Arc<Mutex<State>>);
+
+impl Documents {
+    /// The position encoding rust-analyzer chose, used for the ranges of the changes
+    pub fn encoding(&self) -> PositionEncoding {
+        self.0
+            .lock()
+            .map(|state| state.encoding)
+            .unwrap_or_default()
+    }
+
+    pub fn set_encoding(&self, encoding: PositionEncoding) {
+        if let Ok(mut state) = self.0.lock() {
+            state.encoding = encoding;
+        }
+    }
+
+    /// Open the file in rust-analyzer unless it is open already
+    pub fn open(&self, server: &ServerSocket, uri: &Url, path: &Path) -> Result<()> {
+        let mut state = self.lock()?;
+        if let Some(document) = state.open.get_mut(uri) {
+            document.used = Instant::now();
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if state.open.len() >= MAX_OPEN_DOCUMENTS
+            && let Some(oldest) = state
+                .open
+                .iter()
+                .min_by_key(|(_, document)| document.used)
+                .map(|(uri, _)| uri.clone())
+        {
+            state.open.remove(&oldest);
+            server
+                .notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+                    text_document: TextDocumentIdentifier { uri: oldest },
+                })
+                .context("Sending DidClose notification failed")?;
+        }
+        server
+            .notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "rust".into(),
+                    version: 0,
+                    text: text.clone(),
+                },
+            })
+            .context("Sending DidOpen notification failed")?;
+        state.open.insert(
+            uri.clone(),
+            Document {
+                version: 0,
+                text,
+                used: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// The version of the document, `None` if it isn't open
+    pub fn version(&self, uri: &Url) -> Option<i32> {
+        let state = self.0.lock().ok()?;
+        state.open.get(uri).map(|document| document.version)
+    }
+
+    /// Send the changes of an open document on disk, or close it if it was deleted.
+    /// Returns false if the document isn't open.
+    pub fn sync(&self, server: &ServerSocket, uri: &Url, path: &Path) -> Result<bool> {
+        let mut state = self.lock()?;
+        let encoding = state.encoding;
+        let Some(document) = state.open.get_mut(uri) else {
+            return Ok(false);
+        };
+        let Ok(text) = std::fs::read_to_string(path) else {
+            state.open.remove(uri);
+            server
+                .notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                })
+                .context("Sending DidClose notification failed")?;
+            return Ok(false);
+        };
+        let Some(change) = text_change(&document.text, &text, encoding) else {
+            return Ok(true);
+        };
+        document.version += 1;
+        document.text = text;
+        server
+            .notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version: document.version,
+                },
+                content_changes: vec![change],
+            })
+            .context("Sending DidChange notification failed")?;
+        Ok(true)
+    }
+
+    /// Forget all documents, e.g. because rust-analyzer was restarted
+    pub fn clear(&self) {
+        if let Ok(mut state) = self.0.lock() {
+            state.open.clear();
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, State>> {
+        self.0
+            .lock()
+            .map_err(|_| anyhow::anyhow!("The open documents are poisoned"))
+    }
+}
+
+/// The smallest single edit that turns `old` into `new`, `None` if they're equal
+fn text_change(
+    old: &str,
+    new: &str,
+    encoding: PositionEncoding,
+) -> Option<TextDocumentContentChangeEvent> {
+    if old == new {
+        return None;
+    }
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((index, _), _)| index)
+        .unwrap_or(old.len().min(new.len()));
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    Some(TextDocumentContentChangeEvent {
+        range: Some(Range::new(
+            offset_to_position(old, prefix, encoding),
+            offset_to_position(old, old.len() - suffix, encoding),
+        )),
+        range_length: None,
+        text: new[prefix..new.len() - suffix].to_string(),
+    })
+}
+
+/// The LSP position of a byte offset
+fn offset_to_position(text: &str, offset: usize, encoding: PositionEncoding) -> Position {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    Position::new(line as u32, encoding.columns(&before[line_start..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::apply_text_edits;
+
+    #[test]
+    fn test_text_change() {
+        let old = "fn main() {\n    let ä = 1;\n}\n";
+        let utf16 = PositionEncoding::Utf16;
+        assert!(text_change(old, old, utf16).is_none());
+        for new in [
+            "fn main() {\n    let ä = 2;\n}\n",
+            "fn main() {\n    let ä = 1;\n    let b = ä;\n}\n",
+            "fn main() {\n}\n",
+            "",
+            "fn main() {\n    let ä = 1;\n}\n// end\n",
+        ] {
+            let change = text_change(old, new, utf16).unwrap();
+            let edit = lsp_types::TextEdit::new(change.range.unwrap(), change.text);
+            assert_eq!(apply_text_edits(old, &[edit], utf16), new);
+        }
+        let change = text_change(old, "fn main() {\n    let ä = 2;\n}\n", utf16).unwrap();
+        assert_eq!(
+            change.range,
+            Some(Range::new(Position::new(1, 12), Position::new(1, 13)))
+        );
+        assert_eq!(change.text, "2");
+    }
+}
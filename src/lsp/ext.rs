@@ -0,0 +1,185 @@
+//! rust-analyzer specific LSP extensions
+//! See <https://github.com/rust-lang/rust-analyzer/blob/master/docs/book/src/contributing/lsp-extensions.md>
+
+use lsp_types::notification::Notification;
+use lsp_types::request::Request;
+use lsp_types::{
+    LocationLink, Position, Range, TextDocumentIdentifier, TextDocumentPositionParams,
+    WorkspaceEdit,
+};
+use serde::{Deserialize, Serialize};
+
+pub enum InterpretFunction {}
+
+impl Request for InterpretFunction {
+    type Params = TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/interpretFunction";
+}
+
+pub enum ViewHir {}
+
+impl Request for ViewHir {
+    type Params = TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewHir";
+}
+
+pub enum ViewMir {}
+
+impl Request for ViewMir {
+    type Params = TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewMir";
+}
+
+/// The memory rust-analyzer's database uses, as a human readable table
+pub enum MemoryUsage {}
+
+impl Request for MemoryUsage {
+    type Params = ();
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/memoryUsage";
+}
+
+/// Sent by rust-analyzer whenever its health or its quiescence changes
+pub enum ServerStatusNotification {}
+
+impl Notification for ServerStatusNotification {
+    type Params = ServerStatusParams;
+    const METHOD: &'static str = "experimental/serverStatus";
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ServerStatusParams {
+    pub health: Health,
+    /// Whether rust-analyzer finished all background work
+    pub quiescent: bool,
+    /// What is wrong, if the health isn't `ok`
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Health {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Reload the cargo workspace, e.g. after the dependencies changed
+pub enum ReloadWorkspace {}
+
+impl Request for ReloadWorkspace {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/reloadWorkspace";
+}
+
+pub enum ExpandMacro {}
+
+impl Request for ExpandMacro {
+    type Params = ExpandMacroParams;
+    type Result = Option<ExpandedMacro>;
+    const METHOD: &'static str = "rust-analyzer/expandMacro";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandMacroParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandedMacro {
+    pub name: String,
+    pub expansion: String,
+}
+
+pub enum Ssr {}
+
+impl Request for Ssr {
+    type Params = SsrParams;
+    type Result = WorkspaceEdit;
+    const METHOD: &'static str = "experimental/ssr";
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsrParams {
+    pub query: String,
+    pub parse_only: bool,
+    /// File and position used to resolve paths in the query
+    #[serde(flatten)]
+    pub position: TextDocumentPositionParams,
+    /// Restricts the replacement to these ranges. Empty means the whole workspace
+    pub selections: Vec<Range>,
+}
+
+pub enum Runnables {}
+
+impl Request for Runnables {
+    type Params = RunnablesParams;
+    type Result = Vec<Runnable>;
+    const METHOD: &'static str = "experimental/runnables";
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnablesParams {
+    pub text_document: TextDocumentIdentifier,
+    /// Only return the runnables at this position. `None` returns all runnables of the file
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Runnable {
+    pub label: String,
+    pub location: Option<LocationLink>,
+    /// `cargo` or `shell`
+    pub kind: String,
+    pub args: RunnableArgs,
+}
+
+/// Union of the `cargo` and `shell` runnable arguments
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnableArgs {
+    #[serde(default)]
+    pub cargo_args: Vec<String>,
+    #[serde(default)]
+    pub executable_args: Vec<String>,
+    pub override_cargo: Option<String>,
+    pub program: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Runnable {
+    /// The shell command that executes the runnable
+    pub fn command(&self) -> String {
+        if self.kind == "shell" {
+            let program = self.args.program.clone().unwrap_or_default();
+            return std::iter::once(program)
+                .chain(self.args.args.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(" ");
+        }
+        let cargo = self
+            .args
+            .override_cargo
+            .clone()
+            .unwrap_or_else(|| "cargo".to_string());
+        let mut command = std::iter::once(cargo)
+            .chain(self.args.cargo_args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !self.args.executable_args.is_empty() {
+            command.push_str(" -- ");
+            command.push_str(&self.args.executable_args.join(" "));
+        }
+        command
+    }
+}
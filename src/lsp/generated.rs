@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use ignore::WalkBuilder;
+
+use crate::project::Project;
+
+/// Extensions of IDL files from which build scripts typically generate code
+const IDL_EXTENSIONS: &[&str] = &["proto", "h", "hpp", "idl", "fbs", "capnp", "thrift"];
+
+/// If the path is inside a build script `OUT_DIR` (`target/<profile>/build/<package>-<hash>/out`),
+/// return a note explaining that the file is generated, including the build script and the
+/// IDL files it was likely generated from.
+pub fn generated_code_note(project: &Project, path: &Path) -> Option<String> {
+    let package = out_dir_package(path)?;
+    let mut note = format!(
+        "Note: `{}` is generated code in the `OUT_DIR` of the `{package}` build script. Don't edit it, change its source instead.",
+        path.file_name()?.to_string_lossy()
+    );
+
+    let Some(build_script) = find_build_script(project.root(), &package) else {
+        return Some(note);
+    };
+    note.push_str(&format!("\nBuild script: {}", build_script.display()));
+
+    let sources = idl_sources(project.root(), &build_script, path);
+    if !sources.is_empty() {
+        note.push_str("\nGenerated from:");
+        for source in sources {
+            note.push_str(&format!("\n- {}", source.display()));
+        }
+    }
+    Some(note)
+}
+
+/// Whether the path is inside a build script `OUT_DIR`
+pub fn is_generated_code(path: &Path) -> bool {
+    out_dir_package(path).is_some()
+}
+
+/// Extracts the package name from an `OUT_DIR` path
+fn out_dir_package(path: &Path) -> Option<String> {
+    let components: Vec<_> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    let out_index = components.iter().rposition(|c| c == "out")?;
+    if out_index < 2 || components[out_index - 2] != "build" {
+        return None;
+    }
+    // `<package>-<hash>`
+    let (package, _) = components[out_index - 1].rsplit_once('-')?;
+    Some(package.to_string())
+}
+
+/// Find the build script of a workspace package
+fn find_build_script(root: &Path, package: &str) -> Option<PathBuf> {
+    for entry in WalkBuilder::new(root).build().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.file_name().and_then(|n| n.to_str()) != Some("Cargo.toml") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let Ok(manifest) = toml::from_str::<toml::Value>(&content) else {
+            continue;
+        };
+        let Some(package_table) = manifest.get("package") else {
+            continue;
+        };
+        let name = package_table.get("name").and_then(|n| n.as_str());
+        if name.map(|n| n.replace('-', "_")) != Some(package.replace('-', "_")) {
+            continue;
+        }
+        let dir = path.parent()?;
+        let script = package_table
+            .get("build")
+            .and_then(|b| b.as_str())
+            .unwrap_or("build.rs");
+        let script = dir.join(script);
+        return script.exists().then_some(script);
+    }
+    None
+}
+
+/// IDL files in the workspace referenced by the build script. Files whose name matches
+/// the generated file (prost names its output after the proto package) come first.
+fn idl_sources(root: &Path, build_script: &Path, generated: &Path) -> Vec<PathBuf> {
+    let Ok(script) = fs::read_to_string(build_script) else {
+        return Vec::new();
+    };
+    let generated_stem = generated
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut sources: Vec<PathBuf> = WalkBuilder::new(root)
+        .build()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IDL_EXTENSIONS.contains(&ext))
+        })
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| script.contains(name.to_string_lossy().as_ref()))
+        })
+        .collect();
+    sources.sort_by_key(|path| {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        !generated_stem.ends_with(&stem)
+    });
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_dir_package() {
+        assert_eq!(
+            out_dir_package(Path::new(
+                "/project/target/debug/build/my-protos-1a2b3c4d/out/my.package.rs"
+            )),
+            Some("my-protos".to_string())
+        );
+        assert_eq!(out_dir_package(Path::new("/project/src/out/main.rs")), None);
+        assert_eq!(out_dir_package(Path::new("/project/src/main.rs")), None);
+    }
+}
@@ -1,16 +1,116 @@
 mod change_notifier;
 mod client_state;
+mod documents;
+mod ext;
+mod generated;
 mod rust_analyzer_lsp;
 mod utils;
 
 pub(super) struct Stop;
 
 use std::path::PathBuf;
+use std::time::Duration;
 
-pub use rust_analyzer_lsp::RustAnalyzerLsp;
+use serde::{Deserialize, Serialize};
+
+pub use ext::{Health, ServerStatusParams};
+pub use generated::{generated_code_note, is_generated_code};
+pub use rust_analyzer_lsp::{RequestTimeout, RustAnalyzerLsp, StillIndexing};
 pub use utils::*;
 
+/// How often rust-analyzer is restarted after crashing in a row if the project doesn't say
+pub const DEFAULT_MAX_RESTARTS: u32 = 5;
+
+/// How long rust-analyzer may take to answer a request if the project doesn't say
+pub const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 60;
+
+/// How rust-analyzer runs for a project
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RustAnalyzerConfig {
+    /// Give up restarting rust-analyzer after it crashed this often in a row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+    /// Requests rust-analyzer doesn't answer within this time are cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_seconds: Option<u64>,
+    /// Gitignore style patterns of files whose changes aren't sent to rust-analyzer, in
+    /// addition to the `.gitignore` of the project
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch_ignore: Vec<String>,
+    /// rust-analyzer's settings, e.g. `cargo.features = "all"`, sent as `initializationOptions`
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub settings: serde_json::Map<String, serde_json::Value>,
+}
+
+impl RustAnalyzerConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn max_restarts(&self) -> u32 {
+        self.max_restarts.unwrap_or(DEFAULT_MAX_RESTARTS)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.request_timeout_seconds
+                .unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECONDS),
+        )
+    }
+
+    /// The `initializationOptions`, `None` to use rust-analyzer's defaults
+    pub fn initialization_options(&self) -> Option<serde_json::Value> {
+        (!self.settings.is_empty()).then(|| serde_json::Value::Object(self.settings.clone()))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum LspNotification {
-    Indexing { project: PathBuf, is_indexing: bool },
+    Indexing {
+        project: PathBuf,
+        is_indexing: bool,
+        /// The progress of all indexing steps, while indexing
+        percentage: Option<u32>,
+    },
+    /// rust-analyzer exited unexpectedly, `stderr` are the last lines it printed
+    Crashed { project: PathBuf, stderr: String },
+    /// rust-analyzer reported a change of its health
+    Status {
+        project: PathBuf,
+        status: ServerStatusParams,
+    },
+    /// A `Cargo.toml` or `Cargo.lock` of the project changed
+    ManifestChanged { project: PathBuf },
+    /// A line rust-analyzer printed to stderr
+    Stderr { project: PathBuf, line: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initialization_options() {
+        assert_eq!(RustAnalyzerConfig::default().initialization_options(), None);
+        let config: RustAnalyzerConfig = toml::from_str(
+            r#"
+            max_restarts = 3
+            [settings]
+            cargo.features = "all"
+            cargo.targetDir = true
+            checkOnSave = false
+            linkedProjects = ["crates/a/Cargo.toml"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.max_restarts(), 3);
+        assert_eq!(
+            config.initialization_options(),
+            Some(serde_json::json!({
+                "cargo": { "features": "all", "targetDir": true },
+                "checkOnSave": false,
+                "linkedProjects": ["crates/a/Cargo.toml"]
+            }))
+        );
+    }
 }
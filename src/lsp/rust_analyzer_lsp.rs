@@ -1,6 +1,9 @@
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use async_lsp::concurrency::ConcurrencyLayer;
@@ -8,185 +11,628 @@ use async_lsp::panic::CatchUnwindLayer;
 use async_lsp::server::LifecycleLayer;
 use async_lsp::tracing::TracingLayer;
 use async_lsp::{LanguageServer, ServerSocket};
-use lsp_types::request::GotoTypeDefinitionParams;
+use async_process::Child;
+use lsp_types::notification::{Cancel, DidChangeWorkspaceFolders, Initialized};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, GotoTypeDefinition,
+    GotoTypeDefinitionParams, HoverRequest, Initialize, InlayHintRequest, References, Request,
+    Shutdown, WorkspaceSymbolRequest,
+};
 use lsp_types::{
-    ClientCapabilities, DidOpenTextDocumentParams, DocumentSymbolClientCapabilities,
-    GotoDefinitionResponse, Hover, HoverClientCapabilities, HoverParams, InitializeParams,
-    InitializedParams, Location, MarkupKind, Position, ReferenceContext, ReferenceParams,
-    TextDocumentClientCapabilities, TextDocumentIdentifier, TextDocumentItem,
-    TextDocumentPositionParams, WindowClientCapabilities, WorkDoneProgressParams, WorkspaceFolder,
+    CancelParams, ClientCapabilities, CodeActionClientCapabilities, CodeActionContext,
+    CodeActionKindLiteralSupport, CodeActionLiteralSupport, CodeActionParams, CodeActionResponse,
+    CompletionClientCapabilities, CompletionItemCapability, CompletionParams, CompletionResponse,
+    Diagnostic, DidChangeWorkspaceFoldersParams, DocumentSymbolClientCapabilities,
+    GeneralClientCapabilities, GotoCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverClientCapabilities, HoverParams, InitializeParams, InitializedParams, InlayHint,
+    InlayHintParams, Location, MarkupKind, NumberOrString, OneOf, Position, Range,
+    ReferenceContext, ReferenceParams, SymbolInformation, TextDocumentClientCapabilities,
+    TextDocumentIdentifier, TextDocumentPositionParams, WindowClientCapabilities,
+    WorkDoneProgressParams, WorkspaceClientCapabilities, WorkspaceEdit, WorkspaceFolder,
+    WorkspaceFoldersChangeEvent, WorkspaceSymbolParams, WorkspaceSymbolResponse,
 };
 use serde_json::json;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard, watch};
 use tokio::task::JoinHandle;
 use tower::ServiceBuilder;
 use tracing::{debug, info};
 
 use super::change_notifier::ChangeNotifier;
-use super::client_state::ClientState;
+use super::client_state::{ClientState, DiagnosticsCache, IndexStatus};
+use super::documents::Documents;
+use super::ext::{
+    ExpandMacro, ExpandMacroParams, ExpandedMacro, InterpretFunction, MemoryUsage, ReloadWorkspace,
+    Runnable, Runnables, RunnablesParams, Ssr, SsrParams, ViewHir, ViewMir,
+};
+use super::utils::{PositionEncoding, flatten_document_symbols};
 use crate::lsp::LspNotification;
 use crate::project::Project;
 use flume::Sender;
+use futures::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use url::Url;
+
+/// How long a restart waits for rust-analyzer to shut down before it is killed
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a tool call waits for rust-analyzer to finish indexing before it fails
+const INDEX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long rust-analyzer gets to publish the diagnostics of a file it didn't check yet
+const DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many of the last lines rust-analyzer printed to stderr are kept for crash reports
+const STDERR_TAIL_LINES: usize = 50;
+
+/// How many of the last stderr lines are added to the error of a failed request
+const ERROR_STDERR_LINES: usize = 10;
+
+/// The wait before restarting after the first crash, doubled for every further crash in a row
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
 
+/// A crash after running this long doesn't count as a crash in a row
+const STABLE_AFTER: Duration = Duration::from_secs(300);
+
+/// The last lines rust-analyzer printed to stderr
+type StderrTail = Arc<std::sync::Mutex<VecDeque<String>>>;
+
+/// rust-analyzer for a project. Projects in the same cargo workspace share the
+/// rust-analyzer of the first one, each with its own workspace folder.
 #[derive(Debug)]
 pub struct RustAnalyzerLsp {
+    /// The project relative paths are resolved against
+    project: Project,
+    instance: Arc<Instance>,
+}
+
+/// A rust-analyzer process and its state, shared by the projects using it
+#[derive(Debug)]
+struct Instance {
+    /// The project rust-analyzer was started for, its config applies to all projects
     project: Project,
+    /// The roots of the projects using this instance, sent as workspace folders
+    roots: std::sync::Mutex<Vec<PathBuf>>,
+    notifier: Sender<LspNotification>,
     server: Arc<Mutex<ServerSocket>>,
     #[allow(dead_code)] // Keep the handle to ensure the mainloop runs
     mainloop_handle: Mutex<Option<JoinHandle<()>>>,
-    indexed_rx: Mutex<flume::Receiver<()>>,
-    #[allow(dead_code)] // Keep the handle to ensure the change notifier runs
-    change_notifier: ChangeNotifier,
+    process: Mutex<Option<Child>>,
+    /// Set while rust-analyzer is shut down on purpose, so that it isn't reported as crashed
+    stopping: Arc<AtomicBool>,
+    /// The number of requests sent to the current process, i.e. the id of the next one
+    request_count: AtomicI32,
+    stderr: StderrTail,
+    /// The crashes in a row and when the last one happened
+    crashes: Mutex<Option<(u32, Instant)>>,
+    index_status: watch::Sender<IndexStatus>,
+    change_notifier: std::sync::Mutex<ChangeNotifier>,
+    documents: Documents,
+    diagnostics: DiagnosticsCache,
+    expansions: Mutex<ExpansionCache>,
 }
 
-impl RustAnalyzerLsp {
-    pub async fn new(project: &Project, notifier: Sender<LspNotification>) -> Result<Self> {
-        let (indexed_tx, indexed_rx) = flume::unbounded();
-        let (mainloop, server) = async_lsp::MainLoop::new_client(|_server| {
-            ServiceBuilder::new()
-                .layer(TracingLayer::default())
-                .layer(LifecycleLayer::default()) // Handle init/shutdown automatically
-                .layer(CatchUnwindLayer::default())
-                .layer(ConcurrencyLayer::default())
-                .service(ClientState::new_router(
-                    indexed_tx,
-                    notifier,
-                    project.root().to_path_buf(),
-                ))
-        });
+/// rust-analyzer didn't answer a request in time and it was cancelled
+#[derive(Debug)]
+pub struct RequestTimeout {
+    pub method: &'static str,
+    pub timeout: Duration,
+}
 
-        let process = async_process::Command::new("rust-analyzer")
-            .current_dir(project.root())
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::inherit())
-            .spawn()
-            .context("Failed run rust-analyzer")?;
-
-        let stdout = process.stdout.context("Failed to get stdout")?;
-        let stdin = process.stdin.context("Failed to get stdin")?;
-
-        let mainloop_handle = tokio::spawn(async move {
-            match mainloop.run_buffered(stdout, stdin).await {
-                Ok(()) => debug!("LSP mainloop finished gracefully."),
-                Err(e) => tracing::error!("LSP mainloop finished with error: {}", e),
-            }
-        });
+impl std::fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rust-analyzer didn't answer `{}` within {}s and the request was cancelled. It may be busy, try again or narrow down the request.",
+            self.method,
+            self.timeout.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
+/// rust-analyzer didn't finish indexing the project in time to answer a request
+#[derive(Debug)]
+pub struct StillIndexing {
+    pub percentage: Option<u32>,
+}
+
+impl std::fmt::Display for StillIndexing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.percentage {
+            Some(percentage) => write!(
+                f,
+                "rust-analyzer is still indexing the project ({percentage}% complete), try again in a moment"
+            ),
+            None => write!(
+                f,
+                "rust-analyzer is still indexing the project, try again in a moment"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StillIndexing {}
 
-        let server = Arc::new(Mutex::new(server));
+/// The cache is cleared once it holds this many expansions
+const MAX_CACHED_EXPANSIONS: usize = 256;
+
+/// Macro expansions since the last file change. A change to any file can change the
+/// expansion of a macro in another one, e.g. if it changes the macro.
+#[derive(Debug, Default)]
+struct ExpansionCache {
+    /// The change count of the notifier when the expansions were made
+    change_count: u64,
+    expansions: HashMap<(PathBuf, Position), Option<ExpandedMacro>>,
+}
+
+/// A freshly spawned rust-analyzer process that wasn't initialized yet
+struct Spawned {
+    server: ServerSocket,
+    mainloop_handle: JoinHandle<()>,
+    process: Child,
+}
+
+impl RustAnalyzerLsp {
+    pub async fn new(project: &Project, notifier: Sender<LspNotification>) -> Result<Self> {
+        let diagnostics = DiagnosticsCache::default();
+        let stopping = Arc::new(AtomicBool::new(false));
+        let stderr = StderrTail::default();
+        let (index_status, _) = watch::channel(IndexStatus::default());
+        let spawned = spawn(
+            project,
+            notifier.clone(),
+            diagnostics.clone(),
+            index_status.clone(),
+            stopping.clone(),
+            stderr.clone(),
+        )?;
+        let server = Arc::new(Mutex::new(spawned.server));
 
         // Get the current runtime handle
         let handle = tokio::runtime::Handle::current();
-        let change_notifier = ChangeNotifier::new(server.clone(), project, handle)?;
+        let documents = Documents::default();
+        let change_notifier = ChangeNotifier::new(
+            server.clone(),
+            project,
+            handle,
+            notifier.clone(),
+            documents.clone(),
+        )?;
 
-        let client = Self {
+        let instance = Instance {
             project: project.clone(),
+            roots: std::sync::Mutex::new(vec![project.root().clone()]),
+            notifier,
             server,
-            mainloop_handle: Mutex::new(Some(mainloop_handle)),
-            indexed_rx: Mutex::new(indexed_rx),
-            change_notifier,
+            mainloop_handle: Mutex::new(Some(spawned.mainloop_handle)),
+            process: Mutex::new(Some(spawned.process)),
+            stopping,
+            request_count: AtomicI32::new(0),
+            stderr,
+            crashes: Mutex::default(),
+            index_status,
+            change_notifier: std::sync::Mutex::new(change_notifier),
+            documents,
+            diagnostics,
+            expansions: Mutex::default(),
         };
+        let client = Self {
+            project: project.clone(),
+            instance: Arc::new(instance),
+        };
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// Use this rust-analyzer for another project of the same cargo workspace
+    pub async fn share(&self, project: &Project) -> Result<Self> {
+        let root = project.root().clone();
+        {
+            let mut roots = self.roots()?;
+            self.instance
+                .change_notifier
+                .lock()
+                .map_err(|_| anyhow::anyhow!("The change notifier is poisoned"))?
+                .watch(project, &roots)?;
+            roots.push(root.clone());
+        }
+        self.change_workspace_folders(vec![workspace_folder(project)?], Vec::new())
+            .await?;
+        info!(
+            "Sharing rust-analyzer of {} with {}",
+            self.instance.project.root().display(),
+            root.display()
+        );
+        Ok(Self {
+            project: project.clone(),
+            instance: self.instance.clone(),
+        })
+    }
+
+    /// The root of the project rust-analyzer was started for. Its notifications are
+    /// about this root, also for the projects sharing it.
+    pub fn server_root(&self) -> &PathBuf {
+        self.instance.project.root()
+    }
+
+    fn roots(&self) -> Result<std::sync::MutexGuard<'_, Vec<PathBuf>>> {
+        self.instance
+            .roots
+            .lock()
+            .map_err(|_| anyhow::anyhow!("The workspace folders are poisoned"))
+    }
 
-        // Initialize.
-        let init_ret = client
+    async fn change_workspace_folders(
+        &self,
+        added: Vec<WorkspaceFolder>,
+        removed: Vec<WorkspaceFolder>,
+    ) -> Result<()> {
+        self.instance
             .server
             .lock()
             .await
-            .initialize(InitializeParams {
-                workspace_folders: Some(vec![WorkspaceFolder {
-                    uri: project.uri()?,
-                    name: "root".into(),
-                }]),
-                capabilities: ClientCapabilities {
-                    window: Some(WindowClientCapabilities {
-                        work_done_progress: Some(true), // Required for indexing progress
-                        ..WindowClientCapabilities::default()
-                    }),
-                    text_document: Some(TextDocumentClientCapabilities {
-                        document_symbol: Some(DocumentSymbolClientCapabilities {
-                            // Flat symbols are easier to process for us
-                            hierarchical_document_symbol_support: Some(false),
-                            ..DocumentSymbolClientCapabilities::default()
+            .notify::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+                event: WorkspaceFoldersChangeEvent { added, removed },
+            })
+            .context("Sending DidChangeWorkspaceFolders notification failed")
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let project = &self.instance.project;
+        let folders = self
+            .roots()?
+            .iter()
+            .map(|root| {
+                Url::from_directory_path(root)
+                    .map(|uri| WorkspaceFolder {
+                        uri,
+                        name: folder_name(root),
+                    })
+                    .map_err(|_| anyhow::anyhow!("Failed to create URI for {root:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let server = self.instance.server.lock().await;
+        let timeout = project.rust_analyzer_config().request_timeout();
+        let init_ret = self
+            .send_request::<Initialize>(
+                &server,
+                InitializeParams {
+                    workspace_folders: Some(folders),
+                    capabilities: ClientCapabilities {
+                        workspace: Some(WorkspaceClientCapabilities {
+                            workspace_folders: Some(true),
+                            ..WorkspaceClientCapabilities::default()
+                        }),
+                        window: Some(WindowClientCapabilities {
+                            work_done_progress: Some(true), // Required for indexing progress
+                            ..WindowClientCapabilities::default()
                         }),
-                        hover: Some(HoverClientCapabilities {
-                            content_format: Some(vec![MarkupKind::Markdown]),
-                            ..HoverClientCapabilities::default()
+                        text_document: Some(TextDocumentClientCapabilities {
+                            document_symbol: Some(DocumentSymbolClientCapabilities {
+                                // Nested symbols tell which impl block a method belongs to
+                                hierarchical_document_symbol_support: Some(true),
+                                ..DocumentSymbolClientCapabilities::default()
+                            }),
+                            // Links carry the range of the whole item, not only its name
+                            definition: Some(GotoCapability {
+                                link_support: Some(true),
+                                ..GotoCapability::default()
+                            }),
+                            type_definition: Some(GotoCapability {
+                                link_support: Some(true),
+                                ..GotoCapability::default()
+                            }),
+                            hover: Some(HoverClientCapabilities {
+                                content_format: Some(vec![MarkupKind::Markdown]),
+                                ..HoverClientCapabilities::default()
+                            }),
+                            completion: Some(CompletionClientCapabilities {
+                                completion_item: Some(CompletionItemCapability {
+                                    // Plain text completions, we don't render snippets
+                                    snippet_support: Some(false),
+                                    documentation_format: Some(vec![MarkupKind::Markdown]),
+                                    ..CompletionItemCapability::default()
+                                }),
+                                ..CompletionClientCapabilities::default()
+                            }),
+                            code_action: Some(CodeActionClientCapabilities {
+                                // Without literal support we would only receive commands
+                                code_action_literal_support: Some(CodeActionLiteralSupport {
+                                    code_action_kind: CodeActionKindLiteralSupport {
+                                        value_set: [
+                                            "",
+                                            "quickfix",
+                                            "refactor",
+                                            "refactor.extract",
+                                            "refactor.inline",
+                                            "refactor.rewrite",
+                                            "source",
+                                            "source.organizeImports",
+                                        ]
+                                        .iter()
+                                        .map(|s| s.to_string())
+                                        .collect(),
+                                    },
+                                }),
+                                ..CodeActionClientCapabilities::default()
+                            }),
+                            ..TextDocumentClientCapabilities::default()
                         }),
-                        ..TextDocumentClientCapabilities::default()
-                    }),
-                    experimental: Some(json!({
-                        "hoverActions": true
-                    })),
-                    ..ClientCapabilities::default()
+                        experimental: Some(json!({
+                            "hoverActions": true,
+                            "serverStatusNotification": true
+                        })),
+                        general: Some(GeneralClientCapabilities {
+                            position_encodings: Some(PositionEncoding::supported()),
+                            ..GeneralClientCapabilities::default()
+                        }),
+                    },
+                    initialization_options: project.rust_analyzer_config().initialization_options(),
+                    ..InitializeParams::default()
                 },
-                ..InitializeParams::default()
-            })
-            .await
-            .context("LSP initialize failed")?;
+                timeout,
+                "LSP initialize failed",
+            )
+            .await?;
         tracing::trace!("Initialized: {init_ret:?}");
+        let encoding =
+            PositionEncoding::from_kind(init_ret.capabilities.position_encoding.as_ref());
+        debug!("Position encoding: {encoding:?}");
+        self.instance.documents.set_encoding(encoding);
         info!("LSP Initialized");
 
-        client
-            .server
-            .lock()
-            .await
-            .initialized(InitializedParams {})
+        server
+            .notify::<Initialized>(InitializedParams {})
             .context("Sending Initialized notification failed")?;
+        drop(server);
 
         info!("Waiting for rust-analyzer indexing...");
-        let rx = client.indexed_rx.lock().await.clone();
+        let mut status = self.instance.index_status.subscribe();
         tokio::spawn(async move {
-            while let Ok(()) = rx.recv_async().await {
+            if status.wait_for(|status| status.indexed).await.is_ok() {
                 info!("rust-analyzer indexing finished.");
             }
         });
 
-        Ok(client)
+        Ok(())
     }
 
+    /// Stop using rust-analyzer for the project. It is shut down once no project uses it.
     pub async fn shutdown(&self) -> Result<()> {
-        self.server
-            .lock()
-            .await
-            .shutdown(())
+        let root = self.project.root();
+        let remaining = {
+            let mut roots = self.roots()?;
+            if let Some(index) = roots.iter().position(|existing| existing == root) {
+                roots.remove(index);
+            }
+            roots.clone()
+        };
+        if remaining.is_empty() {
+            return self.stop().await;
+        }
+        if let Ok(mut notifier) = self.instance.change_notifier.lock() {
+            notifier.unwatch(root, &remaining);
+        }
+        // Another project with the same root still uses the folder
+        if remaining.contains(root) {
+            return Ok(());
+        }
+        self.change_workspace_folders(Vec::new(), vec![workspace_folder(&self.project)?])
             .await
-            .context("Sending Shutdown request failed")?;
-        self.server
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.instance.stopping.store(true, Ordering::Relaxed);
+        self.send_request::<Shutdown>(
+            &*self.instance.server.lock().await,
+            (),
+            SHUTDOWN_TIMEOUT,
+            "Sending Shutdown request failed",
+        )
+        .await?;
+        self.instance
+            .server
             .lock()
             .await
             .exit(())
             .context("Sending Exit notification failed")?;
 
         // Wait for the mainloop to finish. This implicitly waits for the process to exit.
-        if let Err(e) = self.mainloop_handle.lock().await.take().unwrap().await {
+        if let Some(handle) = self.instance.mainloop_handle.lock().await.take()
+            && let Err(e) = handle.await
+        {
             tracing::error!("Error joining LSP mainloop task: {:?}", e);
         }
 
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub async fn open_file(&self, relative_path: impl AsRef<Path>, text: String) -> Result<()> {
+    /// Replace the rust-analyzer process with a fresh one, e.g. because it hangs.
+    /// The file watcher keeps running and notifies the new process.
+    pub async fn restart(&self) -> Result<()> {
+        // Restarting by hand gives a crashing rust-analyzer a fresh set of restarts
+        *self.instance.crashes.lock().await = None;
+        self.respawn().await
+    }
+
+    /// Restart rust-analyzer after it crashed. Waits longer after every crash in a row
+    /// and gives up after the `max_restarts` of the project.
+    pub async fn recover(&self) -> Result<()> {
+        let max_restarts = self.instance.project.rust_analyzer_config().max_restarts();
+        let crashes = {
+            let mut crashes = self.instance.crashes.lock().await;
+            let count = match *crashes {
+                Some((count, last)) if last.elapsed() < STABLE_AFTER => count + 1,
+                _ => 1,
+            };
+            *crashes = Some((count, Instant::now()));
+            count
+        };
+        if crashes > max_restarts {
+            anyhow::bail!(
+                "rust-analyzer crashed {crashes} times in a row, restart it by hand once the problem is fixed"
+            );
+        }
+        let delay = RESTART_BACKOFF
+            .saturating_mul(2u32.saturating_pow(crashes - 1))
+            .min(MAX_RESTART_BACKOFF);
+        tracing::warn!(
+            "Restarting rust-analyzer for {} in {delay:?} ({crashes}/{max_restarts})",
+            self.instance.project.root().display()
+        );
+        tokio::time::sleep(delay).await;
+        self.respawn().await
+    }
+
+    async fn respawn(&self) -> Result<()> {
+        info!(
+            "Restarting rust-analyzer for {}",
+            self.instance.project.root().display()
+        );
+        // A wedged rust-analyzer doesn't answer the shutdown request
+        match tokio::time::timeout(SHUTDOWN_TIMEOUT, self.stop()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("rust-analyzer didn't shut down cleanly: {e:?}"),
+            Err(_) => tracing::warn!("rust-analyzer didn't shut down within {SHUTDOWN_TIMEOUT:?}"),
+        }
+        if let Some(handle) = self.instance.mainloop_handle.lock().await.take() {
+            handle.abort();
+        }
+        if let Some(mut process) = self.instance.process.lock().await.take()
+            && let Err(e) = process.kill()
+        {
+            tracing::warn!("Failed to kill rust-analyzer: {e}");
+        }
+        if let Ok(mut diagnostics) = self.instance.diagnostics.lock() {
+            diagnostics.clear();
+        }
+        self.instance.expansions.lock().await.expansions.clear();
+        // The files are opened again in the new process when they're used
+        self.instance.documents.clear();
+
+        self.instance.stopping.store(false, Ordering::Relaxed);
+        self.instance.request_count.store(0, Ordering::Relaxed);
+        self.instance
+            .index_status
+            .send_replace(IndexStatus::default());
+        let spawned = spawn(
+            &self.instance.project,
+            self.instance.notifier.clone(),
+            self.instance.diagnostics.clone(),
+            self.instance.index_status.clone(),
+            self.instance.stopping.clone(),
+            self.instance.stderr.clone(),
+        )?;
+        *self.instance.server.lock().await = spawned.server;
+        *self.instance.mainloop_handle.lock().await = Some(spawned.mainloop_handle);
+        *self.instance.process.lock().await = Some(spawned.process);
+        self.initialize().await
+    }
+
+    /// What the columns of the positions count
+    pub fn position_encoding(&self) -> PositionEncoding {
+        self.instance.documents.encoding()
+    }
+
+    /// The uri of a file, opened in rust-analyzer so that it stays in sync with the disk
+    async fn document(&self, relative_path: impl AsRef<Path>) -> Result<Url> {
+        let relative_path = relative_path.as_ref();
         let uri = self.project.file_uri(relative_path)?;
-        self.server
-            .lock()
-            .await
-            .did_open(DidOpenTextDocumentParams {
-                text_document: TextDocumentItem {
-                    uri: uri.clone(),
-                    language_id: "rust".into(), // Assuming Rust, could be made generic
-                    version: 0,                 // Start with version 0
-                    text,
-                },
-            })
-            .context("Sending DidOpen notification failed")?;
-        self.indexed_rx
-            .lock()
+        self.instance.documents.open(
+            &*self.instance.server.lock().await,
+            &uri,
+            &self.project.root().join(relative_path),
+        )?;
+        Ok(uri)
+    }
+
+    /// The description of a failed request, with what rust-analyzer printed last
+    fn error_context(&self, context: &str) -> String {
+        let Ok(tail) = self.instance.stderr.lock() else {
+            return context.to_string();
+        };
+        if tail.is_empty() {
+            return context.to_string();
+        }
+        let lines = tail
+            .iter()
+            .skip(tail.len().saturating_sub(ERROR_STDERR_LINES))
+            .cloned()
+            .collect::<Vec<_>>();
+        format!(
+            "{context}. The last output of rust-analyzer:\n{}",
+            lines.join("\n")
+        )
+    }
+
+    /// Wait until rust-analyzer finished indexing the project, before that it answers
+    /// with empty or incomplete results
+    pub async fn wait_for_index(&self) -> Result<()> {
+        let mut status = self.instance.index_status.subscribe();
+        let indexed = tokio::time::timeout(INDEX_TIMEOUT, status.wait_for(|status| status.indexed))
             .await
-            .recv_async()
+            .is_ok_and(|indexed| indexed.is_ok());
+        if indexed {
+            return Ok(());
+        }
+        Err(StillIndexing {
+            percentage: self.instance.index_status.borrow().percentage,
+        }
+        .into())
+    }
+
+    /// The connection to rust-analyzer, once it indexed the project
+    async fn server(&self) -> Result<MutexGuard<'_, ServerSocket>> {
+        self.wait_for_index().await?;
+        Ok(self.instance.server.lock().await)
+    }
+
+    /// Send a request once rust-analyzer indexed the project. `context` describes
+    /// failures other than timeouts.
+    async fn request<R: Request>(
+        &self,
+        params: R::Params,
+        context: &'static str,
+    ) -> Result<R::Result> {
+        let server = self.server().await?;
+        let timeout = self
+            .instance
+            .project
+            .rust_analyzer_config()
+            .request_timeout();
+        self.send_request::<R>(&server, params, timeout, context)
             .await
-            .context("Failed waiting for index")?;
-        Ok(())
+    }
+
+    /// Send a request and cancel it with `$/cancelRequest` if rust-analyzer doesn't
+    /// answer within `timeout`
+    async fn send_request<R: Request>(
+        &self,
+        server: &ServerSocket,
+        params: R::Params,
+        timeout: Duration,
+        context: &'static str,
+    ) -> Result<R::Result> {
+        // async-lsp numbers the requests of a main loop from 0 without exposing the ids.
+        // Requests are only sent while holding the server lock, so the count matches.
+        let id = self.instance.request_count.fetch_add(1, Ordering::Relaxed);
+        match tokio::time::timeout(timeout, server.request::<R>(params)).await {
+            Ok(result) => result.with_context(|| self.error_context(context)),
+            Err(_) => {
+                if let Err(e) = server.notify::<Cancel>(CancelParams {
+                    id: NumberOrString::Number(id),
+                }) {
+                    tracing::warn!("Failed to cancel `{}`: {}", R::METHOD, e);
+                }
+                Err(RequestTimeout {
+                    method: R::METHOD,
+                    timeout,
+                }
+                .into())
+            }
+        }
     }
 
     pub async fn hover(
@@ -194,19 +640,38 @@ impl RustAnalyzerLsp {
         relative_path: impl AsRef<Path>,
         position: Position,
     ) -> Result<Option<Hover>> {
-        let uri = self.project.file_uri(relative_path)?;
-        self.server
-            .lock()
-            .await
-            .hover(HoverParams {
+        let uri = self.document(relative_path).await?;
+        self.request::<HoverRequest>(
+            HoverParams {
                 text_document_position_params: TextDocumentPositionParams {
                     text_document: TextDocumentIdentifier { uri },
                     position,
                 },
                 work_done_progress_params: WorkDoneProgressParams::default(),
-            })
-            .await
-            .context("Hover request failed")
+            },
+            "Hover request failed",
+        )
+        .await
+    }
+
+    pub async fn definition(
+        &self,
+        relative_path: impl AsRef<Path>,
+        position: Position,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = self.document(relative_path).await?;
+        self.request::<GotoDefinition>(
+            GotoDefinitionParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri },
+                    position,
+                },
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: Default::default(),
+            },
+            "Definition request failed",
+        )
+        .await
     }
 
     pub async fn type_definition(
@@ -214,20 +679,19 @@ impl RustAnalyzerLsp {
         relative_path: impl AsRef<Path>,
         position: Position,
     ) -> Result<Option<GotoDefinitionResponse>> {
-        let uri = self.project.file_uri(relative_path)?;
-        self.server
-            .lock()
-            .await
-            .type_definition(GotoTypeDefinitionParams {
+        let uri = self.document(relative_path).await?;
+        self.request::<GotoTypeDefinition>(
+            GotoTypeDefinitionParams {
                 text_document_position_params: TextDocumentPositionParams {
                     text_document: TextDocumentIdentifier { uri },
                     position,
                 },
                 work_done_progress_params: WorkDoneProgressParams::default(),
                 partial_result_params: Default::default(),
-            })
-            .await
-            .context("Type definition request failed")
+            },
+            "Type definition request failed",
+        )
+        .await
     }
 
     pub async fn find_references(
@@ -235,11 +699,9 @@ impl RustAnalyzerLsp {
         relative_path: impl AsRef<Path>,
         position: Position,
     ) -> Result<Option<Vec<Location>>> {
-        let uri = self.project.file_uri(relative_path)?;
-        self.server
-            .lock()
-            .await
-            .references(ReferenceParams {
+        let uri = self.document(relative_path).await?;
+        self.request::<References>(
+            ReferenceParams {
                 text_document_position: TextDocumentPositionParams {
                     text_document: TextDocumentIdentifier { uri },
                     position,
@@ -249,34 +711,451 @@ impl RustAnalyzerLsp {
                 context: ReferenceContext {
                     include_declaration: true,
                 },
-            })
-            .await
-            .context("References request failed")
+            },
+            "References request failed",
+        )
+        .await
     }
 
     pub async fn document_symbols(
         &self,
         relative_path: impl AsRef<Path>,
     ) -> Result<Option<Vec<lsp_types::SymbolInformation>>> {
-        let uri = self.project.file_uri(relative_path)?;
+        let uri = self.document(relative_path).await?;
         let o = self
-            .server
-            .lock()
-            .await
-            .document_symbol(lsp_types::DocumentSymbolParams {
+            .request::<DocumentSymbolRequest>(
+                lsp_types::DocumentSymbolParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                    partial_result_params: Default::default(),
+                },
+                "Document symbols request failed",
+            )
+            .await?
+            .map(|symbols| match symbols {
+                lsp_types::DocumentSymbolResponse::Flat(flat) => flat,
+                lsp_types::DocumentSymbolResponse::Nested(nested) => {
+                    flatten_document_symbols(&uri, nested)
+                }
+            });
+        Ok(o)
+    }
+
+    /// Search the symbols of the whole workspace, rust-analyzer ranks them by how well
+    /// they match the query
+    pub async fn workspace_symbols(&self, query: &str) -> Result<Vec<SymbolInformation>> {
+        let symbols = self
+            .request::<WorkspaceSymbolRequest>(
+                WorkspaceSymbolParams {
+                    query: query.to_string(),
+                    work_done_progress_params: WorkDoneProgressParams::default(),
+                    partial_result_params: Default::default(),
+                },
+                "Workspace symbols request failed",
+            )
+            .await?;
+        Ok(match symbols {
+            Some(WorkspaceSymbolResponse::Flat(flat)) => flat,
+            Some(WorkspaceSymbolResponse::Nested(nested)) => nested
+                .into_iter()
+                .filter_map(|symbol| {
+                    // Symbols without a range can't be hovered
+                    let OneOf::Left(location) = symbol.location else {
+                        return None;
+                    };
+                    #[allow(deprecated)]
+                    Some(SymbolInformation {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        tags: symbol.tags,
+                        deprecated: None,
+                        location,
+                        container_name: symbol.container_name,
+                    })
+                })
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    pub async fn code_actions(
+        &self,
+        relative_path: impl AsRef<Path>,
+        range: Range,
+    ) -> Result<Option<CodeActionResponse>> {
+        let uri = self.document(relative_path).await?;
+        self.request::<CodeActionRequest>(
+            CodeActionParams {
                 text_document: TextDocumentIdentifier { uri },
+                range,
+                context: CodeActionContext::default(),
                 work_done_progress_params: WorkDoneProgressParams::default(),
                 partial_result_params: Default::default(),
-            })
+            },
+            "Code action request failed",
+        )
+        .await
+    }
+
+    /// The diagnostics of the file. The file is opened and synced with the disk first,
+    /// so that rust-analyzer checks its current content.
+    pub async fn diagnostics(&self, relative_path: impl AsRef<Path>) -> Result<Vec<Diagnostic>> {
+        let relative_path = relative_path.as_ref();
+        let documents = &self.instance.documents;
+        let previous_version = documents.version(&self.project.file_uri(relative_path)?);
+        let uri = self.document(relative_path).await?;
+        documents.sync(
+            &*self.instance.server.lock().await,
+            &uri,
+            &self.project.root().join(relative_path),
+        )?;
+        // The published diagnostics are for an older text
+        if documents.version(&uri) != previous_version
+            && let Ok(mut diagnostics) = self.instance.diagnostics.lock()
+        {
+            diagnostics.remove(&uri);
+        }
+        let published = || {
+            self.instance
+                .diagnostics
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock diagnostics cache: {}", e))
+                .map(|diagnostics| diagnostics.get(&uri).cloned())
+        };
+        let wait = async {
+            loop {
+                if let Some(diagnostics) = published()? {
+                    return Ok::<_, anyhow::Error>(diagnostics);
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+        match tokio::time::timeout(DIAGNOSTICS_TIMEOUT, wait).await {
+            Ok(diagnostics) => diagnostics,
+            // rust-analyzer may not publish anything for a file without diagnostics
+            Err(_) => Ok(published()?.unwrap_or_default()),
+        }
+    }
+
+    pub async fn inlay_hints(
+        &self,
+        relative_path: impl AsRef<Path>,
+        range: Range,
+    ) -> Result<Option<Vec<InlayHint>>> {
+        let uri = self.document(relative_path).await?;
+        self.request::<InlayHintRequest>(
+            InlayHintParams {
+                text_document: TextDocumentIdentifier { uri },
+                range,
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            },
+            "Inlay hint request failed",
+        )
+        .await
+    }
+
+    pub async fn completion(
+        &self,
+        relative_path: impl AsRef<Path>,
+        position: Position,
+    ) -> Result<Option<CompletionResponse>> {
+        let uri = self.document(relative_path).await?;
+        self.request::<Completion>(
+            CompletionParams {
+                text_document_position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri },
+                    position,
+                },
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: Default::default(),
+                context: None,
+            },
+            "Completion request failed",
+        )
+        .await
+    }
+
+    /// Run the function at the position with rust-analyzer's MIR interpreter
+    pub async fn interpret_function(
+        &self,
+        relative_path: impl AsRef<Path>,
+        position: Position,
+    ) -> Result<String> {
+        let uri = self.document(relative_path).await?;
+        self.request::<InterpretFunction>(
+            TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            "Interpret function request failed",
+        )
+        .await
+    }
+
+    /// Run a structural search replace (`foo($a) ==>> bar($a)`) over the workspace.
+    /// Paths in the query are resolved relative to the given file.
+    pub async fn structural_search_replace(
+        &self,
+        relative_path: impl AsRef<Path>,
+        query: String,
+    ) -> Result<WorkspaceEdit> {
+        let uri = self.document(relative_path).await?;
+        self.request::<Ssr>(
+            SsrParams {
+                query,
+                parse_only: false,
+                position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri },
+                    position: Position::default(),
+                },
+                selections: Vec::new(),
+            },
+            "Structural search replace request failed",
+        )
+        .await
+    }
+
+    /// Let rust-analyzer load the cargo workspace again. Doesn't wait for indexing, the
+    /// reload starts a new one anyway.
+    pub async fn reload_workspace(&self) -> Result<()> {
+        let timeout = self
+            .instance
+            .project
+            .rust_analyzer_config()
+            .request_timeout();
+        self.send_request::<ReloadWorkspace>(
+            &*self.instance.server.lock().await,
+            (),
+            timeout,
+            "Reload workspace request failed",
+        )
+        .await
+    }
+
+    /// The memory usage of rust-analyzer's database, as a table of sizes and queries
+    pub async fn memory_usage(&self) -> Result<String> {
+        self.request::<MemoryUsage>((), "Memory usage request failed")
             .await
-            .context("Document symbols request failed")?
-            .and_then(|symbols| match symbols {
-                lsp_types::DocumentSymbolResponse::Flat(f) => Some(f),
-                lsp_types::DocumentSymbolResponse::Nested(_) => {
-                    tracing::error!("Only support flat symbols for now");
-                    None
+    }
+
+    /// The tests, benches and binaries that can be run from the file
+    pub async fn runnables(&self, relative_path: impl AsRef<Path>) -> Result<Vec<Runnable>> {
+        let uri = self.document(relative_path).await?;
+        self.request::<Runnables>(
+            RunnablesParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: None,
+            },
+            "Runnables request failed",
+        )
+        .await
+    }
+
+    /// The HIR of the function containing the position
+    pub async fn view_hir(
+        &self,
+        relative_path: impl AsRef<Path>,
+        position: Position,
+    ) -> Result<String> {
+        let uri = self.document(relative_path).await?;
+        self.request::<ViewHir>(
+            TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            "View HIR request failed",
+        )
+        .await
+    }
+
+    /// The MIR of the function containing the position
+    pub async fn view_mir(
+        &self,
+        relative_path: impl AsRef<Path>,
+        position: Position,
+    ) -> Result<String> {
+        let uri = self.document(relative_path).await?;
+        self.request::<ViewMir>(
+            TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            "View MIR request failed",
+        )
+        .await
+    }
+
+    /// Expand the macro at the position. Expansions are cached until the file changes
+    pub async fn expand_macro(
+        &self,
+        relative_path: impl AsRef<Path>,
+        position: Position,
+    ) -> Result<Option<ExpandedMacro>> {
+        let change_count = self
+            .instance
+            .change_notifier
+            .lock()
+            .map(|notifier| notifier.change_count())
+            .unwrap_or_default();
+        let key = (self.project.root().join(relative_path.as_ref()), position);
+        {
+            let mut cache = self.instance.expansions.lock().await;
+            if cache.change_count != change_count {
+                cache.change_count = change_count;
+                cache.expansions.clear();
+            }
+            if let Some(expansion) = cache.expansions.get(&key) {
+                return Ok(expansion.clone());
+            }
+        }
+        let uri = self.document(relative_path).await?;
+        let expansion = self
+            .request::<ExpandMacro>(
+                ExpandMacroParams {
+                    text_document: TextDocumentIdentifier { uri },
+                    position,
+                },
+                "Expand macro request failed",
+            )
+            .await?;
+        let mut cache = self.instance.expansions.lock().await;
+        // A file changed during the request, the expansion may be outdated already
+        if cache.change_count == change_count {
+            if cache.expansions.len() >= MAX_CACHED_EXPANSIONS {
+                cache.expansions.clear();
+            }
+            cache.expansions.insert(key, expansion.clone());
+        }
+        Ok(expansion)
+    }
+}
+
+fn folder_name(root: &Path) -> String {
+    root.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "root".to_string())
+}
+
+fn workspace_folder(project: &Project) -> Result<WorkspaceFolder> {
+    Ok(WorkspaceFolder {
+        uri: project.uri()?,
+        name: folder_name(project.root()),
+    })
+}
+
+/// Start rust-analyzer for the project and the mainloop that talks to it. If the
+/// process exits without `stopping` being set, a `Crashed` notification is sent.
+fn spawn(
+    project: &Project,
+    notifier: Sender<LspNotification>,
+    diagnostics: DiagnosticsCache,
+    index_status: watch::Sender<IndexStatus>,
+    stopping: Arc<AtomicBool>,
+    stderr_tail: StderrTail,
+) -> Result<Spawned> {
+    let crash_notifier = notifier.clone();
+    let (mainloop, server) = async_lsp::MainLoop::new_client(|_server| {
+        ServiceBuilder::new()
+            .layer(TracingLayer::default())
+            .layer(LifecycleLayer::default()) // Handle init/shutdown automatically
+            .layer(CatchUnwindLayer::default())
+            .layer(ConcurrencyLayer::default())
+            .service(ClientState::new_router(
+                index_status,
+                notifier,
+                project.root().to_path_buf(),
+                diagnostics,
+            ))
+    });
+
+    let mut process = async_process::Command::new("rust-analyzer")
+        .current_dir(project.root())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // A restart must not leave the old process running
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed run rust-analyzer")?;
+
+    let stdout = process.stdout.take().context("Failed to get stdout")?;
+    let stdin = process.stdin.take().context("Failed to get stdin")?;
+    let stderr = process.stderr.take().context("Failed to get stderr")?;
+
+    if let Ok(mut tail) = stderr_tail.lock() {
+        tail.clear();
+    }
+    let stderr_reader = tokio::spawn(read_stderr(
+        stderr,
+        stderr_tail.clone(),
+        crash_notifier.clone(),
+        project.root().to_path_buf(),
+    ));
+
+    let root = project.root().clone();
+    let mainloop_handle = tokio::spawn(async move {
+        match mainloop.run_buffered(stdout, stdin).await {
+            Ok(()) => debug!("LSP mainloop finished gracefully."),
+            Err(e) => tracing::error!("LSP mainloop finished with error: {}", e),
+        }
+        if stopping.load(Ordering::Relaxed) {
+            return;
+        }
+        // The last lines may still be in the pipe
+        let _ = tokio::time::timeout(Duration::from_secs(1), stderr_reader).await;
+        let stderr = stderr_tail
+            .lock()
+            .map(|tail| tail.iter().cloned().collect::<Vec<_>>().join("\n"))
+            .unwrap_or_default();
+        if let Err(e) = crash_notifier.send(LspNotification::Crashed {
+            project: root,
+            stderr,
+        }) {
+            tracing::error!("Failed to send crash notification: {}", e);
+        }
+    });
+
+    Ok(Spawned {
+        server,
+        mainloop_handle,
+        process,
+    })
+}
+
+/// Keep the last lines of rust-analyzer's stderr until it closes and forward every line
+/// to the UI
+async fn read_stderr(
+    stderr: impl AsyncRead + Unpin,
+    tail: StderrTail,
+    notifier: Sender<LspNotification>,
+    project: PathBuf,
+) {
+    let mut reader = BufReader::new(stderr);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&line).trim_end().to_string();
+                debug!("rust-analyzer: {line}");
+                if let Ok(mut tail) = tail.lock() {
+                    if tail.len() == STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line.clone());
                 }
-            });
-        Ok(o)
+                if let Err(e) = notifier.send(LspNotification::Stderr {
+                    project: project.clone(),
+                    line,
+                }) {
+                    tracing::error!("Failed to send stderr notification: {}", e);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read the stderr of rust-analyzer: {}", e);
+                break;
+            }
+        }
     }
 }
@@ -1,40 +1,143 @@
-use lsp_types::{GotoDefinitionResponse, MarkedString};
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, DocumentSymbol, GotoDefinitionResponse, Location,
+    MarkedString, OneOf, Position, PositionEncodingKind, Range, SymbolInformation, SymbolKind,
+    TextEdit, WorkspaceEdit,
+};
 use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 use url::Url;
 
-pub fn get_location_contents(
-    response: GotoDefinitionResponse,
-) -> Result<Vec<(String, PathBuf)>, std::io::Error> {
-    let urls = match response {
-        GotoDefinitionResponse::Scalar(location) => vec![location.uri],
-        GotoDefinitionResponse::Array(locations) => {
-            locations.into_iter().map(|loc| loc.uri).collect()
+/// What the columns of LSP positions count, negotiated with rust-analyzer during
+/// initialize. Without negotiation LSP uses UTF-16 code units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// The encodings the client supports, the cheapest to convert first
+    pub fn supported() -> Vec<PositionEncodingKind> {
+        vec![
+            PositionEncodingKind::UTF8,
+            PositionEncodingKind::UTF32,
+            PositionEncodingKind::UTF16,
+        ]
+    }
+
+    /// The encoding the server chose
+    pub fn from_kind(kind: Option<&PositionEncodingKind>) -> Self {
+        match kind {
+            Some(kind) if *kind == PositionEncodingKind::UTF8 => Self::Utf8,
+            Some(kind) if *kind == PositionEncodingKind::UTF32 => Self::Utf32,
+            _ => Self::Utf16,
         }
-        GotoDefinitionResponse::Link(links) => {
-            links.into_iter().map(|link| link.target_uri).collect()
+    }
+
+    fn char_columns(self, c: char) -> u32 {
+        match self {
+            Self::Utf8 => c.len_utf8() as u32,
+            Self::Utf16 => c.len_utf16() as u32,
+            Self::Utf32 => 1,
         }
-    };
+    }
 
-    let mut known_files = HashSet::new();
+    /// The number of columns the text spans
+    pub fn columns(self, text: &str) -> u32 {
+        match self {
+            Self::Utf8 => text.len() as u32,
+            Self::Utf16 => text.encode_utf16().count() as u32,
+            Self::Utf32 => text.chars().count() as u32,
+        }
+    }
+
+    /// The byte offset of a column in the line, at most the end of the line
+    pub fn byte_offset(self, line: &str, column: u32) -> usize {
+        let mut current = 0;
+        for (byte_index, c) in line.char_indices() {
+            if current >= column || c == '\n' {
+                return byte_index;
+            }
+            current += self.char_columns(c);
+        }
+        line.len()
+    }
+
+    /// The (0 based) index of the character at the column in the line, for showing columns
+    /// to users
+    pub fn char_index(self, line: &str, column: u32) -> usize {
+        line[..self.byte_offset(line, column)].chars().count()
+    }
+
+    /// The column of the character with the (0 based) index in the line, e.g. of a
+    /// column reported by rustc or a user
+    pub fn char_column(self, line: &str, char_index: usize) -> u32 {
+        line.chars()
+            .take(char_index)
+            .map(|c| self.char_columns(c))
+            .sum()
+    }
+}
+
+/// The source of every location in a definition response: the lines of its range
+/// plus `context_lines` before and after, the path and the range. For location links
+/// the full target range (e.g. the whole item) is used.
+pub fn get_location_contents(
+    response: GotoDefinitionResponse,
+    context_lines: u32,
+) -> Result<Vec<(String, PathBuf, Range)>, std::io::Error> {
+    let mut known_locations = HashSet::new();
 
     let mut contents = Vec::new();
-    for url in urls {
-        if known_files.contains(&url) {
+    for (path, range) in location_ranges(response)? {
+        if !known_locations.insert((path.clone(), range)) {
             continue;
         }
-        known_files.insert(url.clone());
-        // Convert the URL to a file path
-        let path = url_to_file_path(&url)?;
-        // Read the file contents
         let content = fs::read_to_string(&path)?;
-        contents.push((content, path));
+        let lines = slice_lines(&content, range.start.line, range.end.line, context_lines);
+        contents.push((lines, path, range));
     }
 
     Ok(contents)
 }
 
+/// The lines from `start` to `end` (inclusive, 0 based) with `context` lines around
+/// them, clamped to the text
+pub fn slice_lines(text: &str, start: u32, end: u32, context: u32) -> String {
+    let start = start.saturating_sub(context) as usize;
+    let end = end.saturating_add(context).saturating_add(1) as usize;
+    text.lines()
+        .skip(start)
+        .take(end.saturating_sub(start))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The file and range of every location in a definition response.
+/// For location links the full target range (e.g. the whole item) is used.
+pub fn location_ranges(
+    response: GotoDefinitionResponse,
+) -> Result<Vec<(PathBuf, Range)>, std::io::Error> {
+    let locations = match response {
+        GotoDefinitionResponse::Scalar(location) => vec![(location.uri, location.range)],
+        GotoDefinitionResponse::Array(locations) => locations
+            .into_iter()
+            .map(|loc| (loc.uri, loc.range))
+            .collect(),
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| (link.target_uri, link.target_range))
+            .collect(),
+    };
+    locations
+        .into_iter()
+        .map(|(url, range)| Ok((url_to_file_path(&url)?, range)))
+        .collect()
+}
+
 pub fn format_marked_string(marked_string: &MarkedString) -> String {
     match marked_string {
         MarkedString::String(s) => s.clone(),
@@ -54,3 +157,308 @@ fn url_to_file_path(url: &Url) -> Result<PathBuf, std::io::Error> {
         )
     })
 }
+
+/// Flatten nested document symbols, parents first. The container name of a symbol is
+/// the `::` separated path of its parents, where impl blocks are named after their
+/// self type, so the methods of `impl Display for Foo` are in `Foo`.
+pub fn flatten_document_symbols(uri: &Url, symbols: Vec<DocumentSymbol>) -> Vec<SymbolInformation> {
+    fn visit(
+        uri: &Url,
+        symbols: Vec<DocumentSymbol>,
+        container: Option<&str>,
+        flat: &mut Vec<SymbolInformation>,
+    ) {
+        for symbol in symbols {
+            let name = if symbol.kind == SymbolKind::OBJECT && symbol.name.starts_with("impl") {
+                impl_self_type(&symbol.name).to_string()
+            } else {
+                symbol.name.clone()
+            };
+            let path = match container {
+                Some(container) => format!("{container}::{name}"),
+                None => name,
+            };
+            #[allow(deprecated)]
+            flat.push(SymbolInformation {
+                name: symbol.name,
+                kind: symbol.kind,
+                tags: symbol.tags,
+                deprecated: symbol.deprecated,
+                location: Location::new(uri.clone(), symbol.range),
+                container_name: container.map(|c| c.to_string()),
+            });
+            if let Some(children) = symbol.children {
+                visit(uri, children, Some(&path), flat);
+            }
+        }
+    }
+
+    let mut flat = Vec::new();
+    visit(uri, symbols, None, &mut flat);
+    flat
+}
+
+/// The self type of an impl block label like `impl<T> Display for Foo<T>`, i.e. `Foo`
+fn impl_self_type(label: &str) -> &str {
+    let mut rest = label.trim_start_matches("impl").trim_start();
+    if rest.starts_with('<') {
+        let mut depth = 0;
+        for (index, c) in rest.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                rest = rest[index + 1..].trim_start();
+                break;
+            }
+        }
+    }
+    if let Some((_, self_type)) = rest.split_once(" for ") {
+        rest = self_type.trim_start();
+    }
+    rest.split('<').next().unwrap_or(rest).trim()
+}
+
+/// Whether a symbol is called `name`, either plainly (`bar`) or qualified with (the end
+/// of) its container path (`Foo::bar`)
+pub fn symbol_matches(symbol: &SymbolInformation, name: &str) -> bool {
+    if symbol.name == name {
+        return true;
+    }
+    let Some(container) = &symbol.container_name else {
+        return false;
+    };
+    let qualified = format!("{container}::{}", symbol.name);
+    qualified == name || qualified.ends_with(&format!("::{name}"))
+}
+
+/// Flatten a workspace edit into the text edits per file.
+/// Resource operations (create / rename / delete) are not supported and skipped.
+pub fn workspace_edit_changes(edit: &WorkspaceEdit) -> Vec<(Url, Vec<TextEdit>)> {
+    let mut changes: Vec<(Url, Vec<TextEdit>)> = Vec::new();
+    if let Some(map) = &edit.changes {
+        for (url, edits) in map {
+            changes.push((url.clone(), edits.clone()));
+        }
+    }
+    let document_edits = match &edit.document_changes {
+        Some(DocumentChanges::Edits(edits)) => edits.iter().collect::<Vec<_>>(),
+        Some(DocumentChanges::Operations(operations)) => operations
+            .iter()
+            .filter_map(|operation| match operation {
+                DocumentChangeOperation::Edit(edit) => Some(edit),
+                DocumentChangeOperation::Op(op) => {
+                    tracing::warn!("Skipping unsupported resource operation: {:?}", op);
+                    None
+                }
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    for document_edit in document_edits {
+        let edits = document_edit
+            .edits
+            .iter()
+            .map(|edit| match edit {
+                OneOf::Left(edit) => edit.clone(),
+                OneOf::Right(annotated) => annotated.text_edit.clone(),
+            })
+            .collect();
+        changes.push((document_edit.text_document.uri.clone(), edits));
+    }
+    changes
+}
+
+/// Apply a workspace edit to the files on disk. Returns the paths of the changed files
+pub fn apply_workspace_edit(
+    edit: &WorkspaceEdit,
+    encoding: PositionEncoding,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut changed = Vec::new();
+    for (url, edits) in workspace_edit_changes(edit) {
+        let path = url_to_file_path(&url)?;
+        let content = fs::read_to_string(&path)?;
+        fs::write(&path, apply_text_edits(&content, &edits, encoding))?;
+        changed.push(path);
+    }
+    Ok(changed)
+}
+
+/// Apply text edits to the given content. Edits are applied back to front
+/// so that earlier offsets stay valid.
+pub fn apply_text_edits(content: &str, edits: &[TextEdit], encoding: PositionEncoding) -> String {
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+    let mut result = content.to_string();
+    for edit in edits {
+        let start = position_to_offset(&result, edit.range.start, encoding);
+        let end = position_to_offset(&result, edit.range.end, encoding).max(start);
+        result.replace_range(start..end, &edit.new_text);
+    }
+    result
+}
+
+/// Convert a LSP position into a byte offset into `content`
+pub fn position_to_offset(content: &str, position: Position, encoding: PositionEncoding) -> usize {
+    let mut offset = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        if index == position.line as usize {
+            return offset + encoding.byte_offset(line, position.character);
+        }
+        offset += line.len();
+    }
+    content.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_lines() {
+        let text = "a\nb\nc\nd\ne";
+        assert_eq!(slice_lines(text, 2, 2, 0), "c");
+        assert_eq!(slice_lines(text, 1, 2, 1), "a\nb\nc\nd");
+        assert_eq!(slice_lines(text, 0, 4, 3), text);
+        assert_eq!(slice_lines(text, 7, 8, 0), "");
+    }
+
+    #[allow(deprecated)]
+    fn document_symbol(
+        name: &str,
+        kind: SymbolKind,
+        line: u32,
+        children: Vec<DocumentSymbol>,
+    ) -> DocumentSymbol {
+        let range = Range::new(Position::new(line, 0), Position::new(line, 1));
+        DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: range,
+            children: Some(children),
+        }
+    }
+
+    #[test]
+    fn test_flatten_document_symbols() {
+        let uri = Url::parse("file:///project/src/lib.rs").unwrap();
+        let symbols = vec![
+            document_symbol("Foo", SymbolKind::STRUCT, 0, Vec::new()),
+            document_symbol(
+                "impl<T: Clone> Display for Foo<T>",
+                SymbolKind::OBJECT,
+                2,
+                vec![document_symbol("fmt", SymbolKind::METHOD, 3, Vec::new())],
+            ),
+            document_symbol(
+                "inner",
+                SymbolKind::MODULE,
+                6,
+                vec![document_symbol(
+                    "impl Bar",
+                    SymbolKind::OBJECT,
+                    7,
+                    vec![document_symbol("bar", SymbolKind::METHOD, 8, Vec::new())],
+                )],
+            ),
+        ];
+        let flat = flatten_document_symbols(&uri, symbols);
+        let names = flat
+            .iter()
+            .map(|s| (s.name.as_str(), s.container_name.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("Foo", None),
+                ("impl<T: Clone> Display for Foo<T>", None),
+                ("fmt", Some("Foo")),
+                ("inner", None),
+                ("impl Bar", Some("inner")),
+                ("bar", Some("inner::Bar")),
+            ]
+        );
+        assert_eq!(flat[2].location.range.start.line, 3);
+
+        assert!(symbol_matches(&flat[2], "fmt"));
+        assert!(symbol_matches(&flat[2], "Foo::fmt"));
+        assert!(symbol_matches(&flat[5], "Bar::bar"));
+        assert!(symbol_matches(&flat[5], "inner::Bar::bar"));
+        assert!(!symbol_matches(&flat[5], "Foo::bar"));
+        assert!(!symbol_matches(&flat[5], "ar::bar"));
+    }
+
+    fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> TextEdit {
+        TextEdit {
+            range: Range {
+                start: Position::new(start.0, start.1),
+                end: Position::new(end.0, end.1),
+            },
+            new_text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_position_to_offset() {
+        let content = "fn a() {}\nlet ä = 1;\n";
+        let utf16 = PositionEncoding::Utf16;
+        assert_eq!(position_to_offset(content, Position::new(0, 3), utf16), 3);
+        assert_eq!(position_to_offset(content, Position::new(1, 0), utf16), 10);
+        // `ä` is two bytes but a single UTF-16 code unit
+        assert_eq!(position_to_offset(content, Position::new(1, 5), utf16), 16);
+        assert_eq!(
+            position_to_offset(content, Position::new(1, 6), PositionEncoding::Utf8),
+            16
+        );
+        assert_eq!(
+            position_to_offset(content, Position::new(1, 100), utf16),
+            21
+        );
+        assert_eq!(
+            position_to_offset(content, Position::new(5, 0), utf16),
+            content.len()
+        );
+    }
+
+    #[test]
+    fn test_position_encoding() {
+        // `😀` is four bytes, two UTF-16 code units and a single UTF-32 one
+        let line = "// 😀 ä x";
+        let x = line.find('x').unwrap();
+        for (encoding, column) in [
+            (PositionEncoding::Utf8, 11),
+            (PositionEncoding::Utf16, 8),
+            (PositionEncoding::Utf32, 7),
+        ] {
+            assert_eq!(encoding.columns(&line[..x]), column);
+            assert_eq!(encoding.byte_offset(line, column), x);
+            assert_eq!(encoding.char_column(line, 7), column);
+            assert_eq!(encoding.char_index(line, column), 7);
+        }
+        assert_eq!(
+            PositionEncoding::from_kind(Some(&PositionEncodingKind::UTF8)),
+            PositionEncoding::Utf8
+        );
+        assert_eq!(PositionEncoding::from_kind(None), PositionEncoding::Utf16);
+    }
+
+    #[test]
+    fn test_apply_text_edits() {
+        let content = "fn foo(a: u8, b: u8) {}\n";
+        let edits = vec![
+            edit((0, 3), (0, 6), "bar"),
+            edit((0, 7), (0, 12), "b: u8"),
+            edit((0, 14), (0, 19), "a: u8"),
+        ];
+        assert_eq!(
+            apply_text_edits(content, &edits, PositionEncoding::Utf16),
+            "fn bar(b: u8, a: u8) {}\n"
+        );
+    }
+}
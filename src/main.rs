@@ -1,16 +1,23 @@
+mod announce;
 mod cargo_remote;
 mod context;
+mod crash_report;
+mod crates_io;
 mod docs;
+mod instance_lock;
 mod lsp;
+mod maintenance;
 mod mcp;
+mod notify;
 mod project;
 mod ui;
 
 use std::env::args;
 
 use anyhow::Result;
-use context::Context as ContextType;
-use mcp::run_server;
+use context::{Context as ContextType, ServerOverrides};
+use instance_lock::{InstanceLock, RunningInstance};
+use mcp::{read_audit_entry, replay, run_server};
 use tokio::signal;
 use tracing::{error, info};
 use tracing_subscriber::{
@@ -34,13 +41,76 @@ async fn main() -> Result<()> {
         .init();
 
     let no_ui = args().any(|arg| arg == "--no-ui");
+    let take_over = args().any(|arg| arg == "--take-over");
 
     let (sender, receiver) = flume::unbounded();
     let context = ContextType::new(4000, sender).await;
-    context.load_config().await?;
+    context.set_server_overrides(server_overrides()?);
+
+    if args().nth(1).as_deref() == Some("replay") {
+        return replay_command(&context).await;
+    }
+
+    // The lock records the address, which the configuration file may set
+    context.load_settings()?;
+
+    // Only one instance per configuration, otherwise both spawn rust-analyzer and fight over the port
+    let lock_path = context.instance_lock_path();
+    let (host, port) = context.address_information();
+    let instance_lock = match InstanceLock::try_acquire(&lock_path, &host, port)? {
+        Some(lock) => lock,
+        None => {
+            let Some(running) = RunningInstance::find(&lock_path) else {
+                anyhow::bail!(
+                    "Another instance holds the lock {:?} but did not record itself",
+                    lock_path
+                );
+            };
+            let take_over = take_over || (!no_ui && confirm_take_over(&running));
+            if !take_over {
+                info!(
+                    "cursor-rust-tools is already running on {} (pid {}). Start with `--take-over` to replace it.",
+                    running.address(),
+                    running.pid
+                );
+                return Ok(());
+            }
+            info!("Taking over from the instance with pid {}", running.pid);
+            running.take_over(&lock_path, &host, port)?
+        }
+    };
+
+    let crash_reports = context.crash_reports_path();
+    if let Some(report) = crash_report::unseen_report(&crash_reports) {
+        if no_ui {
+            info!(
+                "The previous session crashed, see the report at {:?}",
+                report
+            );
+        } else if confirm_open_crash_report()
+            && let Err(e) = open::that(&report)
+        {
+            error!("Failed to open crash report {:?}: {}", report, e);
+        }
+        crash_report::mark_seen(&crash_reports);
+    }
+    crash_report::install(crash_reports, context.clone());
+
+    context.load_projects().await?;
 
     let final_context = context.clone();
 
+    // Handled on the runtime, the UI blocks the main thread until its window closes.
+    // Another instance taking over sends SIGTERM.
+    let signal_context = context.clone();
+    tokio::spawn(async move {
+        let signal = shutdown_signal().await;
+        info!("{signal} received, shutting down...");
+        signal_context.drain_and_shutdown().await;
+        drop(instance_lock);
+        std::process::exit(0);
+    });
+
     // Run the MCP Server
     let cloned_context = context.clone();
     let server_handle = tokio::spawn(async move {
@@ -65,7 +135,7 @@ async fn main() -> Result<()> {
                 "Cursor mcp json (project/.cursor.mcp.json):\n```json\n{}\n```",
                 context.mcp_configuration()
             );
-            // Keep the CLI mode running indefinitely until Ctrl+C
+            // Keep the CLI mode running indefinitely until Ctrl+C or SIGTERM
             loop {
                 while let Ok(notification) = receiver.try_recv() {
                     info!("  {}", notification.description());
@@ -73,7 +143,7 @@ async fn main() -> Result<()> {
                 // Add a small sleep to avoid busy-waiting if desired, or just rely on Ctrl+C
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             }
-            // Note: This loop will now only exit via Ctrl+C handled by tokio::select!
+            // Note: This loop will now only exit via the signal handler
         } else {
             let project_descriptions = context.project_descriptions().await;
             // run_ui blocks, so we need to handle its potential error
@@ -89,17 +159,171 @@ async fn main() -> Result<()> {
                 info!("Main loop finished normally.");
             }
         },
-        _ = signal::ctrl_c() => {
-            info!("Ctrl+C received, shutting down...");
-        }
         _ = server_handle => {
              info!("Server task finished unexpectedly.");
         }
     }
 
-    if no_ui {
-        final_context.shutdown_all().await;
+    // Let running tool calls finish before the language servers go away
+    final_context.drain_and_shutdown().await;
+
+    Ok(())
+}
+
+/// Wait for Ctrl+C or SIGTERM and return which one it was
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use signal::unix::{SignalKind, signal as unix_signal};
+        match unix_signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = signal::ctrl_c() => "Ctrl+C",
+                    _ = terminate.recv() => "SIGTERM",
+                }
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                let _ = signal::ctrl_c().await;
+                "Ctrl+C"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+/// The server settings of the `--port`, `--bind` (repeatable) and `--allow-remote`
+/// arguments, falling back to the `CURSOR_RUST_TOOLS_PORT`, `CURSOR_RUST_TOOLS_BIND`
+/// (comma separated) and `CURSOR_RUST_TOOLS_ALLOW_REMOTE` environment variables
+fn server_overrides() -> Result<ServerOverrides> {
+    let args = args().collect::<Vec<_>>();
+    let values = |name: &str| arg_values(&args, name);
+    let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+    let port = match values("--port")
+        .pop()
+        .or_else(|| env("CURSOR_RUST_TOOLS_PORT"))
+    {
+        Some(port) => Some(
+            port.parse::<u16>()
+                .map_err(|e| anyhow::anyhow!("Invalid port `{port}`: {e}"))?,
+        ),
+        None => None,
+    };
+    let mut bind_addresses = values("--bind");
+    if bind_addresses.is_empty()
+        && let Some(addresses) = env("CURSOR_RUST_TOOLS_BIND")
+    {
+        bind_addresses = addresses
+            .split(',')
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect();
     }
+    let allow_remote = args.iter().any(|arg| arg == "--allow-remote")
+        || env("CURSOR_RUST_TOOLS_ALLOW_REMOTE")
+            .is_some_and(|value| value != "0" && value != "false");
+    Ok(ServerOverrides {
+        port,
+        // Brackets are only needed in URLs
+        bind_addresses: bind_addresses
+            .iter()
+            .map(|address| address.trim_matches(['[', ']']).to_string())
+            .collect(),
+        allow_remote,
+    })
+}
 
+/// The values of `--name value` and `--name=value` arguments
+fn arg_values(args: &[String], name: &str) -> Vec<String> {
+    let prefix = format!("{name}=");
+    args.iter()
+        .enumerate()
+        .filter_map(|(index, arg)| match arg.strip_prefix(&prefix) {
+            Some(value) => Some(value.to_string()),
+            None if arg == name => args.get(index + 1).cloned(),
+            None => None,
+        })
+        .collect()
+}
+
+/// `replay <audit log> [--line <n>]`: send a call from the audit log (the last one
+/// by default) to the running instance and compare the responses
+async fn replay_command(context: &ContextType) -> Result<()> {
+    let args = args().collect::<Vec<_>>();
+    let Some(path) = args.get(2).filter(|path| !path.starts_with("--")) else {
+        anyhow::bail!("Usage: cursor-rust-tools replay <audit log> [--line <n>]");
+    };
+    let line = match arg_values(&args, "--line").pop() {
+        Some(line) => Some(
+            line.parse::<usize>()
+                .map_err(|e| anyhow::anyhow!("Invalid line `{line}`: {e}"))?,
+        ),
+        None => None,
+    };
+    let entry = read_audit_entry(std::path::Path::new(path), line)?;
+    let Some(running) = RunningInstance::find(&context.instance_lock_path()) else {
+        anyhow::bail!("cursor-rust-tools is not running, start it before replaying a call");
+    };
+    if running.host == "stdio" {
+        anyhow::bail!(
+            "The running instance (pid {}) serves over stdio, calls can only be replayed over SSE",
+            running.pid
+        );
+    }
+    info!(
+        "Replaying `{}` from {} on {}",
+        entry.request.name,
+        entry.timestamp,
+        running.address()
+    );
+    let started = std::time::Instant::now();
+    let response = replay(
+        &running.address(),
+        context.read_api_key().as_deref(),
+        &entry.request,
+    )
+    .await?;
+    let logged = serde_json::to_string_pretty(&entry.response)?;
+    let replayed = serde_json::to_string_pretty(&response)?;
+    println!("{replayed}");
+    info!(
+        "Took {} ms, logged call took {} ms",
+        started.elapsed().as_millis(),
+        entry.duration_ms
+    );
+    if logged == replayed {
+        info!("The response is the same as the logged one");
+    } else {
+        info!("The response differs from the logged one:\n{logged}");
+    }
     Ok(())
 }
+
+fn confirm_take_over(running: &RunningInstance) -> bool {
+    rfd::MessageDialog::new()
+        .set_title("Cursor Rust Tools is already running")
+        .set_description(format!(
+            "Another instance is running on {} (pid {}). Do you want to stop it and take over?",
+            running.address(),
+            running.pid
+        ))
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show()
+        == rfd::MessageDialogResult::Yes
+}
+
+fn confirm_open_crash_report() -> bool {
+    rfd::MessageDialog::new()
+        .set_title("Cursor Rust Tools crashed")
+        .set_description(
+            "The previous session crashed. Do you want to open the crash report? It is only stored on this computer.",
+        )
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show()
+        == rfd::MessageDialogResult::Yes
+}
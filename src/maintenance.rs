@@ -0,0 +1,238 @@
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    path::Path,
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::context::ProjectContext;
+
+/// How often the scheduler checks for due jobs
+const TICK: Duration = Duration::from_secs(60);
+
+/// Periodic background jobs of a project. Jobs without an interval or with an
+/// interval of 0 don't run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Regenerate the docs index every n hours
+    #[serde(
+        default,
+        deserialize_with = "deserialize_hours",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reindex_docs_hours: Option<u64>,
+    /// Remove the incremental compilation caches every n hours
+    #[serde(
+        default,
+        deserialize_with = "deserialize_hours",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub prune_cache_hours: Option<u64>,
+    /// Run `cargo audit` every n hours
+    #[serde(
+        default,
+        deserialize_with = "deserialize_hours",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub audit_hours: Option<u64>,
+}
+
+impl MaintenanceConfig {
+    pub fn is_empty(&self) -> bool {
+        self.reindex_docs_hours.is_none()
+            && self.prune_cache_hours.is_none()
+            && self.audit_hours.is_none()
+    }
+}
+
+/// The last result of every job
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceStatus {
+    pub docs_reindex: Option<JobRun>,
+    pub cache_prune: Option<JobRun>,
+    pub audit: Option<JobRun>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobRun {
+    pub finished: DateTime<Local>,
+    pub output: String,
+}
+
+impl JobRun {
+    fn new(result: Result<String>) -> Self {
+        Self {
+            finished: Local::now(),
+            output: result.unwrap_or_else(|e| format!("Failed: {e:?}")),
+        }
+    }
+}
+
+struct Job {
+    interval: Duration,
+    last_run: Instant,
+}
+
+impl Job {
+    fn new(hours: Option<u64>) -> Option<Self> {
+        Some(Self {
+            interval: Duration::from_secs(hours? * 60 * 60),
+            last_run: Instant::now(),
+        })
+    }
+
+    fn is_due(&mut self) -> bool {
+        if self.last_run.elapsed() < self.interval {
+            return false;
+        }
+        self.last_run = Instant::now();
+        true
+    }
+}
+
+/// Run the configured jobs until the project is removed
+pub fn spawn_scheduler(project: &Arc<ProjectContext>) {
+    let config = project.project.maintenance().clone();
+    if config.is_empty() {
+        return;
+    }
+    let project = Arc::downgrade(project);
+    tokio::spawn(async move {
+        let mut reindex_docs = Job::new(config.reindex_docs_hours);
+        let mut prune_cache = Job::new(config.prune_cache_hours);
+        let mut audit = Job::new(config.audit_hours);
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            let Some(project) = Weak::upgrade(&project) else {
+                return;
+            };
+            if reindex_docs.as_mut().is_some_and(Job::is_due) {
+                tracing::info!("Scheduled docs reindex for {:?}", project.project.root());
+                let result = project
+                    .docs
+                    .update_index()
+                    .await
+                    .map(|_| "Started docs reindex".to_string());
+                project.maintenance.lock().await.docs_reindex = Some(JobRun::new(result));
+            }
+            if prune_cache.as_mut().is_some_and(Job::is_due) {
+                tracing::info!("Scheduled cache pruning for {:?}", project.project.root());
+                let result = prune_incremental(&project).await;
+                project.maintenance.lock().await.cache_prune = Some(JobRun::new(result));
+            }
+            if audit.as_mut().is_some_and(Job::is_due) {
+                tracing::info!("Scheduled cargo audit for {:?}", project.project.root());
+                let result = project.cargo_remote.audit().await;
+                project.maintenance.lock().await.audit = Some(JobRun::new(result));
+            }
+        }
+    });
+}
+
+/// Remove the `incremental` folders of all profiles, cargo recreates them on the next build
+async fn prune_incremental(project: &ProjectContext) -> Result<String> {
+    let target_dir = project.cargo_remote.target_dir().await?;
+    prune_incremental_in(&target_dir)
+}
+
+fn prune_incremental_in(target_dir: &Path) -> Result<String> {
+    let mut removed = Vec::new();
+    let mut skipped = Vec::new();
+    for entry in std::fs::read_dir(target_dir)?.flatten() {
+        // Cross compilation profiles live in `target/<triple>/<profile>`
+        let nested = std::fs::read_dir(entry.path())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|nested| nested.path());
+        for profile_dir in std::iter::once(entry.path())
+            .chain(nested)
+            .filter(|path| path.join("incremental").is_dir())
+        {
+            let path = profile_dir.join("incremental");
+            // Held until the cache is removed, so cargo can't start a build in between
+            let Some(_lock) = lock_profile(&profile_dir)? else {
+                skipped.push(path.display().to_string());
+                continue;
+            };
+            std::fs::remove_dir_all(&path)?;
+            removed.push(path.display().to_string());
+        }
+    }
+    let mut output = if removed.is_empty() {
+        "No incremental caches removed".to_string()
+    } else {
+        format!("Removed {}", removed.join(", "))
+    };
+    if !skipped.is_empty() {
+        output.push_str(&format!(
+            "\nSkipped {}, cargo is building",
+            skipped.join(", ")
+        ));
+    }
+    Ok(output)
+}
+
+/// Take the lock cargo holds on a profile while it builds, `None` if a build (of
+/// this server, rust-analyzer or the user) holds it
+fn lock_profile(profile_dir: &Path) -> Result<Option<File>> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(profile_dir.join(".cargo-lock"))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// An interval of 0 hours disables the job instead of running it on every tick
+fn deserialize_hours<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<u64>, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.filter(|hours| *hours > 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_hours_disable_the_job() {
+        let config: MaintenanceConfig =
+            toml::from_str("reindex_docs_hours = 0\nprune_cache_hours = 24").unwrap();
+        assert_eq!(config.reindex_docs_hours, None);
+        assert_eq!(config.prune_cache_hours, Some(24));
+        assert!(
+            toml::from_str::<MaintenanceConfig>("audit_hours = 0")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_prune_skips_locked_profiles() {
+        let target_dir =
+            std::env::temp_dir().join(format!("prune-incremental-{}", std::process::id()));
+        for profile in ["debug", "release"] {
+            std::fs::create_dir_all(target_dir.join(profile).join("incremental")).unwrap();
+        }
+        // A build is running in debug
+        let lock = lock_profile(&target_dir.join("debug")).unwrap().unwrap();
+        let output = prune_incremental_in(&target_dir).unwrap();
+        assert!(output.contains("Skipped"), "{output}");
+        assert!(target_dir.join("debug").join("incremental").is_dir());
+        assert!(!target_dir.join("release").join("incremental").exists());
+        drop(lock);
+        prune_incremental_in(&target_dir).unwrap();
+        assert!(!target_dir.join("debug").join("incremental").exists());
+        std::fs::remove_dir_all(&target_dir).unwrap();
+    }
+}
@@ -0,0 +1,429 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+use syn::{
+    Block, Expr, ExprAsync, ExprCall, ExprClosure, ExprMethodCall, ImplItemFn, ItemFn, Pat, Stmt,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, rust_files},
+};
+
+/// Function paths that block the executor thread when called from async code
+const BLOCKING_PATHS: &[&str] = &[
+    "std::fs::",
+    "std::thread::sleep",
+    "thread::sleep",
+    "std::process::Command",
+    "std::net::TcpStream",
+    "std::io::stdin",
+    "reqwest::blocking::",
+];
+
+/// Methods that block the executor thread when called from async code
+const BLOCKING_METHODS: &[&str] = &["blocking_lock", "blocking_recv", "blocking_send"];
+
+pub struct AsyncDiagnostics;
+
+impl AsyncDiagnostics {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "async_diagnostics".to_string(),
+            description: Some("Scan a file or the whole project for common async pitfalls: `std::sync::Mutex` guards held across `.await`, blocking calls (std::fs, std::thread::sleep, reqwest::blocking, ...) in async code and futures that are not `Send` as reported by cargo check.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to a Rust file to scan, or to the `Cargo.toml` file to scan the whole project"
+                    },
+                    "run_check": {
+                        "type": "boolean",
+                        "description": "If true, also run cargo check to find futures that are not `Send`. Default is true."
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let run_check = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("run_check"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    // A `Cargo.toml` means the whole project should be scanned
+    let single_file = relative_file
+        .ends_with(".rs")
+        .then(|| relative_file.to_string());
+
+    let root = project.project.root().clone();
+    let file = single_file.clone();
+    let findings = tokio::task::spawn_blocking(move || scan_files(&root, file.as_deref()))
+        .await
+        .map_err(|e| error_response(ErrorCode::Aborted, &e.to_string()))?;
+
+    let mut contents = String::new();
+    contents.push_str("## Source analysis\n");
+    if findings.is_empty() {
+        contents.push_str("No async pitfalls found\n");
+    } else {
+        contents.push_str(&findings.join("\n"));
+        contents.push('\n');
+    }
+
+    if run_check {
+        let messages = project
+            .cargo_remote
+            .check(true)
+            .await
+            .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+        let send_errors = messages
+            .into_iter()
+            .filter(|message| message.contains("cannot be sent between threads safely"))
+            .filter(|message| {
+                single_file
+                    .as_ref()
+                    .is_none_or(|file| message.contains(file.as_str()))
+            })
+            .collect::<Vec<_>>();
+        contents.push_str("\n## Futures that are not `Send`\n");
+        if send_errors.is_empty() {
+            contents.push_str("No `Send` errors reported by cargo check\n");
+        } else {
+            contents.push_str(&send_errors.join("\n"));
+        }
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// The async pitfalls in the file, or in every Rust file of the project
+fn scan_files(root: &Path, single_file: Option<&str>) -> Vec<String> {
+    let files = match single_file {
+        Some(file) => vec![root.join(file)],
+        None => rust_files(root),
+    };
+
+    let mut findings = Vec::new();
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let syntax = match syn::parse_file(&content) {
+            Ok(syntax) => syntax,
+            Err(e) => {
+                tracing::debug!("Failed to parse {:?}: {}", file, e);
+                continue;
+            }
+        };
+        let mut visitor = AsyncVisitor::default();
+        visitor.visit_file(&syntax);
+        let path = file
+            .strip_prefix(root)
+            .unwrap_or(&file)
+            .display()
+            .to_string();
+        findings.extend(
+            visitor
+                .findings
+                .into_iter()
+                .map(|(line, message)| format!("- {path}:{line}: {message}")),
+        );
+    }
+    findings
+}
+
+/// Collects async pitfalls as (1-based line, message)
+#[derive(Default)]
+struct AsyncVisitor {
+    findings: Vec<(usize, String)>,
+    /// Whether we're currently inside async code
+    in_async: bool,
+}
+
+impl AsyncVisitor {
+    fn with_async<F: FnOnce(&mut Self)>(&mut self, is_async: bool, f: F) {
+        let previous = self.in_async;
+        self.in_async = is_async;
+        f(self);
+        self.in_async = previous;
+    }
+
+    /// Find `let guard = mutex.lock().unwrap()` bindings that are still alive at a later `.await`
+    fn check_guards(&mut self, block: &Block) {
+        for (index, stmt) in block.stmts.iter().enumerate() {
+            let Stmt::Local(local) = stmt else {
+                continue;
+            };
+            let Some(init) = &local.init else {
+                continue;
+            };
+            let Pat::Ident(pat) = &local.pat else {
+                continue;
+            };
+            if !is_sync_lock(&init.expr) {
+                continue;
+            }
+            let guard = pat.ident.to_string();
+            for later in &block.stmts[index + 1..] {
+                let mut finder = StmtFinder {
+                    dropped: &guard,
+                    ..Default::default()
+                };
+                finder.visit_stmt(later);
+                if finder.drops {
+                    break;
+                }
+                if let Some(line) = finder.await_line {
+                    self.findings.push((
+                        line,
+                        format!(
+                            "lock guard `{guard}` (line {}) is held across `.await`. Drop it before awaiting or use an async mutex",
+                            local.span().start().line
+                        ),
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for AsyncVisitor {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.with_async(node.sig.asyncness.is_some(), |v| {
+            visit::visit_item_fn(v, node)
+        });
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.with_async(node.sig.asyncness.is_some(), |v| {
+            visit::visit_impl_item_fn(v, node)
+        });
+    }
+
+    fn visit_expr_async(&mut self, node: &'ast ExprAsync) {
+        self.with_async(true, |v| visit::visit_expr_async(v, node));
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast ExprClosure) {
+        // Closures (e.g. for `spawn_blocking`) run outside of the async context
+        self.with_async(node.asyncness.is_some(), |v| {
+            visit::visit_expr_closure(v, node)
+        });
+    }
+
+    fn visit_block(&mut self, node: &'ast Block) {
+        if self.in_async {
+            self.check_guards(node);
+        }
+        visit::visit_block(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if self.in_async
+            && let Expr::Path(path) = &*node.func
+        {
+            let path = path_to_string(&path.path);
+            if BLOCKING_PATHS.iter().any(|p| path.starts_with(p)) {
+                self.findings.push((
+                    node.span().start().line,
+                    format!(
+                        "blocking call `{path}` in async code. Use the async equivalent or `spawn_blocking`"
+                    ),
+                ));
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        let method = node.method.to_string();
+        if self.in_async && BLOCKING_METHODS.contains(&method.as_str()) {
+            self.findings.push((
+                node.method.span().start().line,
+                format!("blocking method `{method}` in async code"),
+            ));
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+}
+
+/// Searches a statement for `.await` and `drop(<dropped>)`
+#[derive(Default)]
+struct StmtFinder<'a> {
+    dropped: &'a str,
+    drops: bool,
+    await_line: Option<usize>,
+}
+
+impl<'ast> Visit<'ast> for StmtFinder<'_> {
+    fn visit_expr_await(&mut self, node: &'ast syn::ExprAwait) {
+        if self.await_line.is_none() {
+            self.await_line = Some(node.await_token.span.start().line);
+        }
+        visit::visit_expr_await(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let (Expr::Path(func), Some(Expr::Path(arg))) = (&*node.func, node.args.first())
+            && func.path.is_ident("drop")
+            && arg.path.is_ident(self.dropped)
+        {
+            self.drops = true;
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    // Awaits in nested async blocks or closures don't hold the guard
+    fn visit_expr_async(&mut self, _node: &'ast ExprAsync) {}
+
+    fn visit_expr_closure(&mut self, _node: &'ast ExprClosure) {}
+}
+
+/// `mutex.lock().unwrap()`, `lock.read().expect(..)`, etc. Async locks are awaited instead.
+/// The types aren't known, but the lock methods take no arguments, unlike
+/// `io::Read::read` and `io::Write::write`.
+fn is_sync_lock(expr: &Expr) -> bool {
+    let Expr::MethodCall(call) = expr else {
+        return false;
+    };
+    if !matches!(call.method.to_string().as_str(), "unwrap" | "expect") {
+        return false;
+    }
+    let Expr::MethodCall(inner) = &*call.receiver else {
+        return false;
+    };
+    inner.args.is_empty() && matches!(inner.method.to_string().as_str(), "lock" | "read" | "write")
+}
+
+fn path_to_string(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn findings(source: &str) -> Vec<(usize, String)> {
+        let mut visitor = AsyncVisitor::default();
+        visitor.visit_file(&syn::parse_file(source).unwrap());
+        visitor.findings
+    }
+
+    #[test]
+    fn test_guard_held_across_await() {
+        let source = r#"
+async fn run(state: std::sync::Mutex<u8>) {
+    let guard = state.lock().unwrap();
+    work().await;
+}
+
+async fn dropped(state: std::sync::Mutex<u8>) {
+    let guard = state.lock().unwrap();
+    drop(guard);
+    work().await;
+}
+"#;
+        let findings = findings(source);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].0, 4);
+        assert!(findings[0].1.contains("`guard`"));
+    }
+
+    #[test]
+    fn test_io_is_not_a_lock() {
+        let source = r#"
+async fn copy(mut file: std::fs::File, mut out: std::fs::File, state: std::sync::RwLock<u8>) {
+    let mut buffer = [0; 1024];
+    let read = file.read(&mut buffer).unwrap();
+    let written = out.write(&buffer[..read]).unwrap();
+    let value = state.read().unwrap();
+    work().await;
+}
+"#;
+        let findings = findings(source);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].1.contains("`value`"));
+    }
+
+    #[test]
+    fn test_blocking_calls() {
+        let source = r#"
+async fn run() {
+    let content = std::fs::read_to_string("a").unwrap();
+    tokio::task::spawn_blocking(|| std::thread::sleep(DURATION));
+}
+
+fn sync() {
+    std::thread::sleep(DURATION);
+}
+"#;
+        let findings = findings(source);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].0, 3);
+    }
+}
@@ -0,0 +1,127 @@
+//! The opt-in audit log. With `audit_log = true`, every tool call is appended to
+//! `audit.jsonl` in the cache folder of its project, with the request, the response,
+//! when it started and how long it took. A logged call can be sent to the running
+//! server again with `cursor-rust-tools replay`, see [`super::replay`].
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context as _, Result};
+use chrono::Utc;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse},
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use super::sessions::{SESSION_META_KEY, current_session};
+use crate::context::Context;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the call started, in RFC 3339
+    pub timestamp: String,
+    pub duration_ms: u64,
+    /// The client that made the call, for the SSE transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    pub request: CallToolRequest,
+    pub response: CallToolResponse,
+}
+
+/// Wrap a tool handler so that its calls are written to the audit log of their
+/// project while the audit log is enabled
+pub fn track(context: &Context, handler: ToolHandlerFn) -> ToolHandlerFn {
+    let handler = Arc::new(handler);
+    let context = context.clone();
+    Box::new(move |request: CallToolRequest| {
+        let handler = handler.clone();
+        let context = context.clone();
+        Box::pin(async move {
+            if !context.audit_log() {
+                return handler(request).await;
+            }
+            let timestamp = Utc::now().to_rfc3339();
+            let started = Instant::now();
+            let response = handler(request.clone()).await;
+            let entry = AuditEntry {
+                timestamp,
+                duration_ms: started.elapsed().as_millis() as u64,
+                client: current_session().map(|session| session.to_string()),
+                request: without_session(request),
+                response: response.clone(),
+            };
+            if let Err(e) = append(&context, &entry).await {
+                tracing::error!("Failed to write the audit log: {:?}", e);
+            }
+            response
+        })
+    })
+}
+
+/// The logged call on the line (1 based), the last one without a line
+pub fn read_entry(path: &Path, line: Option<usize>) -> Result<AuditEntry> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the audit log {}", path.display()))?;
+    let lines = contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let entry = match line {
+        Some(line) => line.checked_sub(1).and_then(|index| lines.get(index)),
+        None => lines.last(),
+    };
+    let Some(entry) = entry else {
+        anyhow::bail!(
+            "The audit log has {} calls, there is no call on line {}",
+            lines.len(),
+            line.unwrap_or_default()
+        );
+    };
+    serde_json::from_str(entry).context("Failed to parse the logged call")
+}
+
+async fn append(context: &Context, entry: &AuditEntry) -> Result<()> {
+    // Calls without a file of a project, e.g. with a wrong path, aren't logged
+    let Some(file) = entry
+        .request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("file"))
+        .and_then(|file| file.as_str())
+    else {
+        return Ok(());
+    };
+    let Some(project) = context.get_project_by_path(Path::new(file)).await else {
+        return Ok(());
+    };
+    let path = project.project.audit_log_path();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    // One write per call, so concurrent calls don't interleave their lines
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?
+        .write_all(line.as_bytes())
+        .await?;
+    Ok(())
+}
+
+/// The request as the client sent it, without the session the SSE transport added
+fn without_session(mut request: CallToolRequest) -> CallToolRequest {
+    if let Some(meta) = request.meta.as_mut().and_then(|meta| meta.as_object_mut()) {
+        meta.remove(SESSION_META_KEY);
+        if meta.is_empty() {
+            request.meta = None;
+        }
+    }
+    request
+}
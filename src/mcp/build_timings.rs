@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use crate::{
+    cargo_remote::{UnitTiming, critical_path},
+    context::{Context, ProjectContext},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, output_forwarder},
+};
+
+pub struct BuildTimings;
+
+impl BuildTimings {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "build_timings".to_string(),
+            description: Some("Build the project with `cargo build --timings` and report the slowest crates and the critical path of the build. Use this to find out why a build is slow. Only crates which are not already compiled are measured.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project to build"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "The number of slowest crates to return. Default is 10."
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                // The output belongs before the response
+                let _ = forwarded.await;
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+    output: flume::Sender<String>,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let cargo_remote = project.cargo_remote.with_output(output);
+    let limit = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("limit"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10) as usize;
+
+    let units = cargo_remote
+        .build_timings()
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+    if units.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "Nothing was compiled, everything is up to date. Run `cargo clean` first to measure a full build",
+        ));
+    }
+
+    let total = units.iter().map(UnitTiming::end).fold(0.0, f64::max);
+    let mut text = format!("Total build time: {total:.1}s, {} units\n", units.len());
+
+    let mut slowest = units.iter().collect::<Vec<_>>();
+    slowest.sort_by(|a, b| b.duration.total_cmp(&a.duration));
+    text.push_str("\n# Slowest units\n");
+    for unit in slowest.into_iter().take(limit) {
+        text.push_str(&format!("- {}\n", format_unit(unit)));
+    }
+
+    text.push_str("\n# Critical path\n");
+    for unit in critical_path(&units) {
+        text.push_str(&format!(
+            "- {} (started at {:.1}s)\n",
+            format_unit(unit),
+            unit.start
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+fn format_unit(unit: &UnitTiming) -> String {
+    let mut output = format!("{} v{}", unit.name, unit.version);
+    if !unit.target.is_empty() {
+        output.push_str(&unit.target);
+    }
+    if unit.mode != "todo" {
+        output.push_str(&format!(" ({})", unit.mode));
+    }
+    output.push_str(&format!(": {:.1}s", unit.duration));
+    output
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, line_diff},
+};
+
+pub struct CargoAddDependency;
+
+impl CargoAddDependency {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_add_dependency".to_string(),
+            description: Some("Add a dependency to a `Cargo.toml` with `cargo add`, which picks the latest compatible version. Returns the resulting diff of the `Cargo.toml`. Prefer this over editing `Cargo.toml` by hand.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file to add the dependency to"
+                    },
+                    "dependency": {
+                        "type": "string",
+                        "description": "The name of the crate, optionally with a version requirement, e.g. `serde` or `serde@1.0`"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Features of the dependency to enable"
+                    },
+                    "dev": {
+                        "type": "boolean",
+                        "description": "Add the crate as a dev-dependency. Default is false."
+                    }
+                },
+                "required": ["file", "dependency"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let arguments = request.arguments.as_ref();
+    let manifest = arguments
+        .and_then(|args| args.get("file"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "File is required"))?;
+    let dependency = arguments
+        .and_then(|args| args.get("dependency"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Dependency is required"))?;
+    let features = arguments
+        .and_then(|args| args.get("features"))
+        .and_then(|v| v.as_array())
+        .map(|features| {
+            features
+                .iter()
+                .filter_map(|f| f.as_str().map(|f| f.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let dev = arguments
+        .and_then(|args| args.get("dev"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let before = std::fs::read_to_string(manifest)
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    let output = project
+        .cargo_remote
+        .add_dependency(manifest, dependency, &features, dev)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+    let after = std::fs::read_to_string(manifest)
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    if before == after {
+        return Err(error_response(
+            ErrorCode::CargoFailed,
+            &format!("`Cargo.toml` was not changed:\n{output}"),
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!("```diff\n{}\n```", line_diff(&before, &after, 2)),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
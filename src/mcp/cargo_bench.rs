@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use crate::{
+    cargo_remote::BenchResult,
+    context::{Context, ProjectContext},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, error_response, get_build_selection, get_info_from_request, output_forwarder,
+    },
+};
+
+pub struct CargoBench;
+
+impl CargoBench {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_bench".to_string(),
+            description: Some("Run the benchmarks of the project (criterion or libtest `#[bench]`) and return the time per iteration of each. Save the results as a named baseline before a performance change and compare against it afterwards to verify that the change helped.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "filter": {
+                        "type": "string",
+                        "description": "Optional filter, only the benchmarks whose name contains it are run"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Optional workspace member to run the benchmarks of"
+                    },
+                    "save_baseline": {
+                        "type": "string",
+                        "description": "Optional name to store the results under, e.g. `before`"
+                    },
+                    "baseline": {
+                        "type": "string",
+                        "description": "Optional name of a saved baseline to compare the results against"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                // The output belongs before the response
+                let _ = forwarded.await;
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+    output: flume::Sender<String>,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let string = |name: &str| {
+        request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    };
+    let cargo_remote = project.cargo_remote.with_output(output);
+
+    // Fail before running the benchmarks if the baseline doesn't exist
+    let baseline = match string("baseline") {
+        Some(name) => Some((
+            name,
+            cargo_remote
+                .bench_baseline(name)
+                .await
+                .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?,
+        )),
+        None => None,
+    };
+
+    let results = cargo_remote
+        .bench(string("filter"), &get_build_selection(request))
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let mut text = String::new();
+    for result in &results {
+        text.push_str(&format!(
+            "- {}: {} [{} {}]",
+            result.name,
+            format_nanos(result.time_ns),
+            format_nanos(result.low_ns),
+            format_nanos(result.high_ns)
+        ));
+        if let Some((name, baseline)) = &baseline {
+            match baseline.iter().find(|before| before.name == result.name) {
+                Some(before) => text.push_str(&format!(" {}", compare(before, result, name))),
+                None => text.push_str(&format!(" (not in `{name}`)")),
+            }
+        }
+        text.push('\n');
+    }
+    if let Some(name) = string("save_baseline") {
+        cargo_remote
+            .save_bench_baseline(name, &results)
+            .await
+            .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+        text.push_str(&format!("\nSaved the results as baseline `{name}`\n"));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// The change against the baseline. Only counts as faster or slower if the ranges don't overlap.
+fn compare(before: &BenchResult, after: &BenchResult, baseline: &str) -> String {
+    let change = (after.time_ns - before.time_ns) / before.time_ns * 100.0;
+    let verdict = if after.high_ns < before.low_ns {
+        "faster"
+    } else if after.low_ns > before.high_ns {
+        "slower"
+    } else {
+        "within noise"
+    };
+    format!("({change:+.1}% vs `{baseline}`, {verdict})")
+}
+
+fn format_nanos(nanos: f64) -> String {
+    match nanos {
+        nanos if nanos >= 1_000_000_000.0 => format!("{:.2} s", nanos / 1_000_000_000.0),
+        nanos if nanos >= 1_000_000.0 => format!("{:.2} ms", nanos / 1_000_000.0),
+        nanos if nanos >= 1_000.0 => format!("{:.2} µs", nanos / 1_000.0),
+        nanos => format!("{nanos:.2} ns"),
+    }
+}
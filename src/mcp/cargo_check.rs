@@ -1,7 +1,12 @@
 use std::sync::Arc;
 
-use crate::context::{Context, ProjectContext};
+use crate::{
+    cargo_remote::CompilerMessage,
+    context::{Context, ProjectContext},
+    lsp::format_marked_string,
+};
 use anyhow::Result;
+use lsp_types::{HoverContents, Position};
 use mcp_core::{
     tools::ToolHandlerFn,
     types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
@@ -10,9 +15,17 @@ use serde_json::json;
 
 use super::{
     McpNotification,
-    utils::{error_response, get_info_from_request},
+    utils::{
+        ErrorCode, error_response, get_build_selection, get_file_lines, get_info_from_request,
+        limit_messages, output_forwarder,
+    },
 };
 
+/// Borrow checker errors which get enriched with source snippets and binding types
+const BORROW_ERROR_CODES: &[&str] = &[
+    "E0382", "E0499", "E0502", "E0503", "E0505", "E0506", "E0597", "E0713", "E0716",
+];
+
 pub struct CargoCheck;
 
 impl CargoCheck {
@@ -20,7 +33,7 @@ impl CargoCheck {
         Tool {
             name: "cargo_check".to_string(),
             description: Some(
-                "Run the cargo check command in this project. Returns the response in JSON format"
+                "Run the cargo check command in this project. Returns the response in JSON format. Borrow checker errors include the source of the involved spans and the types of the bindings."
                     .to_string(),
             ),
             input_schema: json!({
@@ -30,6 +43,23 @@ impl CargoCheck {
                         "type": "string",
                         "description": "The absolute path to the `Cargo.toml` file of the project to check"
                     },
+                    "package": {
+                        "type": "string",
+                        "description": "Optional workspace member to check instead of the whole workspace"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional features to enable"
+                    },
+                    "no_default_features": {
+                        "type": "boolean",
+                        "description": "If true, the default features are disabled. Default is false."
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": "Optional target triple to check for, e.g. `wasm32-unknown-unknown`"
+                    },
                     "only_errors": {
                         "type": "boolean",
                         "description": "If true, only errors will be returned. If false, errors and warnings will be returned."
@@ -58,10 +88,14 @@ impl CargoCheck {
                 {
                     tracing::error!("Failed to send MCP notification: {}", e);
                 }
-                let response = match handle_request(project, &relative_file, &request).await {
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
                     Ok(response) => response,
                     Err(response) => response,
                 };
+                // The output belongs before the response
+                let _ = forwarded.await;
                 if let Err(e) = clone
                     .send_mcp_notification(McpNotification::Response {
                         content: response.clone(),
@@ -81,7 +115,9 @@ async fn handle_request(
     project: Arc<ProjectContext>,
     _relative_file: &str,
     request: &CallToolRequest,
+    output: flume::Sender<String>,
 ) -> Result<CallToolResponse, CallToolResponse> {
+    let cargo_remote = project.cargo_remote.with_output(output);
     let only_errors = request
         .arguments
         .as_ref()
@@ -89,14 +125,28 @@ async fn handle_request(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
-    let messages = project
-        .cargo_remote
-        .check(only_errors)
+    let mut compiler_messages = cargo_remote
+        .check_messages(only_errors, &get_build_selection(request))
         .await
-        .map_err(|e| error_response(&format!("{e:?}")))?;
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
 
-    let response_message =
-        serde_json::to_string_pretty(&messages).map_err(|e| error_response(&format!("{e:?}")))?;
+    // The errors are kept if the response has to be truncated
+    compiler_messages.sort_by_key(|message| message.level != "error");
+    let mut messages = Vec::new();
+    for message in compiler_messages {
+        let is_borrow_error = message
+            .error_code()
+            .is_some_and(|code| BORROW_ERROR_CODES.contains(&code));
+        if is_borrow_error {
+            messages.push(enrich_borrow_error(&project, &message).await);
+        } else {
+            messages.push(message.rendered);
+        }
+    }
+
+    let messages = limit_messages(messages, project.project.max_output_bytes());
+    let response_message = serde_json::to_string_pretty(&messages)
+        .map_err(|e| error_response(ErrorCode::Internal, &format!("{e:?}")))?;
 
     Ok(CallToolResponse {
         content: vec![ToolResponseContent::Text {
@@ -106,3 +156,55 @@ async fn handle_request(
         meta: None,
     })
 }
+
+/// Attach the source of every span and the hover type of the involved bindings
+async fn enrich_borrow_error(project: &Arc<ProjectContext>, message: &CompilerMessage) -> String {
+    let mut output = message.rendered.clone();
+    output.push_str("\nContext:\n");
+    for span in &message.spans {
+        let absolute_path = project.project.root().join(&span.file_name);
+        let start_line = span.line_start.saturating_sub(1) as u32;
+        let end_line = span.line_end.saturating_sub(1) as u32;
+        if let Ok(Some(lines)) = get_file_lines(&absolute_path, start_line, end_line, 2, 2) {
+            output.push_str(&format!(
+                "{}:{}: {}\n```rust\n{}\n```\n",
+                span.file_name,
+                span.line_start,
+                span.label.as_deref().unwrap_or_default(),
+                lines
+            ));
+        }
+        // rustc counts the columns in characters
+        let character = std::fs::read_to_string(&absolute_path)
+            .ok()
+            .and_then(|content| {
+                content.lines().nth(start_line as usize).map(|line| {
+                    project
+                        .lsp
+                        .position_encoding()
+                        .char_column(line, span.column_start.saturating_sub(1))
+                })
+            })
+            .unwrap_or(span.column_start.saturating_sub(1) as u32);
+        let position = Position::new(start_line, character);
+        let hover = match project.lsp.hover(&span.file_name, position).await {
+            Ok(Some(hover)) => hover,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::debug!("Failed to get hover for {}: {}", span.file_name, e);
+                continue;
+            }
+        };
+        let hover = match hover.contents {
+            HoverContents::Scalar(s) => format_marked_string(&s),
+            HoverContents::Array(a) => a
+                .into_iter()
+                .map(|s| format_marked_string(&s))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            HoverContents::Markup(m) => m.value,
+        };
+        output.push_str(&format!("Type:\n{hover}\n"));
+    }
+    output
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::{
+    cargo_remote::DENY_CHECKS,
+    context::{Context, ProjectContext},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+/// All checks `cargo deny check` supports
+const CHECKS: &[&str] = &["advisories", "bans", "licenses", "sources"];
+
+pub struct CargoDeny;
+
+impl CargoDeny {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_deny".to_string(),
+            description: Some("Check the dependencies against the license, ban and advisory policies of the project's `deny.toml` with `cargo deny` (requires `cargo-deny`). Returns the violations as JSON. Run this after adding or updating dependencies to keep them compliant.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "checks": {
+                        "type": "array",
+                        "items": { "type": "string", "enum": CHECKS },
+                        "description": format!("The checks to run. Default is {}.", DENY_CHECKS.join(", "))
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let mut checks = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("checks"))
+        .and_then(|v| v.as_array())
+        .map(|checks| checks.iter().filter_map(|c| c.as_str()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if checks.is_empty() {
+        checks = DENY_CHECKS.to_vec();
+    }
+    if let Some(unknown) = checks.iter().find(|check| !CHECKS.contains(check)) {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!(
+                "Unknown check `{unknown}`, expected one of {}",
+                CHECKS.join(", ")
+            ),
+        ));
+    }
+
+    let violations = project
+        .cargo_remote
+        .deny(&checks)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let mut text = String::new();
+    let root = project.project.root();
+    if !root.join("deny.toml").exists() && !root.join(".deny.toml").exists() {
+        text.push_str("The project has no `deny.toml`, so cargo-deny's defaults were used. Create one with `cargo deny init`.\n\n");
+    }
+    if violations.is_empty() {
+        text.push_str(&format!("No violations found ({})", checks.join(", ")));
+    } else {
+        text.push_str(
+            &serde_json::to_string_pretty(&violations)
+                .map_err(|e| error_response(ErrorCode::Internal, &format!("{e:?}")))?,
+        );
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, error_response, get_build_selection, get_info_from_request, output_forwarder,
+        truncate_text,
+    },
+};
+
+pub struct CargoExpand;
+
+impl CargoExpand {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_expand".to_string(),
+            description: Some("Expand all macros of a module or item with `cargo expand` (requires `cargo-expand`) and return the resulting source. Unlike `expand_macro`, which expands a single call, this shows the complete code the compiler sees after all derives, attribute and proc macros ran, which helps to debug complex proc macro output.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "item": {
+                        "type": "string",
+                        "description": "Optional path of the module or item to expand, relative to the crate root, e.g. `parser` or `parser::Token`. The whole crate if empty."
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Optional workspace member to expand"
+                    },
+                    "bin": {
+                        "type": "string",
+                        "description": "Optional binary to expand instead of the library"
+                    },
+                    "test": {
+                        "type": "string",
+                        "description": "Optional integration test to expand instead of the library"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional features to enable"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                // The output belongs before the response
+                let _ = forwarded.await;
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+    output: flume::Sender<String>,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let string = |name: &str| {
+        request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    };
+    let target = match (string("bin"), string("test")) {
+        (Some(_), Some(_)) => {
+            return Err(error_response(
+                ErrorCode::InvalidArguments,
+                "Pass either `bin` or `test`, not both",
+            ));
+        }
+        (Some(bin), None) => vec!["--bin", bin],
+        (None, Some(test)) => vec!["--test", test],
+        (None, None) => Vec::new(),
+    };
+    // `cargo expand` takes the path without the crate name
+    let item = string("item").map(|item| item.trim_start_matches("crate::"));
+
+    let expanded = project
+        .cargo_remote
+        .with_output(output)
+        .expand(item, &target, &get_build_selection(request))
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!(
+                "```rust\n{}\n```",
+                truncate_text(expanded.trim_end(), project.project.max_output_bytes())
+            ),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
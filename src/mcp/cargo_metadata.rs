@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct CargoMetadata;
+
+impl CargoMetadata {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_metadata".to_string(),
+            description: Some("Get the structure of the cargo workspace: the member crates with their edition, targets (lib, bins, examples, tests, benches), features and direct dependencies. Returns the response in JSON format.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let metadata = project
+        .cargo_remote
+        .metadata(true)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let members = metadata
+        .workspace_packages()
+        .map(|package| {
+            json!({
+                "name": package.name,
+                "version": package.version,
+                "edition": package.edition,
+                "manifest_path": package.manifest_path,
+                "features": package.features,
+                "targets": package.targets,
+                "dependencies": package.dependencies,
+            })
+        })
+        .collect::<Vec<_>>();
+    let response = json!({
+        "workspace_root": metadata.workspace_root,
+        "target_directory": metadata.target_directory,
+        "members": members,
+    });
+    let text = serde_json::to_string_pretty(&response)
+        .map_err(|e| error_response(ErrorCode::Internal, &format!("{e:?}")))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
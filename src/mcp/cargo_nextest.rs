@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::{
+    cargo_remote::BuildSelection,
+    context::{Context, ProjectContext},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, error_response, format_test_results, get_info_from_request, output_forwarder,
+        truncate_text,
+    },
+};
+
+pub struct CargoNextest;
+
+impl CargoNextest {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_nextest".to_string(),
+            description: Some(
+                "Run the tests of this project with `cargo nextest`. Returns structured per test results in JSON format: the number of passed, failed and ignored tests and the output of every failed test. Requires cargo-nextest to be installed."
+                    .to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "test": {
+                        "type": "string",
+                        "description": "Optional filter for the names of the tests to run."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project to test"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                // The output belongs before the response
+                let _ = forwarded.await;
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+    output: flume::Sender<String>,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let cargo_remote = project.cargo_remote.with_output(output);
+    let test = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("test"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    if !cargo_remote.has_nextest().await {
+        return Err(error_response(
+            ErrorCode::CargoFailed,
+            "cargo-nextest is not installed. Install it with `cargo install cargo-nextest` or use the `cargo_test` tool",
+        ));
+    }
+
+    let results = cargo_remote
+        .nextest(test, &BuildSelection::default())
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: truncate_text(
+                &format_test_results(&results),
+                project.project.max_output_bytes(),
+            ),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
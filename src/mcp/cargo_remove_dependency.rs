@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, line_diff},
+};
+
+pub struct CargoRemoveDependency;
+
+impl CargoRemoveDependency {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_remove_dependency".to_string(),
+            description: Some("Remove a dependency from a `Cargo.toml` with `cargo remove`. Returns the resulting diff of the `Cargo.toml`.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file to remove the dependency from"
+                    },
+                    "dependency": {
+                        "type": "string",
+                        "description": "The name of the crate to remove"
+                    },
+                    "dev": {
+                        "type": "boolean",
+                        "description": "Remove the crate from the dev-dependencies. Default is false."
+                    }
+                },
+                "required": ["file", "dependency"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let arguments = request.arguments.as_ref();
+    let manifest = arguments
+        .and_then(|args| args.get("file"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "File is required"))?;
+    let dependency = arguments
+        .and_then(|args| args.get("dependency"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Dependency is required"))?;
+    let dev = arguments
+        .and_then(|args| args.get("dev"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let before = std::fs::read_to_string(manifest)
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    let output = project
+        .cargo_remote
+        .remove_dependency(manifest, dependency, dev)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+    let after = std::fs::read_to_string(manifest)
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    if before == after {
+        return Err(error_response(
+            ErrorCode::CargoFailed,
+            &format!("`Cargo.toml` was not changed:\n{output}"),
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!("```diff\n{}\n```", line_diff(&before, &after, 2)),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
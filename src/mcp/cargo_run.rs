@@ -0,0 +1,243 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    cargo_remote::RunOutput,
+    context::{Context, ProjectContext},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, output_forwarder, truncate_text},
+};
+
+/// How long the binary may run if the request doesn't say
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+
+/// The longest a binary may run, so that a call can't block the server forever
+const MAX_TIMEOUT_SECONDS: u64 = 600;
+
+pub struct CargoRun;
+
+impl CargoRun {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "cargo_run".to_string(),
+            description: Some("Build a binary of the project and run it with arguments, like `cargo run --bin <name> -- <args>`. Returns the exit code and the captured stdout and stderr. The binary is killed if it doesn't exit within the timeout and gets no stdin, so don't use this for interactive programs or servers.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "bin": {
+                        "type": "string",
+                        "description": "The name of the binary to run. Only optional if the project has a single binary."
+                    },
+                    "args": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "The arguments for the binary"
+                    },
+                    "timeout_seconds": {
+                        "type": "number",
+                        "description": format!("How long the binary may run before it is killed. Default is {DEFAULT_TIMEOUT_SECONDS}, at most {MAX_TIMEOUT_SECONDS}.")
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                // The output belongs before the response
+                let _ = forwarded.await;
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RunArguments {
+    bin: Option<String>,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+/// The arguments of a call, with the timeout clamped to the allowed range
+fn run_arguments(request: &CallToolRequest) -> Result<RunArguments, CallToolResponse> {
+    let arguments = request.arguments.as_ref();
+    let bin = arguments
+        .and_then(|args| args.get("bin"))
+        .and_then(|v| v.as_str())
+        .filter(|bin| !bin.is_empty());
+    // It is passed to cargo after `--bin`, where it would be read as an option
+    if let Some(bin) = bin
+        && bin.starts_with('-')
+    {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!("Invalid binary name `{bin}`"),
+        ));
+    }
+    let args = arguments
+        .and_then(|args| args.get("args"))
+        .and_then(|v| v.as_array())
+        .map(|args| {
+            args.iter()
+                .filter_map(|arg| arg.as_str().map(|arg| arg.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // Clients may send fractions or write integers as floats
+    let timeout_seconds = arguments
+        .and_then(|args| args.get("timeout_seconds"))
+        .and_then(|v| v.as_f64())
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS as f64)
+        .clamp(1.0, MAX_TIMEOUT_SECONDS as f64);
+    Ok(RunArguments {
+        bin: bin.map(|bin| bin.to_string()),
+        args,
+        timeout: Duration::from_secs_f64(timeout_seconds),
+    })
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+    output: flume::Sender<String>,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let RunArguments { bin, args, timeout } = run_arguments(request)?;
+
+    let run = project
+        .cargo_remote
+        .with_output(output)
+        .run(bin.as_deref(), &args, timeout)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format_run(&run, timeout, project.project.max_output_bytes()),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// The status and the output of the run, stdout and stderr get half of `max_bytes` each
+fn format_run(run: &RunOutput, timeout: Duration, max_bytes: usize) -> String {
+    let status = match (run.timed_out, run.exit_code) {
+        (true, _) => format!(
+            "Killed after {}s because it didn't exit",
+            timeout.as_secs_f64()
+        ),
+        (false, Some(code)) => format!("Exit code: {code}"),
+        (false, None) => "Terminated by a signal".to_string(),
+    };
+    let mut text = status;
+    for (name, output) in [("stdout", &run.stdout), ("stderr", &run.stderr)] {
+        if output.trim().is_empty() {
+            text.push_str(&format!("\n\nNo {name} output"));
+        } else {
+            let output = truncate_text(output.trim_end(), max_bytes / 2);
+            text.push_str(&format!("\n\n## {name}\n```\n{output}\n```"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(arguments: serde_json::Value) -> CallToolRequest {
+        CallToolRequest {
+            name: "cargo_run".to_string(),
+            arguments: Some(serde_json::from_value(arguments).unwrap()),
+            meta: None,
+        }
+    }
+
+    #[test]
+    fn test_run_arguments() {
+        let arguments = run_arguments(&request(json!({
+            "file": "/app/Cargo.toml",
+            "bin": "tool",
+            "args": ["--verbose", 1, "input.txt"],
+            "timeout_seconds": 2.5
+        })))
+        .unwrap();
+        assert_eq!(
+            arguments,
+            RunArguments {
+                bin: Some("tool".to_string()),
+                args: vec!["--verbose".to_string(), "input.txt".to_string()],
+                timeout: Duration::from_millis(2500),
+            }
+        );
+
+        let defaults =
+            run_arguments(&request(json!({ "file": "/app/Cargo.toml", "bin": "" }))).unwrap();
+        assert_eq!(defaults.bin, None);
+        assert!(defaults.args.is_empty());
+        assert_eq!(
+            defaults.timeout,
+            Duration::from_secs(DEFAULT_TIMEOUT_SECONDS)
+        );
+
+        let clamped = |timeout: serde_json::Value| {
+            run_arguments(&request(json!({ "timeout_seconds": timeout })))
+                .unwrap()
+                .timeout
+        };
+        assert_eq!(clamped(json!(0)), Duration::from_secs(1));
+        assert_eq!(clamped(json!(-5)), Duration::from_secs(1));
+        assert_eq!(
+            clamped(json!(1e9)),
+            Duration::from_secs(MAX_TIMEOUT_SECONDS)
+        );
+
+        assert!(run_arguments(&request(json!({ "bin": "--release" }))).is_err());
+    }
+}
@@ -10,7 +10,10 @@ use serde_json::json;
 
 use super::{
     McpNotification,
-    utils::{error_response, get_info_from_request},
+    utils::{
+        ErrorCode, error_response, format_test_results, get_build_selection, get_info_from_request,
+        output_forwarder, truncate_text,
+    },
 };
 
 pub struct CargoTest;
@@ -20,7 +23,7 @@ impl CargoTest {
         Tool {
             name: "cargo_test".to_string(),
             description: Some(
-                "Run the cargo test command in this project. Returns the response in JSON format"
+                "Run the tests of this project, or a single one, with cargo test. Returns the response in JSON format"
                     .to_string(),
             ),
             input_schema: json!({
@@ -34,6 +37,23 @@ impl CargoTest {
                         "type": "string",
                         "description": "The absolute path to the `Cargo.toml` file of the project to check"
                     },
+                    "package": {
+                        "type": "string",
+                        "description": "Optional workspace member to test instead of the whole workspace"
+                    },
+                    "features": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Optional features to enable"
+                    },
+                    "no_default_features": {
+                        "type": "boolean",
+                        "description": "If true, the default features are disabled. Default is false."
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": "Optional target triple to build the tests for, e.g. `wasm32-unknown-unknown`"
+                    },
                     "backtrace": {
                         "type": "boolean",
                         "description": "If true, the backtrace will be included in the response. Default is false."
@@ -62,10 +82,14 @@ impl CargoTest {
                 {
                     tracing::error!("Failed to send MCP notification: {}", e);
                 }
-                let response = match handle_request(project, &relative_file, &request).await {
+                let (output, forwarded) = output_forwarder(&clone, absolute_file.clone());
+                let response = match handle_request(project, &relative_file, &request, output).await
+                {
                     Ok(response) => response,
                     Err(response) => response,
                 };
+                // The output belongs before the response
+                let _ = forwarded.await;
                 if let Err(e) = clone
                     .send_mcp_notification(McpNotification::Response {
                         content: response.clone(),
@@ -85,7 +109,9 @@ async fn handle_request(
     project: Arc<ProjectContext>,
     _relative_file: &str,
     request: &CallToolRequest,
+    output: flume::Sender<String>,
 ) -> Result<CallToolResponse, CallToolResponse> {
+    let cargo_remote = project.cargo_remote.with_output(output);
     let test = request
         .arguments
         .as_ref()
@@ -100,15 +126,33 @@ async fn handle_request(
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
-    let messages: Vec<String> = project
-        .cargo_remote
-        .test(test, backtrace)
+    let selection = get_build_selection(request);
+
+    if project.project.use_nextest() && cargo_remote.has_nextest().await {
+        let results = cargo_remote
+            .nextest(test, &selection)
+            .await
+            .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+        return Ok(CallToolResponse {
+            content: vec![ToolResponseContent::Text {
+                text: truncate_text(
+                    &format_test_results(&results),
+                    project.project.max_output_bytes(),
+                ),
+            }],
+            is_error: None,
+            meta: None,
+        });
+    }
+
+    let messages: Vec<String> = cargo_remote
+        .test(test, &selection, backtrace)
         .await
-        .map_err(|e| error_response(&format!("{e:?}")))?;
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
 
     Ok(CallToolResponse {
         content: vec![ToolResponseContent::Text {
-            text: messages.join("\n\n"),
+            text: truncate_text(&messages.join("\n\n"), project.project.max_output_bytes()),
         }],
         is_error: None,
         meta: None,
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct CleanTarget;
+
+impl CleanTarget {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "clean_target".to_string(),
+            description: Some("Remove build artifacts with `cargo clean`. Without `confirm` this only reports what would be removed. Only set `confirm` after the user agreed, as the next build has to recompile everything that was removed.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Only clean the artifacts of this package"
+                    },
+                    "profile": {
+                        "type": "string",
+                        "description": "Only clean the artifacts of this profile, e.g. `dev` or `release`"
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "Actually remove the artifacts. Default is false, which performs a dry run."
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let arguments = request.arguments.as_ref();
+    let package = arguments
+        .and_then(|args| args.get("package"))
+        .and_then(|v| v.as_str());
+    let profile = arguments
+        .and_then(|args| args.get("profile"))
+        .and_then(|v| v.as_str());
+    let confirm = arguments
+        .and_then(|args| args.get("confirm"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let output = project
+        .cargo_remote
+        .clean(package, profile, !confirm)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let text = if confirm {
+        output
+    } else {
+        format!(
+            "Dry run, nothing was removed:\n{output}\nCall this tool again with `confirm` set to true to remove the files."
+        )
+    };
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::apply_workspace_edit,
+};
+use anyhow::Result;
+use lsp_types::{CodeAction, CodeActionOrCommand};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, format_workspace_edit, full_line_range,
+        get_info_from_request, lsp_error_response,
+    },
+};
+
+pub struct CodeActions;
+
+impl CodeActions {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "code_actions".to_string(),
+            description: Some("Get the rust-analyzer code actions (quick fixes and refactorings such as \"add missing match arms\" or \"implement missing members\") available for a range of lines in a file, including the edits they would perform. Optionally apply one of the actions.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The first line of the range to get code actions for (1 based)"
+                    },
+                    "end_line": {
+                        "type": "number",
+                        "description": "The optional last line of the range. Defaults to `line`."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file"
+                    },
+                    "apply": {
+                        "type": "number",
+                        "description": "The optional index of an action (as returned by a previous call) to apply to the files on disk"
+                    }
+                },
+                "required": ["line", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let end_line = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("end_line"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(line);
+    let apply = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("apply"))
+        .and_then(|v| v.as_u64());
+
+    let range = full_line_range(
+        project.project.root().join(relative_file),
+        line as u32,
+        end_line as u32,
+        project.lsp.position_encoding(),
+    )
+    .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+
+    let Some(actions) = project
+        .lsp
+        .code_actions(relative_file, range)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(ErrorCode::NotFound, "No code actions found"));
+    };
+
+    // Commands can't be executed without an editor, so we only expose actions with edits
+    let actions: Vec<CodeAction> = actions
+        .into_iter()
+        .filter_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) if action.disabled.is_none() => Some(action),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(index) = apply {
+        let Some(action) = actions.get(index as usize) else {
+            return Err(error_response(
+                ErrorCode::InvalidArguments,
+                &format!(
+                    "No code action with index {index}. There are {} actions",
+                    actions.len()
+                ),
+            ));
+        };
+        let Some(edit) = &action.edit else {
+            return Err(error_response(
+                ErrorCode::LspFailed,
+                &format!("Code action `{}` has no edits to apply", action.title),
+            ));
+        };
+        let changed = apply_workspace_edit(edit, project.lsp.position_encoding())
+            .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+        let changed = changed
+            .iter()
+            .map(|path| format!("- {}", path.display()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Ok(CallToolResponse {
+            content: vec![ToolResponseContent::Text {
+                text: format!("Applied `{}`. Changed files:\n{}", action.title, changed),
+            }],
+            is_error: None,
+            meta: None,
+        });
+    }
+
+    if actions.is_empty() {
+        return Err(error_response(ErrorCode::NotFound, "No code actions found"));
+    }
+
+    let mut contents = String::new();
+    for (index, action) in actions.iter().enumerate() {
+        let kind = action
+            .kind
+            .as_ref()
+            .map(|kind| kind.as_str().to_string())
+            .unwrap_or_default();
+        contents.push_str(&format!("## {index}: {} ({kind})\n", action.title));
+        if let Some(edit) = &action.edit {
+            contents.push_str(&format_workspace_edit(edit));
+        }
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
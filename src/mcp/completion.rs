@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use lsp_types::{CompletionResponse, Documentation, Position};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, get_info_from_request, lsp_error_response,
+    },
+};
+
+/// Number of completions returned if the request doesn't specify a limit
+const DEFAULT_LIMIT: u64 = 20;
+
+pub struct Completion;
+
+impl Completion {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "completion".to_string(),
+            description: Some("Get the top ranked rust-analyzer completions (label, kind, detail, documentation) at a position in a file. Use it after a `.` or `::` to find the methods and items available on a type or module.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The line number of the position in the file (1 based)"
+                    },
+                    "column": {
+                        "type": "number",
+                        "description": "The column (0 based character offset) of the position in the line, e.g. directly after the `.`"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "The maximum number of completions to return. Default is 20."
+                    }
+                },
+                "required": ["line", "column", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let column = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("column"))
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Column is required"))?;
+    let limit = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("limit"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_LIMIT);
+    let content = std::fs::read_to_string(project.project.root().join(relative_file))
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    let character = content
+        .lines()
+        .nth(line as usize)
+        .map(|text| {
+            project
+                .lsp
+                .position_encoding()
+                .char_column(text, column as usize)
+        })
+        .unwrap_or(column as u32);
+
+    let Some(completions) = project
+        .lsp
+        .completion(relative_file, Position::new(line as u32, character))
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(ErrorCode::NotFound, "No completions found"));
+    };
+
+    let mut items = match completions {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => list.items,
+    };
+    // rust-analyzer encodes its relevance ranking in the sort text
+    items.sort_by(|a, b| {
+        let a_key = a.sort_text.as_ref().unwrap_or(&a.label);
+        let b_key = b.sort_text.as_ref().unwrap_or(&b.label);
+        a_key.cmp(b_key)
+    });
+
+    let mut contents = String::new();
+    for item in items.into_iter().take(limit as usize) {
+        let kind = item
+            .kind
+            .map(|kind| format!("{kind:?}"))
+            .unwrap_or_default();
+        contents.push_str(&format!("## {} ({kind})\n", item.label));
+        if let Some(detail) = item.detail {
+            contents.push_str(&format!("`{detail}`\n"));
+        }
+        match item.documentation {
+            Some(Documentation::String(docs)) => contents.push_str(&format!("{docs}\n")),
+            Some(Documentation::MarkupContent(docs)) => {
+                contents.push_str(&format!("{}\n", docs.value))
+            }
+            None => {}
+        }
+    }
+
+    if contents.is_empty() {
+        return Err(error_response(ErrorCode::NotFound, "No completions found"));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::format_marked_string,
+};
+use anyhow::Result;
+use lsp_types::{HoverContents, SymbolKind};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_item_in_file, get_info_from_request,
+        lsp_error_response,
+    },
+};
+
+pub struct ConstEval;
+
+impl ConstEval {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "const_eval".to_string(),
+            description: Some("Evaluate a `const` / `static` item or run a function without arguments (e.g. a test or a small helper) with rust-analyzer's interpreter and return the resulting value or output. Useful to verify bit-twiddling or const generics reasoning.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The line number of the item in the file (1 based)"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the const, static or function to evaluate"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file containing the item"
+                    }
+                },
+                "required": ["line", "symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let symbol = request.get_symbol()?;
+
+    let (kind, position) = find_item_in_file(&project, relative_file, &symbol, line)
+        .await
+        .map_err(|e| lsp_error_response(&e))?;
+
+    let text = match kind {
+        SymbolKind::FUNCTION | SymbolKind::METHOD => project
+            .lsp
+            .interpret_function(relative_file, position)
+            .await
+            .map_err(|e| lsp_error_response(&e))?,
+        SymbolKind::CONSTANT | SymbolKind::VARIABLE => {
+            // rust-analyzer evaluates consts and statics and shows the value in the hover
+            let Some(hover) = project
+                .lsp
+                .hover(relative_file, position)
+                .await
+                .map_err(|e| lsp_error_response(&e))?
+            else {
+                return Err(error_response(
+                    ErrorCode::NotFound,
+                    "No hover information found",
+                ));
+            };
+            match hover.contents {
+                HoverContents::Scalar(s) => format_marked_string(&s),
+                HoverContents::Array(a) => a
+                    .into_iter()
+                    .map(|s| format_marked_string(&s))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                HoverContents::Markup(m) => m.value,
+            }
+        }
+        kind => {
+            return Err(error_response(
+                ErrorCode::InvalidArguments,
+                &format!(
+                    "Can only evaluate consts, statics and functions, `{symbol}` is a {kind:?}"
+                ),
+            ));
+        }
+    };
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::context::{Context, ProjectContext};
+use crate::docs::page::DEFAULT_MAX_TOKENS;
 use anyhow::Result;
 use mcp_core::{
     tools::ToolHandlerFn,
@@ -10,7 +11,7 @@ use serde_json::json;
 
 use super::{
     McpNotification,
-    utils::{error_response, get_info_from_request},
+    utils::{ErrorCode, error_response, get_info_from_request},
 };
 
 pub struct CrateDocs;
@@ -18,8 +19,8 @@ pub struct CrateDocs;
 impl CrateDocs {
     pub fn tool() -> Tool {
         Tool {
-            name: "symbol_docs".to_string(),
-            description: Some("Get the documentation for a cargo dependency".to_string()),
+            name: "crate_docs".to_string(),
+            description: Some("Get the documentation for a cargo dependency or one of its symbols. Previously also named `symbol_docs`, which now only resolves symbols in a file.".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -29,7 +30,15 @@ impl CrateDocs {
                     },
                     "symbol": {
                         "type": "string",
-                        "description": "The optional name of a symbol in the documentation. If not provided, the main readme for the dependency will be returned."
+                        "description": "The optional symbol in the documentation, either as listed by `crate_symbols` or as a path like `tokio::sync::mpsc::Sender` (paths where re-exported items are defined work as well). If not provided, the crate overview with a list of its modules will be returned."
+                    },
+                    "max_tokens": {
+                        "type": "integer",
+                        "description": format!("The maximum size of the response in tokens, longer docs are split into pages. Defaults to {DEFAULT_MAX_TOKENS}.")
+                    },
+                    "page": {
+                        "type": "integer",
+                        "description": "The page of the docs to return, starting at 1. Defaults to 1."
                     },
                     "file": {
                         "type": "string",
@@ -88,7 +97,7 @@ async fn handle_request(
         .as_ref()
         .and_then(|args| args.get("dependency"))
         .and_then(|v| v.as_str())
-        .ok_or_else(|| error_response("Dependency is required"))
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Dependency is required"))
         .map(|s| s.to_string())?;
 
     let symbol = request
@@ -98,28 +107,39 @@ async fn handle_request(
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
-    if let Some(symbol) = symbol {
-        let docs = project
-            .docs
-            .crate_symbol_docs(&dependency, &symbol)
-            .await
-            .map_err(|e| error_response(&format!("{e:?}")))?;
-        let docs = docs.into_iter().map(|(k, v)| format!("{k}: {v}")).collect();
-        Ok(CallToolResponse {
-            content: vec![ToolResponseContent::Text { text: docs }],
-            is_error: None,
-            meta: None,
-        })
-    } else {
-        let docs = project
-            .docs
-            .crate_docs(&dependency)
-            .await
-            .map_err(|e| error_response(&format!("{e:?}")))?;
-        Ok(CallToolResponse {
-            content: vec![ToolResponseContent::Text { text: docs }],
-            is_error: None,
-            meta: None,
-        })
+    let max_tokens = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("max_tokens"))
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_MAX_TOKENS, |v| v as usize);
+
+    let page = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("page"))
+        .and_then(|v| v.as_u64())
+        .map_or(1, |v| v as usize);
+
+    let docs = match symbol {
+        Some(symbol) => {
+            project
+                .docs
+                .crate_symbol_docs(&dependency, &symbol, max_tokens, page)
+                .await
+        }
+        None => project.docs.crate_docs(&dependency, max_tokens, page).await,
     }
+    .map_err(|e| error_response(ErrorCode::DocsFailed, &format!("{e:?}")))?;
+
+    let mut text = docs.with_continuation();
+    if let Some(error) = project.docs.last_failure() {
+        text = format!("Docs may be stale, the last generation failed with:\n{error}\n\n{text}");
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
 }
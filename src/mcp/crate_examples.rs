@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use crate::docs::page::DEFAULT_MAX_TOKENS;
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct CrateExamples;
+
+impl CrateExamples {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "crate_examples".to_string(),
+            description: Some("Get only the code examples from the documentation of a cargo dependency or of one of its symbols. Examples are often the fastest way to learn how to use an API.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "dependency": {
+                        "type": "string",
+                        "description": "The name of the cargo dependency to get the examples for"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The optional name or path of a symbol (e.g. `Mutex` or `sync::Mutex`). If not provided, the examples of the whole crate are returned."
+                    },
+                    "max_tokens": {
+                        "type": "integer",
+                        "description": format!("The maximum size of the response in tokens, more examples are split into pages. Defaults to {DEFAULT_MAX_TOKENS}.")
+                    },
+                    "page": {
+                        "type": "integer",
+                        "description": "The page of the examples to return, starting at 1. Defaults to 1."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["dependency", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let dependency = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("dependency"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Dependency is required"))
+        .map(|s| s.to_string())?;
+
+    let symbol = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("symbol"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let max_tokens = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("max_tokens"))
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_MAX_TOKENS, |v| v as usize);
+
+    let page = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("page"))
+        .and_then(|v| v.as_u64())
+        .map_or(1, |v| v as usize);
+
+    let examples = project
+        .docs
+        .crate_examples(&dependency, symbol.as_deref(), max_tokens, page)
+        .await
+        .map_err(|e| error_response(ErrorCode::DocsFailed, &format!("{e:?}")))?;
+
+    let mut text = examples.with_continuation();
+    if let Some(error) = project.docs.last_failure() {
+        text = format!("Docs may be stale, the last generation failed with:\n{error}\n\n{text}");
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct CrateSymbols;
+
+impl CrateSymbols {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "crate_symbols".to_string(),
+            description: Some("List the documented symbols of a cargo dependency grouped by kind (struct, trait, fn, macro, ...). Use this to discover what a crate offers before requesting the documentation of a symbol.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "dependency": {
+                        "type": "string",
+                        "description": "The name of the cargo dependency"
+                    },
+                    "filter": {
+                        "type": "string",
+                        "description": "Only list symbols whose path contains this text (case insensitive)"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["dependency", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let args = request.arguments.as_ref();
+    let dependency = args
+        .and_then(|args| args.get("dependency"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Dependency is required"))?;
+    let filter = args
+        .and_then(|args| args.get("filter"))
+        .and_then(|v| v.as_str());
+
+    let symbols = project
+        .docs
+        .crate_symbols(dependency, filter)
+        .await
+        .map_err(|e| error_response(ErrorCode::DocsFailed, &format!("{e:?}")))?;
+    if symbols.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No symbols found in `{dependency}`"),
+        ));
+    }
+
+    let text = symbols
+        .into_iter()
+        .map(|(kind, paths)| format!("## {kind}\n{}", paths.join("\n")))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
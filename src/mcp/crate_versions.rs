@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    crates_io::{CratesIo, latest},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use semver::VersionReq;
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct CrateVersions;
+
+impl CrateVersions {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "crate_versions".to_string(),
+            description: Some("Look up the latest version of a crate on crates.io, the latest version compatible with a requirement and the features of these versions. If no requirement is given, the requirement the project already uses for the crate is used. Use this to write correct `Cargo.toml` entries.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "crate_name": {
+                        "type": "string",
+                        "description": "The name of the crate on crates.io"
+                    },
+                    "requirement": {
+                        "type": "string",
+                        "description": "An optional version requirement (e.g. `1.2`) to find the latest compatible version for"
+                    }
+                },
+                "required": ["file", "crate_name"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let args = request.arguments.as_ref();
+    let Some(crate_name) = args
+        .and_then(|args| args.get("crate_name"))
+        .and_then(|v| v.as_str())
+    else {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            "crate_name is required",
+        ));
+    };
+    let requirement = match args
+        .and_then(|args| args.get("requirement"))
+        .and_then(|v| v.as_str())
+    {
+        Some(requirement) => Some(requirement.to_string()),
+        None => project
+            .cargo_remote
+            .metadata(true)
+            .await
+            .ok()
+            .and_then(|metadata| {
+                metadata
+                    .workspace_packages()
+                    .flat_map(|package| &package.dependencies)
+                    .find(|dependency| dependency.name == crate_name && dependency.is_crates_io())
+                    .map(|dependency| dependency.req.clone())
+            }),
+    };
+    let parsed_requirement = requirement
+        .as_deref()
+        .map(VersionReq::parse)
+        .transpose()
+        .map_err(|e| {
+            error_response(
+                ErrorCode::InvalidArguments,
+                &format!("Invalid requirement: {e}"),
+            )
+        })?;
+
+    let crates_io =
+        CratesIo::new().map_err(|e| error_response(ErrorCode::NetworkFailed, &format!("{e:?}")))?;
+    let published = crates_io
+        .published_versions(crate_name)
+        .await
+        .map_err(|e| {
+            error_response(
+                ErrorCode::NetworkFailed,
+                &format!("Failed to query crates.io: {e}"),
+            )
+        })?;
+    let versions = published
+        .iter()
+        .map(|published| published.version.clone())
+        .collect::<Vec<_>>();
+    let Some(newest) = latest(&versions, None) else {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No stable versions of `{crate_name}` found"),
+        ));
+    };
+
+    let features = |version: &semver::Version| {
+        published
+            .iter()
+            .find(|published| published.version == *version)
+            .map(|published| {
+                published
+                    .features
+                    .iter()
+                    .map(|(name, enables)| {
+                        if enables.is_empty() {
+                            format!("- {name}")
+                        } else {
+                            format!("- {name}: {}", enables.join(", "))
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .filter(|features| !features.is_empty())
+            .unwrap_or_else(|| "No features".to_string())
+    };
+
+    let mut text = format!(
+        "# {crate_name}\nLatest version: {newest}\n`{crate_name} = \"{newest}\"`\n\n## Features of {newest}\n{}\n",
+        features(newest)
+    );
+    if let (Some(requirement), Some(parsed_requirement)) = (&requirement, &parsed_requirement) {
+        match latest(&versions, Some(parsed_requirement)) {
+            Some(compatible) if compatible != newest => text.push_str(&format!(
+                "\nLatest version compatible with `{requirement}`: {compatible}\n\n## Features of {compatible}\n{}\n",
+                features(compatible)
+            )),
+            Some(_) => text.push_str(&format!(
+                "\nThe latest version is compatible with `{requirement}`\n"
+            )),
+            None => text.push_str(&format!(
+                "\nNo version is compatible with `{requirement}`\n"
+            )),
+        }
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,437 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::{
+    cargo_remote::Metadata,
+    context::{Context, ProjectContext},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct DependencyGraph;
+
+impl DependencyGraph {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "dependency_graph".to_string(),
+            description: Some("Get the dependency graph of the workspace from `cargo metadata` as a Mermaid or Graphviz DOT diagram. Workspace members are drawn as boxes and build dependencies as dashed edges. Restrict it to the workspace members to explain the architecture, or to the paths between two crates to find out why a dependency is pulled in.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["mermaid", "dot"],
+                        "description": "The format of the diagram. Default is `mermaid`."
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Optional crate name, only the crates it depends on (directly or indirectly) are included"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Optional crate name, only the crates that depend on it (directly or indirectly) are included. Together with `from` this returns the paths between the two."
+                    },
+                    "workspace_only": {
+                        "type": "boolean",
+                        "description": "Only include the workspace members (default false)"
+                    },
+                    "include_dev": {
+                        "type": "boolean",
+                        "description": "Include dev dependencies (default false)"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let arguments = request.arguments.as_ref();
+    let string = |name: &str| {
+        arguments
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    };
+    let flag = |name: &str| {
+        arguments
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+    let format = string("format").unwrap_or("mermaid");
+    if format != "mermaid" && format != "dot" {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!("Unknown format `{format}`, expected `mermaid` or `dot`"),
+        ));
+    }
+
+    let metadata = project
+        .cargo_remote
+        .metadata(false)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+    let mut graph = Graph::new(&metadata, flag("include_dev"), flag("workspace_only"))
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &e))?;
+    graph
+        .restrict(string("from"), string("to"))
+        .map_err(|e| error_response(ErrorCode::InvalidArguments, &e))?;
+    if graph.nodes.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No crates match the restrictions",
+        ));
+    }
+
+    let text = match format {
+        "dot" => graph.to_dot(),
+        _ => graph.to_mermaid(),
+    };
+    let max_bytes = project.project.max_output_bytes();
+    if text.len() > max_bytes {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!(
+                "The graph has {} crates and is larger than {max_bytes} bytes. Restrict it with `workspace_only`, `from` or `to`.",
+                graph.nodes.len()
+            ),
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!("```{format}\n{text}```"),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+struct Package {
+    name: String,
+    version: String,
+    is_member: bool,
+}
+
+/// The dependencies between the packages of `cargo metadata`, by their index
+struct Graph {
+    packages: Vec<Package>,
+    /// The packages in the graph
+    nodes: BTreeSet<usize>,
+    /// From the dependent to the dependency, `true` for build dependencies
+    edges: BTreeMap<(usize, usize), bool>,
+}
+
+impl Graph {
+    /// The graph of the packages the workspace members depend on
+    fn new(metadata: &Metadata, include_dev: bool, workspace_only: bool) -> Result<Self, String> {
+        let Some(resolve) = &metadata.resolve else {
+            return Err("cargo metadata returned no dependency graph".to_string());
+        };
+        let index = metadata
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(i, package)| (package.id.as_str(), i))
+            .collect::<HashMap<_, _>>();
+        let packages = metadata
+            .packages
+            .iter()
+            .map(|package| Package {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                is_member: metadata.workspace_members.contains(&package.id),
+            })
+            .collect::<Vec<_>>();
+
+        let mut edges = BTreeMap::new();
+        for node in &resolve.nodes {
+            let Some(&from) = index.get(node.id.as_str()) else {
+                continue;
+            };
+            for dep in &node.deps {
+                let Some(&to) = index.get(dep.pkg.as_str()) else {
+                    continue;
+                };
+                if workspace_only && !(packages[from].is_member && packages[to].is_member) {
+                    continue;
+                }
+                // Cargo before 1.41 doesn't report the kinds
+                let normal = dep.dep_kinds.is_empty()
+                    || dep.dep_kinds.iter().any(|kind| kind.kind.is_none());
+                let build = dep
+                    .dep_kinds
+                    .iter()
+                    .any(|kind| kind.kind.as_deref() == Some("build"));
+                if !normal && !build && !include_dev {
+                    continue;
+                }
+                let build_only = !normal && build;
+                edges
+                    .entry((from, to))
+                    .and_modify(|edge| *edge &= build_only)
+                    .or_insert(build_only);
+            }
+        }
+
+        let mut graph = Self {
+            nodes: (0..packages.len()).collect(),
+            packages,
+            edges,
+        };
+        // E.g. the dev dependencies of dependencies are resolved but never built
+        let members = (0..graph.packages.len())
+            .filter(|&i| graph.packages[i].is_member)
+            .collect::<Vec<_>>();
+        graph.nodes = graph.reachable(&members, true);
+        Ok(graph)
+    }
+
+    /// Only keep the crates reachable from `from` and those that reach `to`
+    fn restrict(&mut self, from: Option<&str>, to: Option<&str>) -> Result<(), String> {
+        for (name, forward) in [(from, true), (to, false)] {
+            let Some(name) = name else {
+                continue;
+            };
+            let starts = self
+                .nodes
+                .iter()
+                .copied()
+                .filter(|&i| self.packages[i].name == name)
+                .collect::<Vec<_>>();
+            if starts.is_empty() {
+                return Err(format!("`{name}` is not in the dependency graph"));
+            }
+            self.nodes = self.reachable(&starts, forward);
+        }
+        Ok(())
+    }
+
+    /// The nodes reachable from `starts` following the edges forward or backward
+    fn reachable(&self, starts: &[usize], forward: bool) -> BTreeSet<usize> {
+        let mut reached = BTreeSet::new();
+        let mut queue = starts.to_vec();
+        while let Some(node) = queue.pop() {
+            if !self.nodes.contains(&node) || !reached.insert(node) {
+                continue;
+            }
+            for &(from, to) in self.edges.keys() {
+                match forward {
+                    true if from == node => queue.push(to),
+                    false if to == node => queue.push(from),
+                    _ => {}
+                }
+            }
+        }
+        reached
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        self.edges
+            .iter()
+            .filter(|((from, to), _)| self.nodes.contains(from) && self.nodes.contains(to))
+            .map(|(&(from, to), &build)| (from, to, build))
+    }
+
+    /// The name of the crate, with the version if the graph has several versions of it
+    fn label(&self, node: usize) -> String {
+        let package = &self.packages[node];
+        let versions = self
+            .nodes
+            .iter()
+            .filter(|&&i| self.packages[i].name == package.name)
+            .count();
+        match versions {
+            1 => package.name.clone(),
+            _ => format!("{} {}", package.name, package.version),
+        }
+    }
+
+    fn to_mermaid(&self) -> String {
+        let mut text = "graph LR\n".to_string();
+        for &node in &self.nodes {
+            let label = self.label(node);
+            match self.packages[node].is_member {
+                true => text.push_str(&format!("    n{node}[\"{label}\"]\n")),
+                false => text.push_str(&format!("    n{node}(\"{label}\")\n")),
+            }
+        }
+        for (from, to, build) in self.edges() {
+            let arrow = if build { "-.->" } else { "-->" };
+            text.push_str(&format!("    n{from} {arrow} n{to}\n"));
+        }
+        text
+    }
+
+    fn to_dot(&self) -> String {
+        let mut text = "digraph dependencies {\n    rankdir=LR;\n".to_string();
+        for &node in &self.nodes {
+            let shape = match self.packages[node].is_member {
+                true => "box",
+                false => "ellipse",
+            };
+            text.push_str(&format!(
+                "    n{node} [label=\"{}\", shape={shape}];\n",
+                self.label(node)
+            ));
+        }
+        for (from, to, build) in self.edges() {
+            let style = if build { " [style=dashed]" } else { "" };
+            text.push_str(&format!("    n{from} -> n{to}{style};\n"));
+        }
+        text.push_str("}\n");
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str) -> serde_json::Value {
+        json!({
+            "id": format!("{name}@{version}"),
+            "name": name,
+            "version": version,
+            "edition": "2021",
+            "manifest_path": "",
+            "features": {},
+            "targets": [],
+            "dependencies": [],
+        })
+    }
+
+    fn dep(name: &str, kind: Option<&str>) -> serde_json::Value {
+        json!({ "pkg": name, "dep_kinds": [{ "kind": kind }] })
+    }
+
+    fn metadata() -> Metadata {
+        serde_json::from_value(json!({
+            "workspace_root": "/app",
+            "target_directory": "/app/target",
+            "workspace_members": ["app@0.1.0", "core@0.1.0"],
+            "packages": [
+                package("app", "0.1.0"),
+                package("core", "0.1.0"),
+                package("serde", "1.0.0"),
+                package("cc", "1.0.0"),
+                package("proptest", "1.0.0"),
+                package("rand", "0.7.0"),
+                package("rand", "0.8.0"),
+            ],
+            "resolve": {
+                "nodes": [
+                    { "id": "app@0.1.0", "deps": [dep("core@0.1.0", None), dep("rand@0.8.0", None), dep("proptest@1.0.0", Some("dev"))] },
+                    { "id": "core@0.1.0", "deps": [dep("serde@1.0.0", None), dep("cc@1.0.0", Some("build"))] },
+                    { "id": "proptest@1.0.0", "deps": [dep("rand@0.7.0", None)] },
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    fn names(graph: &Graph) -> Vec<String> {
+        graph.nodes.iter().map(|&node| graph.label(node)).collect()
+    }
+
+    #[test]
+    fn test_graph() {
+        let graph = Graph::new(&metadata(), false, false).unwrap();
+        assert_eq!(names(&graph), ["app", "core", "serde", "cc", "rand"]);
+        assert_eq!(
+            graph.to_mermaid(),
+            "graph LR\n    n0[\"app\"]\n    n1[\"core\"]\n    n2(\"serde\")\n    n3(\"cc\")\n    n6(\"rand\")\n    n0 --> n1\n    n0 --> n6\n    n1 --> n2\n    n1 -.-> n3\n"
+        );
+
+        let graph = Graph::new(&metadata(), true, false).unwrap();
+        assert_eq!(
+            names(&graph),
+            [
+                "app",
+                "core",
+                "serde",
+                "cc",
+                "proptest",
+                "rand 0.7.0",
+                "rand 0.8.0"
+            ]
+        );
+
+        let graph = Graph::new(&metadata(), true, true).unwrap();
+        assert_eq!(names(&graph), ["app", "core"]);
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dependencies {\n    rankdir=LR;\n    n0 [label=\"app\", shape=box];\n    n1 [label=\"core\", shape=box];\n    n0 -> n1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_restrict() {
+        let mut graph = Graph::new(&metadata(), true, false).unwrap();
+        graph.restrict(Some("app"), Some("rand")).unwrap();
+        assert_eq!(
+            names(&graph),
+            ["app", "proptest", "rand 0.7.0", "rand 0.8.0"]
+        );
+
+        let mut graph = Graph::new(&metadata(), false, false).unwrap();
+        graph.restrict(None, Some("serde")).unwrap();
+        assert_eq!(names(&graph), ["app", "core", "serde"]);
+        assert!(graph.restrict(Some("tokio"), None).is_err());
+    }
+}
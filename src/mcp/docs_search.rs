@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+/// The number of results if the request doesn't set a limit
+const DEFAULT_LIMIT: u64 = 10;
+
+pub struct DocsSearch;
+
+impl DocsSearch {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "docs_search".to_string(),
+            description: Some("Full-text search across the documentation of all dependencies, e.g. for `backpressure` or `graceful shutdown`. Returns the best matching crates and symbols with an excerpt. Use `crate_docs` afterwards to read the full documentation of a result.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "The words or phrase (in quotes) to search for"
+                    },
+                    "dependency": {
+                        "type": "string",
+                        "description": "Only search the docs of this dependency"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "The maximum number of results (default 10)"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["query", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let args = request.arguments.as_ref();
+    let query = args
+        .and_then(|args| args.get("query"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Query is required"))?;
+    let dependency = args
+        .and_then(|args| args.get("dependency"))
+        .and_then(|v| v.as_str());
+    let limit = args
+        .and_then(|args| args.get("limit"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_LIMIT) as usize;
+
+    let hits = project
+        .docs
+        .search(query, dependency, limit)
+        .await
+        .map_err(|e| error_response(ErrorCode::DocsFailed, &format!("{e:?}")))?;
+    if hits.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No docs found for `{query}`"),
+        ));
+    }
+
+    let text = hits
+        .into_iter()
+        .map(|hit| {
+            format!(
+                "## {}: {} (score {:.2})\n{}",
+                hit.krate, hit.symbol, hit.score, hit.snippet
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,155 @@
+//! The codes of failed tool calls. The response of a failed call has the message for
+//! humans, followed by a JSON object with a stable code that agents can branch on:
+//!
+//! ```json
+//! {"error": {"code": "still_indexing", "message": "rust-analyzer is still indexing ..."}}
+//! ```
+
+use std::fmt;
+
+use mcp_core::types::{CallToolResponse, ToolResponseContent};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::lsp::{RequestTimeout, StillIndexing};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A required argument is missing or an argument is invalid
+    InvalidArguments,
+    /// The file isn't part of a project that was added
+    ProjectNotFound,
+    /// rust-analyzer is still indexing the project, the call can be retried later
+    StillIndexing,
+    /// rust-analyzer didn't answer in time, the call can be retried or narrowed down
+    LspTimeout,
+    /// A request to rust-analyzer failed
+    LspFailed,
+    /// The symbol isn't on the given line or rust-analyzer doesn't know it
+    SymbolNotFound,
+    /// The call worked but found nothing
+    NotFound,
+    /// A cargo command couldn't be run or failed, e.g. because a subcommand isn't
+    /// installed. Compile errors and failing tests are results, not errors.
+    CargoFailed,
+    /// The docs of a dependency couldn't be generated or read
+    DocsFailed,
+    /// A request to crates.io or another service failed
+    NetworkFailed,
+    /// Reading or writing a file failed
+    IoFailed,
+    /// The server is shutting down and doesn't accept calls
+    ShuttingDown,
+    /// The call was aborted before it finished
+    Aborted,
+    /// The server failed to build the response, e.g. to serialize it
+    Internal,
+}
+
+impl ErrorCode {
+    /// The code of an error, `fallback` unless rust-analyzer timed out or is still
+    /// indexing or the symbol wasn't found
+    pub fn of(error: &anyhow::Error, fallback: ErrorCode) -> ErrorCode {
+        for cause in error.chain() {
+            if cause.is::<StillIndexing>() {
+                return ErrorCode::StillIndexing;
+            }
+            if cause.is::<RequestTimeout>() {
+                return ErrorCode::LspTimeout;
+            }
+            if cause.is::<SymbolNotFound>() {
+                return ErrorCode::SymbolNotFound;
+            }
+        }
+        fallback
+    }
+}
+
+/// The symbol isn't in the document symbols rust-analyzer returned for the file
+#[derive(Debug)]
+pub struct SymbolNotFound(pub String);
+
+impl fmt::Display for SymbolNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for SymbolNotFound {}
+
+/// The response of a failed call, with the message and the code as JSON
+pub fn error_response(code: ErrorCode, message: &str) -> CallToolResponse {
+    let error = json!({
+        "error": {
+            "code": code,
+            "message": message,
+        }
+    });
+    CallToolResponse {
+        content: vec![
+            ToolResponseContent::Text {
+                text: message.to_string(),
+            },
+            ToolResponseContent::Text {
+                text: error.to_string(),
+            },
+        ],
+        is_error: Some(true),
+        meta: None,
+    }
+}
+
+/// The response of a failed rust-analyzer request
+pub fn lsp_error_response(error: &anyhow::Error) -> CallToolResponse {
+    error_response(
+        ErrorCode::of(error, ErrorCode::LspFailed),
+        &error.to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        let timeout = anyhow::Error::from(RequestTimeout {
+            method: "textDocument/hover",
+            timeout: Duration::from_secs(30),
+        })
+        .context("Failed to get hover information");
+        assert_eq!(
+            ErrorCode::of(&timeout, ErrorCode::LspFailed),
+            ErrorCode::LspTimeout
+        );
+        let indexing = anyhow::Error::from(StillIndexing {
+            percentage: Some(40),
+        });
+        assert_eq!(
+            ErrorCode::of(&indexing, ErrorCode::LspFailed),
+            ErrorCode::StillIndexing
+        );
+        let not_found = anyhow::Error::from(SymbolNotFound("Symbol Foo not found".to_string()));
+        assert_eq!(
+            ErrorCode::of(&not_found, ErrorCode::LspFailed),
+            ErrorCode::SymbolNotFound
+        );
+        let other = anyhow::anyhow!("Connection closed");
+        assert_eq!(
+            ErrorCode::of(&other, ErrorCode::LspFailed),
+            ErrorCode::LspFailed
+        );
+
+        let response = error_response(ErrorCode::ProjectNotFound, "No project found");
+        let ToolResponseContent::Text { text } = &response.content[1] else {
+            panic!("Expected text");
+        };
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(text).unwrap(),
+            json!({"error": {"code": "project_not_found", "message": "No project found"}})
+        );
+    }
+}
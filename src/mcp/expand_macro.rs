@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use lsp_types::{Position, Range};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_identifier_position,
+        get_info_from_request, lsp_error_response,
+    },
+};
+
+pub struct ExpandMacro;
+
+impl ExpandMacro {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "expand_macro".to_string(),
+            description: Some("Expand a macro call or derive, e.g. to see the code generated by `#[derive(Serialize)]` or `thiserror::Error`. Expansions are cached until the file changes.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The line number of the macro call or derive attribute in the file (1 based)"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the macro, e.g. `vec` or `Serialize`"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file containing the macro"
+                    }
+                },
+                "required": ["line", "symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()? as u32;
+    let symbol = request.get_symbol()?;
+
+    let range = Range::new(Position::new(line, 0), Position::new(line, 0));
+    let Some(position) = find_identifier_position(
+        project.project.root().join(relative_file),
+        range,
+        &symbol,
+        project.lsp.position_encoding(),
+    )
+    .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?
+    else {
+        return Err(error_response(
+            ErrorCode::SymbolNotFound,
+            &format!("Macro {symbol} not found in line {line} of {relative_file}"),
+        ));
+    };
+
+    let Some(expansion) = project
+        .lsp
+        .expand_macro(relative_file, position)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!("{symbol} is not a macro"),
+        ));
+    };
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!(
+                "Expansion of `{}`:\n```rust\n{}\n```",
+                expansion.name, expansion.expansion
+            ),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
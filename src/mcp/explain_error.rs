@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct ExplainError;
+
+impl ExplainError {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "explain_error".to_string(),
+            description: Some("Get the long form explanation of a rustc error code (e.g. `E0382`) with examples of the error and how to fix it, as returned by `rustc --explain`.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "code": {
+                        "type": "string",
+                        "description": "The error code, e.g. `E0382`"
+                    }
+                },
+                "required": ["file", "code"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let code = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("code"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Code is required"))?;
+    let Some(code) = normalize_error_code(code) else {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!("`{code}` is not an error code, expected something like `E0382`"),
+        ));
+    };
+
+    let explanation = project
+        .cargo_remote
+        .explain(&code)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: explanation }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Accepts `E0382`, `e0382`, `0382` and `error[E0382]`
+fn normalize_error_code(code: &str) -> Option<String> {
+    let code = code
+        .trim()
+        .trim_start_matches("error[")
+        .trim_end_matches(']');
+    let digits = code
+        .strip_prefix('E')
+        .or_else(|| code.strip_prefix('e'))
+        .unwrap_or(code);
+    (digits.len() == 4 && digits.chars().all(|c| c.is_ascii_digit())).then(|| format!("E{digits}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_error_code() {
+        assert_eq!(normalize_error_code("E0382").as_deref(), Some("E0382"));
+        assert_eq!(normalize_error_code("e0502").as_deref(), Some("E0502"));
+        assert_eq!(normalize_error_code("0499").as_deref(), Some("E0499"));
+        assert_eq!(
+            normalize_error_code("error[E0308]").as_deref(),
+            Some("E0308")
+        );
+        assert_eq!(normalize_error_code("E038"), None);
+        assert_eq!(normalize_error_code("rm -rf"), None);
+    }
+}
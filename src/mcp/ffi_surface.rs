@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+use syn::{
+    Attribute, ForeignItem, ImplItemFn, ItemFn, ItemForeignMod, ItemStatic,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, rust_files},
+};
+
+pub struct FfiSurface;
+
+impl FfiSurface {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "ffi_surface".to_string(),
+            description: Some("List the FFI surface of the project: all `extern \"C\"` functions, `#[no_mangle]` items and `extern` blocks with their declarations, grouped by file. Useful to keep C headers in sync with the Rust code.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let root = project.project.root().clone();
+    let mut contents = String::new();
+    for file in rust_files(&root) {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let Ok(syntax) = syn::parse_file(&content) else {
+            tracing::debug!("Failed to parse {:?}", file);
+            continue;
+        };
+        let mut visitor = FfiVisitor::default();
+        visitor.visit_file(&syntax);
+        if visitor.items.is_empty() {
+            continue;
+        }
+        let lines: Vec<&str> = content.lines().collect();
+        let path = file.strip_prefix(&root).unwrap_or(&file).display();
+        contents.push_str(&format!("## {path}\n"));
+        for (line, kind) in visitor.items {
+            let declaration = lines.get(line - 1).map(|l| l.trim()).unwrap_or_default();
+            contents.push_str(&format!("- {line} ({kind}): `{declaration}`\n"));
+        }
+    }
+
+    if contents.is_empty() {
+        return Err(error_response(ErrorCode::NotFound, "No FFI items found"));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Collects FFI items as (1-based line, kind)
+#[derive(Default)]
+struct FfiVisitor {
+    items: Vec<(usize, &'static str)>,
+}
+
+impl<'ast> Visit<'ast> for FfiVisitor {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        if has_no_mangle(&node.attrs) {
+            self.items
+                .push((node.sig.span().start().line, "no_mangle fn"));
+        } else if node.sig.abi.is_some() {
+            self.items.push((node.sig.span().start().line, "extern fn"));
+        }
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        if has_no_mangle(&node.attrs) {
+            self.items
+                .push((node.sig.span().start().line, "no_mangle fn"));
+        } else if node.sig.abi.is_some() {
+            self.items.push((node.sig.span().start().line, "extern fn"));
+        }
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_item_static(&mut self, node: &'ast ItemStatic) {
+        if has_no_mangle(&node.attrs) {
+            self.items
+                .push((node.static_token.span.start().line, "no_mangle static"));
+        }
+        visit::visit_item_static(self, node);
+    }
+
+    fn visit_item_foreign_mod(&mut self, node: &'ast ItemForeignMod) {
+        self.items
+            .push((node.abi.span().start().line, "extern block"));
+        for item in &node.items {
+            match item {
+                ForeignItem::Fn(item) => self
+                    .items
+                    .push((item.sig.span().start().line, "foreign fn")),
+                ForeignItem::Static(item) => self
+                    .items
+                    .push((item.static_token.span.start().line, "foreign static")),
+                ForeignItem::Type(item) => self
+                    .items
+                    .push((item.type_token.span.start().line, "foreign type")),
+                _ => {}
+            }
+        }
+        visit::visit_item_foreign_mod(self, node);
+    }
+}
+
+/// `#[no_mangle]` or the edition 2024 `#[unsafe(no_mangle)]`
+fn has_no_mangle(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        if attr.path().is_ident("no_mangle") {
+            return true;
+        }
+        let mut found = false;
+        if attr.path().is_ident("unsafe") {
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident("no_mangle");
+                Ok(())
+            });
+        }
+        found
+    })
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use lsp_types::{DiagnosticSeverity, NumberOrString};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{get_info_from_request, lsp_error_response},
+};
+
+pub struct FileDiagnostics;
+
+impl FileDiagnostics {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "file_diagnostics".to_string(),
+            description: Some("Get the current errors and warnings rust-analyzer reported for a file. This is much faster than running `cargo_check`. The file is opened in rust-analyzer and checked with its current content, the `cargo check` diagnostics only appear once rust-analyzer ran it after a save.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file to get the diagnostics for"
+                    },
+                    "only_errors": {
+                        "type": "boolean",
+                        "description": "If true, only errors will be returned. If false, errors and warnings will be returned."
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let only_errors = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("only_errors"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let diagnostics = project
+        .lsp
+        .diagnostics(relative_file)
+        .await
+        .map_err(|e| lsp_error_response(&e))?;
+    let content =
+        std::fs::read_to_string(project.project.root().join(relative_file)).unwrap_or_default();
+    let source_lines = content.lines().collect::<Vec<_>>();
+    let encoding = project.lsp.position_encoding();
+
+    let lines = diagnostics
+        .into_iter()
+        .filter(|diagnostic| !only_errors || diagnostic.severity == Some(DiagnosticSeverity::ERROR))
+        .map(|diagnostic| {
+            let severity = match diagnostic.severity {
+                Some(DiagnosticSeverity::ERROR) => "error",
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                Some(DiagnosticSeverity::INFORMATION) => "info",
+                Some(DiagnosticSeverity::HINT) => "hint",
+                _ => "unknown",
+            };
+            let code = match diagnostic.code {
+                Some(NumberOrString::String(code)) => format!("[{code}] "),
+                Some(NumberOrString::Number(code)) => format!("[{code}] "),
+                None => String::new(),
+            };
+            let start = diagnostic.range.start;
+            let column = source_lines
+                .get(start.line as usize)
+                .map(|line| encoding.char_index(line, start.character))
+                .unwrap_or(start.character as usize);
+            format!(
+                "- {}:{} {severity}: {code}{}",
+                start.line + 1,
+                column + 1,
+                diagnostic.message
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let text = if lines.is_empty() {
+        format!("No diagnostics for {relative_file}")
+    } else {
+        lines.join("\n")
+    };
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
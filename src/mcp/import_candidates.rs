@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::apply_workspace_edit,
+};
+use anyhow::Result;
+use lsp_types::{CodeAction, CodeActionOrCommand, Position, Range};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_identifier_position,
+        get_info_from_request, lsp_error_response,
+    },
+};
+
+pub struct ImportCandidates;
+
+impl ImportCandidates {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "import_candidates".to_string(),
+            description: Some("Find the `use` statements that would resolve an unresolved name in a file, ranked by rust-analyzer. Use this instead of guessing module paths. Optionally insert one of the imports.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "The unresolved name, e.g. `HashMap`"
+                    },
+                    "line": {
+                        "type": "number",
+                        "description": "The optional line number where the name is used (1 based). Defaults to the first usage in the file."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file using the name"
+                    },
+                    "apply": {
+                        "type": "number",
+                        "description": "The optional index of a candidate (as returned by a previous call) to insert into the file"
+                    }
+                },
+                "required": ["symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let symbol = request.get_symbol()?;
+    let apply = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("apply"))
+        .and_then(|v| v.as_u64());
+    let search_range = match request.get_line() {
+        Ok(line) => Range::new(Position::new(line as u32, 0), Position::new(line as u32, 0)),
+        Err(_) => Range::new(Position::new(0, 0), Position::new(u32::MAX, 0)),
+    };
+
+    let Some(position) = find_identifier_position(
+        project.project.root().join(relative_file),
+        search_range,
+        &symbol,
+        project.lsp.position_encoding(),
+    )
+    .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?
+    else {
+        return Err(error_response(
+            ErrorCode::SymbolNotFound,
+            &format!("`{symbol}` not found in file {relative_file}"),
+        ));
+    };
+    let range = Range::new(
+        position,
+        Position::new(
+            position.line,
+            position.character + project.lsp.position_encoding().columns(&symbol),
+        ),
+    );
+
+    let actions = project
+        .lsp
+        .code_actions(relative_file, range)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+        .unwrap_or_default();
+
+    // rust-analyzer titles its auto import actions "Import `path::to::Item`"
+    let candidates: Vec<(String, CodeAction)> = actions
+        .into_iter()
+        .filter_map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => {
+                let path = action
+                    .title
+                    .strip_prefix("Import `")?
+                    .strip_suffix('`')?
+                    .to_string();
+                Some((path, action))
+            }
+            CodeActionOrCommand::Command(_) => None,
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No import candidates found for `{symbol}`"),
+        ));
+    }
+
+    if let Some(index) = apply {
+        let Some((path, action)) = candidates.get(index as usize) else {
+            return Err(error_response(
+                ErrorCode::InvalidArguments,
+                &format!(
+                    "No import candidate with index {index}. There are {} candidates",
+                    candidates.len()
+                ),
+            ));
+        };
+        let Some(edit) = &action.edit else {
+            return Err(error_response(
+                ErrorCode::LspFailed,
+                &format!("Import of `{path}` has no edits"),
+            ));
+        };
+        apply_workspace_edit(edit, project.lsp.position_encoding())
+            .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+        return Ok(CallToolResponse {
+            content: vec![ToolResponseContent::Text {
+                text: format!("Inserted `use {path};` into {relative_file}"),
+            }],
+            is_error: None,
+            meta: None,
+        });
+    }
+
+    let text = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, (path, _))| format!("{index}: use {path};"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use mcp_core::{tools::ToolHandlerFn, types::CallToolRequest};
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
+
+use super::progress;
+use super::utils::{ErrorCode, error_response};
+
+/// Keeps track of the tool calls that are currently running so that
+/// shutdown can wait for them instead of dropping them mid-response.
+#[derive(Clone, Default)]
+pub struct InFlightCalls {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    draining: AtomicBool,
+    next_id: AtomicU64,
+    calls: Mutex<HashMap<u64, RunningCall>>,
+    finished: Notify,
+}
+
+struct RunningCall {
+    tool: String,
+    file: Option<String>,
+    started: Instant,
+    handle: AbortHandle,
+}
+
+/// A tool call that was still running when the grace period ended
+#[derive(Debug)]
+pub struct AbortedCall {
+    pub tool: String,
+    pub running_for: Duration,
+}
+
+impl InFlightCalls {
+    /// Wrap a tool handler so that its calls are tracked and can be aborted.
+    /// Aborting drops the handler, which kills any cargo child it spawned.
+    pub fn track(&self, handler: ToolHandlerFn) -> ToolHandlerFn {
+        let handler = Arc::new(handler);
+        let inner = self.inner.clone();
+        Box::new(move |request: CallToolRequest| {
+            let handler = handler.clone();
+            let inner = inner.clone();
+            Box::pin(async move {
+                if inner.draining.load(Ordering::Relaxed) {
+                    return error_response(ErrorCode::ShuttingDown, "The server is shutting down");
+                }
+                let tool = request.name.clone();
+                let file = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("file"))
+                    .and_then(|v| v.as_str())
+                    .map(|file| file.to_string());
+                // The progress of the call belongs to the task that runs it
+                let task = tokio::spawn(progress::scope(progress::current(), handler(request)));
+                let id = inner.next_id.fetch_add(1, Ordering::Relaxed);
+                if let Ok(mut calls) = inner.calls.lock() {
+                    calls.insert(
+                        id,
+                        RunningCall {
+                            tool: tool.clone(),
+                            file,
+                            started: Instant::now(),
+                            handle: task.abort_handle(),
+                        },
+                    );
+                }
+                let result = task.await;
+                if let Ok(mut calls) = inner.calls.lock() {
+                    calls.remove(&id);
+                }
+                inner.finished.notify_waiters();
+                result.unwrap_or_else(|e| {
+                    error_response(
+                        ErrorCode::Aborted,
+                        &format!("The `{tool}` call was aborted: {e}"),
+                    )
+                })
+            })
+        })
+    }
+
+    /// Stop accepting new calls, wait up to `grace` for the running ones
+    /// and abort whatever is left.
+    pub async fn drain(&self, grace: Duration) -> Vec<AbortedCall> {
+        self.inner.draining.store(true, Ordering::Relaxed);
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            // Register before checking, otherwise a call finishing in between is missed
+            let finished = self.inner.finished.notified();
+            if self.running() == 0 {
+                return Vec::new();
+            }
+            if tokio::time::timeout_at(deadline, finished).await.is_err() {
+                break;
+            }
+        }
+
+        let Ok(mut calls) = self.inner.calls.lock() else {
+            return Vec::new();
+        };
+        calls
+            .drain()
+            .map(|(_, call)| {
+                call.handle.abort();
+                AbortedCall {
+                    tool: call.tool,
+                    running_for: call.started.elapsed(),
+                }
+            })
+            .collect()
+    }
+
+    /// A description of every running call. Doesn't block, so that it can be
+    /// used from a panic hook.
+    pub fn describe(&self) -> Vec<String> {
+        let Ok(calls) = self.inner.calls.try_lock() else {
+            return vec!["(the list of running calls is locked)".to_string()];
+        };
+        calls
+            .values()
+            .map(|call| {
+                format!(
+                    "{} ({}) running for {:.1}s",
+                    call.tool,
+                    call.file.as_deref().unwrap_or("no file"),
+                    call.started.elapsed().as_secs_f64()
+                )
+            })
+            .collect()
+    }
+
+    fn running(&self) -> usize {
+        self.inner
+            .calls
+            .lock()
+            .map(|calls| calls.len())
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use lsp_types::{InlayHintKind, InlayHintLabel};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, full_line_range, get_info_from_request,
+        lsp_error_response,
+    },
+};
+
+pub struct InlayHints;
+
+impl InlayHints {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "inlay_hints".to_string(),
+            description: Some("Get the inferred types of bindings, closure return types and parameter names (rust-analyzer inlay hints) for a range of lines in a file. Each hint is annotated with its line and column (1 based).".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The first line of the range (1 based)"
+                    },
+                    "end_line": {
+                        "type": "number",
+                        "description": "The optional last line of the range. Defaults to `line`."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file"
+                    },
+                    "only_types": {
+                        "type": "boolean",
+                        "description": "If true, only type hints will be returned. Default is false."
+                    }
+                },
+                "required": ["line", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let end_line = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("end_line"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(line);
+    let only_types = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("only_types"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let absolute_file = project.project.root().join(relative_file);
+    let range = full_line_range(
+        &absolute_file,
+        line as u32,
+        end_line as u32,
+        project.lsp.position_encoding(),
+    )
+    .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    let content = std::fs::read_to_string(&absolute_file)
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+    let source_lines: Vec<&str> = content.lines().collect();
+
+    let Some(hints) = project
+        .lsp
+        .inlay_hints(relative_file, range)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(ErrorCode::NotFound, "No inlay hints found"));
+    };
+
+    let mut contents = String::new();
+    for hint in hints {
+        if only_types && hint.kind != Some(InlayHintKind::TYPE) {
+            continue;
+        }
+        let kind = match hint.kind {
+            Some(InlayHintKind::TYPE) => "type",
+            Some(InlayHintKind::PARAMETER) => "parameter",
+            _ => "other",
+        };
+        let label = match hint.label {
+            InlayHintLabel::String(label) => label,
+            InlayHintLabel::LabelParts(parts) => parts
+                .into_iter()
+                .map(|part| part.value)
+                .collect::<Vec<_>>()
+                .join(""),
+        };
+        let source_line = source_lines
+            .get(hint.position.line as usize)
+            .copied()
+            .unwrap_or_default();
+        let column = project
+            .lsp
+            .position_encoding()
+            .char_index(source_line, hint.position.character);
+        let source_line = source_line.trim();
+        contents.push_str(&format!(
+            "- {}:{} {kind} `{}` in `{}`\n",
+            hint.position.line + 1,
+            column + 1,
+            label.trim(),
+            source_line
+        ));
+    }
+
+    if contents.is_empty() {
+        return Err(error_response(ErrorCode::NotFound, "No inlay hints found"));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, RequestExtension, error_response, get_info_from_request},
+};
+
+pub struct ItemPaths;
+
+impl ItemPaths {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "item_paths".to_string(),
+            description: Some("List all public paths under which an item of a cargo dependency is reachable (re-exports, preludes), shortest first. Use this to pick the idiomatic import, e.g. `tokio::sync::Mutex` instead of a deep internal path.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "dependency": {
+                        "type": "string",
+                        "description": "The name of the cargo dependency that defines the item"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the item, e.g. `Mutex`"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["dependency", "symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let dependency = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("dependency"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Dependency is required"))
+        .map(|s| s.to_string())?;
+    let symbol = request.get_symbol()?;
+
+    let paths = project
+        .docs
+        .item_paths(&dependency, &symbol)
+        .await
+        .map_err(|e| error_response(ErrorCode::DocsFailed, &format!("{e:?}")))?;
+
+    if paths.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No public path found for `{symbol}` in {dependency}"),
+        ));
+    }
+
+    let text = paths
+        .into_iter()
+        .map(|path| {
+            if path.contains("::prelude::") {
+                format!("- {path} (prelude)")
+            } else {
+                format!("- {path}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
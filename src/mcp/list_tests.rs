@@ -0,0 +1,158 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct ListTests;
+
+impl ListTests {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "list_tests".to_string(),
+            description: Some("List the tests of every workspace member, grouped by crate and module. Returns the response in JSON format. The names can be passed to `cargo_test` to run a single test.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Only list the tests of this workspace member"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let package = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("package"))
+        .and_then(|v| v.as_str());
+
+    let metadata = project
+        .cargo_remote
+        .metadata(true)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let mut crates = BTreeMap::new();
+    for member in metadata
+        .workspace_packages()
+        .filter(|member| package.is_none_or(|package| package == member.name))
+    {
+        let tests = project
+            .cargo_remote
+            .list_tests(&member.name)
+            .await
+            .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+        crates.insert(member.name.clone(), group_by_module(tests));
+    }
+    if crates.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No matching workspace member found",
+        ));
+    }
+
+    let text = serde_json::to_string_pretty(&crates)
+        .map_err(|e| error_response(ErrorCode::Internal, &format!("{e:?}")))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Group `module::tests::name` by `module::tests`. Doc tests are grouped by their file
+fn group_by_module(tests: Vec<String>) -> BTreeMap<String, Vec<String>> {
+    let mut modules: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for test in tests {
+        let (module, name) = match test.split_once(" - ") {
+            Some((file, name)) => (file.to_string(), name.to_string()),
+            None => match test.rsplit_once("::") {
+                Some((module, name)) => (module.to_string(), name.to_string()),
+                None => (String::new(), test),
+            },
+        };
+        modules.entry(module).or_default().push(name);
+    }
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_by_module() {
+        let modules = group_by_module(vec![
+            "lsp::utils::tests::test_apply_text_edits".to_string(),
+            "lsp::utils::tests::test_position_to_offset".to_string(),
+            "top_level".to_string(),
+            "src/lib.rs - Docs::new (line 12)".to_string(),
+        ]);
+        assert_eq!(
+            modules["lsp::utils::tests"],
+            vec!["test_apply_text_edits", "test_position_to_offset"]
+        );
+        assert_eq!(modules[""], vec!["top_level"]);
+        assert_eq!(modules["src/lib.rs"], vec!["Docs::new (line 12)"]);
+    }
+}
@@ -0,0 +1,364 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use ignore::WalkBuilder;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use regex::Regex;
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+/// Maximum number of candidates returned to the agent
+const MAX_CANDIDATES: usize = 10;
+
+pub struct LogSource;
+
+impl LogSource {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "log_source".to_string(),
+            description: Some("Find the statements in the project that could have emitted a log line (e.g. from `tracing`, `log` or `println!`). Matches the format strings of logging macros and the module path (target) of the log line and returns the candidate locations including the enclosing function.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "log_line": {
+                        "type": "string",
+                        "description": "The log line to find the source for"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["log_line", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let log_line = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("log_line"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Log line is required"))
+        .map(|s| s.to_string())?;
+
+    let root = project.project.root().clone();
+    let candidates = tokio::task::spawn_blocking(move || find_log_sources(&root, &log_line))
+        .await
+        .map_err(|e| error_response(ErrorCode::Aborted, &e.to_string()))?
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+
+    if candidates.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No matching log statements found",
+        ));
+    }
+
+    let mut contents = String::new();
+    for candidate in candidates {
+        contents.push_str(&format!(
+            "## {}:{} (in `fn {}`)\n```rust\n{}\n```\n",
+            candidate.path,
+            candidate.line + 1,
+            candidate.function.as_deref().unwrap_or("?"),
+            candidate.statement
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+struct LogCandidate {
+    path: String,
+    /// 0-based line of the logging statement
+    line: usize,
+    function: Option<String>,
+    statement: String,
+    score: usize,
+}
+
+fn find_log_sources(root: &Path, log_line: &str) -> Result<Vec<LogCandidate>> {
+    let macro_regex = Regex::new(
+        r"\b(?:(?:tracing|log)::)?(?:trace|debug|info|warn|error|println|eprintln|print|eprint|panic)!\s*\(",
+    )?;
+    let fn_regex = Regex::new(r"\bfn\s+([A-Za-z_][A-Za-z0-9_]*)")?;
+    let target_regex = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*(?:::[A-Za-z_][A-Za-z0-9_]*)+")?;
+    let targets: Vec<&str> = target_regex
+        .find_iter(log_line)
+        .map(|m| m.as_str())
+        .collect();
+
+    let mut candidates = Vec::new();
+    let walker = WalkBuilder::new(root).build();
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let relative_path = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string();
+        let module_path = module_path(&relative_path);
+
+        for call in macro_regex.find_iter(&content) {
+            let Some(format_string) = format_string(&content[call.end()..]) else {
+                continue;
+            };
+            let Some(mut score) = match_format_string(format_string, log_line) else {
+                continue;
+            };
+            if !module_path.is_empty()
+                && targets
+                    .iter()
+                    .any(|target| target.ends_with(module_path.as_str()))
+            {
+                score += module_path.len();
+            }
+            let start = call.start();
+            let line = content[..start].matches('\n').count();
+            let function = fn_regex
+                .captures_iter(&content[..start])
+                .last()
+                .map(|c| c[1].to_string());
+            let statement = content[start..]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            candidates.push(LogCandidate {
+                path: relative_path.clone(),
+                line,
+                function,
+                statement,
+                score,
+            });
+        }
+    }
+
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.score));
+    candidates.truncate(MAX_CANDIDATES);
+    Ok(candidates)
+}
+
+/// The format string of a logging macro call, from the source after its opening
+/// parenthesis: the first argument that is nothing but a string literal. The fields
+/// `tracing` takes before the message (`target: "app"`, `user = "bob"`) aren't.
+fn format_string(arguments: &str) -> Option<&str> {
+    let bytes = arguments.as_bytes();
+    let mut depth = 0usize;
+    let mut argument_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                let literal = arguments.get(start..i)?;
+                if depth == 0
+                    && arguments[argument_start..start - 1].trim().is_empty()
+                    && arguments.get(i + 1..)?.trim_start().starts_with([',', ')'])
+                {
+                    return Some(literal);
+                }
+            }
+            // Char literals like `'"'`, lifetimes have no closing quote
+            b'\'' => {
+                let rest = &arguments[i + 1..];
+                if rest.starts_with('\\') {
+                    i += 2;
+                    while i + 1 < bytes.len() && bytes[i + 1] != b'\'' {
+                        i += 1;
+                    }
+                    i += 1;
+                } else if let Some(c) = rest.chars().next()
+                    && rest[c.len_utf8()..].starts_with('\'')
+                {
+                    i += c.len_utf8() + 1;
+                }
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                if depth == 0 {
+                    return None;
+                }
+                depth -= 1;
+            }
+            b',' if depth == 0 => argument_start = i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Converts a relative file path into the module path it defines (`src/lsp/utils.rs` -> `lsp::utils`)
+fn module_path(relative_path: &str) -> String {
+    let path = relative_path
+        .strip_suffix(".rs")
+        .unwrap_or(relative_path)
+        .trim_end_matches("/mod");
+    let path = path.split_once("src/").map(|(_, p)| p).unwrap_or(path);
+    if matches!(path, "main" | "lib") {
+        return String::new();
+    }
+    path.replace('/', "::")
+}
+
+/// Matches a format string against a log line. Placeholders (`{}`, `{name:?}`) match
+/// anything. Returns the number of literal characters that matched, or `None`
+fn match_format_string(format_string: &str, log_line: &str) -> Option<usize> {
+    let mut literals = Vec::new();
+    let mut current = String::new();
+    let mut chars = format_string.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                current.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                current.push('}');
+            }
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+                literals.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    literals.push(current);
+
+    let literal_length: usize = literals.iter().map(|l| l.trim().len()).sum();
+    // Format strings like "{}" would match everything
+    if literal_length < 3 {
+        return None;
+    }
+
+    let pattern = literals
+        .iter()
+        .map(|l| regex::escape(l))
+        .collect::<Vec<_>>()
+        .join(".*?");
+    let regex = Regex::new(&pattern).ok()?;
+    regex.is_match(log_line).then_some(literal_length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_string() {
+        assert_eq!(
+            format_string(r#""Indexing {}", name);"#),
+            Some("Indexing {}")
+        );
+        assert_eq!(
+            format_string(r#"target: "lsp", "Indexing {}", name);"#),
+            Some("Indexing {}")
+        );
+        assert_eq!(
+            format_string(r#"user = "bob", %id, "Logged in as {}", "admin");"#),
+            Some("Logged in as {}")
+        );
+        assert_eq!(
+            format_string(r#"sep = '"', "Split on \"{}\"", sep);"#),
+            Some(r#"Split on \"{}\""#)
+        );
+        assert_eq!(format_string(r#""{}", "a".repeat(3));"#), Some("{}"));
+        assert_eq!(format_string(r#"error = %e);"#), None);
+        assert_eq!(format_string(r#"message.as_str());"#), None);
+    }
+
+    #[test]
+    fn test_find_log_sources() {
+        let root = std::env::temp_dir().join(format!("log-source-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/lsp")).unwrap();
+        std::fs::write(
+            root.join("src/lsp/client.rs"),
+            r#"fn start(name: &str) {
+    tracing::info!(target: "lsp", "Starting rust-analyzer for {}", name);
+}
+"#,
+        )
+        .unwrap();
+        let candidates =
+            find_log_sources(&root, "INFO lsp: Starting rust-analyzer for my-project").unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].path, "src/lsp/client.rs");
+        assert_eq!(candidates[0].line, 1);
+        assert_eq!(candidates[0].function.as_deref(), Some("start"));
+    }
+}
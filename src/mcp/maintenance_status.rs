@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    maintenance::JobRun,
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{McpNotification, utils::get_info_from_request};
+
+pub struct MaintenanceStatus;
+
+impl MaintenanceStatus {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "maintenance_status".to_string(),
+            description: Some("Get the results of the scheduled background jobs of the project: docs reindex, cache pruning and the last `cargo audit` report, plus the crate the docs indexing is working on.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let config = project.project.maintenance();
+    let status = project.maintenance.lock().await.clone();
+    let jobs = [
+        (
+            "Docs reindex",
+            config.reindex_docs_hours,
+            status.docs_reindex,
+        ),
+        (
+            "Cache pruning",
+            config.prune_cache_hours,
+            status.cache_prune,
+        ),
+        ("Cargo audit", config.audit_hours, status.audit),
+    ];
+
+    let mut text = String::new();
+    if let Some(progress) = project.docs_progress() {
+        text.push_str(&format!("Docs indexing {progress}\n\n"));
+    }
+    for (name, hours, run) in jobs {
+        let schedule = match hours {
+            Some(hours) => format!("every {hours} hours"),
+            None => "not scheduled".to_string(),
+        };
+        text.push_str(&format!("# {name} ({schedule})\n"));
+        match run {
+            Some(JobRun { finished, output }) => text.push_str(&format!(
+                "Last run {}:\n{output}\n\n",
+                finished.format("%Y-%m-%d %H:%M")
+            )),
+            None => text.push_str("Did not run yet\n\n"),
+        }
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
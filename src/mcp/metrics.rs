@@ -0,0 +1,185 @@
+//! Counts the tool calls per project and tool, with their errors and latencies, to
+//! show which tools the agent actually uses. The metrics are kept in memory since the
+//! start of the server.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use mcp_core::{tools::ToolHandlerFn, types::CallToolRequest};
+
+use crate::context::Context;
+
+/// The latencies of the last calls per tool the percentiles are computed from
+const LATENCY_SAMPLES: usize = 1000;
+
+/// The project of the calls, `None` without a file of a known project, and the tool
+type ToolKey = (Option<PathBuf>, String);
+
+#[derive(Default)]
+struct ToolMetrics {
+    calls: u64,
+    errors: u64,
+    latencies: VecDeque<Duration>,
+}
+
+/// The metrics of one tool in one project
+#[derive(Debug, Clone)]
+pub struct ToolStats {
+    /// `None` for calls without a file of a known project
+    pub project: Option<PathBuf>,
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl ToolStats {
+    pub fn error_rate(&self) -> f64 {
+        match self.calls {
+            0 => 0.0,
+            calls => self.errors as f64 / calls as f64,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Metrics {
+    tools: Arc<Mutex<HashMap<ToolKey, ToolMetrics>>>,
+}
+
+impl Metrics {
+    /// Wrap a tool handler so that its calls are counted for the project of their file
+    pub fn track(&self, context: &Context, handler: ToolHandlerFn) -> ToolHandlerFn {
+        let handler = Arc::new(handler);
+        let metrics = self.clone();
+        let context = context.clone();
+        Box::new(move |request: CallToolRequest| {
+            let handler = handler.clone();
+            let metrics = metrics.clone();
+            let context = context.clone();
+            Box::pin(async move {
+                let project = match request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("file"))
+                    .and_then(|file| file.as_str())
+                {
+                    Some(file) => context
+                        .get_project_by_path(Path::new(file))
+                        .await
+                        .map(|project| project.project.root().clone()),
+                    None => None,
+                };
+                let tool = request.name.clone();
+                let started = Instant::now();
+                let response = handler(request).await;
+                metrics.record(
+                    project,
+                    tool,
+                    started.elapsed(),
+                    response.is_error == Some(true),
+                );
+                response
+            })
+        })
+    }
+
+    fn record(&self, project: Option<PathBuf>, tool: String, latency: Duration, is_error: bool) {
+        let Ok(mut tools) = self.tools.lock() else {
+            return;
+        };
+        let metrics = tools.entry((project, tool)).or_default();
+        metrics.calls += 1;
+        if is_error {
+            metrics.errors += 1;
+        }
+        if metrics.latencies.len() == LATENCY_SAMPLES {
+            metrics.latencies.pop_front();
+        }
+        metrics.latencies.push_back(latency);
+    }
+
+    /// The metrics of every tool that was called, the most used first
+    pub fn stats(&self) -> Vec<ToolStats> {
+        let Ok(tools) = self.tools.lock() else {
+            return Vec::new();
+        };
+        let mut stats = tools
+            .iter()
+            .map(|((project, tool), metrics)| {
+                let mut latencies = metrics.latencies.iter().copied().collect::<Vec<_>>();
+                latencies.sort();
+                ToolStats {
+                    project: project.clone(),
+                    tool: tool.clone(),
+                    calls: metrics.calls,
+                    errors: metrics.errors,
+                    p50: percentile(&latencies, 50),
+                    p95: percentile(&latencies, 95),
+                    p99: percentile(&latencies, 99),
+                }
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool.cmp(&b.tool)));
+        stats
+    }
+}
+
+/// The nearest-rank percentile of sorted latencies
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// A latency for humans, e.g. `850ms` or `12.3s`
+pub fn format_latency(latency: Duration) -> String {
+    match latency.as_millis() {
+        millis @ 0..1000 => format!("{millis}ms"),
+        _ => format!("{:.1}s", latency.as_secs_f64()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let metrics = Metrics::default();
+        let project = Some(PathBuf::from("/app"));
+        for millis in 1..=100 {
+            metrics.record(
+                project.clone(),
+                "cargo_check".to_string(),
+                Duration::from_millis(millis),
+                millis % 10 == 0,
+            );
+        }
+        metrics.record(None, "crate_docs".to_string(), Duration::ZERO, false);
+
+        let stats = metrics.stats();
+        assert_eq!(stats.len(), 2);
+        let check = &stats[0];
+        assert_eq!(check.tool, "cargo_check");
+        assert_eq!(check.project, project);
+        assert_eq!((check.calls, check.errors), (100, 10));
+        assert_eq!(check.error_rate(), 0.1);
+        assert_eq!(check.p50, Duration::from_millis(50));
+        assert_eq!(check.p95, Duration::from_millis(95));
+        assert_eq!(check.p99, Duration::from_millis(99));
+        assert_eq!(stats[1].p99, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_format_latency() {
+        assert_eq!(format_latency(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_latency(Duration::from_millis(12_345)), "12.3s");
+    }
+}
@@ -1,23 +1,83 @@
+mod async_diagnostics;
+mod audit;
+mod build_timings;
+mod cargo_add_dependency;
+mod cargo_bench;
 mod cargo_check;
+mod cargo_deny;
+mod cargo_expand;
+mod cargo_metadata;
+mod cargo_nextest;
+mod cargo_remove_dependency;
+mod cargo_run;
 mod cargo_test;
+mod clean_target;
+mod code_actions;
+mod completion;
+mod const_eval;
 mod crate_docs;
+mod crate_examples;
+mod crate_symbols;
+mod crate_versions;
+mod dependency_graph;
+mod docs_search;
+mod errors;
+mod expand_macro;
+mod explain_error;
+mod ffi_surface;
+mod file_diagnostics;
+mod import_candidates;
+mod in_flight;
+mod inlay_hints;
+mod item_paths;
+mod list_tests;
+mod log_source;
+mod maintenance_status;
+mod metrics;
+mod module_tree;
+mod no_std_check;
+mod outdated_dependencies;
+mod progress;
+mod prompts;
+mod replay;
+mod runnables;
+mod server;
+mod server_stats;
+mod sessions;
+mod sse;
+mod ssr;
+mod std_docs;
+mod symbol_definition;
 mod symbol_docs;
 mod symbol_impl;
 mod symbol_references;
 mod symbol_resolve;
+mod target_dir_stats;
+mod trait_implementors;
+mod type_layout;
+mod unsafe_audit;
 mod utils;
+mod view_ir;
+mod wasm_check;
 
 use std::path::PathBuf;
 
+use crate::announce::announce;
 use crate::context::Context;
+use crate::notify;
 use crate::project::TransportType;
-use anyhow::Result;
+use anyhow::{Context as _, Result};
+pub use audit::read_entry as read_audit_entry;
+pub use in_flight::InFlightCalls;
 use mcp_core::{
-    server::Server,
-    transport::{ServerSseTransport, ServerStdioTransport},
-    types::{CallToolRequest, CallToolResponse, ServerCapabilities},
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool},
 };
-use serde_json::json;
+pub use metrics::{Metrics, format_latency};
+pub use replay::replay;
+use server::McpServer;
+pub use sessions::{ClientSession, Sessions, current_session};
+use sse::SseServer;
 
 #[derive(Debug, Clone)]
 pub(super) enum McpNotification {
@@ -29,54 +89,412 @@ pub(super) enum McpNotification {
         content: CallToolResponse,
         project: PathBuf,
     },
+    /// A line cargo printed while running a tool call, sent as it comes in
+    Output { line: String, project: PathBuf },
 }
 
-pub async fn run_server(context: Context) -> Result<()> {
-    let server_protocol = Server::builder("cursor_rust_tools".to_string(), "1.0".to_string())
-        .capabilities(ServerCapabilities {
-            tools: Some(json!({
-                "listChanged": false,
-            })),
-            ..Default::default()
-        })
-        .register_tool(
+/// Every tool the server offers with its handler
+fn tools(context: &Context) -> Vec<(Tool, ToolHandlerFn)> {
+    vec![
+        (
             symbol_docs::SymbolDocs::tool(),
             symbol_docs::SymbolDocs::call(context.clone()),
-        )
-        .register_tool(
+        ),
+        (
             symbol_impl::SymbolImpl::tool(),
             symbol_impl::SymbolImpl::call(context.clone()),
-        )
-        .register_tool(
+        ),
+        (
             symbol_references::SymbolReferences::tool(),
             symbol_references::SymbolReferences::call(context.clone()),
-        )
-        .register_tool(
+        ),
+        (
             symbol_resolve::SymbolResolve::tool(),
             symbol_resolve::SymbolResolve::call(context.clone()),
-        )
-        .register_tool(
+        ),
+        (
             crate_docs::CrateDocs::tool(),
             crate_docs::CrateDocs::call(context.clone()),
-        )
-        .register_tool(
+        ),
+        (
+            crate_examples::CrateExamples::tool(),
+            crate_examples::CrateExamples::call(context.clone()),
+        ),
+        (
             cargo_check::CargoCheck::tool(),
             cargo_check::CargoCheck::call(context.clone()),
-        )
-        .register_tool(
+        ),
+        (
             cargo_test::CargoTest::tool(),
             cargo_test::CargoTest::call(context.clone()),
-        )
-        .build();
+        ),
+        (
+            cargo_run::CargoRun::tool(),
+            cargo_run::CargoRun::call(context.clone()),
+        ),
+        (
+            cargo_bench::CargoBench::tool(),
+            cargo_bench::CargoBench::call(context.clone()),
+        ),
+        (
+            code_actions::CodeActions::tool(),
+            code_actions::CodeActions::call(context.clone()),
+        ),
+        (
+            log_source::LogSource::tool(),
+            log_source::LogSource::call(context.clone()),
+        ),
+        (
+            async_diagnostics::AsyncDiagnostics::tool(),
+            async_diagnostics::AsyncDiagnostics::call(context.clone()),
+        ),
+        (
+            file_diagnostics::FileDiagnostics::tool(),
+            file_diagnostics::FileDiagnostics::call(context.clone()),
+        ),
+        (
+            inlay_hints::InlayHints::tool(),
+            inlay_hints::InlayHints::call(context.clone()),
+        ),
+        (
+            type_layout::TypeLayout::tool(),
+            type_layout::TypeLayout::call(context.clone()),
+        ),
+        (
+            completion::Completion::tool(),
+            completion::Completion::call(context.clone()),
+        ),
+        (
+            const_eval::ConstEval::tool(),
+            const_eval::ConstEval::call(context.clone()),
+        ),
+        (
+            import_candidates::ImportCandidates::tool(),
+            import_candidates::ImportCandidates::call(context.clone()),
+        ),
+        (
+            item_paths::ItemPaths::tool(),
+            item_paths::ItemPaths::call(context.clone()),
+        ),
+        (
+            ssr::StructuralSearchReplace::tool(),
+            ssr::StructuralSearchReplace::call(context.clone()),
+        ),
+        (
+            runnables::Runnables::tool(),
+            runnables::Runnables::call(context.clone()),
+        ),
+        (
+            ffi_surface::FfiSurface::tool(),
+            ffi_surface::FfiSurface::call(context.clone()),
+        ),
+        (
+            view_ir::ViewIr::tool(),
+            view_ir::ViewIr::call(context.clone()),
+        ),
+        (
+            wasm_check::WasmCheck::tool(),
+            wasm_check::WasmCheck::call(context.clone()),
+        ),
+        (
+            no_std_check::NoStdCheck::tool(),
+            no_std_check::NoStdCheck::call(context.clone()),
+        ),
+        (
+            build_timings::BuildTimings::tool(),
+            build_timings::BuildTimings::call(context.clone()),
+        ),
+        (
+            cargo_metadata::CargoMetadata::tool(),
+            cargo_metadata::CargoMetadata::call(context.clone()),
+        ),
+        (
+            dependency_graph::DependencyGraph::tool(),
+            dependency_graph::DependencyGraph::call(context.clone()),
+        ),
+        (
+            cargo_add_dependency::CargoAddDependency::tool(),
+            cargo_add_dependency::CargoAddDependency::call(context.clone()),
+        ),
+        (
+            cargo_remove_dependency::CargoRemoveDependency::tool(),
+            cargo_remove_dependency::CargoRemoveDependency::call(context.clone()),
+        ),
+        (
+            target_dir_stats::TargetDirStats::tool(),
+            target_dir_stats::TargetDirStats::call(context.clone()),
+        ),
+        (
+            clean_target::CleanTarget::tool(),
+            clean_target::CleanTarget::call(context.clone()),
+        ),
+        (
+            expand_macro::ExpandMacro::tool(),
+            expand_macro::ExpandMacro::call(context.clone()),
+        ),
+        (
+            cargo_expand::CargoExpand::tool(),
+            cargo_expand::CargoExpand::call(context.clone()),
+        ),
+        (
+            outdated_dependencies::OutdatedDependencies::tool(),
+            outdated_dependencies::OutdatedDependencies::call(context.clone()),
+        ),
+        (
+            cargo_deny::CargoDeny::tool(),
+            cargo_deny::CargoDeny::call(context.clone()),
+        ),
+        (
+            cargo_nextest::CargoNextest::tool(),
+            cargo_nextest::CargoNextest::call(context.clone()),
+        ),
+        (
+            list_tests::ListTests::tool(),
+            list_tests::ListTests::call(context.clone()),
+        ),
+        (
+            maintenance_status::MaintenanceStatus::tool(),
+            maintenance_status::MaintenanceStatus::call(context.clone()),
+        ),
+        (
+            explain_error::ExplainError::tool(),
+            explain_error::ExplainError::call(context.clone()),
+        ),
+        (
+            symbol_definition::SymbolDefinition::tool(),
+            symbol_definition::SymbolDefinition::call(context.clone()),
+        ),
+        (
+            module_tree::ModuleTree::tool(),
+            module_tree::ModuleTree::call(context.clone()),
+        ),
+        (
+            trait_implementors::TraitImplementors::tool(),
+            trait_implementors::TraitImplementors::call(context.clone()),
+        ),
+        (
+            unsafe_audit::UnsafeAudit::tool(),
+            unsafe_audit::UnsafeAudit::call(context.clone()),
+        ),
+        (
+            crate_versions::CrateVersions::tool(),
+            crate_versions::CrateVersions::call(context.clone()),
+        ),
+        (
+            std_docs::StdDocs::tool(),
+            std_docs::StdDocs::call(context.clone()),
+        ),
+        (
+            docs_search::DocsSearch::tool(),
+            docs_search::DocsSearch::call(context.clone()),
+        ),
+        (
+            crate_symbols::CrateSymbols::tool(),
+            crate_symbols::CrateSymbols::call(context.clone()),
+        ),
+        (
+            server_stats::ServerStats::tool(),
+            server_stats::ServerStats::call(context.clone()),
+        ),
+    ]
+}
 
+/// The server with every tool, wrapped to track its calls
+fn server(context: &Context) -> McpServer {
+    let in_flight = context.in_flight();
+    let sessions = context.sessions();
+    let metrics = context.metrics();
+    McpServer::new(
+        tools(context)
+            .into_iter()
+            .map(|(tool, call)| {
+                let call = audit::track(context, notify::track(context, call));
+                let call = in_flight.track(sessions.track(metrics.track(context, call)));
+                (tool, call)
+            })
+            .collect(),
+    )
+}
+
+pub async fn run_server(context: Context) -> Result<()> {
+    let server = server(&context);
     match context.transport() {
-        TransportType::Stdio => {
-            let transport = ServerStdioTransport::new(server_protocol);
-            Server::start(transport).await
+        TransportType::Stdio => server::serve_stdio(server).await,
+        TransportType::Sse { .. } => {
+            let port = context.port();
+            for address in context.rejected_bind_addresses() {
+                tracing::warn!(
+                    "Not listening on {}, set `allow_remote` to listen on addresses other machines can reach",
+                    address
+                );
+            }
+            let addresses = context.bind_addresses();
+            // Keep the daemon alive while the server runs
+            let _announcement = if context.announce_mdns() {
+                // With multiple addresses, let mDNS announce all interfaces
+                let host = match addresses.as_slice() {
+                    [address] => address.as_str(),
+                    _ => "::",
+                };
+                announce(host, port)
+                    .inspect_err(|e| tracing::error!("Failed to announce via mDNS: {}", e))
+                    .ok()
+            } else {
+                None
+            };
+            let api_key = context.api_key();
+            let sse = SseServer::new(server, api_key.clone(), context.sessions());
+            let mut listeners = Vec::new();
+            for address in addresses {
+                let listener = tokio::net::TcpListener::bind((address.as_str(), port))
+                    .await
+                    .with_context(|| format!("Failed to listen on {address}:{port}"))?;
+                match api_key {
+                    Some(_) => tracing::info!("Listening on {}:{} with API key", address, port),
+                    None => tracing::info!("Listening on {}:{}", address, port),
+                }
+                listeners.push(sse.clone().serve(listener));
+            }
+            futures::future::try_join_all(listeners).await?;
+            Ok(())
         }
-        TransportType::Sse { host, port } => {
-            let transport = ServerSseTransport::new(host.to_string(), *port, server_protocol);
-            Server::start(transport).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    const EXPECTED_TOOLS: &[&str] = &[
+        "async_diagnostics",
+        "build_timings",
+        "cargo_add_dependency",
+        "cargo_bench",
+        "cargo_check",
+        "cargo_deny",
+        "cargo_expand",
+        "cargo_metadata",
+        "cargo_nextest",
+        "cargo_remove_dependency",
+        "cargo_run",
+        "cargo_test",
+        "clean_target",
+        "code_actions",
+        "completion",
+        "const_eval",
+        "crate_docs",
+        "crate_examples",
+        "crate_symbols",
+        "crate_versions",
+        "dependency_graph",
+        "docs_search",
+        "expand_macro",
+        "explain_error",
+        "ffi_surface",
+        "file_diagnostics",
+        "import_candidates",
+        "inlay_hints",
+        "item_paths",
+        "list_tests",
+        "log_source",
+        "maintenance_status",
+        "module_tree",
+        "no_std_check",
+        "outdated_dependencies",
+        "runnables",
+        "server_stats",
+        "std_docs",
+        "structural_search_replace",
+        "symbol_definition",
+        "symbol_docs",
+        "symbol_impl",
+        "symbol_references",
+        "symbol_resolve",
+        "target_dir_stats",
+        "trait_implementors",
+        "type_layout",
+        "unsafe_audit",
+        "view_ir",
+        "wasm_check",
+    ];
+
+    fn expected_tools() -> BTreeSet<String> {
+        EXPECTED_TOOLS.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_tools() {
+        let (notifier, _) = flume::unbounded();
+        let context = Context::new(0, notifier).await;
+        let names = tools(&context)
+            .into_iter()
+            .map(|(tool, _)| tool.name)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(names, expected_tools());
+    }
+
+    /// Lists the tools like a client would, over the SSE transport
+    #[tokio::test]
+    async fn test_list_tools_over_sse() {
+        let (notifier, _) = flume::unbounded();
+        let context = Context::new(0, notifier).await;
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let sse = SseServer::new(server(&context), None, context.sessions());
+        tokio::spawn(sse.serve(listener));
+
+        let client = reqwest::Client::new();
+        let response = client.get(format!("{base}/sse")).send().await.unwrap();
+        let mut events = replay::Events::new(response);
+        let endpoint = loop {
+            let (event, data) = events.next().await.unwrap();
+            if event == "endpoint" {
+                break format!("{base}{data}");
+            }
+        };
+        let post = |message: serde_json::Value| client.post(&endpoint).json(&message).send();
+        post(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }
+        }))
+        .await
+        .unwrap();
+        let initialized = events.message(0).await.unwrap();
+        assert!(initialized["result"]["capabilities"]["tools"].is_object());
+        post(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }))
+            .await
+            .unwrap();
+        let listed = events.message(1).await.unwrap();
+        let names = listed["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(names, expected_tools());
+    }
+
+    #[tokio::test]
+    async fn test_tool_names_are_unique() {
+        let (notifier, _) = flume::unbounded();
+        let context = Context::new(0, notifier).await;
+        let mut names = BTreeSet::new();
+        for (tool, _) in tools(&context) {
+            assert!(
+                names.insert(tool.name.clone()),
+                "Duplicate tool {}",
+                tool.name
+            );
         }
     }
 }
@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+use syn::{Attribute, Expr, Item, Lit, Meta, Visibility};
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct ModuleTree;
+
+impl ModuleTree {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "module_tree".to_string(),
+            description: Some("Get an outline of every crate in the workspace: the tree of modules (following `mod` declarations) with the types, traits and functions defined in each module and the file they live in. Use this as a map of the codebase before making cross-cutting changes.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "modules_only": {
+                        "type": "boolean",
+                        "description": "Only list the modules without their items (default false)"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let modules_only = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("modules_only"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let metadata = project
+        .cargo_remote
+        .metadata(true)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let root = project.project.root();
+    let mut contents = String::new();
+    for package in metadata.workspace_packages() {
+        for target in &package.targets {
+            let kind = target.kind.join(", ");
+            if !["lib", "proc-macro", "bin"]
+                .iter()
+                .any(|k| target.kind.iter().any(|t| t == k))
+            {
+                continue;
+            }
+            let path = PathBuf::from(&target.src_path);
+            contents.push_str(&format!(
+                "# {} ({kind}) {}\n",
+                target.name,
+                path.strip_prefix(root).unwrap_or(&path).display()
+            ));
+            let mut outline = Outline {
+                root,
+                modules_only,
+                lines: Vec::new(),
+            };
+            outline.file(&path, true, 0);
+            for line in outline.lines {
+                contents.push_str(&line);
+                contents.push('\n');
+            }
+            contents.push('\n');
+        }
+    }
+
+    if contents.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No library or binary targets found",
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+struct Outline<'a> {
+    root: &'a Path,
+    modules_only: bool,
+    lines: Vec<String>,
+}
+
+impl Outline<'_> {
+    /// Outline a module file. `owns_directory` is true for crate roots and `mod.rs`
+    /// files, whose submodules live next to them instead of in a directory named
+    /// after the module.
+    fn file(&mut self, path: &Path, owns_directory: bool, depth: usize) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            self.push(depth, "(file not found)".to_string());
+            return;
+        };
+        let Ok(syntax) = syn::parse_file(&content) else {
+            self.push(depth, "(failed to parse)".to_string());
+            return;
+        };
+        let directory = match (owns_directory, path.parent()) {
+            (true, Some(parent)) => parent.to_path_buf(),
+            (false, Some(parent)) => parent.join(path.file_stem().unwrap_or_default()),
+            (_, None) => PathBuf::new(),
+        };
+        let file_directory = path.parent().unwrap_or(Path::new(""));
+        self.items(&syntax.items, &directory, file_directory, depth);
+    }
+
+    /// `directory` holds the submodule files, `file_directory` is where `#[path]`
+    /// attributes are resolved from
+    fn items(&mut self, items: &[Item], directory: &Path, file_directory: &Path, depth: usize) {
+        for item in items {
+            if let Item::Mod(module) = item {
+                let name = module.ident.to_string();
+                let visibility = visibility(&module.vis);
+                if let Some((_, items)) = &module.content {
+                    self.push(depth, format!("{visibility}mod {name}"));
+                    self.items(items, &directory.join(&name), file_directory, depth + 1);
+                    continue;
+                }
+                let Some((file, owns_directory)) =
+                    module_file(directory, file_directory, &name, &module.attrs)
+                else {
+                    self.push(depth, format!("{visibility}mod {name} (file not found)"));
+                    continue;
+                };
+                self.push(
+                    depth,
+                    format!(
+                        "{visibility}mod {name} ({})",
+                        file.strip_prefix(self.root).unwrap_or(&file).display()
+                    ),
+                );
+                self.file(&file, owns_directory, depth + 1);
+                continue;
+            }
+            if self.modules_only {
+                continue;
+            }
+            let entry = match item {
+                Item::Struct(item) => format!("{}struct {}", visibility(&item.vis), item.ident),
+                Item::Enum(item) => format!("{}enum {}", visibility(&item.vis), item.ident),
+                Item::Union(item) => format!("{}union {}", visibility(&item.vis), item.ident),
+                Item::Trait(item) => format!("{}trait {}", visibility(&item.vis), item.ident),
+                Item::Type(item) => format!("{}type {}", visibility(&item.vis), item.ident),
+                Item::Fn(item) => format!("{}fn {}", visibility(&item.vis), item.sig.ident),
+                Item::Macro(item) => match &item.ident {
+                    Some(ident) => format!("macro_rules! {ident}"),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            self.push(depth, entry);
+        }
+    }
+
+    fn push(&mut self, depth: usize, entry: String) {
+        self.lines.push(format!("{}- {entry}", "  ".repeat(depth)));
+    }
+}
+
+fn visibility(vis: &Visibility) -> &'static str {
+    match vis {
+        Visibility::Public(_) => "pub ",
+        Visibility::Restricted(_) => "pub(..) ",
+        Visibility::Inherited => "",
+    }
+}
+
+/// The file of an out of line `mod name;`, honoring `#[path = "..."]`
+fn module_file(
+    directory: &Path,
+    file_directory: &Path,
+    name: &str,
+    attrs: &[Attribute],
+) -> Option<(PathBuf, bool)> {
+    let custom_path = attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(meta) if meta.path.is_ident("path") => match &meta.value {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Str(path) => Some(path.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    });
+    if let Some(custom_path) = custom_path {
+        let file = file_directory.join(custom_path);
+        let owns_directory = file.file_name().is_some_and(|name| name == "mod.rs");
+        return file.exists().then_some((file, owns_directory));
+    }
+    let file = directory.join(format!("{name}.rs"));
+    if file.exists() {
+        return Some((file, false));
+    }
+    let file = directory.join(name).join("mod.rs");
+    file.exists().then_some((file, true))
+}
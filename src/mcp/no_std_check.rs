@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, format_target_check, get_info_from_request},
+};
+
+/// Used if neither the request nor the project configuration name a target
+const DEFAULT_TARGET: &str = "thumbv7em-none-eabihf";
+
+pub struct NoStdCheck;
+
+impl NoStdCheck {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "no_std_check".to_string(),
+            description: Some("Check whether the project builds for a bare metal target without default features. Reports which crates require `std` and through which dependencies they are pulled in.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project to check"
+                    },
+                    "target": {
+                        "type": "string",
+                        "description": format!("The bare metal target to check. Defaults to the `no_std_target` of the project configuration or `{DEFAULT_TARGET}`")
+                    },
+                    "features": {
+                        "type": "string",
+                        "description": "Comma separated list of features to enable"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let argument = |name: &str| {
+        request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+    let target = argument("target")
+        .or_else(|| project.project.no_std_target().map(|t| t.to_string()))
+        .unwrap_or_else(|| DEFAULT_TARGET.to_string());
+    let features = argument("features");
+
+    let mut args = vec!["--no-default-features"];
+    if let Some(features) = &features {
+        args.extend(["--features", features]);
+    }
+
+    let check = project
+        .cargo_remote
+        .check_target(&target, &args)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let mut text = format_target_check(&check, &target);
+    if !check.success && check.errors.is_empty() {
+        text.push_str(&format!(
+            "\nIf the target is missing, install it with `rustup target add {target}`"
+        ));
+    }
+
+    let mut std_crates = Vec::new();
+    for (package, message) in &check.errors {
+        if message.rendered.contains("can't find crate for `std`") && !std_crates.contains(&package)
+        {
+            std_crates.push(package);
+        }
+    }
+    if !std_crates.is_empty() {
+        text.push_str("\n# Crates requiring `std`\n");
+    }
+    args.extend(["--target", &target]);
+    for package in std_crates {
+        if package.is_local {
+            text.push_str(&format!(
+                "\n## {}\nWorkspace crate, add `#![no_std]` or gate the `std` usage behind a feature\n",
+                package.name
+            ));
+            continue;
+        }
+        let tree = project
+            .cargo_remote
+            .inverse_tree(&package.name, &args)
+            .await
+            .unwrap_or_else(|e| format!("Failed to get dependency tree: {e}"));
+        text.push_str(&format!(
+            "\n## {}\nPulled in by:\n```\n{tree}\n```\n",
+            package.name
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use crate::{
+    cargo_remote::locked_versions,
+    context::{Context, ProjectContext},
+    crates_io::{CratesIo, latest},
+};
+use anyhow::Result;
+use futures::{StreamExt, stream};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use semver::VersionReq;
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+/// The number of requests to crates.io in flight. They're still sent one per second,
+/// see [`CratesIo`], this only overlaps the time waiting for the responses.
+const CONCURRENT_REQUESTS: usize = 4;
+
+pub struct OutdatedDependencies;
+
+impl OutdatedDependencies {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "outdated_dependencies".to_string(),
+            description: Some("Compare the locked versions of the direct crates.io dependencies of the workspace with the latest versions on crates.io. Returns a table which distinguishes semver compatible upgrades (`cargo update`) from breaking upgrades (requirement in `Cargo.toml` has to change).".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let metadata = project
+        .cargo_remote
+        .metadata(true)
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+    let locked = locked_versions(&metadata.workspace_root).map_err(|e| {
+        error_response(
+            ErrorCode::IoFailed,
+            &format!("Failed to read Cargo.lock: {e:?}"),
+        )
+    })?;
+
+    let mut dependencies = Vec::new();
+    for dependency in metadata
+        .workspace_packages()
+        .flat_map(|package| &package.dependencies)
+        .filter(|dependency| dependency.is_crates_io())
+    {
+        if !dependencies
+            .iter()
+            .any(|(name, req)| *name == dependency.name && *req == dependency.req)
+        {
+            dependencies.push((dependency.name.clone(), dependency.req.clone()));
+        }
+    }
+
+    let crates_io =
+        CratesIo::new().map_err(|e| error_response(ErrorCode::NetworkFailed, &format!("{e:?}")))?;
+    let results = stream::iter(dependencies)
+        .map(|(name, req)| {
+            let crates_io = crates_io.clone();
+            async move {
+                let versions = crates_io.versions(&name).await;
+                (name, req, versions)
+            }
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut rows = Vec::new();
+    let mut failures = Vec::new();
+    for (name, req, versions) in results {
+        let versions = match versions {
+            Ok(versions) => versions,
+            Err(e) => {
+                failures.push(format!("- {name}: {e}"));
+                continue;
+            }
+        };
+        let Ok(parsed_req) = VersionReq::parse(&req) else {
+            continue;
+        };
+        let Some(current) = locked
+            .get(&name)
+            .and_then(|locked| locked.iter().filter(|v| parsed_req.matches(v)).max())
+        else {
+            continue;
+        };
+        let compatible = latest(&versions, Some(&parsed_req)).filter(|v| *v > current);
+        let newest = latest(&versions, None).filter(|v| !parsed_req.matches(v) && *v > current);
+        if compatible.is_none() && newest.is_none() {
+            continue;
+        }
+        let kind = if newest.is_some() {
+            "breaking"
+        } else {
+            "compatible"
+        };
+        rows.push(format!(
+            "| {name} | {req} | {current} | {} | {} | {kind} |",
+            compatible.map(|v| v.to_string()).unwrap_or_default(),
+            newest.map(|v| v.to_string()).unwrap_or_default()
+        ));
+    }
+    rows.sort();
+
+    let mut text = if rows.is_empty() {
+        "All dependencies are up to date".to_string()
+    } else {
+        format!(
+            "| crate | requirement | locked | latest compatible | latest breaking | upgrade |\n|---|---|---|---|---|---|\n{}",
+            rows.join("\n")
+        )
+    };
+    if !failures.is_empty() {
+        text.push_str(&format!(
+            "\n\nFailed to query crates.io for:\n{}",
+            failures.join("\n")
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
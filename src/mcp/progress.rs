@@ -0,0 +1,118 @@
+//! Progress notifications for long tool calls. A client that sends a `progressToken`
+//! in the `_meta` of a tool call gets `notifications/progress` while the call runs,
+//! with the lines cargo prints as the message.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+/// Steps closer together than this are counted but not sent, the client only needs
+/// to see that the call is alive
+const MIN_INTERVAL: Duration = Duration::from_millis(500);
+
+tokio::task_local! {
+    /// The progress of the tool call the current task handles
+    static CURRENT: Option<Progress>;
+}
+
+#[derive(Clone)]
+pub struct Progress {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    token: Value,
+    /// The messages for the client that made the call
+    outbox: flume::Sender<Value>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    steps: u64,
+    last_sent: Option<Instant>,
+}
+
+impl Progress {
+    /// The progress of a tool call with the `_meta`, if the client asked for it
+    pub fn of_call(meta: Option<&Value>, outbox: &flume::Sender<Value>) -> Option<Self> {
+        let token = meta?.get("progressToken")?;
+        if !token.is_string() && !token.is_number() {
+            return None;
+        }
+        Some(Self {
+            inner: Arc::new(Inner {
+                token: token.clone(),
+                outbox: outbox.clone(),
+                state: Mutex::default(),
+            }),
+        })
+    }
+
+    /// The call made progress, e.g. cargo printed a line. The total isn't known, so
+    /// the progress counts the steps.
+    pub fn step(&self, message: &str) {
+        let Ok(mut state) = self.inner.state.lock() else {
+            return;
+        };
+        state.steps += 1;
+        if state
+            .last_sent
+            .is_some_and(|sent| sent.elapsed() < MIN_INTERVAL)
+        {
+            return;
+        }
+        state.last_sent = Some(Instant::now());
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {
+                "progressToken": self.inner.token,
+                "progress": state.steps,
+                "message": message,
+            },
+        });
+        // The client may be gone, the call finishes anyway
+        let _ = self.inner.outbox.send(notification);
+    }
+}
+
+/// The progress of the tool call the current task handles, `None` outside of tool
+/// calls and if the client didn't ask for progress
+pub fn current() -> Option<Progress> {
+    CURRENT.try_with(|progress| progress.clone()).ok().flatten()
+}
+
+/// Run the future with the progress as [`current`], e.g. a task that a tool call
+/// spawns
+pub(super) async fn scope<F: Future>(progress: Option<Progress>, future: F) -> F::Output {
+    CURRENT.scope(progress, future).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        let (outbox, messages) = flume::unbounded();
+        assert!(Progress::of_call(None, &outbox).is_none());
+        assert!(Progress::of_call(Some(&json!({ "other": 1 })), &outbox).is_none());
+        let progress =
+            Progress::of_call(Some(&json!({ "progressToken": "abc" })), &outbox).expect("progress");
+        progress.step("Compiling foo");
+        // Too close to the first one
+        progress.step("Compiling bar");
+        let sent = messages.drain().collect::<Vec<_>>();
+        assert_eq!(
+            sent,
+            vec![json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": { "progressToken": "abc", "progress": 1, "message": "Compiling foo" },
+            })]
+        );
+        assert_eq!(progress.inner.state.lock().unwrap().steps, 2);
+    }
+}
@@ -0,0 +1,302 @@
+//! Prompt templates for common Rust workflows. A prompt combines instructions with
+//! the results of the tools that gather the context for them, so the agent starts
+//! with the docs, references or diagnostics it would otherwise request first.
+//!
+//! The templates are offered with `prompts/list` and `prompts/get`.
+
+use std::collections::HashMap;
+
+use mcp_core::types::{CallToolRequest, ToolResponseContent};
+use serde_json::{Value, json};
+
+use super::server::McpServer;
+
+struct PromptTemplate {
+    name: &'static str,
+    description: &'static str,
+    /// The arguments besides `file`, all of them are required
+    arguments: &'static [&'static str],
+    /// The instructions, `{argument}` is replaced with the value of the argument
+    instructions: &'static str,
+    /// The tools whose results are added to the prompt, with the arguments passed to
+    /// them. String arguments are filled like the instructions.
+    tools: &'static [(&'static str, &'static [(&'static str, &'static str)])],
+}
+
+const TEMPLATES: &[PromptTemplate] = &[
+    PromptTemplate {
+        name: "review_unsafe",
+        description: "Review the unsafe code of a function",
+        arguments: &["symbol", "line"],
+        instructions: "Review the unsafe usage of `{symbol}` in `{file}`. For every `unsafe` block and call, name the invariants it relies on, check whether the surrounding code upholds them and whether a `// SAFETY:` comment documents them. Suggest safe alternatives where they exist.",
+        tools: &[
+            (
+                "symbol_definition",
+                &[
+                    ("file", "{file}"),
+                    ("symbol", "{symbol}"),
+                    ("line", "{line}"),
+                ],
+            ),
+            ("file_diagnostics", &[("file", "{file}")]),
+        ],
+    },
+    PromptTemplate {
+        name: "upgrade_dependency",
+        description: "Upgrade a dependency to its latest version and fix the breaking changes",
+        arguments: &["dependency"],
+        instructions: "Upgrade the dependency `{dependency}` in `{file}` to its latest version. Update the version requirement, run `cargo_check` and fix the errors caused by breaking changes, using the documentation of the new version. Finish by running `cargo_test`.",
+        tools: &[
+            (
+                "crate_versions",
+                &[("file", "{file}"), ("crate_name", "{dependency}")],
+            ),
+            (
+                "crate_docs",
+                &[("file", "{file}"), ("dependency", "{dependency}")],
+            ),
+        ],
+    },
+    PromptTemplate {
+        name: "fix_diagnostics",
+        description: "Fix the errors and warnings of a file",
+        arguments: &[],
+        instructions: "Fix the errors and warnings rust-analyzer reports for `{file}`. Prefer the quick fixes of `code_actions` where they apply and keep the behavior of the code unchanged.",
+        tools: &[("file_diagnostics", &[("file", "{file}")])],
+    },
+    PromptTemplate {
+        name: "explain_symbol",
+        description: "Explain what a symbol does and how it is used",
+        arguments: &["symbol", "line"],
+        instructions: "Explain what `{symbol}` in `{file}` does, which invariants its callers rely on and how it is used across the project.",
+        tools: &[
+            (
+                "symbol_definition",
+                &[
+                    ("file", "{file}"),
+                    ("symbol", "{symbol}"),
+                    ("line", "{line}"),
+                ],
+            ),
+            (
+                "symbol_references",
+                &[
+                    ("file", "{file}"),
+                    ("symbol", "{symbol}"),
+                    ("line", "{line}"),
+                    ("max_results", "20"),
+                ],
+            ),
+        ],
+    },
+];
+
+/// The result of `prompts/list`
+pub fn list() -> Value {
+    let prompts = TEMPLATES
+        .iter()
+        .map(|template| {
+            let arguments = std::iter::once("file")
+                .chain(template.arguments.iter().copied())
+                .map(|argument| {
+                    json!({
+                        "name": argument,
+                        "description": argument_description(argument),
+                        "required": true,
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "name": template.name,
+                "description": template.description,
+                "arguments": arguments,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "prompts": prompts })
+}
+
+/// The result of `prompts/get`: the instructions of the prompt followed by the
+/// results of its tools. The tools are called like any other call of the client.
+pub async fn get(
+    server: &McpServer,
+    params: &Value,
+    outbox: &flume::Sender<Value>,
+) -> Result<Value, String> {
+    let Some(name) = params.get("name").and_then(Value::as_str) else {
+        return Err("The name of the prompt is required".to_string());
+    };
+    let Some(template) = TEMPLATES.iter().find(|t| t.name == name) else {
+        return Err(format!(
+            "Unknown prompt `{name}`, expected one of {}",
+            TEMPLATES
+                .iter()
+                .map(|t| t.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    };
+    let arguments = params
+        .get("arguments")
+        .and_then(Value::as_object)
+        .map(|args| {
+            args.iter()
+                .filter_map(|(name, value)| {
+                    let value = match value {
+                        Value::String(s) => s.clone(),
+                        Value::Number(n) => n.to_string(),
+                        _ => return None,
+                    };
+                    Some((name.clone(), value))
+                })
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+    if let Some(missing) = std::iter::once(&"file")
+        .chain(template.arguments)
+        .find(|argument| !arguments.contains_key(**argument))
+    {
+        return Err(format!(
+            "The prompt `{}` requires the argument `{missing}`",
+            template.name
+        ));
+    }
+
+    let mut text = fill(template.instructions, &arguments);
+    for (tool_name, tool_arguments) in template.tools {
+        let tool_arguments = tool_arguments
+            .iter()
+            .map(|(name, value)| {
+                let value = fill(value, &arguments);
+                // Numbers are passed as numbers, the tools don't parse strings
+                let value = value
+                    .parse::<u64>()
+                    .map(Value::from)
+                    .unwrap_or(Value::String(value));
+                (name.to_string(), value)
+            })
+            .collect();
+        let request = CallToolRequest {
+            name: tool_name.to_string(),
+            arguments: Some(tool_arguments),
+            // The calls belong to the session of the prompt and report its progress
+            meta: params.get("_meta").cloned(),
+        };
+        let Some(response) = server.call(request, outbox).await else {
+            continue;
+        };
+        let status = if response.is_error == Some(true) {
+            " (failed)"
+        } else {
+            ""
+        };
+        text.push_str(&format!("\n\n## `{tool_name}`{status}\n"));
+        for content in response.content {
+            if let ToolResponseContent::Text { text: content } = content {
+                text.push_str(&content);
+            }
+        }
+    }
+
+    Ok(json!({
+        "description": template.description,
+        "messages": [{
+            "role": "user",
+            "content": {
+                "type": "text",
+                "text": text,
+            },
+        }],
+    }))
+}
+
+fn argument_description(argument: &str) -> &'static str {
+    match argument {
+        "file" => {
+            "The absolute path to the file the prompt is about, or the `Cargo.toml` of the project for dependencies"
+        }
+        "symbol" => "The symbol the prompt is about",
+        "line" => "The line number of the symbol in the file (1 based)",
+        "dependency" => "The name of the dependency",
+        _ => "",
+    }
+}
+
+/// Replace every `{argument}` in the template with its value
+fn fill(template: &str, arguments: &HashMap<String, String>) -> String {
+    arguments
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Context;
+
+    #[test]
+    fn test_fill() {
+        let arguments = HashMap::from([
+            ("symbol".to_string(), "Foo::bar".to_string()),
+            ("line".to_string(), "12".to_string()),
+        ]);
+        assert_eq!(
+            fill("`{symbol}` on line {line} of {file}", &arguments),
+            "`Foo::bar` on line 12 of {file}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_templates_use_known_tools() {
+        let (notifier, _) = flume::unbounded();
+        let context = Context::new(0, notifier).await;
+        let tools = super::super::tools(&context);
+        for template in TEMPLATES {
+            for (name, _) in template.tools {
+                assert!(
+                    tools.iter().any(|(tool, _)| tool.name == *name),
+                    "Unknown tool {name}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get() {
+        let server = McpServer::new(Vec::new());
+        let (outbox, _) = flume::unbounded();
+        let missing = get(
+            &server,
+            &json!({ "name": "explain_symbol", "arguments": { "file": "/a.rs" } }),
+            &outbox,
+        )
+        .await;
+        assert_eq!(
+            missing,
+            Err("The prompt `explain_symbol` requires the argument `symbol`".to_string())
+        );
+        let prompt = get(
+            &server,
+            &json!({
+                "name": "explain_symbol",
+                "arguments": { "file": "/a.rs", "symbol": "Foo", "line": "3" }
+            }),
+            &outbox,
+        )
+        .await
+        .unwrap();
+        assert_eq!(prompt["messages"][0]["role"], "user");
+        assert!(
+            prompt["messages"][0]["content"]["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("Explain what `Foo` in `/a.rs` does")
+        );
+
+        let listed = list();
+        assert_eq!(listed["prompts"].as_array().unwrap().len(), TEMPLATES.len());
+        assert_eq!(listed["prompts"][0]["arguments"][0]["name"], "file");
+    }
+}
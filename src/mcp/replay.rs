@@ -0,0 +1,162 @@
+//! Send a tool call from the audit log to the running server, to check whether a
+//! change in the tools or in the project changed its response. The server is reached
+//! like any other MCP client would: over the SSE endpoint, with the API key.
+
+use anyhow::{Context, Result};
+use mcp_core::types::{CallToolRequest, CallToolResponse};
+use serde_json::{Value, json};
+
+/// Call the tool of the request on the SSE server at `address` (`host:port`)
+pub async fn replay(
+    address: &str,
+    api_key: Option<&str>,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse> {
+    let base = format!("http://{address}");
+    let client = reqwest::Client::new();
+    let authorize = |builder: reqwest::RequestBuilder| match api_key {
+        Some(api_key) => builder.bearer_auth(api_key),
+        None => builder,
+    };
+
+    let response = authorize(client.get(format!("{base}/sse")))
+        .send()
+        .await
+        .with_context(|| format!("Failed to connect to {base}/sse"))?
+        .error_for_status()?;
+    let mut events = Events::new(response);
+    // The first event names the endpoint the messages of the session are posted to
+    let endpoint = loop {
+        let (event, data) = events.next().await?;
+        if event == "endpoint" {
+            break data;
+        }
+    };
+    let endpoint = match endpoint.starts_with("http") {
+        true => endpoint,
+        false => format!("{base}{endpoint}"),
+    };
+    let post = |message: Value| authorize(client.post(&endpoint)).json(&message).send();
+
+    post(json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "replay", "version": env!("CARGO_PKG_VERSION") }
+        }
+    }))
+    .await?
+    .error_for_status()?;
+    events.message(0).await?;
+    post(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+        .await?
+        .error_for_status()?;
+
+    post(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": { "name": request.name, "arguments": request.arguments }
+    }))
+    .await?
+    .error_for_status()?;
+    let message = events.message(1).await?;
+    if let Some(error) = message.get("error") {
+        anyhow::bail!("The server returned an error: {error}");
+    }
+    serde_json::from_value(message["result"].clone()).context("Failed to parse the response")
+}
+
+/// The events of the SSE stream
+pub(super) struct Events {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+}
+
+impl Events {
+    pub(super) fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The next event with its name and data
+    pub(super) async fn next(&mut self) -> Result<(String, String)> {
+        loop {
+            if let Some(event) = take_event(&mut self.buffer) {
+                return Ok(event);
+            }
+            let chunk = self
+                .response
+                .chunk()
+                .await?
+                .context("The server closed the SSE stream")?;
+            self.buffer
+                .extend(chunk.iter().copied().filter(|byte| *byte != b'\r'));
+        }
+    }
+
+    /// The JSON-RPC message with the id, skipping all others
+    pub(super) async fn message(&mut self, id: u64) -> Result<Value> {
+        loop {
+            let (event, data) = self.next().await?;
+            if event != "message" {
+                continue;
+            }
+            if let Ok(message) = serde_json::from_str::<Value>(&data)
+                && message.get("id").and_then(Value::as_u64) == Some(id)
+            {
+                return Ok(message);
+            }
+        }
+    }
+}
+
+/// Remove the first complete event from the buffer. The buffer has no `\r`.
+fn take_event(buffer: &mut Vec<u8>) -> Option<(String, String)> {
+    let end = buffer.windows(2).position(|window| window == b"\n\n")?;
+    let raw = buffer.drain(..end + 2).collect::<Vec<_>>();
+    let mut event = "message".to_string();
+    let mut data = Vec::new();
+    for line in String::from_utf8_lossy(&raw).lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data.push(value.to_string()),
+            _ => {}
+        }
+    }
+    Some((event, data.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_event() {
+        let mut buffer =
+            b"event: endpoint\ndata: /message?sessionId=1\n\n: keep-alive\n\ndata: {\"id\""
+                .to_vec();
+        assert_eq!(
+            take_event(&mut buffer),
+            Some(("endpoint".to_string(), "/message?sessionId=1".to_string()))
+        );
+        assert_eq!(
+            take_event(&mut buffer),
+            Some(("message".to_string(), String::new()))
+        );
+        assert_eq!(take_event(&mut buffer), None);
+        buffer.extend(b":0}\ndata: more\n\n");
+        assert_eq!(
+            take_event(&mut buffer),
+            Some(("message".to_string(), "{\"id\":0}\nmore".to_string()))
+        );
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, lsp_error_response},
+};
+
+pub struct Runnables;
+
+impl Runnables {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "runnables".to_string(),
+            description: Some("List the tests, benches, doctests and binaries that can be run from a file, with the exact cargo command to run each of them.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let runnables = project
+        .lsp
+        .runnables(relative_file)
+        .await
+        .map_err(|e| lsp_error_response(&e))?;
+
+    if runnables.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No runnables found in {relative_file}"),
+        ));
+    }
+
+    let text = runnables
+        .iter()
+        .map(|runnable| {
+            let line = runnable
+                .location
+                .as_ref()
+                .map(|location| format!(" (line {})", location.target_range.start.line + 1))
+                .unwrap_or_default();
+            format!("- {}{line}\n  `{}`", runnable.label, runnable.command())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,322 @@
+//! The MCP server. Both transports pass the JSON-RPC messages of their clients to
+//! [`McpServer::handle`], which answers them in-process: the stdio transport below
+//! and the SSE transport in [`super::sse`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool},
+};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use super::progress::{self, Progress};
+use super::prompts;
+use super::sessions::tag_session;
+
+/// The MCP version the server speaks
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Clone)]
+pub struct McpServer {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, ToolHandlerFn>,
+}
+
+impl McpServer {
+    pub fn new(tools: Vec<(Tool, ToolHandlerFn)>) -> Self {
+        let mut handlers = HashMap::new();
+        let tools = tools
+            .into_iter()
+            .map(|(tool, handler)| {
+                handlers.insert(tool.name.clone(), handler);
+                tool
+            })
+            .collect();
+        Self {
+            inner: Arc::new(Inner { tools, handlers }),
+        }
+    }
+
+    /// Answer a message or a batch of messages from a client. Returns `None` if
+    /// nothing has to be sent back, e.g. for notifications. Notifications for the
+    /// client while the message is handled go to the `outbox`.
+    pub async fn handle(&self, message: Value, outbox: &flume::Sender<Value>) -> Option<Value> {
+        match message {
+            Value::Array(messages) => {
+                let responses = futures::future::join_all(
+                    messages
+                        .into_iter()
+                        .map(|message| self.handle_message(message, outbox)),
+                )
+                .await
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            message => self.handle_message(message, outbox).await,
+        }
+    }
+
+    async fn handle_message(&self, message: Value, outbox: &flume::Sender<Value>) -> Option<Value> {
+        // Responses to requests of the server and notifications aren't answered
+        let id = message.get("id").cloned()?;
+        let method = message.get("method").and_then(Value::as_str)?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {
+                    "tools": {
+                        "listChanged": false,
+                    },
+                    "prompts": {
+                        "listChanged": false,
+                    },
+                },
+                "serverInfo": {
+                    "name": "cursor_rust_tools",
+                    "version": "1.0",
+                },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.inner.tools })),
+            "tools/call" => self.call_tool(params, outbox).await,
+            "prompts/list" => Ok(prompts::list()),
+            "prompts/get" => prompts::get(self, &params, outbox)
+                .await
+                .map_err(|message| (INVALID_PARAMS, message)),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method `{method}`"))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, &message),
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        params: Value,
+        outbox: &flume::Sender<Value>,
+    ) -> Result<Value, (i64, String)> {
+        let request = serde_json::from_value::<CallToolRequest>(params)
+            .map_err(|e| (INVALID_PARAMS, format!("Invalid tool call: {e}")))?;
+        let name = request.name.clone();
+        let Some(response) = self.call(request, outbox).await else {
+            return Err((INVALID_PARAMS, format!("Unknown tool `{name}`")));
+        };
+        serde_json::to_value(response).map_err(|e| (INTERNAL_ERROR, e.to_string()))
+    }
+
+    /// Call a tool, `None` if there is no tool with the name. If the call has a
+    /// `progressToken`, its progress is sent to the `outbox`.
+    pub async fn call(
+        &self,
+        request: CallToolRequest,
+        outbox: &flume::Sender<Value>,
+    ) -> Option<CallToolResponse> {
+        let handler = self.inner.handlers.get(&request.name)?;
+        let progress = Progress::of_call(request.meta.as_ref(), outbox);
+        Some(progress::scope(progress, handler(request)).await)
+    }
+}
+
+/// A JSON-RPC error response
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message,
+        },
+    })
+}
+
+/// Serve one client over stdin and stdout, one message per line
+pub async fn serve_stdio(server: McpServer) -> Result<()> {
+    let (outbox, messages) = flume::unbounded::<Value>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Ok(message) = messages.recv_async().await {
+            let line = format!("{message}\n");
+            if stdout.write_all(line.as_bytes()).await.is_err() || stdout.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut message = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = outbox.send(error(Value::Null, PARSE_ERROR, &e.to_string()));
+                continue;
+            }
+        };
+        // There are no sessions on stdio, a session the client names isn't ours
+        tag_session(&mut message, None);
+        // Tool calls take a while, the client may send more messages in the meantime
+        let server = server.clone();
+        let outbox = outbox.clone();
+        tokio::spawn(async move {
+            if let Some(response) = server.handle(message, &outbox).await {
+                let _ = outbox.send(response);
+            }
+        });
+    }
+    drop(outbox);
+    // The client closed stdin, it doesn't wait for the running calls anymore
+    writer.abort();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mcp_core::types::ToolResponseContent;
+
+    use super::*;
+
+    fn echo() -> (Tool, ToolHandlerFn) {
+        let tool = Tool {
+            name: "echo".to_string(),
+            description: None,
+            input_schema: json!({ "type": "object" }),
+        };
+        let handler: ToolHandlerFn = Box::new(|request: CallToolRequest| {
+            Box::pin(async move {
+                CallToolResponse {
+                    content: vec![ToolResponseContent::Text {
+                        text: serde_json::to_string(&request.arguments).unwrap(),
+                    }],
+                    is_error: None,
+                    meta: None,
+                }
+            })
+        });
+        (tool, handler)
+    }
+
+    #[tokio::test]
+    async fn test_handle() {
+        let server = McpServer::new(vec![echo()]);
+        let (outbox, _) = flume::unbounded();
+        let response = server
+            .handle(
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+                &outbox,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["tools"][0]["name"], "echo");
+
+        let response = server
+            .handle(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 2,
+                    "method": "tools/call",
+                    "params": { "name": "echo", "arguments": { "x": 1 } }
+                }),
+                &outbox,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response["id"], 2);
+        assert_eq!(response["result"]["content"][0]["text"], r#"{"x":1}"#);
+
+        let response = server
+            .handle(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 3,
+                    "method": "tools/call",
+                    "params": { "name": "missing" }
+                }),
+                &outbox,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+
+        assert_eq!(
+            server
+                .handle(
+                    json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+                    &outbox
+                )
+                .await,
+            None
+        );
+        let response = server
+            .handle(
+                json!([
+                    { "jsonrpc": "2.0", "id": 4, "method": "ping" },
+                    { "jsonrpc": "2.0", "method": "notifications/initialized" },
+                    { "jsonrpc": "2.0", "id": 5, "method": "resources/list" }
+                ]),
+                &outbox,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response[0]["result"], json!({}));
+        assert_eq!(response[1]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_progress() {
+        let tool = Tool {
+            name: "build".to_string(),
+            description: None,
+            input_schema: json!({ "type": "object" }),
+        };
+        let handler: ToolHandlerFn = Box::new(|_: CallToolRequest| {
+            Box::pin(async move {
+                if let Some(progress) = progress::current() {
+                    progress.step("Compiling foo");
+                }
+                CallToolResponse {
+                    content: Vec::new(),
+                    is_error: None,
+                    meta: None,
+                }
+            })
+        });
+        let server = McpServer::new(vec![(tool, handler)]);
+        let (outbox, messages) = flume::unbounded();
+        let call = |meta: Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": "build", "_meta": meta }
+            })
+        };
+        server.handle(call(json!({})), &outbox).await.unwrap();
+        assert!(messages.is_empty());
+        server
+            .handle(call(json!({ "progressToken": 7 })), &outbox)
+            .await
+            .unwrap();
+        let notification = messages.try_recv().unwrap();
+        assert_eq!(notification["method"], "notifications/progress");
+        assert_eq!(notification["params"]["progressToken"], 7);
+        assert_eq!(notification["params"]["message"], "Compiling foo");
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    metrics::format_latency,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+pub struct ServerStats;
+
+impl ServerStats {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "server_stats".to_string(),
+            description: Some("Get how often each tool was called since the server started, with the error rate and the 50th, 95th and 99th percentile of the latency. Use it to find out which tools are used and which are slow or fail.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to any file of the project"
+                    },
+                    "all_projects": {
+                        "type": "boolean",
+                        "description": "Include the calls of all projects instead of only this one (default false)"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, _, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(&clone, project, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    context: &Context,
+    project: Arc<ProjectContext>,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let all_projects = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("all_projects"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let root = project.project.root();
+    let stats = context
+        .metrics()
+        .stats()
+        .into_iter()
+        .filter(|stats| all_projects || stats.project.as_ref() == Some(root))
+        .collect::<Vec<_>>();
+    if stats.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No tools were called yet",
+        ));
+    }
+
+    let mut text = String::new();
+    if all_projects {
+        text.push_str("| Project ");
+    }
+    text.push_str("| Tool | Calls | Error rate | p50 | p95 | p99 |\n");
+    if all_projects {
+        text.push_str("|---");
+    }
+    text.push_str("|---|---:|---:|---:|---:|---:|\n");
+    for stats in stats {
+        if all_projects {
+            let project = stats
+                .project
+                .as_ref()
+                .map(|project| project.display().to_string())
+                .unwrap_or_else(|| "(none)".to_string());
+            text.push_str(&format!("| {project} "));
+        }
+        text.push_str(&format!(
+            "| `{}` | {} | {:.1}% | {} | {} | {} |\n",
+            stats.tool,
+            stats.calls,
+            stats.error_rate() * 100.0,
+            format_latency(stats.p50),
+            format_latency(stats.p95),
+            format_latency(stats.p99),
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
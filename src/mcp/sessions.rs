@@ -0,0 +1,144 @@
+//! The MCP clients connected to the SSE server. Every client (e.g. a Cursor window)
+//! opens its own SSE session, the SSE transport records the sessions and tags the
+//! tool calls with their session, so the notifications of a call can name the client
+//! that made it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use mcp_core::{tools::ToolHandlerFn, types::CallToolRequest};
+use serde_json::{Value, json};
+
+/// The key in the `_meta` of a tool call which holds the session id
+pub const SESSION_META_KEY: &str = "cursorRustToolsSession";
+
+tokio::task_local! {
+    /// The session of the tool call the current task handles
+    static CURRENT: Option<ClientSession>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientSession {
+    /// Counts the sessions since the start, to tell clients with the same name apart
+    pub number: usize,
+    /// The `clientInfo.name` the client sent in `initialize`
+    pub name: Option<String>,
+}
+
+impl fmt::Display for ClientSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} #{}", name, self.number),
+            None => write!(f, "Client #{}", self.number),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Sessions {
+    next_number: Arc<AtomicUsize>,
+    sessions: Arc<Mutex<HashMap<String, ClientSession>>>,
+}
+
+impl Sessions {
+    /// Record a request of the session, the first one numbers it
+    pub fn seen(&self, id: &str) {
+        let Ok(mut sessions) = self.sessions.lock() else {
+            return;
+        };
+        sessions.entry(id.to_string()).or_insert_with(|| {
+            let number = self.next_number.fetch_add(1, Ordering::Relaxed) + 1;
+            tracing::info!("MCP client #{} connected", number);
+            ClientSession { number, name: None }
+        });
+    }
+
+    /// The client introduced itself in `initialize`
+    pub fn set_name(&self, id: &str, name: &str) {
+        self.seen(id);
+        if let Ok(mut sessions) = self.sessions.lock()
+            && let Some(session) = sessions.get_mut(id)
+        {
+            session.name = Some(name.to_string());
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<ClientSession> {
+        self.sessions.lock().ok()?.get(id).cloned()
+    }
+
+    /// Every client that connected since the start, in the order they connected
+    pub fn all(&self) -> Vec<ClientSession> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map(|sessions| sessions.values().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        sessions.sort_by_key(|session| session.number);
+        sessions
+    }
+
+    /// Wrap a tool handler so that [`current_session`] returns the session of the
+    /// call while it runs
+    pub fn track(&self, handler: ToolHandlerFn) -> ToolHandlerFn {
+        let handler = Arc::new(handler);
+        let sessions = self.clone();
+        Box::new(move |request: CallToolRequest| {
+            let session = request
+                .meta
+                .as_ref()
+                .and_then(|meta| meta.get(SESSION_META_KEY))
+                .and_then(|id| id.as_str())
+                .and_then(|id| sessions.get(id));
+            let handler = handler.clone();
+            Box::pin(CURRENT.scope(session, async move { handler(request).await }))
+        })
+    }
+}
+
+/// Tag the tool calls and prompts (which call tools) of a message or a batch of
+/// messages with the session and drop the session any message claims itself, so
+/// that clients can't make their calls look like those of another client. Without a
+/// session the tags are only dropped.
+pub(super) fn tag_session(body: &mut Value, id: Option<&str>) {
+    let messages = match body {
+        Value::Array(messages) => messages.iter_mut().collect::<Vec<_>>(),
+        message => vec![message],
+    };
+    for message in messages {
+        let calls_tools = matches!(
+            message.get("method").and_then(Value::as_str),
+            Some("tools/call" | "prompts/get")
+        );
+        let Some(params) = message.get_mut("params").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        if let Some(meta) = params.get_mut("_meta").and_then(Value::as_object_mut) {
+            meta.remove(SESSION_META_KEY);
+        }
+        let Some(id) = id.filter(|_| calls_tools) else {
+            continue;
+        };
+        let meta = params.entry("_meta").or_insert_with(|| json!({}));
+        if !meta.is_object() {
+            *meta = json!({});
+        }
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert(SESSION_META_KEY.to_string(), id.into());
+        }
+    }
+}
+
+/// The session of the tool call the current task handles, `None` outside of tool
+/// calls and for the stdio transport
+pub fn current_session() -> Option<ClientSession> {
+    CURRENT.try_with(|session| session.clone()).ok().flatten()
+}
+
+/// Run the future with the session as [`current_session`], e.g. a task that a tool
+/// call spawns
+pub(super) async fn scope<F: Future>(session: Option<ClientSession>, future: F) -> F::Output {
+    CURRENT.scope(session, future).await
+}
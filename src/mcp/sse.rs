@@ -0,0 +1,532 @@
+//! The SSE transport. A client opens a session with `GET /sse`, whose event stream
+//! first names the endpoint to post the messages of the session to and then carries
+//! the responses. The messages are answered in-process by [`McpServer`], and the
+//! sessions are recorded so that the notifications of a tool call can name the
+//! client, see [`super::sessions`]. With an API key, every request has to carry
+//! `Authorization: Bearer <api key>`.
+//!
+//! The transport of mcp-core isn't used because it binds and serves its own HTTP
+//! server without a way to look at the headers of a request, so the key could only
+//! be checked by a proxy in front of it, which left mcp-core listening without one.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::server::McpServer;
+use super::sessions::{Sessions, tag_session};
+
+/// Requests with a larger header are rejected
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Requests with a larger body are rejected
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// How often an idle event stream gets a comment, to notice clients that are gone
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// How long accepting connections pauses after it failed
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+const CONTINUE: &str = "HTTP/1.1 100 Continue\r\n\r\n";
+const UNAUTHORIZED: &str = "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Bearer\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const ACCEPTED: &str = "HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n";
+const BAD_REQUEST: &str = "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n";
+const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+const EVENT_STREAM: &str =
+    "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+
+/// The SSE server, shared by the listeners of all bind addresses
+#[derive(Clone)]
+pub struct SseServer {
+    server: McpServer,
+    api_key: Option<String>,
+    sessions: Sessions,
+    /// The messages for the event stream of each open session
+    streams: Arc<Mutex<HashMap<String, flume::Sender<Value>>>>,
+    next_stream: Arc<AtomicU64>,
+}
+
+impl SseServer {
+    pub fn new(server: McpServer, api_key: Option<String>, sessions: Sessions) -> Self {
+        Self {
+            server,
+            api_key,
+            sessions,
+            streams: Arc::default(),
+            next_stream: Arc::default(),
+        }
+    }
+
+    /// Accept the connections of the listener
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // E.g. too many open files, which only resolves once connections close
+                    tracing::error!("Failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.connection(stream).await {
+                    tracing::debug!("Connection from {} failed: {:?}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn connection(&self, stream: TcpStream) -> Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut read = BufReader::new(read);
+        while let Some(head) = read_head(&mut read).await? {
+            // The body of an unauthorized request isn't even read
+            if let Some(api_key) = &self.api_key
+                && !is_authorized(&head, api_key)
+            {
+                tracing::warn!("Rejected an SSE request without a valid API key");
+                write.write_all(UNAUTHORIZED.as_bytes()).await?;
+                return Ok(());
+            }
+            // The client waits for this before it sends the body
+            if header(&head, "expect")
+                .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+            {
+                write.write_all(CONTINUE.as_bytes()).await?;
+            }
+            let body = read_body(&mut read, &head).await?;
+            let request = Request { head, body };
+            let (method, path) = request.target().unwrap_or_default();
+            let path = path.split('?').next().unwrap_or_default();
+            let status = match (method, path) {
+                // The connection carries the event stream from now on
+                ("GET", "/sse") => return self.event_stream(write).await,
+                ("POST", "/message") => self.post(&request),
+                _ => NOT_FOUND,
+            };
+            write.write_all(status.as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    /// Open a session and send its messages until the client is gone
+    async fn event_stream(&self, mut write: tokio::net::tcp::OwnedWriteHalf) -> Result<()> {
+        let id = self.new_session_id();
+        let (sender, messages) = flume::unbounded::<Value>();
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.insert(id.clone(), sender);
+        }
+        self.sessions.seen(&id);
+        let result = async {
+            write.write_all(EVENT_STREAM.as_bytes()).await?;
+            write
+                .write_all(format!("event: endpoint\ndata: /message?sessionId={id}\n\n").as_bytes())
+                .await?;
+            loop {
+                let event = tokio::select! {
+                    message = messages.recv_async() => match message {
+                        Ok(message) => format!("event: message\ndata: {message}\n\n"),
+                        Err(_) => break,
+                    },
+                    _ = tokio::time::sleep(KEEP_ALIVE) => ": keep-alive\n\n".to_string(),
+                };
+                write.write_all(event.as_bytes()).await?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        if let Ok(mut streams) = self.streams.lock() {
+            streams.remove(&id);
+        }
+        result
+    }
+
+    /// Answer the posted message on the event stream of its session
+    fn post(&self, request: &Request) -> &'static str {
+        let Some(id) = session_id(&request.head) else {
+            return BAD_REQUEST;
+        };
+        let Some(stream) = self
+            .streams
+            .lock()
+            .ok()
+            .and_then(|streams| streams.get(id).cloned())
+        else {
+            return NOT_FOUND;
+        };
+        let Ok(mut message) = serde_json::from_slice::<Value>(&request.body) else {
+            return BAD_REQUEST;
+        };
+        tag_messages(&mut message, id, &self.sessions);
+        let server = self.server.clone();
+        tokio::spawn(async move {
+            if let Some(response) = server.handle(message, &stream).await {
+                // The stream is gone if the client disconnected in the meantime
+                let _ = stream.send(response);
+            }
+        });
+        ACCEPTED
+    }
+
+    /// A session id other clients can't guess
+    fn new_session_id(&self) -> String {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let number = self.next_stream.fetch_add(1, Ordering::Relaxed);
+        let seed = format!("{}-{}-{}-{:p}", std::process::id(), nanos, number, &number);
+        Sha256::digest(seed.as_bytes())
+            .iter()
+            .take(16)
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+struct Request {
+    /// The request line and the headers, including the empty line after them
+    head: String,
+    /// The body, decoded if it was chunked
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// The method and the path with the query
+    fn target(&self) -> Option<(&str, &str)> {
+        let mut request_line = self.head.lines().next()?.split(' ');
+        Some((request_line.next()?, request_line.next()?))
+    }
+}
+
+/// Read the request line and the headers of the next request of the connection, `None`
+/// once the client closed it
+async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<String>> {
+    let mut head = Vec::new();
+    loop {
+        if reader.read_until(b'\n', &mut head).await? == 0 {
+            return Ok(None);
+        }
+        if head.len() > MAX_HEAD_BYTES {
+            anyhow::bail!("Request header too large");
+        }
+        if head == b"\r\n" || head == b"\n" {
+            // Empty lines before a request are ignored
+            head.clear();
+        } else if head.ends_with(b"\r\n\r\n") || head.ends_with(b"\n\n") {
+            break;
+        }
+    }
+    String::from_utf8(head)
+        .map(Some)
+        .context("Request header is not UTF-8")
+}
+
+/// Read the body of the request with the `head`
+async fn read_body<R: AsyncBufRead + Unpin>(reader: &mut R, head: &str) -> Result<Vec<u8>> {
+    let chunked = header(head, "transfer-encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"));
+    if chunked {
+        return read_chunked_body(reader).await;
+    }
+    let length = header(head, "content-length")
+        .map(|length| length.parse::<usize>())
+        .transpose()
+        .context("Invalid Content-Length")?
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        anyhow::bail!("Request body too large");
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    Ok(body)
+}
+
+/// Decode a chunked body, including the trailers after the last chunk
+async fn read_chunked_body<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            anyhow::bail!("Connection closed in a chunked body");
+        }
+        // The size may be followed by extensions
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .with_context(|| format!("Invalid chunk size `{size}`"))?;
+        if size == 0 {
+            break;
+        }
+        if body.len() + size > MAX_BODY_BYTES {
+            anyhow::bail!("Request body too large");
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        let mut end = String::new();
+        reader.read_line(&mut end).await?;
+        if !end.trim().is_empty() {
+            anyhow::bail!("Chunk is longer than its size");
+        }
+    }
+    let mut trailers = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            return Ok(body);
+        }
+        trailers += line.len();
+        if trailers > MAX_HEAD_BYTES {
+            anyhow::bail!("Request trailers too large");
+        }
+    }
+}
+
+/// The session of a message the client posts, from the `sessionId` in the query
+fn session_id(head: &str) -> Option<&str> {
+    let mut request_line = head.lines().next()?.split(' ');
+    if request_line.next()? != "POST" {
+        return None;
+    }
+    let (_, query) = request_line.next()?.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(name, _)| *name == "sessionId" || *name == "session_id")
+        .map(|(_, id)| id)
+        .filter(|id| !id.is_empty())
+}
+
+/// Record the session of the messages and tag their tool calls with it
+fn tag_messages(body: &mut Value, id: &str, sessions: &Sessions) {
+    sessions.seen(id);
+    let messages = match &*body {
+        Value::Array(messages) => messages.iter().collect::<Vec<_>>(),
+        message => vec![message],
+    };
+    for message in messages {
+        if message.get("method").and_then(Value::as_str) == Some("initialize")
+            && let Some(name) = message
+                .pointer("/params/clientInfo/name")
+                .and_then(Value::as_str)
+        {
+            sessions.set_name(id, name);
+        }
+    }
+    tag_session(body, Some(id));
+}
+
+/// The value of the first header with the name
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(header, _)| header.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Whether the request head has an `Authorization: Bearer` header with the key
+fn is_authorized(head: &str, api_key: &str) -> bool {
+    head.lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .filter_map(|(_, value)| {
+            let (scheme, token) = value.trim().split_once(' ')?;
+            scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+        })
+        .any(|token| constant_time_eq(token.as_bytes(), api_key.as_bytes()))
+}
+
+/// Compare without returning early, so the time doesn't tell how much of the key matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::super::sessions::SESSION_META_KEY;
+    use super::*;
+
+    #[test]
+    fn test_is_authorized() {
+        let head = |auth: &str| format!("GET /sse HTTP/1.1\r\nHost: localhost\r\n{auth}\r\n\r\n");
+        assert!(is_authorized(
+            &head("Authorization: Bearer secret"),
+            "secret"
+        ));
+        assert!(is_authorized(
+            &head("authorization: bearer  secret "),
+            "secret"
+        ));
+        assert!(!is_authorized(
+            &head("Authorization: Bearer wrong"),
+            "secret"
+        ));
+        assert!(!is_authorized(
+            &head("Authorization: Basic secret"),
+            "secret"
+        ));
+        assert!(!is_authorized(&head("X-Api-Key: secret"), "secret"));
+        assert!(!is_authorized(
+            "GET /sse?Authorization: Bearer secret HTTP/1.1\r\n\r\n",
+            "secret"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_chunked_request() {
+        let raw = b"POST /message?sessionId=abc HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\n{\"id\"\r\n3\r\n:1}\r\n0\r\nX-Trailer: 1\r\n\r\nGET /sse HTTP/1.1\r\n\r\n";
+        let mut reader = raw.as_slice();
+        let head = read_head(&mut reader).await.unwrap().unwrap();
+        assert_eq!(read_body(&mut reader, &head).await.unwrap(), br#"{"id":1}"#);
+        // The next request on the connection starts after the trailers
+        let head = read_head(&mut reader).await.unwrap().unwrap();
+        assert!(head.starts_with("GET /sse "));
+        assert!(read_body(&mut reader, &head).await.unwrap().is_empty());
+        assert!(read_head(&mut reader).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_tag_messages() {
+        let sessions = Sessions::default();
+        let mut initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": { "clientInfo": { "name": "Cursor", "version": "1.0" } }
+        });
+        let untouched = initialize.clone();
+        tag_messages(&mut initialize, "abc", &sessions);
+        assert_eq!(initialize, untouched);
+        assert_eq!(sessions.get("abc").unwrap().to_string(), "Cursor #1");
+
+        let mut call = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "cargo_check", "arguments": {} }
+        });
+        tag_messages(&mut call, "abc", &sessions);
+        assert_eq!(call["params"]["_meta"][SESSION_META_KEY], "abc");
+        assert_eq!(call["params"]["name"], "cargo_check");
+
+        // Clients can't claim the session of another client
+        let mut spoofed = json!([
+            {
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": { "name": "cargo_check", "_meta": { SESSION_META_KEY: "other" } }
+            },
+            {
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "tools/list",
+                "params": { "_meta": { SESSION_META_KEY: "other", "progressToken": 1 } }
+            }
+        ]);
+        tag_messages(&mut spoofed, "abc", &sessions);
+        assert_eq!(spoofed[0]["params"]["_meta"][SESSION_META_KEY], "abc");
+        assert_eq!(spoofed[1]["params"]["_meta"], json!({ "progressToken": 1 }));
+
+        assert_eq!(session_id("GET /sse HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(
+            session_id("POST /message?x=1&sessionId=42 HTTP/1.1\r\n\r\n"),
+            Some("42")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unauthorized_requests() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = SseServer::new(
+            McpServer::new(Vec::new()),
+            Some("secret".to_string()),
+            Sessions::default(),
+        );
+        tokio::spawn(server.serve(listener));
+        let client = reqwest::Client::new();
+        let sse = format!("http://{address}/sse");
+        let message = format!("http://{address}/message?sessionId=abc");
+        assert_eq!(client.get(&sse).send().await.unwrap().status(), 401);
+        assert_eq!(
+            client
+                .post(&message)
+                .body("{}")
+                .send()
+                .await
+                .unwrap()
+                .status(),
+            401
+        );
+        assert_eq!(
+            client
+                .post(&message)
+                .bearer_auth("wrong")
+                .body("{}")
+                .send()
+                .await
+                .unwrap()
+                .status(),
+            401
+        );
+        // The body of an unauthorized request isn't waited for
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"POST /message?sessionId=abc HTTP/1.1\r\nContent-Length: 2\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert_eq!(response, UNAUTHORIZED);
+        // Authorized, but the session doesn't exist
+        assert_eq!(
+            client
+                .post(&message)
+                .bearer_auth("secret")
+                .body("{}")
+                .send()
+                .await
+                .unwrap()
+                .status(),
+            404
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expect_continue() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = SseServer::new(McpServer::new(Vec::new()), None, Sessions::default());
+        tokio::spawn(server.serve(listener));
+        let mut stream = BufReader::new(TcpStream::connect(address).await.unwrap());
+        stream
+            .write_all(b"POST /message?sessionId=abc HTTP/1.1\r\nContent-Length: 2\r\nExpect: 100-continue\r\n\r\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "HTTP/1.1 100 Continue\r\n");
+        stream.read_line(&mut line).await.unwrap();
+        stream.write_all(b"{}").await.unwrap();
+        let mut status = String::new();
+        stream.read_line(&mut status).await.unwrap();
+        assert_eq!(status, "HTTP/1.1 404 Not Found\r\n");
+    }
+}
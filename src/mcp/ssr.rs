@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::{apply_workspace_edit, workspace_edit_changes},
+};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, format_workspace_edit, get_info_from_request},
+};
+
+pub struct StructuralSearchReplace;
+
+impl StructuralSearchReplace {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "structural_search_replace".to_string(),
+            description: Some("Run a rust-analyzer structural search replace (SSR) over the whole project. The pattern matches code by its syntax and semantics, not by text, e.g. `foo($a, $b) ==>> bar($b, $a)` or `$s.len() == 0 ==>> $s.is_empty()`. Returns a preview of the edits and optionally applies them.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "The SSR rule in the form `search ==>> replacement`. Placeholders are written as `$name`."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to a file of the project. Paths in the pattern are resolved relative to this file."
+                    },
+                    "apply": {
+                        "type": "boolean",
+                        "description": "If true, the edits are applied to the files on disk. Default is false."
+                    }
+                },
+                "required": ["pattern", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let pattern = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("pattern"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Pattern is required"))
+        .map(|s| s.to_string())?;
+    let apply = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("apply"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let edit = project
+        .lsp
+        .structural_search_replace(relative_file, pattern)
+        .await
+        .map_err(|e| error_response(ErrorCode::of(&e, ErrorCode::LspFailed), &format!("{e:?}")))?;
+
+    if workspace_edit_changes(&edit).is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "The pattern did not match anything",
+        ));
+    }
+
+    let mut text = format_workspace_edit(&edit);
+    if apply {
+        let changed = apply_workspace_edit(&edit, project.lsp.position_encoding())
+            .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?;
+        text.push_str(&format!("\nApplied the edits to {} files", changed.len()));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::{generated_code_note, location_ranges},
+};
+use anyhow::Result;
+use lsp_types::{Position, Range};
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_symbol_position_in_file, get_file_lines,
+        get_info_from_request, lsp_error_response,
+    },
+};
+
+const DEFAULT_CONTEXT_LINES: u64 = 2;
+
+pub struct SymbolDefinition;
+
+impl SymbolDefinition {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "symbol_definition".to_string(),
+            description: Some("Get the source of the item that defines a symbol. Unlike `symbol_impl` this only returns the definition itself (e.g. the whole function, struct or impl item) plus a few lines of surrounding context instead of entire files.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The line number of the symbol in the file (1 based)"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the symbol to get the definition for. Methods can be qualified with their type, e.g. `Foo::bar`."
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file containing the symbol"
+                    },
+                    "context_lines": {
+                        "type": "number",
+                        "description": "Number of lines to include before and after the definition (default 2)"
+                    }
+                },
+                "required": ["line", "symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let symbol = request.get_symbol()?;
+    let context_lines = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("context_lines"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_CONTEXT_LINES)
+        .min(u8::MAX as u64) as u8;
+
+    let position = find_symbol_position_in_file(&project, relative_file, &symbol, line)
+        .await
+        .map_err(|e| lsp_error_response(&e))?;
+
+    let Some(definition) = project
+        .lsp
+        .definition(relative_file, position)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(ErrorCode::NotFound, "No definition found"));
+    };
+
+    let locations = location_ranges(definition).map_err(|e| lsp_error_response(&e.into()))?;
+
+    let mut contents = Vec::new();
+    for (path, range) in locations {
+        let range = enclosing_item_range(&project, &path, range).await;
+        let Some(code) = get_file_lines(
+            &path,
+            range.start.line,
+            range.end.line,
+            context_lines,
+            context_lines,
+        )
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?
+        else {
+            continue;
+        };
+        let note = generated_code_note(&project.project, &path)
+            .map(|note| format!("{note}\n"))
+            .unwrap_or_default();
+        contents.push(format!(
+            r#"## {}:{}-{}
+{}``` rust
+{}
+```"#,
+            path.display(),
+            range.start.line + 1,
+            range.end.line + 1,
+            note,
+            code
+        ));
+    }
+
+    if contents.is_empty() {
+        return Err(error_response(
+            ErrorCode::IoFailed,
+            "Could not read the definition source",
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: contents.join("\n"),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Plain locations only cover the name of the definition. Widen the range to
+/// the smallest document symbol in the target file that contains it.
+async fn enclosing_item_range(
+    project: &ProjectContext,
+    path: &std::path::Path,
+    range: Range,
+) -> Range {
+    if range.start.line != range.end.line {
+        return range;
+    }
+    let Ok(Some(symbols)) = project.lsp.document_symbols(path).await else {
+        return range;
+    };
+    symbols
+        .into_iter()
+        .map(|symbol| symbol.location.range)
+        .filter(|candidate| contains(candidate, range.start))
+        .min_by_key(|candidate| candidate.end.line - candidate.start.line)
+        .unwrap_or(range)
+}
+
+fn contains(range: &Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
@@ -15,7 +15,8 @@ use serde_json::json;
 use super::{
     McpNotification,
     utils::{
-        RequestExtension, error_response, find_symbol_position_in_file, get_info_from_request,
+        ErrorCode, RequestExtension, error_response, find_symbol_position_in_file,
+        get_info_from_request, lsp_error_response,
     },
 };
 
@@ -35,7 +36,7 @@ impl SymbolDocs {
                     },
                     "symbol": {
                         "type": "string",
-                        "description": "The name of the symbol to get the documentation for"
+                        "description": "The name of the symbol to get the documentation for. Methods can be qualified with their type, e.g. `Foo::bar`."
                     },
                     "file": {
                         "type": "string",
@@ -94,15 +95,18 @@ async fn handle_request(
 
     let position = find_symbol_position_in_file(&project, relative_file, &symbol, line)
         .await
-        .map_err(|e| error_response(&e))?;
+        .map_err(|e| lsp_error_response(&e))?;
 
     let Some(hover) = project
         .lsp
         .hover(relative_file, position)
         .await
-        .map_err(|e| error_response(&e.to_string()))?
+        .map_err(|e| lsp_error_response(&e))?
     else {
-        return Err(error_response("No hover information found"));
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No hover information found",
+        ));
     };
 
     let response = match hover.contents {
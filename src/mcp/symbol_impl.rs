@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     context::{Context, ProjectContext},
-    lsp::get_location_contents,
+    lsp::{generated_code_note, get_location_contents},
 };
 use anyhow::Result;
 use mcp_core::{
@@ -14,17 +14,21 @@ use serde_json::json;
 use super::{
     McpNotification,
     utils::{
-        RequestExtension, error_response, find_symbol_position_in_file, get_info_from_request,
+        ErrorCode, RequestExtension, error_response, find_symbol_position_in_file,
+        get_info_from_request, lsp_error_response,
     },
 };
 
+/// Lines of context around each implementation if the request doesn't specify them
+const DEFAULT_CONTEXT_LINES: u64 = 2;
+
 pub struct SymbolImpl;
 
 impl SymbolImpl {
     pub fn tool() -> Tool {
         Tool {
             name: "symbol_impl".to_string(),
-            description: Some("Get the implementation for a symbol. If the implementation is in multiple files, will return multiple files. Will return the full file that contains the implementation including other contents of the file.".to_string()),
+            description: Some("Get the implementation for a symbol. If the implementation is in multiple places, will return all of them. Returns the source of the implementing item plus a few lines of surrounding context, not the whole file.".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -34,11 +38,15 @@ impl SymbolImpl {
                     },
                     "symbol": {
                         "type": "string",
-                        "description": "The name of the symbol to get the documentation for"
+                        "description": "The name of the symbol to get the documentation for. Methods can be qualified with their type, e.g. `Foo::bar`."
                     },
                     "file": {
                         "type": "string",
                         "description": "The absolute path to the file containing the symbol"
+                    },
+                    "context_lines": {
+                        "type": "number",
+                        "description": "Number of lines to include before and after the implementation (default 2)"
                     }
                 },
                 "required": ["line", "symbol", "file"]
@@ -90,30 +98,46 @@ async fn handle_request(
 ) -> Result<CallToolResponse, CallToolResponse> {
     let line = request.get_line()?;
     let symbol = request.get_symbol()?;
+    let context_lines = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("context_lines"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_CONTEXT_LINES)
+        .min(u32::MAX as u64) as u32;
 
     let position = find_symbol_position_in_file(&project, relative_file, &symbol, line)
         .await
-        .map_err(|e| error_response(&e))?;
+        .map_err(|e| lsp_error_response(&e))?;
 
     let Some(type_definition) = project
         .lsp
         .type_definition(relative_file, position)
         .await
-        .map_err(|e| error_response(&e.to_string()))?
+        .map_err(|e| lsp_error_response(&e))?
     else {
-        return Err(error_response("No type definition found"));
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No type definition found",
+        ));
     };
 
-    let contents = get_location_contents(type_definition)
-        .map_err(|e| error_response(&e.to_string()))?
+    let contents = get_location_contents(type_definition, context_lines)
+        .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?
         .iter()
-        .map(|(content, path)| {
+        .map(|(content, path, range)| {
+            let note = generated_code_note(&project.project, path)
+                .map(|note| format!("{note}\n"))
+                .unwrap_or_default();
             format!(
-                r#"## {}
-``` rust
+                r#"## {}:{}-{}
+{}``` rust
 {}
 ```"#,
                 path.display(),
+                range.start.line + 1,
+                range.end.line + 1,
+                note,
                 content
             )
         })
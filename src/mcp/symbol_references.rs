@@ -1,7 +1,15 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::context::{Context, ProjectContext};
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::{generated_code_note, is_generated_code, slice_lines},
+};
 use anyhow::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use mcp_core::{
     tools::ToolHandlerFn,
     types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
@@ -11,18 +19,24 @@ use serde_json::json;
 use super::{
     McpNotification,
     utils::{
-        RequestExtension, error_response, find_symbol_position_in_file, get_file_lines,
-        get_info_from_request,
+        ErrorCode, RequestExtension, error_response, find_symbol_position_in_file,
+        get_info_from_request, lsp_error_response,
     },
 };
 
+/// How many references are returned if the request doesn't specify it
+const DEFAULT_MAX_RESULTS: u64 = 50;
+/// Lines of context shown around each reference, references whose context overlaps
+/// are shown as one snippet
+const PREVIEW_LINES: u32 = 4;
+
 pub struct SymbolReferences;
 
 impl SymbolReferences {
     pub fn tool() -> Tool {
         Tool {
             name: "symbol_references".to_string(),
-            description: Some("Get all the references for a symbol. Will return a list of files that contain the symbol including a preview of the usage.".to_string()),
+            description: Some("Get all the references for a symbol. Returns the number of references per file, followed by previews of the usages grouped by file, where nearby usages share one preview. Use `max_results` and `offset` to page through symbols with many references, and `include_tests: false`, `include_generated: false` and `exclude` to get production usages only.".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -32,11 +46,32 @@ impl SymbolReferences {
                     },
                     "symbol": {
                         "type": "string",
-                        "description": "The name of the symbol to get the documentation for"
+                        "description": "The name of the symbol to get the documentation for. Methods can be qualified with their type, e.g. `Foo::bar`."
                     },
                     "file": {
                         "type": "string",
                         "description": "The absolute path to the file containing the symbol"
+                    },
+                    "max_results": {
+                        "type": "number",
+                        "description": format!("Maximum number of references to preview (default {DEFAULT_MAX_RESULTS})")
+                    },
+                    "offset": {
+                        "type": "number",
+                        "description": "Number of references to skip, to get the next page (default 0)"
+                    },
+                    "include_tests": {
+                        "type": "boolean",
+                        "description": "Include references in `tests/`, `tests.rs` files, `#[cfg(test)]` modules and `#[test]` functions (default true)"
+                    },
+                    "include_generated": {
+                        "type": "boolean",
+                        "description": "Include references in `target` and in code generated by build scripts (default true)"
+                    },
+                    "exclude": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Gitignore style patterns, relative to the project root, of paths whose references are skipped, e.g. `examples/` or `*_bench.rs`"
                     }
                 },
                 "required": ["line", "symbol", "file"]
@@ -93,29 +128,152 @@ async fn handle_request(
 
     let position = find_symbol_position_in_file(&project, relative_file, &symbol, line)
         .await
-        .map_err(|e| error_response(&e))?;
+        .map_err(|e| lsp_error_response(&e))?;
 
     let Some(references) = project
         .lsp
         .find_references(relative_file, position)
         .await
-        .map_err(|e| error_response(&e.to_string()))?
+        .map_err(|e| lsp_error_response(&e))?
     else {
-        return Err(error_response("No references found"));
+        return Err(error_response(ErrorCode::NotFound, "No references found"));
     };
 
-    let mut contents = String::new();
-    for reference in references {
-        let Ok(Some(lines)) = get_file_lines(
-            reference.uri.path(),
-            reference.range.start.line,
-            reference.range.end.line,
-            4,
-            4,
-        ) else {
+    let number = |name: &str| {
+        request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_u64())
+    };
+    let max_results = number("max_results").unwrap_or(DEFAULT_MAX_RESULTS) as usize;
+    let offset = number("offset").unwrap_or_default() as usize;
+    let flag = |name: &str| {
+        request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    };
+    let include_tests = flag("include_tests");
+    let include_generated = flag("include_generated");
+    let exclude = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("exclude"))
+        .and_then(|v| v.as_array())
+        .map(|patterns| {
+            patterns
+                .iter()
+                .filter_map(|p| p.as_str())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let exclude = exclude_filter(project.project.root(), &exclude)
+        .map_err(|e| error_response(ErrorCode::InvalidArguments, &e))?;
+    let target = project.project.root().join("target");
+
+    // Sorted by file and line, without the duplicates rust-analyzer sometimes returns
+    let mut references = references
+        .into_iter()
+        .filter_map(|reference| {
+            let path = reference.uri.to_file_path().ok()?;
+            Some((path, reference.range.start.line, reference.range.end.line))
+        })
+        .filter(|(path, _, _)| {
+            // References in dependencies are outside of the root the patterns apply to
+            if path.starts_with(exclude.path())
+                && exclude.matched_path_or_any_parents(path, false).is_ignore()
+            {
+                return false;
+            }
+            if !include_generated && (path.starts_with(&target) || is_generated_code(path)) {
+                return false;
+            }
+            include_tests || !is_test_file(project.project.root(), path)
+        })
+        .collect::<Vec<_>>();
+    if !include_tests {
+        let test_ranges = read_test_code_ranges(&references).await?;
+        references.retain(|(path, line, _)| {
+            !test_ranges.get(path).is_some_and(|ranges| {
+                ranges
+                    .iter()
+                    .any(|(start, end)| start <= line && line <= end)
+            })
+        });
+    }
+    references.sort();
+    references.dedup();
+    if references.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No references found that match the filters",
+        ));
+    }
+
+    let mut counts = BTreeMap::<&PathBuf, usize>::new();
+    for (path, _, _) in &references {
+        *counts.entry(path).or_default() += 1;
+    }
+    let mut contents = format!(
+        "{} references in {} files:\n",
+        references.len(),
+        counts.len()
+    );
+    for (path, count) in &counts {
+        contents.push_str(&format!("- {} ({count})\n", path.display()));
+    }
+
+    let page = references
+        .iter()
+        .skip(offset)
+        .take(max_results)
+        .collect::<Vec<_>>();
+    if page.is_empty() {
+        return Err(error_response(
+            ErrorCode::InvalidArguments,
+            &format!(
+                "The offset {offset} is past the last of the {} references",
+                references.len()
+            ),
+        ));
+    }
+    let end = offset + page.len();
+    if offset > 0 || end < references.len() {
+        contents.push_str(&format!(
+            "\nShowing references {}-{end} of {}.",
+            offset + 1,
+            references.len()
+        ));
+        if end < references.len() {
+            contents.push_str(&format!(" Pass `offset: {end}` for the next ones."));
+        }
+        contents.push('\n');
+    }
+
+    let mut files = BTreeMap::<&PathBuf, Vec<(u32, u32)>>::new();
+    for (path, start, end) in page {
+        files.entry(path).or_default().push((*start, *end));
+    }
+    for (path, lines) in files {
+        let Ok(text) = std::fs::read_to_string(path) else {
             continue;
         };
-        contents.push_str(&format!("## {}\n```\n{}\n```\n", reference.uri, lines));
+        contents.push_str(&format!("\n## {} ({})\n", path.display(), lines.len()));
+        if let Some(note) = generated_code_note(&project.project, path) {
+            contents.push_str(&format!("{note}\n"));
+        }
+        let line_count = text.lines().count() as u32;
+        for (start, end) in collapse(&lines, PREVIEW_LINES) {
+            contents.push_str(&format!(
+                "Lines {}-{}:\n```\n{}\n```\n",
+                start.saturating_sub(PREVIEW_LINES) + 1,
+                (end + PREVIEW_LINES + 1).min(line_count),
+                slice_lines(&text, start, end, PREVIEW_LINES)
+            ));
+        }
     }
 
     Ok(CallToolResponse {
@@ -124,3 +282,236 @@ async fn handle_request(
         meta: None,
     })
 }
+
+/// The test code of every file with references, read once per file
+async fn read_test_code_ranges(
+    references: &[(PathBuf, u32, u32)],
+) -> Result<HashMap<PathBuf, Vec<(u32, u32)>>, CallToolResponse> {
+    let mut paths = references
+        .iter()
+        .map(|(path, _, _)| path.clone())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let text = std::fs::read_to_string(&path).ok()?;
+                let ranges = test_code_ranges(&text);
+                Some((path, ranges))
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| error_response(ErrorCode::Aborted, &e.to_string()))
+}
+
+fn exclude_filter(root: &Path, patterns: &[&str]) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| format!("Invalid exclude pattern `{pattern}`: {e}"))?;
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Files that only contain tests: integration tests and `tests.rs` test modules
+fn is_test_file(root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative.components().any(|c| c.as_os_str() == "tests")
+        || path.file_stem().is_some_and(|stem| stem == "tests")
+}
+
+/// The (0 based, inclusive) line ranges of the items marked with `#[cfg(test)]` or
+/// `#[test]`, found by matching the braces of the item after the attribute
+fn test_code_ranges(text: &str) -> Vec<(u32, u32)> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].trim();
+        if !line.starts_with("#[cfg(test)]")
+            && !line.starts_with("#[test]")
+            && !line.starts_with("#[tokio::test")
+        {
+            index += 1;
+            continue;
+        }
+        let end = item_end(&lines, index);
+        ranges.push((index as u32, end as u32));
+        index = end + 1;
+    }
+    ranges
+}
+
+/// A string literal the item scanner is in, which may span lines
+enum Literal {
+    Str,
+    /// With the number of `#`s
+    Raw(usize),
+}
+
+/// The line where the item starting at line `start` ends: at the brace closing its
+/// body, or at its `;` if it has none. Braces in attributes, comments and literals
+/// don't count.
+fn item_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0;
+    // `[]` before the body, e.g. of attributes or array types, which may contain `;`
+    let mut brackets = 0;
+    let mut opened = false;
+    let mut literal = None;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match literal {
+                Some(Literal::Str) => {
+                    match c {
+                        '\\' => i += 1,
+                        '"' => literal = None,
+                        _ => {}
+                    }
+                    i += 1;
+                    continue;
+                }
+                Some(Literal::Raw(hashes)) => {
+                    if c == '"'
+                        && chars[i + 1..].iter().take_while(|c| **c == '#').count() >= hashes
+                    {
+                        literal = None;
+                        i += hashes;
+                    }
+                    i += 1;
+                    continue;
+                }
+                None => {}
+            }
+            let ident_before = i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_');
+            match c {
+                '/' if chars.get(i + 1) == Some(&'/') => break,
+                '"' => literal = Some(Literal::Str),
+                'r' if !ident_before || (i > 0 && chars[i - 1] == 'b') => {
+                    let hashes = chars[i + 1..].iter().take_while(|c| **c == '#').count();
+                    if chars.get(i + 1 + hashes) == Some(&'"') {
+                        literal = Some(Literal::Raw(hashes));
+                        i += 1 + hashes;
+                    }
+                }
+                // A char literal, not a lifetime
+                '\'' if chars.get(i + 1) == Some(&'\\') => {
+                    i += 2;
+                    while i < chars.len() && chars[i] != '\'' {
+                        i += 1;
+                    }
+                }
+                '\'' if chars.get(i + 2) == Some(&'\'') => i += 2,
+                '[' if !opened => brackets += 1,
+                ']' if !opened => brackets -= 1,
+                '{' if brackets == 0 => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' if brackets == 0 => {
+                    depth -= 1;
+                    if opened && depth == 0 {
+                        return index;
+                    }
+                }
+                // `#[cfg(test)] mod tests;` or `#[cfg(test)] use ...;`
+                ';' if !opened && brackets == 0 => return index,
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    lines.len().saturating_sub(1)
+}
+
+/// Merge the sorted line ranges whose previews with `context` lines around them
+/// overlap or touch
+fn collapse(lines: &[(u32, u32)], context: u32) -> Vec<(u32, u32)> {
+    let mut collapsed: Vec<(u32, u32)> = Vec::new();
+    for &(start, end) in lines {
+        match collapsed.last_mut() {
+            Some(last) if start.saturating_sub(context) <= last.1 + context + 1 => {
+                last.1 = last.1.max(end);
+            }
+            _ => collapsed.push((start, end)),
+        }
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_code_ranges() {
+        let text = r#"fn main() {}
+
+#[cfg(test)]
+use std::fmt;
+
+#[test]
+fn check() {
+    assert!(true); // {
+}
+
+#[cfg(test)]
+mod tests {
+    fn helper() {}
+}
+fn after() {}
+"#;
+        assert_eq!(test_code_ranges(text), vec![(2, 3), (5, 8), (10, 13)]);
+
+        let text = r###"#[cfg(test)] use std::fmt;
+fn main() {}
+#[cfg(test)] mod tests {
+    const S: &str = "}";
+    const R: &str = r#"
+}"#;
+    const C: char = '}';
+    fn f<'a>(s: &'a str) -> &'a str { s }
+}
+fn after() {}
+#[test] fn one_line() {}
+#[cfg(test)]
+const A: [u8; 2] = [1, 2];
+"###;
+        assert_eq!(
+            test_code_ranges(text),
+            vec![(0, 0), (2, 8), (10, 10), (11, 12)]
+        );
+    }
+
+    #[test]
+    fn test_is_test_file() {
+        let root = Path::new("/project");
+        assert!(is_test_file(root, Path::new("/project/tests/api.rs")));
+        assert!(is_test_file(
+            root,
+            Path::new("/project/crates/a/tests/api.rs")
+        ));
+        assert!(is_test_file(
+            root,
+            Path::new("/project/src/parser/tests.rs")
+        ));
+        assert!(!is_test_file(root, Path::new("/project/src/testing.rs")));
+    }
+
+    #[test]
+    fn test_collapse() {
+        assert_eq!(collapse(&[], 4), Vec::new());
+        assert_eq!(
+            collapse(&[(3, 3), (5, 6), (20, 20)], 1),
+            vec![(3, 6), (20, 20)]
+        );
+        assert_eq!(collapse(&[(0, 0), (3, 3)], 1), vec![(0, 3)]);
+        assert_eq!(collapse(&[(0, 0), (4, 4)], 1), vec![(0, 0), (4, 4)]);
+    }
+}
@@ -2,11 +2,11 @@ use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     context::{Context, ProjectContext},
-    lsp::format_marked_string,
+    lsp::{format_marked_string, symbol_matches},
 };
 use anyhow::Result;
 use fuzzt::get_top_n;
-use lsp_types::HoverContents;
+use lsp_types::{HoverContents, SymbolInformation};
 use mcp_core::{
     tools::ToolHandlerFn,
     types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
@@ -15,7 +15,9 @@ use serde_json::json;
 
 use super::{
     McpNotification,
-    utils::{RequestExtension, error_response, get_info_from_request},
+    utils::{
+        ErrorCode, RequestExtension, error_response, get_info_from_request, lsp_error_response,
+    },
 };
 
 pub struct SymbolResolve;
@@ -23,8 +25,8 @@ pub struct SymbolResolve;
 impl SymbolResolve {
     pub fn tool() -> Tool {
         Tool {
-            name: "symbol_docs".to_string(),
-            description: Some("Resolve a symbol based on its name. Provide any symbol from the file and it will try to resolve it and return documentation about it.".to_string()),
+            name: "symbol_resolve".to_string(),
+            description: Some("Resolve a symbol based on its name (previously also named `symbol_docs`). Provide any symbol from the file and it will try to resolve it and return documentation about it. If the file has no similar symbol, the whole project is searched, so any name seen anywhere in the codebase can be resolved.".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {
@@ -35,6 +37,10 @@ impl SymbolResolve {
                     "file": {
                         "type": "string",
                         "description": "The absolute path to the file containing the symbol"
+                    },
+                    "search_workspace": {
+                        "type": "boolean",
+                        "description": "Search the whole project if the file has no similar symbol. Default is true."
                     }
                 },
                 "required": [ "symbol", "file"]
@@ -79,49 +85,87 @@ impl SymbolResolve {
     }
 }
 
+/// How similar a symbol of the file has to be to the name to be used
+const MATCH_CUTOFF: f64 = 0.7;
+
 async fn handle_request(
     project: Arc<ProjectContext>,
     relative_file: &str,
     request: &CallToolRequest,
 ) -> Result<CallToolResponse, CallToolResponse> {
     let symbol = request.get_symbol()?;
-
-    let symbols = match project.lsp.document_symbols(relative_file).await {
-        Ok(Some(symbols)) => symbols,
-        Ok(None) => return Err(error_response("No symbols found")),
-        Err(e) => return Err(error_response(&e.to_string())),
-    };
-
-    let mut symbol_map = HashMap::new();
-
-    for file_symbol in symbols {
-        symbol_map.insert(file_symbol.name.clone(), file_symbol);
-    }
-
-    let keys = symbol_map.keys().map(|s| s.as_str()).collect::<Vec<_>>();
-
-    let matches = get_top_n(&symbol, &keys, None, Some(1), None, None);
-    let Some(best_match) = matches.first() else {
-        return Err(error_response("No match for symbol found"));
-    };
-
-    let match_str = best_match.to_string();
-    let Some(symbol_match) = symbol_map.get(&match_str) else {
-        return Err(error_response("No match for symbol found"));
+    let search_workspace = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("search_workspace"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let symbols = project
+        .lsp
+        .document_symbols(relative_file)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+        .unwrap_or_default();
+
+    let (file, position, location) = match best_match(&symbol, symbols) {
+        Some(symbol_match) => (
+            relative_file.to_string(),
+            symbol_match.location.range.start,
+            None,
+        ),
+        None if search_workspace => {
+            let symbols = project
+                .lsp
+                .workspace_symbols(&symbol)
+                .await
+                .map_err(|e| lsp_error_response(&e))?;
+            let Some(symbol_match) = symbols
+                .iter()
+                .find(|candidate| symbol_matches(candidate, &symbol))
+                .or(symbols.first())
+            else {
+                return Err(error_response(
+                    ErrorCode::SymbolNotFound,
+                    &format!("No match for symbol `{symbol}` found in the file or the project"),
+                ));
+            };
+            let Ok(path) = symbol_match.location.uri.to_file_path() else {
+                return Err(error_response(
+                    ErrorCode::LspFailed,
+                    "Invalid symbol location",
+                ));
+            };
+            let position = symbol_match.location.range.start;
+            // Symbols of dependencies are outside of the project, their absolute path works as well
+            let file = project
+                .project
+                .relative_path(&path)
+                .unwrap_or_else(|_| path.to_string_lossy().to_string());
+            let location = format!("Found in `{}:{}`\n\n", path.display(), position.line + 1);
+            (file, position, Some(location))
+        }
+        None => {
+            return Err(error_response(
+                ErrorCode::SymbolNotFound,
+                "No match for symbol found",
+            ));
+        }
     };
 
-    let position = symbol_match.location.range.start;
-
     let Some(hover) = project
         .lsp
-        .hover(relative_file, position)
+        .hover(&file, position)
         .await
-        .map_err(|e| error_response(&e.to_string()))?
+        .map_err(|e| lsp_error_response(&e))?
     else {
-        return Err(error_response("No hover information found"));
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No hover information found",
+        ));
     };
 
-    let response = match hover.contents {
+    let contents = match hover.contents {
         HoverContents::Scalar(s) => format_marked_string(&s),
         HoverContents::Array(a) => a
             .into_iter()
@@ -130,6 +174,7 @@ async fn handle_request(
             .join("\n"),
         HoverContents::Markup(m) => m.value,
     };
+    let response = format!("{}{contents}", location.unwrap_or_default());
 
     Ok(CallToolResponse {
         content: vec![ToolResponseContent::Text { text: response }],
@@ -137,3 +182,15 @@ async fn handle_request(
         meta: None,
     })
 }
+
+/// The symbol of the file whose name is most similar to `symbol`, if any is similar enough
+fn best_match(symbol: &str, symbols: Vec<SymbolInformation>) -> Option<SymbolInformation> {
+    let mut symbol_map = HashMap::new();
+    for file_symbol in symbols {
+        symbol_map.insert(file_symbol.name.clone(), file_symbol);
+    }
+    let keys = symbol_map.keys().map(|s| s.as_str()).collect::<Vec<_>>();
+    let matches = get_top_n(symbol, &keys, Some(MATCH_CUTOFF), Some(1), None, None);
+    let best_match = matches.first()?.to_string();
+    symbol_map.remove(&best_match)
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::context::{Context, ProjectContext};
+use crate::docs::utils::{dir_size, format_size};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request},
+};
+
+/// Workspace members above this size get a `cargo clean -p` suggestion
+const BLOATED_MEMBER_SIZE: u64 = 500 * 1024 * 1024;
+
+/// The folders of a profile which contain per crate artifacts
+const ARTIFACT_DIRS: &[&str] = &["deps", "incremental", "build", ".fingerprint"];
+
+pub struct TargetDirStats;
+
+impl TargetDirStats {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "target_dir_stats".to_string(),
+            description: Some("Report the size of the target directory broken down by profile and crate, including the incremental compilation cache. Suggests `cargo clean -p` for bloated workspace members. Use the `clean_target` tool to actually free space.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "The number of largest crates to list per profile. Default is 10."
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let limit = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("limit"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10) as usize;
+
+    let target_dir = project
+        .cargo_remote
+        .target_dir()
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+    if !target_dir.exists() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("The target directory {target_dir:?} does not exist"),
+        ));
+    }
+    let scanned_dir = target_dir.clone();
+    let (size, profiles) = tokio::task::spawn_blocking(move || scan_target_dir(&scanned_dir))
+        .await
+        .map_err(|e| error_response(ErrorCode::Aborted, &e.to_string()))?;
+    // Only needed for the suggestions, which most target dirs don't get
+    let has_bloated_crates = profiles
+        .iter()
+        .flat_map(|profile| profile.crates.values())
+        .any(|size| *size >= BLOATED_MEMBER_SIZE);
+    let members = if has_bloated_crates {
+        project
+            .cargo_remote
+            .metadata(true)
+            .await
+            .map(|metadata| {
+                metadata
+                    .workspace_packages()
+                    .map(|package| package.name.replace('-', "_"))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let mut text = format!(
+        "Target directory {}: {}\n",
+        target_dir.display(),
+        format_size(size)
+    );
+    let mut member_sizes: HashMap<String, u64> = HashMap::new();
+    for profile in profiles {
+        text.push_str(&format!(
+            "\n# {}: {} (incremental cache {})\n",
+            profile.name,
+            format_size(profile.size),
+            format_size(profile.incremental)
+        ));
+        let mut crates = profile.crates.into_iter().collect::<Vec<_>>();
+        crates.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        for (crate_name, size) in &crates {
+            if members.contains(crate_name) {
+                *member_sizes.entry(crate_name.clone()).or_default() += size;
+            }
+        }
+        for (crate_name, size) in crates.into_iter().take(limit) {
+            text.push_str(&format!("- {crate_name}: {}\n", format_size(size)));
+        }
+    }
+    let mut bloated = member_sizes
+        .into_iter()
+        .filter(|(_, size)| *size >= BLOATED_MEMBER_SIZE)
+        .collect::<Vec<_>>();
+    bloated.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    if !bloated.is_empty() {
+        text.push_str("\n# Suggestions\n");
+        for (member, size) in bloated {
+            text.push_str(&format!(
+                "- `cargo clean -p {member}` would free about {}\n",
+                format_size(size)
+            ));
+        }
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Profile folders (`debug`, `release`, `<triple>/debug`, ...) are the ones containing `deps`
+fn profile_dirs(target_dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut profiles = Vec::new();
+    for entry in std::fs::read_dir(target_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.join("deps").is_dir() {
+            profiles.push((name, path));
+            continue;
+        }
+        for nested in std::fs::read_dir(&path).into_iter().flatten().flatten() {
+            if nested.path().join("deps").is_dir() {
+                let nested_name = nested.file_name().to_string_lossy().to_string();
+                profiles.push((format!("{name}/{nested_name}"), nested.path()));
+            }
+        }
+    }
+    profiles.sort();
+    profiles
+}
+
+/// The sizes of a profile folder
+#[derive(Debug, Default)]
+struct ProfileSizes {
+    name: String,
+    size: u64,
+    incremental: u64,
+    /// The artifacts by crate name
+    crates: HashMap<String, u64>,
+}
+
+/// The size of the target directory and of its profiles, in a single walk
+fn scan_target_dir(target_dir: &Path) -> (u64, Vec<ProfileSizes>) {
+    let dirs = profile_dirs(target_dir);
+    let skipped = dirs.iter().map(|(_, dir)| dir.clone()).collect::<Vec<_>>();
+    let profiles = dirs
+        .into_iter()
+        .map(|(name, dir)| scan_profile(name, &dir))
+        .collect::<Vec<_>>();
+    let size = size_outside(target_dir, &skipped)
+        + profiles.iter().map(|profile| profile.size).sum::<u64>();
+    (size, profiles)
+}
+
+/// The size of everything in the directory except the `skipped` folders
+fn size_outside(dir: &Path, skipped: &[PathBuf]) -> u64 {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if skipped.contains(&path) {
+                0
+            } else if skipped.iter().any(|skipped| skipped.starts_with(&path)) {
+                size_outside(&path, skipped)
+            } else {
+                dir_size(&path)
+            }
+        })
+        .sum()
+}
+
+/// Sum the artifacts of a profile by crate name
+fn scan_profile(name: String, profile_dir: &Path) -> ProfileSizes {
+    let mut profile = ProfileSizes {
+        name,
+        ..ProfileSizes::default()
+    };
+    for entry in std::fs::read_dir(profile_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        if !ARTIFACT_DIRS.contains(&dir_name.as_str()) {
+            profile.size += dir_size(&entry.path());
+            continue;
+        }
+        for artifact in std::fs::read_dir(entry.path())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            let size = dir_size(&artifact.path());
+            profile.size += size;
+            if dir_name == "incremental" {
+                profile.incremental += size;
+            }
+            let file_name = artifact.file_name().to_string_lossy().to_string();
+            if let Some(name) = crate_name(&file_name) {
+                *profile.crates.entry(name.to_string()).or_default() += size;
+            }
+        }
+    }
+    profile
+}
+
+/// The crate name of an artifact such as `libserde-1f2e3d4c5b6a7980.rlib` or `serde-1f2e3d4c5b6a7980`
+fn crate_name(file_name: &str) -> Option<&str> {
+    let (stem, extension) = file_name.split_once('.').unwrap_or((file_name, ""));
+    let stem = match extension {
+        "rlib" | "rmeta" | "so" | "dylib" | "a" => stem.strip_prefix("lib").unwrap_or(stem),
+        _ => stem,
+    };
+    let (name, hash) = stem.rsplit_once('-')?;
+    hash.chars()
+        .all(|c| c.is_ascii_alphanumeric())
+        .then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crate_name() {
+        assert_eq!(crate_name("libserde-1f2e3d4c5b6a7980.rlib"), Some("serde"));
+        assert_eq!(crate_name("liblibc-1f2e3d4c5b6a7980.rmeta"), Some("libc"));
+        assert_eq!(crate_name("libc-1f2e3d4c5b6a7980.d"), Some("libc"));
+        assert_eq!(
+            crate_name("serde_derive-1f2e3d4c5b6a7980"),
+            Some("serde_derive")
+        );
+        assert_eq!(crate_name("serde-3kx9sd1ebnsvu"), Some("serde"));
+        assert_eq!(crate_name("README"), None);
+    }
+
+    #[test]
+    fn test_scan_target_dir() {
+        let target_dir =
+            std::env::temp_dir().join(format!("cursor-rust-tools-target-{}", std::process::id()));
+        let files = [
+            ("CACHEDIR.TAG", 10),
+            ("debug/deps/libserde-1f2e3d4c5b6a7980.rlib", 100),
+            (
+                "debug/incremental/serde-3kx9sd1ebnsvu/s-1/query-cache.bin",
+                50,
+            ),
+            ("debug/my-app", 20),
+            (
+                "x86_64-unknown-linux-gnu/release/deps/libc-1f2e3d4c5b6a7980.d",
+                5,
+            ),
+        ];
+        for (file, size) in files {
+            let path = target_dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, vec![0; size]).unwrap();
+        }
+        let (size, profiles) = scan_target_dir(&target_dir);
+        std::fs::remove_dir_all(&target_dir).unwrap();
+
+        assert_eq!(size, 185);
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "debug");
+        assert_eq!(profiles[0].size, 170);
+        assert_eq!(profiles[0].incremental, 50);
+        assert_eq!(profiles[0].crates["serde"], 150);
+        assert_eq!(profiles[1].name, "x86_64-unknown-linux-gnu/release");
+        assert_eq!(profiles[1].crates["libc"], 5);
+    }
+}
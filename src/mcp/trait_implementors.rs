@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use crate::lsp::PositionEncoding;
+use anyhow::Result;
+use lsp_types::Position;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use proc_macro2::LineColumn;
+use serde_json::json;
+use syn::{
+    ItemImpl,
+    visit::{self, Visit},
+};
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_symbol_position_in_file,
+        get_info_from_request, lsp_error_response,
+    },
+};
+
+pub struct TraitImplementors;
+
+impl TraitImplementors {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "trait_implementors".to_string(),
+            description: Some("List every type in the workspace that implements a trait, with the header and location of each impl block.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The line number of the trait name in the file (1 based)"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the trait"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file containing the trait name"
+                    }
+                },
+                "required": ["line", "symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                tracing::debug!("Sending MCP notification for symbol references");
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                tracing::debug!("Sending MCP notification for symbol references");
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let symbol = request.get_symbol()?;
+
+    let position = find_symbol_position_in_file(&project, relative_file, &symbol, line)
+        .await
+        .map_err(|e| lsp_error_response(&e))?;
+
+    let Some(references) = project
+        .lsp
+        .find_references(relative_file, position)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(ErrorCode::NotFound, "No references found"));
+    };
+
+    // Reference start positions per file
+    let mut positions_per_file: HashMap<PathBuf, Vec<Position>> = HashMap::new();
+    for reference in references {
+        let Ok(path) = reference.uri.to_file_path() else {
+            continue;
+        };
+        positions_per_file
+            .entry(path)
+            .or_default()
+            .push(reference.range.start);
+    }
+
+    let root = project.project.root();
+    let encoding = project.lsp.position_encoding();
+    let mut implementors = BTreeMap::new();
+    for (path, positions) in positions_per_file {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(syntax) = syn::parse_file(&content) else {
+            tracing::debug!("Failed to parse {:?}", path);
+            continue;
+        };
+        let source: Vec<&str> = content.lines().collect();
+        let mut visitor = TraitImplVisitor {
+            references: &positions,
+            source: &source,
+            encoding,
+            impls: Vec::new(),
+        };
+        visitor.visit_file(&syntax);
+        let path = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        for (start, header_end) in visitor.impls {
+            let header = source
+                .get(start - 1..header_end.min(source.len()))
+                .unwrap_or_default()
+                .iter()
+                .map(|line| line.trim())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let header = header.trim_end_matches('{').trim_end();
+            implementors
+                .entry(path.clone())
+                .or_insert_with(Vec::new)
+                .push(format!("- {start}: `{header}`"));
+        }
+    }
+
+    if implementors.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!("No implementations of `{symbol}` found"),
+        ));
+    }
+
+    let count: usize = implementors.values().map(Vec::len).sum();
+    let mut contents = format!("{count} implementations of `{symbol}`\n");
+    for (path, impls) in implementors {
+        contents.push_str(&format!("## {}\n{}\n", path.display(), impls.join("\n")));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text: contents }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+/// Collects the trait impls whose trait name is one of the references, as
+/// (1-based impl line, 1-based line of the opening brace). A reference elsewhere in
+/// the impl header, e.g. in a bound or generic argument, doesn't count.
+struct TraitImplVisitor<'a> {
+    references: &'a [Position],
+    source: &'a [&'a str],
+    encoding: PositionEncoding,
+    impls: Vec<(usize, usize)>,
+}
+
+impl TraitImplVisitor<'_> {
+    /// The LSP position of a syn location, whose column counts characters
+    fn position(&self, location: LineColumn) -> Position {
+        let line = self
+            .source
+            .get(location.line - 1)
+            .copied()
+            .unwrap_or_default();
+        Position::new(
+            location.line as u32 - 1,
+            self.encoding.char_column(line, location.column),
+        )
+    }
+}
+
+impl<'ast> Visit<'ast> for TraitImplVisitor<'_> {
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if let Some((_, path, _)) = &node.trait_
+            && let Some(segment) = path.segments.last()
+        {
+            let name = self.position(segment.ident.span().start());
+            if self.references.contains(&name) {
+                self.impls.push((
+                    node.impl_token.span.start().line,
+                    node.brace_token.span.open().start().line,
+                ));
+            }
+        }
+        visit::visit_item_impl(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trait_impl_visitor() {
+        let content = "impl Show for Ä {}\nimpl Other for Show {}\nimpl<T: Show> Other for T {}\nimpl fmt::Show for\n    B {\n}\n";
+        let source: Vec<&str> = content.lines().collect();
+        // The names of the trait in the impls, after a multi byte character on the
+        // first line
+        let references = [
+            Position::new(0, 5),
+            Position::new(1, 15),
+            Position::new(2, 8),
+            Position::new(3, 10),
+        ];
+        let mut visitor = TraitImplVisitor {
+            references: &references,
+            source: &source,
+            encoding: PositionEncoding::Utf16,
+            impls: Vec::new(),
+        };
+        visitor.visit_file(&syn::parse_file(content).unwrap());
+        assert_eq!(visitor.impls, [(1, 1), (4, 5)]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use crate::{
+    context::{Context, ProjectContext},
+    lsp::format_marked_string,
+};
+use anyhow::Result;
+use lsp_types::HoverContents;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_identifier_position,
+        get_info_from_request, lsp_error_response,
+    },
+};
+
+pub struct TypeLayout;
+
+impl TypeLayout {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "type_layout".to_string(),
+            description: Some("Get the memory layout (size, alignment, niches) of a type defined in a file. Uses rust-analyzer by default. Optionally compiles the project with `-Zprint-type-sizes` on nightly for a per-field / per-variant breakdown.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the struct, enum or union"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file containing the type definition"
+                    },
+                    "use_nightly": {
+                        "type": "boolean",
+                        "description": "If true, compile with `cargo +nightly rustc -- -Zprint-type-sizes` to get the detailed layout. Requires a nightly toolchain. Default is false."
+                    }
+                },
+                "required": ["symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let symbol = request.get_symbol()?;
+    let use_nightly = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("use_nightly"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    if use_nightly {
+        let lines = project
+            .cargo_remote
+            .print_type_sizes(&project.project.root().join(relative_file), &symbol)
+            .await
+            .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+        if lines.is_empty() {
+            return Err(error_response(
+                ErrorCode::NotFound,
+                &format!(
+                    "No layout for `{symbol}` found. Is a nightly toolchain installed and is the type used in the crate?"
+                ),
+            ));
+        }
+        return Ok(CallToolResponse {
+            content: vec![ToolResponseContent::Text {
+                text: lines.join("\n"),
+            }],
+            is_error: None,
+            meta: None,
+        });
+    }
+
+    let symbols = match project.lsp.document_symbols(relative_file).await {
+        Ok(Some(symbols)) => symbols,
+        Ok(None) => return Err(error_response(ErrorCode::NotFound, "No symbols found")),
+        Err(e) => {
+            return Err(lsp_error_response(&e));
+        }
+    };
+    let Some(type_symbol) = symbols.iter().find(|s| s.name == symbol) else {
+        return Err(error_response(
+            ErrorCode::SymbolNotFound,
+            &format!("Type {symbol} not found in file {relative_file}"),
+        ));
+    };
+
+    let position = find_identifier_position(
+        project.project.root().join(relative_file),
+        type_symbol.location.range,
+        &symbol,
+        project.lsp.position_encoding(),
+    )
+    .map_err(|e| error_response(ErrorCode::IoFailed, &e.to_string()))?
+    .unwrap_or(type_symbol.location.range.start);
+
+    let Some(hover) = project
+        .lsp
+        .hover(relative_file, position)
+        .await
+        .map_err(|e| lsp_error_response(&e))?
+    else {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            "No hover information found",
+        ));
+    };
+
+    let hover = match hover.contents {
+        HoverContents::Scalar(s) => format_marked_string(&s),
+        HoverContents::Array(a) => a
+            .into_iter()
+            .map(|s| format_marked_string(&s))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        HoverContents::Markup(m) => m.value,
+    };
+
+    // rust-analyzer renders the layout as `size = 16 (0x10), align = 0x8, niches = 1`
+    let layout = hover
+        .lines()
+        .filter(|line| line.contains("size = ") || line.contains("align = "))
+        .collect::<Vec<_>>();
+    if layout.is_empty() {
+        return Err(error_response(
+            ErrorCode::NotFound,
+            &format!(
+                "rust-analyzer returned no layout for `{symbol}`. Generic types have no layout, try `use_nightly`"
+            ),
+        ));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!("{symbol}: {}", layout.join("\n")),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
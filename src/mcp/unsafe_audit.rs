@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+use syn::{
+    ExprUnsafe, ImplItemFn, ItemFn, ItemImpl, ItemMod, ItemTrait, TraitItemFn, Type,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, get_info_from_request, rust_files},
+};
+
+pub struct UnsafeAudit;
+
+impl UnsafeAudit {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "unsafe_audit".to_string(),
+            description: Some("List all `unsafe` code in the project: unsafe blocks, functions, impls and traits with their location and the enclosing item, grouped by file. Use this to review the unsafe usage of a codebase.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let root = project.project.root().clone();
+    let mut contents = String::new();
+    let mut count = 0;
+    for file in rust_files(&root) {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let Ok(syntax) = syn::parse_file(&content) else {
+            tracing::debug!("Failed to parse {:?}", file);
+            continue;
+        };
+        let mut visitor = UnsafeVisitor::default();
+        visitor.visit_file(&syntax);
+        if visitor.items.is_empty() {
+            continue;
+        }
+        count += visitor.items.len();
+        let lines: Vec<&str> = content.lines().collect();
+        let path = file.strip_prefix(&root).unwrap_or(&file).display();
+        contents.push_str(&format!("## {path}\n"));
+        for item in visitor.items {
+            let source = lines
+                .get(item.line - 1)
+                .map(|l| l.trim())
+                .unwrap_or_default();
+            let enclosing = if item.enclosing.is_empty() {
+                String::new()
+            } else {
+                format!(" in `{}`", item.enclosing)
+            };
+            contents.push_str(&format!(
+                "- {} ({}){enclosing}: `{source}`\n",
+                item.line, item.kind
+            ));
+        }
+    }
+
+    if contents.is_empty() {
+        return Err(error_response(ErrorCode::NotFound, "No unsafe code found"));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!("{count} unsafe items\n{contents}"),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
+
+struct UnsafeItem {
+    /// 1-based
+    line: usize,
+    kind: &'static str,
+    /// Path of the enclosing modules, impls and functions, e.g. `io::Reader::read`
+    enclosing: String,
+}
+
+#[derive(Default)]
+struct UnsafeVisitor {
+    items: Vec<UnsafeItem>,
+    scope: Vec<String>,
+}
+
+impl UnsafeVisitor {
+    fn push(&mut self, line: usize, kind: &'static str) {
+        self.items.push(UnsafeItem {
+            line,
+            kind,
+            enclosing: self.scope.join("::"),
+        });
+    }
+
+    fn scoped(&mut self, name: String, visit: impl FnOnce(&mut Self)) {
+        self.scope.push(name);
+        visit(self);
+        self.scope.pop();
+    }
+}
+
+impl<'ast> Visit<'ast> for UnsafeVisitor {
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        self.scoped(node.ident.to_string(), |v| visit::visit_item_mod(v, node));
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        if let Some(unsafety) = node.sig.unsafety {
+            self.push(unsafety.span.start().line, "unsafe fn");
+        }
+        self.scoped(node.sig.ident.to_string(), |v| {
+            visit::visit_item_fn(v, node)
+        });
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if let Some(unsafety) = node.unsafety {
+            self.push(unsafety.span.start().line, "unsafe impl");
+        }
+        let name = match node.self_ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        }
+        .unwrap_or_else(|| "impl".to_string());
+        self.scoped(name, |v| visit::visit_item_impl(v, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        if let Some(unsafety) = node.sig.unsafety {
+            self.push(unsafety.span.start().line, "unsafe fn");
+        }
+        self.scoped(node.sig.ident.to_string(), |v| {
+            visit::visit_impl_item_fn(v, node)
+        });
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        if let Some(unsafety) = node.unsafety {
+            self.push(unsafety.span.start().line, "unsafe trait");
+        }
+        self.scoped(node.ident.to_string(), |v| visit::visit_item_trait(v, node));
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        if let Some(unsafety) = node.sig.unsafety {
+            self.push(unsafety.span.start().line, "unsafe fn");
+        }
+        self.scoped(node.sig.ident.to_string(), |v| {
+            visit::visit_trait_item_fn(v, node)
+        });
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
+        self.push(node.span().start().line, "unsafe block");
+        visit::visit_expr_unsafe(self, node);
+    }
+}
@@ -1,18 +1,74 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::cargo_remote::{BuildSelection, PackageId, TargetCheck, TestResult};
 use crate::context::{Context, ProjectContext};
+use crate::lsp::{PositionEncoding, symbol_matches, workspace_edit_changes};
+use crate::mcp::{McpNotification, current_session, progress, sessions::scope};
 use anyhow::Result;
-use lsp_types::Position;
-use mcp_core::types::{CallToolRequest, CallToolResponse, ToolResponseContent};
+use ignore::WalkBuilder;
+use lsp_types::{Position, Range, SymbolInformation, SymbolKind, WorkspaceEdit};
+use mcp_core::types::{CallToolRequest, CallToolResponse};
 
-pub fn error_response(message: &str) -> CallToolResponse {
-    CallToolResponse {
-        content: vec![ToolResponseContent::Text {
-            text: message.to_string(),
-        }],
-        is_error: Some(true),
-        meta: None,
+pub use super::errors::{ErrorCode, SymbolNotFound, error_response, lsp_error_response};
+
+/// A sender for the output of a cargo command which forwards every line to the UI
+/// as an [`McpNotification::Output`] and reports it as progress of the tool call.
+/// The task finishes once the sender is dropped and all lines were forwarded.
+pub(super) fn output_forwarder(
+    context: &Context,
+    project: PathBuf,
+) -> (flume::Sender<String>, tokio::task::JoinHandle<()>) {
+    let (sender, receiver) = flume::unbounded::<String>();
+    let context = context.clone();
+    // The lines belong to the client of the tool call which runs cargo
+    let session = current_session();
+    let progress = progress::current();
+    let forwarded = tokio::spawn(scope(session, async move {
+        while let Ok(line) = receiver.recv_async().await {
+            if let Some(progress) = &progress {
+                progress.step(&line);
+            }
+            let notification = McpNotification::Output {
+                line,
+                project: project.clone(),
+            };
+            if let Err(e) = context.send_mcp_notification(notification).await {
+                tracing::error!("Failed to send MCP notification: {}", e);
+            }
+        }
+    }));
+    (sender, forwarded)
+}
+
+/// The optional `package`, `features`, `no_default_features` and `target` arguments
+/// of the cargo tools
+pub(super) fn get_build_selection(request: &CallToolRequest) -> BuildSelection {
+    let arguments = request.arguments.as_ref();
+    let string = |name: &str| {
+        arguments
+            .and_then(|args| args.get(name))
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+    };
+    BuildSelection {
+        package: string("package"),
+        features: arguments
+            .and_then(|args| args.get("features"))
+            .and_then(|v| v.as_array())
+            .map(|features| {
+                features
+                    .iter()
+                    .filter_map(|f| f.as_str().map(|f| f.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        no_default_features: arguments
+            .and_then(|args| args.get("no_default_features"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        target: string("target"),
     }
 }
 
@@ -29,7 +85,7 @@ impl RequestExtension for CallToolRequest {
             .as_ref()
             .and_then(|args| args.get("line"))
             .and_then(|v| v.as_u64())
-            .ok_or_else(|| error_response("Line is required"))?;
+            .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Line is required"))?;
         // I'm not sure about this. Cursor just now used 0 based indexing
         // Cursor gives llm's line numbers as 1-based, but the LSP uses 0-based
         Ok(number)
@@ -46,7 +102,7 @@ impl RequestExtension for CallToolRequest {
             .as_ref()
             .and_then(|args| args.get("symbol"))
             .and_then(|v| v.as_str())
-            .ok_or_else(|| error_response("Symbol is required"))
+            .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "Symbol is required"))
             .map(|s| s.to_string())
     }
 
@@ -55,7 +111,7 @@ impl RequestExtension for CallToolRequest {
             .as_ref()
             .and_then(|args| args.get("file"))
             .and_then(|v| v.as_str())
-            .ok_or_else(|| error_response("File is required"))
+            .ok_or_else(|| error_response(ErrorCode::InvalidArguments, "File is required"))
             .map(|s| s.to_string())
     }
 }
@@ -68,13 +124,16 @@ pub async fn get_info_from_request(
     let file = request.get_file()?;
     let absolute_path = PathBuf::from(file.clone());
     let Some(project) = context.get_project_by_path(&absolute_path).await else {
-        return Err(error_response("No project found for file {file}"));
+        return Err(error_response(
+            ErrorCode::ProjectNotFound,
+            &format!("No project found for file {file}"),
+        ));
     };
 
     let relative_path = project
         .project
         .relative_path(&file)
-        .map_err(|e| error_response(&e))?;
+        .map_err(|e| error_response(ErrorCode::ProjectNotFound, &e))?;
 
     Ok((project, relative_path, absolute_path))
 }
@@ -84,24 +143,67 @@ pub async fn find_symbol_position_in_file(
     relative_file: &str,
     symbol: &str,
     line: u64,
-) -> Result<Position, String> {
-    let symbols = match project.lsp.document_symbols(relative_file).await {
-        Ok(Some(symbols)) => symbols,
-        Ok(None) => return Err("No symbols found".to_string()),
-        Err(e) => return Err(e.to_string()),
+) -> Result<Position> {
+    let Some(symbols) = project.lsp.document_symbols(relative_file).await? else {
+        return Err(SymbolNotFound("No symbols found".to_string()).into());
     };
-    for symbol in symbols {
-        if symbol.location.range.start.line == line as u32 {
-            return Ok(symbol.location.range.start);
-        }
+    let on_line = |s: &&SymbolInformation| s.location.range.start.line == line as u32;
+    // Prefer the symbol with the name on the line, then any symbol on the line, then
+    // the symbol with the (possibly qualified) name anywhere in the file
+    if let Some(item) = symbols
+        .iter()
+        .find(|s| on_line(s) && symbol_matches(s, symbol))
+        .or_else(|| symbols.iter().find(|s| symbol_matches(s, symbol)))
+    {
+        let position = find_identifier_position(
+            project.project.root().join(relative_file),
+            item.location.range,
+            &item.name,
+            project.lsp.position_encoding(),
+        )?
+        .unwrap_or(item.location.range.start);
+        return Ok(position);
     }
-    Err(format!("Symbol {symbol} not found in file {relative_file}"))
+    if let Some(item) = symbols.iter().find(on_line) {
+        return Ok(item.location.range.start);
+    }
+    Err(SymbolNotFound(format!("Symbol {symbol} not found in file {relative_file}")).into())
+}
+
+/// Find an item by name (preferring the one starting at `line`) and return its kind
+/// and the position of its name
+pub async fn find_item_in_file(
+    project: &Arc<ProjectContext>,
+    relative_file: &str,
+    symbol: &str,
+    line: u64,
+) -> Result<(SymbolKind, Position)> {
+    let Some(symbols) = project.lsp.document_symbols(relative_file).await? else {
+        return Err(SymbolNotFound("No symbols found".to_string()).into());
+    };
+    let Some(item) = symbols
+        .iter()
+        .find(|s| symbol_matches(s, symbol) && s.location.range.start.line == line as u32)
+        .or_else(|| symbols.iter().find(|s| symbol_matches(s, symbol)))
+    else {
+        return Err(
+            SymbolNotFound(format!("Symbol {symbol} not found in file {relative_file}")).into(),
+        );
+    };
+    let position = find_identifier_position(
+        project.project.root().join(relative_file),
+        item.location.range,
+        &item.name,
+        project.lsp.position_encoding(),
+    )?
+    .unwrap_or(item.location.range.start);
+    Ok((item.kind, position))
 }
 
 /// Returns the lines between start_line and end_line (inclusive) from the given file path
 /// Optionally includes prefix lines before start_line and suffix lines after end_line
-/// Line numbers are 0-based
-/// Returns None if any line number is out of bounds after adjusting for prefix/suffix
+/// Line numbers are 0-based, the end is clamped to the last line of the file
+/// Returns None if the file is empty or the range starts after its last line
 pub fn get_file_lines(
     file_path: impl AsRef<Path>,
     start_line: u32,
@@ -111,21 +213,356 @@ pub fn get_file_lines(
 ) -> std::io::Result<Option<String>> {
     let content = std::fs::read_to_string(file_path)?;
     let lines: Vec<&str> = content.lines().collect();
+    let Some(last) = lines.len().checked_sub(1) else {
+        return Ok(None);
+    };
 
     // Calculate actual line range accounting for prefix/suffix
-    let start = start_line.saturating_sub(prefix as u32);
-    let mut end = end_line.saturating_add(suffix as u32);
-
-    if end > lines.len() as u32 {
-        end = lines.len() as u32;
-    }
+    let start = start_line.saturating_sub(prefix as u32) as usize;
+    let end = (end_line.saturating_add(suffix as u32) as usize).min(last);
 
     // Check if line range is valid
-    if start > end || end >= lines.len() as u32 {
+    if start > end {
         return Ok(None);
     }
 
     // Extract and join the requested lines
-    let selected_lines = lines[start as usize..=end as usize].join("\n");
+    let selected_lines = lines[start..=end].join("\n");
     Ok(Some(selected_lines))
 }
+
+/// Returns a range covering the full lines between start_line and end_line (inclusive)
+/// Line numbers are 0-based
+pub fn full_line_range(
+    file_path: impl AsRef<Path>,
+    start_line: u32,
+    end_line: u32,
+    encoding: PositionEncoding,
+) -> std::io::Result<Range> {
+    let content = std::fs::read_to_string(file_path)?;
+    let end_character = content
+        .lines()
+        .nth(end_line as usize)
+        .map(|line| encoding.columns(line))
+        .unwrap_or(0);
+    Ok(Range {
+        start: Position::new(start_line, 0),
+        end: Position::new(end_line, end_character),
+    })
+}
+
+/// Render the edits of a workspace edit as a markdown preview
+pub fn format_workspace_edit(edit: &WorkspaceEdit) -> String {
+    let mut output = String::new();
+    for (url, edits) in workspace_edit_changes(edit) {
+        output.push_str(&format!("### {}\n", url.path()));
+        for edit in edits {
+            output.push_str(&format!(
+                "- {}:{}-{}:{} replace with:\n```\n{}\n```\n",
+                edit.range.start.line,
+                edit.range.start.character,
+                edit.range.end.line,
+                edit.range.end.character,
+                edit.new_text
+            ));
+        }
+    }
+    output
+}
+
+/// Find the position of `name` as a whole word within `range` of the file.
+/// Useful to hover the name of an item instead of its start (attributes, visibility, ...)
+pub fn find_identifier_position(
+    file_path: impl AsRef<Path>,
+    range: Range,
+    name: &str,
+    encoding: PositionEncoding,
+) -> std::io::Result<Option<Position>> {
+    let content = std::fs::read_to_string(file_path)?;
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    for (index, line) in content
+        .lines()
+        .enumerate()
+        .skip(range.start.line as usize)
+        .take(range.end.line.saturating_sub(range.start.line) as usize + 1)
+    {
+        for (offset, _) in line.match_indices(name) {
+            let before = line[..offset].chars().next_back();
+            let after = line[offset + name.len()..].chars().next();
+            if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+                continue;
+            }
+            let character = encoding.columns(&line[..offset]);
+            return Ok(Some(Position::new(index as u32, character)));
+        }
+    }
+    Ok(None)
+}
+
+/// Group the errors of a target check by package with dependencies first, as a
+/// failing dependency is usually the root cause of the errors in the workspace
+pub fn format_target_check(check: &TargetCheck, target: &str) -> String {
+    if check.success {
+        return format!("The project compiles for `{target}`");
+    }
+    if check.errors.is_empty() {
+        return format!("Checking for `{target}` failed:\n{}", check.stderr);
+    }
+    let mut packages: Vec<(&PackageId, Vec<&str>)> = Vec::new();
+    for (package, message) in &check.errors {
+        match packages.iter_mut().find(|(p, _)| *p == package) {
+            Some((_, messages)) => messages.push(&message.rendered),
+            None => packages.push((package, vec![&message.rendered])),
+        }
+    }
+    packages.sort_by_key(|(package, _)| package.is_local);
+    let mut output = format!("The project does not compile for `{target}`.\n");
+    for (package, messages) in packages {
+        let kind = if package.is_local {
+            "workspace crate"
+        } else {
+            "dependency"
+        };
+        output.push_str(&format!(
+            "\n## {} ({kind}, {} errors)\n",
+            package.name,
+            messages.len()
+        ));
+        for message in messages {
+            output.push_str(message);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Keep the messages from the start that fit into `max_bytes` together and replace
+/// the others with a summary, so errors should come first. A failing build can
+/// print more than the client accepts.
+pub fn limit_messages(mut messages: Vec<String>, max_bytes: usize) -> Vec<String> {
+    let mut used = 0;
+    let Some(keep) = messages.iter().position(|message| {
+        used += message.len();
+        used > max_bytes
+    }) else {
+        return messages;
+    };
+    let mut dropped = messages.split_off(keep);
+    if messages.is_empty() {
+        messages.push(truncate_text(&dropped.remove(0), max_bytes));
+        if dropped.is_empty() {
+            return messages;
+        }
+    }
+    let count = |level: &str| {
+        dropped
+            .iter()
+            .filter(|message| message.starts_with(level))
+            .count()
+    };
+    messages.push(format!(
+        "[{} more messages ({} errors, {} warnings) were dropped to keep the response small]",
+        dropped.len(),
+        count("error"),
+        count("warning")
+    ));
+    messages
+}
+
+/// Cut `text` to about `max_bytes` by dropping lines from the middle. The start has
+/// the first errors, the end the summary of cargo.
+pub fn truncate_text(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let lines = text.lines().collect::<Vec<_>>();
+    let head_budget = max_bytes * 2 / 3;
+    let mut head_bytes = 0;
+    let head = lines
+        .iter()
+        .take_while(|line| {
+            head_bytes += line.len() + 1;
+            head_bytes <= head_budget
+        })
+        .count();
+    let mut tail_bytes = 0;
+    let tail = lines[head..]
+        .iter()
+        .rev()
+        .take_while(|line| {
+            tail_bytes += line.len() + 1;
+            tail_bytes <= max_bytes - head_budget
+        })
+        .count();
+
+    let mut output = lines[..head].join("\n");
+    if head == 0 {
+        // A single line that is too long on its own
+        let mut end = head_budget;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.push_str(&text[..end]);
+    }
+    let dropped = &lines[head..lines.len() - tail];
+    let dropped_bytes = dropped.iter().map(|line| line.len() + 1).sum::<usize>();
+    output.push_str(&format!(
+        "\n[... {} lines ({dropped_bytes} bytes) were dropped to keep the response small ...]\n",
+        dropped.len()
+    ));
+    output.push_str(&lines[lines.len() - tail..].join("\n"));
+    output
+}
+
+/// Summarize nextest results as JSON, only including the output of failed tests
+pub fn format_test_results(results: &[TestResult]) -> String {
+    let count = |event: &str| results.iter().filter(|r| r.event == event).count();
+    let failures = results
+        .iter()
+        .filter(|r| r.event == "failed")
+        .collect::<Vec<_>>();
+    let passed = results
+        .iter()
+        .filter(|r| r.event == "ok")
+        .map(|r| r.name.as_str())
+        .collect::<Vec<_>>();
+    let summary = serde_json::json!({
+        "passed": count("ok"),
+        "failed": count("failed"),
+        "ignored": count("ignored"),
+        "failures": failures,
+        "passed_tests": passed,
+    });
+    serde_json::to_string_pretty(&summary).unwrap_or_else(|e| e.to_string())
+}
+
+/// All Rust source files of the project, respecting `.gitignore`
+pub fn rust_files(root: &Path) -> Vec<PathBuf> {
+    WalkBuilder::new(root)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("rs"))
+        .collect()
+}
+
+/// A minimal line based diff of two (small) files, prefixing changed lines with `-` / `+`
+/// and keeping `context` unchanged lines around every change
+pub fn line_diff(old: &str, new: &str, context: usize) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    // Longest common subsequence lengths of the suffixes
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+    let changed = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (kind, _))| *kind != ' ')
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    let mut output = Vec::new();
+    let mut last_printed = None;
+    for (index, (kind, line)) in lines.iter().enumerate() {
+        let near_change = changed.iter().any(|&c| c.abs_diff(index) <= context);
+        if !near_change {
+            continue;
+        }
+        if last_printed.is_some_and(|last| last + 1 != index) {
+            output.push("...".to_string());
+        }
+        output.push(format!("{kind}{line}"));
+        last_printed = Some(index);
+    }
+    output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_messages() {
+        let messages = ["error: a", "error: b", "warning: c", "warning: d"].map(String::from);
+        assert_eq!(limit_messages(messages.to_vec(), 100), messages);
+        assert_eq!(
+            limit_messages(messages.to_vec(), 18),
+            [
+                "error: a",
+                "error: b",
+                "[2 more messages (0 errors, 2 warnings) were dropped to keep the response small]"
+            ]
+        );
+        let limited = limit_messages(vec!["x".repeat(100)], 30);
+        assert_eq!(limited.len(), 1);
+        assert!(limited[0].starts_with(&format!("{}\n[... 1 lines", "x".repeat(20))));
+    }
+
+    #[test]
+    fn test_truncate_text() {
+        let text = (1..=10)
+            .map(|line| format!("line {line:02}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(truncate_text(&text, 1000), text);
+        // Each line is 8 bytes with its newline, 2/3 of the budget go to the start
+        assert_eq!(
+            truncate_text(&text, 30),
+            "line 01\nline 02\n[... 7 lines (56 bytes) were dropped to keep the response small ...]\nline 10"
+        );
+    }
+
+    #[test]
+    fn test_get_file_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "cursor-rust-tools-file-lines-{}.rs",
+            std::process::id()
+        ));
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+        assert_eq!(get_file_lines(&path, 1, 1, 0, 0).unwrap().unwrap(), "b");
+        // The suffix is clamped to the last line
+        assert_eq!(
+            get_file_lines(&path, 1, 2, 1, 5).unwrap().unwrap(),
+            "a\nb\nc"
+        );
+        assert_eq!(get_file_lines(&path, 2, 9, 0, 0).unwrap().unwrap(), "c");
+        assert!(get_file_lines(&path, 3, 3, 0, 0).unwrap().is_none());
+        std::fs::write(&path, "").unwrap();
+        assert!(get_file_lines(&path, 0, 0, 2, 2).unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let old = "[dependencies]\nanyhow = \"1\"\nserde = \"1\"\n\n[dev-dependencies]\n";
+        let new = "[dependencies]\nanyhow = \"1\"\nregex = \"1.11\"\nserde = \"1\"\n\n[dev-dependencies]\n";
+        assert_eq!(
+            line_diff(old, new, 1),
+            " anyhow = \"1\"\n+regex = \"1.11\"\n serde = \"1\""
+        );
+        assert_eq!(line_diff(old, old, 1), "");
+        assert_eq!(line_diff("a\nb", "a\nc", 0), "-b\n+c");
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{
+        ErrorCode, RequestExtension, error_response, find_item_in_file, get_info_from_request,
+        lsp_error_response,
+    },
+};
+
+pub struct ViewIr;
+
+impl ViewIr {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "view_ir".to_string(),
+            description: Some("Get rust-analyzer's lowered representation (HIR or MIR) of a function. Shows desugared expressions, inferred types, moves and borrows, which helps to debug confusing type inference or borrow checker errors.".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "line": {
+                        "type": "number",
+                        "description": "The line number of the function in the file (1 based)"
+                    },
+                    "symbol": {
+                        "type": "string",
+                        "description": "The name of the function"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the file containing the function"
+                    },
+                    "representation": {
+                        "type": "string",
+                        "enum": ["hir", "mir"],
+                        "description": "Which representation to return. Default is `hir`."
+                    }
+                },
+                "required": ["line", "symbol", "file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    relative_file: &str,
+    request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let line = request.get_line()?;
+    let symbol = request.get_symbol()?;
+    let representation = request
+        .arguments
+        .as_ref()
+        .and_then(|args| args.get("representation"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("hir")
+        .to_string();
+
+    let (_, position) = find_item_in_file(&project, relative_file, &symbol, line)
+        .await
+        .map_err(|e| lsp_error_response(&e))?;
+
+    let text = match representation.as_str() {
+        "hir" => project.lsp.view_hir(relative_file, position).await,
+        "mir" => project.lsp.view_mir(relative_file, position).await,
+        other => {
+            return Err(error_response(
+                ErrorCode::InvalidArguments,
+                &format!("Unknown representation `{other}`, expected `hir` or `mir`"),
+            ));
+        }
+    }
+    .map_err(|e| lsp_error_response(&e))?;
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text {
+            text: format!("```\n{text}\n```"),
+        }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use crate::context::{Context, ProjectContext};
+use anyhow::Result;
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, Tool, ToolResponseContent},
+};
+use serde_json::json;
+
+use super::{
+    McpNotification,
+    utils::{ErrorCode, error_response, format_target_check, get_info_from_request},
+};
+
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Dependencies which commonly block wasm builds and how to fix them
+const KNOWN_BLOCKERS: &[(&str, &str)] = &[
+    (
+        "getrandom",
+        "enable the `js` feature (getrandom 0.2) or the `wasm_js` backend (getrandom 0.3)",
+    ),
+    (
+        "mio",
+        "networking and tokio's `net`/`fs`/`process` features are not available, use `wasm-bindgen-futures` or a browser API crate instead",
+    ),
+    (
+        "ring",
+        "use a pure Rust crypto crate or enable the wasm support of the crate depending on `ring`",
+    ),
+    (
+        "openssl-sys",
+        "switch the dependency to `rustls` or a pure Rust TLS backend",
+    ),
+];
+
+pub struct WasmCheck;
+
+impl WasmCheck {
+    pub fn tool() -> Tool {
+        Tool {
+            name: "wasm_check".to_string(),
+            description: Some(format!(
+                "Check whether the project compiles for `{WASM_TARGET}`. Errors are grouped by crate, dependencies first, with hints for dependencies that commonly block wasm builds."
+            )),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "file": {
+                        "type": "string",
+                        "description": "The absolute path to the `Cargo.toml` file of the project to check"
+                    }
+                },
+                "required": ["file"]
+            }),
+        }
+    }
+
+    pub fn call(context: Context) -> ToolHandlerFn {
+        Box::new(move |request: CallToolRequest| {
+            let clone = context.clone();
+            Box::pin(async move {
+                let (project, relative_file, absolute_file) =
+                    match get_info_from_request(&clone, &request).await {
+                        Ok(info) => info,
+                        Err(response) => return response,
+                    };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Request {
+                        content: request.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                let response = match handle_request(project, &relative_file, &request).await {
+                    Ok(response) => response,
+                    Err(response) => response,
+                };
+                if let Err(e) = clone
+                    .send_mcp_notification(McpNotification::Response {
+                        content: response.clone(),
+                        project: absolute_file.clone(),
+                    })
+                    .await
+                {
+                    tracing::error!("Failed to send MCP notification: {}", e);
+                }
+                response
+            })
+        })
+    }
+}
+
+async fn handle_request(
+    project: Arc<ProjectContext>,
+    _relative_file: &str,
+    _request: &CallToolRequest,
+) -> Result<CallToolResponse, CallToolResponse> {
+    let check = project
+        .cargo_remote
+        .check_target(WASM_TARGET, &[])
+        .await
+        .map_err(|e| error_response(ErrorCode::CargoFailed, &format!("{e:?}")))?;
+
+    let mut text = format_target_check(&check, WASM_TARGET);
+    if !check.success && check.errors.is_empty() {
+        text.push_str(&format!(
+            "\nIf the target is missing, install it with `rustup target add {WASM_TARGET}`"
+        ));
+    }
+    let hints = KNOWN_BLOCKERS
+        .iter()
+        .filter(|(name, _)| {
+            check
+                .errors
+                .iter()
+                .any(|(package, _)| package.name == *name)
+        })
+        .map(|(name, hint)| format!("- `{name}`: {hint}"))
+        .collect::<Vec<_>>();
+    if !hints.is_empty() {
+        text.push_str(&format!("\nKnown blockers:\n{}\n", hints.join("\n")));
+    }
+
+    Ok(CallToolResponse {
+        content: vec![ToolResponseContent::Text { text }],
+        is_error: None,
+        meta: None,
+    })
+}
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use mcp_core::{
+    tools::ToolHandlerFn,
+    types::{CallToolRequest, CallToolResponse, ToolResponseContent},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::context::Context;
+
+/// Chat services cut off longer messages
+const MAX_MESSAGE_LENGTH: usize = 1500;
+
+/// A webhook which receives a message for tool calls
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// The tools to notify about, all tools if empty
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Only notify about failed tool calls
+    #[serde(default)]
+    pub only_failures: bool,
+}
+
+impl WebhookConfig {
+    fn matches(&self, tool: &str, failed: bool) -> bool {
+        (self.tools.is_empty() || self.tools.iter().any(|t| t == tool))
+            && (failed || !self.only_failures)
+    }
+}
+
+/// Wrap a tool handler so that its results are posted to the webhooks. The
+/// wrapper sees the request and the response of the same call, so concurrent calls
+/// on the same file can't be confused.
+pub fn track(context: &Context, handler: ToolHandlerFn) -> ToolHandlerFn {
+    let handler = Arc::new(handler);
+    let context = context.clone();
+    Box::new(move |request: CallToolRequest| {
+        let handler = handler.clone();
+        let context = context.clone();
+        Box::pin(async move {
+            let webhooks = context.webhooks();
+            if webhooks.is_empty() {
+                return handler(request).await;
+            }
+            let tool = request.name.clone();
+            let file = request
+                .arguments
+                .as_ref()
+                .and_then(|args| args.get("file"))
+                .and_then(|file| file.as_str())
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            let response = handler(request).await;
+            notify_webhooks(&webhooks, &tool, &file, &response);
+            response
+        })
+    })
+}
+
+/// Post the result of a tool call to all matching webhooks
+fn notify_webhooks(
+    webhooks: &[WebhookConfig],
+    tool: &str,
+    path: &Path,
+    response: &CallToolResponse,
+) {
+    let output = response
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            ToolResponseContent::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let failed = is_failure(tool, response.is_error, &output);
+    let urls = webhooks
+        .iter()
+        .filter(|webhook| webhook.matches(tool, failed))
+        .map(|webhook| webhook.url.clone())
+        .collect::<Vec<_>>();
+    if urls.is_empty() {
+        return;
+    }
+
+    let output = match output.char_indices().nth(MAX_MESSAGE_LENGTH) {
+        Some((index, _)) => format!("{}\n...", &output[..index]),
+        None => output,
+    };
+    let status = if failed { "failed" } else { "succeeded" };
+    // Tools without a file argument work on the whole project
+    let location = if path.as_os_str().is_empty() {
+        String::new()
+    } else {
+        format!(" in `{}`", path.display())
+    };
+    let message = format!("`{tool}` {status}{location}:\n```\n{output}\n```");
+    // Slack reads `text`, Discord reads `content`
+    let payload = json!({ "text": message, "content": message });
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        for url in urls {
+            if let Err(e) = client
+                .post(&url)
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+            {
+                tracing::error!("Failed to send webhook to {}: {}", url, e);
+            }
+        }
+    });
+}
+
+/// Test runs report failures in their output instead of as an error response:
+/// `cargo_test` and `cargo_nextest` in their summary, cargo itself in its test result.
+/// The output of other tools, e.g. a file, may contain the same text.
+fn is_failure(tool: &str, is_error: Option<bool>, output: &str) -> bool {
+    if is_error == Some(true) {
+        return true;
+    }
+    matches!(tool, "cargo_test" | "cargo_nextest")
+        && (output.contains("test result: FAILED") || failed_tests(output) > 0)
+}
+
+/// The `failed` count of a test summary. The output may be truncated, so it isn't
+/// parsed as JSON.
+fn failed_tests(output: &str) -> usize {
+    output
+        .split("\"failed\": ")
+        .skip(1)
+        .filter_map(|rest| {
+            rest.split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse::<usize>()
+                .ok()
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_matches() {
+        let webhook = WebhookConfig {
+            url: "https://example.com".to_string(),
+            tools: vec!["cargo_test".to_string()],
+            only_failures: true,
+        };
+        assert!(webhook.matches("cargo_test", true));
+        assert!(!webhook.matches("cargo_test", false));
+        assert!(!webhook.matches("cargo_check", true));
+        assert!(is_failure(
+            "cargo_test",
+            None,
+            "test result: FAILED. 1 passed; 1 failed"
+        ));
+        assert!(!is_failure(
+            "cargo_test",
+            None,
+            "test result: ok. 2 passed; 0 failed"
+        ));
+        // The summary of `cargo_test` and `cargo_nextest`
+        let summary =
+            "{\n  \"failed\": 1,\n  \"failures\": [{ \"event\": \"failed\" }],\n  \"ignored\": 0";
+        assert!(is_failure("cargo_nextest", None, summary));
+        // E.g. the source of a test which checks a test summary
+        assert!(!is_failure("symbol_impl", None, summary));
+        assert!(!is_failure(
+            "symbol_impl",
+            None,
+            "test result: FAILED. 1 passed; 1 failed"
+        ));
+        assert!(is_failure("cargo_test", None, summary));
+        assert!(!is_failure(
+            "cargo_test",
+            None,
+            "{\n  \"failed\": 0,\n  \"failures\": [],\n  \"ignored\": 0"
+        ));
+        assert!(is_failure("cargo_check", Some(true), "error"));
+    }
+}
@@ -1,8 +1,17 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 use url::Url;
 
+use crate::docs::DocsConfig;
+use crate::docs::utils::shared_cache_dir;
+use crate::lsp::RustAnalyzerConfig;
+use crate::maintenance::MaintenanceConfig;
+
+/// The size of cargo tool responses if the project doesn't configure it, about 12k tokens
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 50_000;
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransportType {
     Stdio,
@@ -13,6 +22,21 @@ pub enum TransportType {
 pub struct Project {
     pub root: PathBuf,
     pub ignore_crates: Vec<String>,
+    /// The bare metal target used to check `no_std` compatibility
+    #[serde(default)]
+    pub no_std_target: Option<String>,
+    /// Run tests with `cargo nextest` if it is installed
+    #[serde(default)]
+    pub use_nextest: bool,
+    /// Cargo tool responses are truncated to this size
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub docs: DocsConfig,
+    #[serde(default)]
+    pub rust_analyzer: RustAnalyzerConfig,
 }
 
 impl Project {
@@ -21,6 +45,12 @@ impl Project {
         Ok(Self {
             root,
             ignore_crates: vec![],
+            no_std_target: None,
+            use_nextest: false,
+            max_output_bytes: None,
+            maintenance: MaintenanceConfig::default(),
+            docs: DocsConfig::default(),
+            rust_analyzer: RustAnalyzerConfig::default(),
         })
     }
 
@@ -28,6 +58,30 @@ impl Project {
         &self.ignore_crates
     }
 
+    pub fn no_std_target(&self) -> Option<&str> {
+        self.no_std_target.as_deref()
+    }
+
+    pub fn use_nextest(&self) -> bool {
+        self.use_nextest
+    }
+
+    pub fn max_output_bytes(&self) -> usize {
+        self.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES)
+    }
+
+    pub fn maintenance(&self) -> &MaintenanceConfig {
+        &self.maintenance
+    }
+
+    pub fn docs_config(&self) -> &DocsConfig {
+        &self.docs
+    }
+
+    pub fn rust_analyzer_config(&self) -> &RustAnalyzerConfig {
+        &self.rust_analyzer
+    }
+
     pub fn root(&self) -> &PathBuf {
         &self.root
     }
@@ -45,8 +99,36 @@ impl Project {
         ".docs-cache"
     }
 
+    /// `.docs-cache` in the project, or a folder per project in the shared cache
     pub fn cache_dir(&self) -> PathBuf {
-        self.root.join(self.cache_folder())
+        let Some(shared) = self.shared_cache_dir() else {
+            return self.root.join(self.cache_folder());
+        };
+        // The hash of the root keeps projects with the same name apart
+        let hash: String = Sha256::digest(self.root.to_string_lossy().as_bytes())
+            .iter()
+            .take(8)
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let name = self
+            .root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        shared.join("projects").join(format!("{name}-{hash}"))
+    }
+
+    /// The log of the tool calls, if `audit_log` is enabled
+    pub fn audit_log_path(&self) -> PathBuf {
+        self.cache_dir().join("audit.jsonl")
+    }
+
+    /// The cache whose docs all projects share, if the project uses it
+    pub fn shared_cache_dir(&self) -> Option<PathBuf> {
+        if !self.docs.shared_cache {
+            return None;
+        }
+        shared_cache_dir()
     }
 
     pub fn file_uri(&self, relative_path: impl AsRef<Path>) -> Result<Url> {
@@ -1,33 +1,50 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
 use chrono::{DateTime, Utc};
 use egui::{CentralPanel, Color32, Context as EguiContext, RichText, ScrollArea, SidePanel, Ui};
 use flume::Receiver;
 
+use super::transcript::{TranscriptRange, transcript};
 use crate::{
     context::{Context, ContextNotification},
+    docs::{DocsNotification, DocsProgress, cache::CacheStats, utils::format_size},
+    lsp::{Health, LspNotification, ServerStatusParams},
+    mcp::{ClientSession, McpNotification, format_latency},
     project::Project,
 };
 
+/// How many lines rust-analyzer printed to stderr are kept per project for the logs
+const LSP_LOG_LINES: usize = 1000;
+
 #[derive(Clone, Debug)]
 pub struct ProjectDescription {
     pub root: PathBuf,
+    /// The root of the rust-analyzer the project uses, which may be shared
+    pub lsp_root: PathBuf,
     pub name: String,
     pub is_indexing_lsp: bool,
+    /// How far the LSP indexing got in percent, while `is_indexing_lsp`
+    pub lsp_percentage: u32,
     pub is_indexing_docs: bool,
+    pub docs_progress: Option<DocsProgress>,
+    pub lsp_status: Option<ServerStatusParams>,
+    /// The memory usage report of rust-analyzer, once it was requested
+    pub lsp_memory: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 enum SidebarTab {
     Projects,
+    Stats,
     Info,
 }
 
 #[derive(Clone, Debug)]
-pub struct TimestampedEvent(DateTime<Utc>, ContextNotification);
+pub struct TimestampedEvent(pub DateTime<Utc>, pub ContextNotification);
 
 impl PartialEq for TimestampedEvent {
     fn eq(&self, other: &Self) -> bool {
@@ -44,6 +61,17 @@ pub struct App {
     selected_sidebar_tab: SidebarTab,
     selected_event: Option<TimestampedEvent>,
     project_descriptions: Vec<ProjectDescription>,
+    transcript_range: TranscriptRange,
+    /// Loaded in the background when the docs cache of a project is shown
+    cache_stats: Arc<Mutex<HashMap<PathBuf, CacheStats>>>,
+    /// The last line cargo printed per project while a tool call runs
+    cargo_output: HashMap<PathBuf, String>,
+    /// The last lines rust-analyzer printed to stderr per project
+    lsp_logs: HashMap<PathBuf, VecDeque<String>>,
+    /// Show the rust-analyzer logs instead of the events
+    show_lsp_logs: bool,
+    /// Only show the tool calls of this MCP client
+    client_filter: Option<ClientSession>,
 }
 
 impl App {
@@ -61,6 +89,12 @@ impl App {
             selected_sidebar_tab: SidebarTab::Projects,
             selected_event: None,
             project_descriptions,
+            transcript_range: TranscriptRange::LastHour,
+            cache_stats: Default::default(),
+            cargo_output: HashMap::new(),
+            lsp_logs: HashMap::new(),
+            client_filter: None,
+            show_lsp_logs: false,
         }
     }
 
@@ -74,11 +108,48 @@ impl App {
                 continue;
             }
 
+            // Cargo prints a lot while it runs, only the latest line is shown
+            if let ContextNotification::Mcp(McpNotification::Output { line, project }, _) =
+                &notification
+            {
+                if let Some(root) = find_root_project(project, &self.project_descriptions) {
+                    self.cargo_output.insert(root, line.clone());
+                }
+                has_new_events = true;
+                continue;
+            }
+            if let ContextNotification::Lsp(LspNotification::Stderr { line, project }) =
+                &notification
+            {
+                let logs = self.lsp_logs.entry(project.clone()).or_default();
+                if logs.len() == LSP_LOG_LINES {
+                    logs.pop_front();
+                }
+                logs.push_back(line.clone());
+                has_new_events = self.show_lsp_logs;
+                continue;
+            }
+            if let ContextNotification::Mcp(McpNotification::Response { project, .. }, _) =
+                &notification
+                && let Some(root) = find_root_project(project, &self.project_descriptions)
+            {
+                self.cargo_output.remove(&root);
+            }
+
             // If its not a new project notification, request projects
             self.context.request_project_descriptions();
 
-            // If its a lsp, ignore because there's a lot of them
-            if matches!(notification, ContextNotification::Lsp(_)) {
+            // If its a lsp or docs progress, ignore because there's a lot of them
+            if matches!(
+                notification,
+                ContextNotification::Lsp(
+                    LspNotification::Indexing { .. } | LspNotification::Status { .. }
+                ) | ContextNotification::Docs(
+                    DocsNotification::CrateStarted { .. }
+                        | DocsNotification::CrateFinished { .. }
+                        | DocsNotification::Progress { .. }
+                )
+            ) {
                 has_new_events = true;
                 continue;
             }
@@ -102,25 +173,86 @@ impl App {
 
     fn draw_left_sidebar(&mut self, ui: &mut Ui, project_descriptions: &[ProjectDescription]) {
         ui.add_space(10.0);
-        ui.columns(2, |columns| {
+        ui.columns(3, |columns| {
             columns[0].selectable_value(
                 &mut self.selected_sidebar_tab,
                 SidebarTab::Projects,
                 "Projects",
             );
-            columns[1].selectable_value(&mut self.selected_sidebar_tab, SidebarTab::Info, "Info");
+            columns[1].selectable_value(&mut self.selected_sidebar_tab, SidebarTab::Stats, "Stats");
+            columns[2].selectable_value(&mut self.selected_sidebar_tab, SidebarTab::Info, "Info");
         });
 
         match self.selected_sidebar_tab {
             SidebarTab::Projects => {
                 self.draw_projects_tab(ui, project_descriptions);
             }
+            SidebarTab::Stats => {
+                self.draw_stats_tab(ui, project_descriptions);
+            }
             SidebarTab::Info => {
                 self.draw_info_tab(ui);
             }
         }
     }
 
+    fn draw_stats_tab(&mut self, ui: &mut Ui, project_descriptions: &[ProjectDescription]) {
+        let stats = self.context.metrics().stats();
+        if stats.is_empty() {
+            ui.label("No tools were called yet");
+            return;
+        }
+        let mut projects = stats
+            .iter()
+            .map(|stats| stats.project.clone())
+            .collect::<Vec<_>>();
+        projects.sort();
+        projects.dedup();
+        ScrollArea::vertical().show(ui, |ui| {
+            for project in projects {
+                let name = match &project {
+                    Some(root) => project_descriptions
+                        .iter()
+                        .find(|description| description.root == *root)
+                        .map(|description| description.name.clone())
+                        .unwrap_or_else(|| root.display().to_string()),
+                    None => "Without project".to_string(),
+                };
+                let calls = stats
+                    .iter()
+                    .filter(|stats| stats.project == project)
+                    .map(|stats| stats.calls)
+                    .sum::<u64>();
+                ui.collapsing(format!("{name} ({calls} calls)"), |ui| {
+                    egui::Grid::new(format!("stats_{name}"))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("Tool");
+                            ui.strong("Calls");
+                            ui.strong("Errors");
+                            ui.strong("p50");
+                            ui.strong("p95");
+                            ui.end_row();
+                            for stats in stats.iter().filter(|stats| stats.project == project) {
+                                ui.label(&stats.tool)
+                                    .on_hover_text(format!("p99: {}", format_latency(stats.p99)));
+                                ui.label(stats.calls.to_string());
+                                let errors = format!("{:.0}%", stats.error_rate() * 100.0);
+                                if stats.errors > 0 {
+                                    ui.colored_label(Color32::ORANGE, errors);
+                                } else {
+                                    ui.label(errors);
+                                }
+                                ui.label(format_latency(stats.p50));
+                                ui.label(format_latency(stats.p95));
+                                ui.end_row();
+                            }
+                        });
+                });
+            }
+        });
+    }
+
     fn draw_projects_tab(&mut self, ui: &mut Ui, project_descriptions: &[ProjectDescription]) {
         ScrollArea::vertical().show(ui, |ui| {
             let selected_path = self.selected_project.clone();
@@ -149,6 +281,12 @@ impl App {
                             .add_project(Project {
                                 root: path_buf,
                                 ignore_crates: vec![],
+                                no_std_target: None,
+                                use_nextest: false,
+                                max_output_bytes: None,
+                                maintenance: Default::default(),
+                                docs: Default::default(),
+                                rust_analyzer: Default::default(),
                             })
                             .await
                         {
@@ -180,6 +318,31 @@ impl App {
         let config_file = self.context.configuration_file();
         ui.label(format!("Address: {}", host));
         ui.label(format!("Port: {}", port));
+        let remote = self.context.remote_bind_addresses();
+        if !remote.is_empty() {
+            let protection = if self.context.api_key().is_some() {
+                "Clients need the API key."
+            } else {
+                "Anyone who can connect can run cargo commands in your projects, set an `api_key`."
+            };
+            ui.colored_label(
+                Color32::ORANGE,
+                format!(
+                    "Reachable from other machines via {}. {protection}",
+                    remote.join(", ")
+                ),
+            );
+        }
+        let rejected = self.context.rejected_bind_addresses();
+        if !rejected.is_empty() {
+            ui.colored_label(
+                Color32::ORANGE,
+                format!(
+                    "Not listening on {}, set `allow_remote` to use addresses other machines can reach.",
+                    rejected.join(", ")
+                ),
+            );
+        }
 
         ui.add_space(10.0);
 
@@ -229,11 +392,103 @@ impl App {
                             self.logs
                                 .push(format!("Update Docs Index clicked for: {}", project.name));
                         }
+                        if ui
+                            .button("Docs Cache")
+                            .on_hover_text("Show the disk usage of the docs cache")
+                            .clicked()
+                        {
+                            self.load_cache_stats(&project.root);
+                        }
+                        if ui
+                            .button("Restart rust-analyzer")
+                            .on_hover_text("Replace a hanging rust-analyzer with a fresh one")
+                            .clicked()
+                        {
+                            let context = self.context.clone();
+                            let root = project.root.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = context.restart_lsp(&root).await {
+                                    tracing::error!("Failed to restart rust-analyzer: {}", e);
+                                }
+                            });
+                            self.logs.push(format!(
+                                "Restart rust-analyzer clicked for: {}",
+                                project.name
+                            ));
+                        }
+                        if ui
+                            .button("Memory")
+                            .on_hover_text("Show how much memory rust-analyzer uses")
+                            .clicked()
+                        {
+                            self.load_lsp_memory(&project.root);
+                        }
+                        ui.toggle_value(&mut self.show_lsp_logs, "Logs")
+                            .on_hover_text("Show what rust-analyzer printed to stderr");
                         if ui.button("Open Project").clicked() {
                             if let Err(e) = open::that(project.root.to_string_lossy().to_string()) {
                                 tracing::error!("Failed to open project: {}", e);
                             }
                         }
+                        let clients = self.context.sessions().all();
+                        if !clients.is_empty() {
+                            egui::ComboBox::from_id_salt("client_filter")
+                                .selected_text(match &self.client_filter {
+                                    Some(client) => client.to_string(),
+                                    None => "All clients".to_string(),
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut self.client_filter,
+                                        None,
+                                        "All clients",
+                                    );
+                                    for client in clients {
+                                        let label = client.to_string();
+                                        ui.selectable_value(
+                                            &mut self.client_filter,
+                                            Some(client),
+                                            label,
+                                        );
+                                    }
+                                })
+                                .response
+                                .on_hover_text("Only show the tool calls of one MCP client");
+                        }
+                        egui::ComboBox::from_id_salt("transcript_range")
+                            .selected_text(self.transcript_range.label())
+                            .show_ui(ui, |ui| {
+                                for range in TranscriptRange::ALL {
+                                    ui.selectable_value(
+                                        &mut self.transcript_range,
+                                        range,
+                                        range.label(),
+                                    );
+                                }
+                            });
+                        if ui
+                            .button("Export Transcript")
+                            .on_hover_text("Save the MCP requests and responses as markdown")
+                            .clicked()
+                        {
+                            let events = self
+                                .events
+                                .get(&project.name)
+                                .into_iter()
+                                .flatten()
+                                .filter(|event| shows_event(&self.client_filter, &event.1))
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            let markdown =
+                                transcript(&project.name, &events, self.transcript_range);
+                            if let Some(path) = rfd::FileDialog::new()
+                                .set_file_name(format!("{}-transcript.md", project.name))
+                                .save_file()
+                                && let Err(e) = std::fs::write(&path, markdown)
+                            {
+                                tracing::error!("Failed to write transcript: {}", e);
+                            }
+                        }
                         if !config_path.exists()
                             && ui
                                 .button("Install mcp.json")
@@ -248,15 +503,29 @@ impl App {
                         ui.add_space(10.0);
                         if project.is_indexing_lsp {
                             ui.add(egui::Spinner::new());
-                            ui.label("Indexing LSP...");
+                            ui.label(format!("Indexing LSP {}%", project.lsp_percentage));
+                        }
+                        if let Some(status) = &project.lsp_status {
+                            draw_lsp_status(ui, status);
                         }
                         ui.add_space(10.0);
                         if project.is_indexing_docs {
                             ui.add(egui::Spinner::new());
-                            ui.label("Indexing Docs...");
+                            match &project.docs_progress {
+                                Some(progress) => ui.label(format!("Indexing Docs {progress}")),
+                                None => ui.label("Indexing Docs..."),
+                            };
+                        }
+                        if let Some(line) = self.cargo_output.get(&project.root) {
+                            ui.add_space(10.0);
+                            ui.add(egui::Spinner::new());
+                            ui.label(line.chars().take(80).collect::<String>());
                         }
                     });
 
+                    self.draw_cache_stats(ui, project);
+                    self.draw_lsp_memory(ui, project);
+
                     // Allocate the remaining available space in the vertical layout
                     let remaining_space = ui.available_size_before_wrap();
                     ui.allocate_ui(remaining_space, |ui| {
@@ -269,13 +538,21 @@ impl App {
                                 ScrollArea::vertical()
                                     .auto_shrink([false, false]) // Don't shrink, fill space
                                     .show(ui, |ui| {
-                                        if let Some(project_events) = self.events.get(&project.name)
+                                        if self.show_lsp_logs {
+                                            draw_lsp_logs(ui, self.lsp_logs.get(&project.lsp_root));
+                                        } else if let Some(project_events) =
+                                            self.events.get(&project.name)
                                         {
                                             let mut event_to_select = None;
                                             for event_tuple in project_events.iter().rev() {
                                                 if matches!(
                                                     event_tuple.1,
-                                                    ContextNotification::Lsp(_)
+                                                    ContextNotification::Lsp(
+                                                        LspNotification::Indexing { .. }
+                                                    )
+                                                ) || !shows_event(
+                                                    &self.client_filter,
+                                                    &event_tuple.1,
                                                 ) {
                                                     continue;
                                                 }
@@ -332,6 +609,100 @@ impl App {
         }
     }
 
+    fn load_cache_stats(&self, root: &Path) {
+        let context = self.context.clone();
+        let cache_stats = self.cache_stats.clone();
+        let root = root.to_path_buf();
+        tokio::spawn(async move {
+            match context.docs_cache_stats(&root).await {
+                Ok(stats) => {
+                    if let Ok(mut cache_stats) = cache_stats.lock() {
+                        cache_stats.insert(root, stats);
+                    }
+                }
+                Err(e) => tracing::error!("Failed to read the docs cache size: {}", e),
+            }
+        });
+    }
+
+    fn load_lsp_memory(&self, root: &Path) {
+        let context = self.context.clone();
+        let root = root.to_path_buf();
+        tokio::spawn(async move {
+            if let Err(e) = context.load_lsp_memory(&root).await {
+                tracing::error!("Failed to get the memory usage of rust-analyzer: {}", e);
+            }
+        });
+    }
+
+    /// The memory report of rust-analyzer, once it was loaded with the "Memory" button
+    fn draw_lsp_memory(&mut self, ui: &mut Ui, project: &ProjectDescription) {
+        let Some(memory) = &project.lsp_memory else {
+            return;
+        };
+        ui.collapsing("rust-analyzer memory", |ui| {
+            for line in memory.lines() {
+                ui.label(RichText::new(line).monospace());
+            }
+            if ui.button("Refresh").clicked() {
+                self.load_lsp_memory(&project.root);
+            }
+        });
+    }
+
+    /// The largest crates in the docs cache, once they were loaded with the "Docs Cache" button
+    fn draw_cache_stats(&mut self, ui: &mut Ui, project: &ProjectDescription) {
+        let Some(stats) = self
+            .cache_stats
+            .lock()
+            .ok()
+            .and_then(|cache_stats| cache_stats.get(&project.root).cloned())
+        else {
+            return;
+        };
+        let indexed = stats.crates.iter().filter(|usage| !usage.evicted).count();
+        ui.collapsing(
+            format!(
+                "Docs cache: {} ({indexed} crates indexed)",
+                format_size(stats.total_bytes)
+            ),
+            |ui| {
+                for usage in stats.crates.iter().filter(|usage| !usage.evicted).take(20) {
+                    ui.label(format!(
+                        "{} {}: {}",
+                        usage.name,
+                        usage.version,
+                        format_size(usage.bytes())
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Clear")
+                        .on_hover_text("Evict the docs of all crates, they're generated again when they're needed")
+                        .clicked()
+                    {
+                        let context = self.context.clone();
+                        let root = project.root.clone();
+                        let cache_stats = self.cache_stats.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = context.clear_docs_cache(&root).await {
+                                tracing::error!("Failed to clear the docs cache: {}", e);
+                            }
+                            if let Ok(mut cache_stats) = cache_stats.lock() {
+                                cache_stats.remove(&root);
+                            }
+                        });
+                        self.logs
+                            .push(format!("Clear Docs Cache clicked for: {}", project.name));
+                    }
+                    if ui.button("Refresh").clicked() {
+                        self.load_cache_stats(&project.root);
+                    }
+                });
+            },
+        );
+    }
+
     #[allow(dead_code)]
     fn draw_bottom_bar(&mut self, ui: &mut Ui) {
         ui.label("Logs:");
@@ -350,6 +721,20 @@ impl App {
             if ui.button("Copy").on_hover_text("Copy").clicked() {
                 ui.ctx().copy_text(format!("{:#?}", event.1));
             }
+            if let ContextNotification::Docs(DocsNotification::Failed { project, .. }) = &event.1
+                && ui
+                    .button("Retry")
+                    .on_hover_text("Generate the docs again")
+                    .clicked()
+            {
+                let context = self.context.clone();
+                let project = project.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = context.force_index_docs(&project).await {
+                        tracing::error!("Failed to update docs index: {}", e);
+                    }
+                });
+            }
             ui.heading("Details");
         });
         ui.separator();
@@ -488,6 +873,39 @@ impl<'a> ListCell<'a> {
         response
     }
 }
+/// The health of rust-analyzer, with the reason as hover text if it isn't ok
+fn draw_lsp_status(ui: &mut Ui, status: &ServerStatusParams) {
+    let (text, color) = match status.health {
+        Health::Ok if status.quiescent => ("rust-analyzer OK", Color32::GREEN),
+        Health::Ok => ("rust-analyzer busy", Color32::GRAY),
+        Health::Warning => ("rust-analyzer warning", Color32::YELLOW),
+        Health::Error => ("rust-analyzer error", Color32::RED),
+    };
+    let label = ui.label(RichText::new(text).color(color));
+    if let Some(message) = &status.message {
+        label.on_hover_text(message);
+    }
+}
+
+fn draw_lsp_logs(ui: &mut Ui, logs: Option<&VecDeque<String>>) {
+    let Some(logs) = logs.filter(|logs| !logs.is_empty()) else {
+        ui.label("rust-analyzer didn't print anything yet");
+        return;
+    };
+    for line in logs {
+        ui.label(RichText::new(line).monospace());
+    }
+}
+
+/// Whether the event passes the client filter. With a filter, only the tool calls of
+/// the client are shown.
+fn shows_event(filter: &Option<ClientSession>, event: &ContextNotification) -> bool {
+    let Some(filter) = filter else {
+        return true;
+    };
+    matches!(event, ContextNotification::Mcp(_, Some(session)) if session.number == filter.number)
+}
+
 fn find_root_project(mut path: &Path, projects: &[ProjectDescription]) -> Option<PathBuf> {
     if let Some(project) = projects.iter().find(|p| p.root == *path) {
         return Some(project.root.clone());
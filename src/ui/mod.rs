@@ -1,6 +1,7 @@
 mod app;
 mod log;
 mod theme;
+mod transcript;
 
 use std::sync::Arc;
 
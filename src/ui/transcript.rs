@@ -0,0 +1,134 @@
+use chrono::{DateTime, Duration, Local, Utc};
+use mcp_core::types::ToolResponseContent;
+
+use super::app::TimestampedEvent;
+use crate::{
+    context::ContextNotification,
+    mcp::{ClientSession, McpNotification},
+};
+
+/// Payloads longer than this are cut off in the transcript
+const MAX_PAYLOAD_LENGTH: usize = 2000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TranscriptRange {
+    LastFifteenMinutes,
+    LastHour,
+    Today,
+    Everything,
+}
+
+impl TranscriptRange {
+    pub const ALL: [TranscriptRange; 4] = [
+        TranscriptRange::LastFifteenMinutes,
+        TranscriptRange::LastHour,
+        TranscriptRange::Today,
+        TranscriptRange::Everything,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TranscriptRange::LastFifteenMinutes => "Last 15 minutes",
+            TranscriptRange::LastHour => "Last hour",
+            TranscriptRange::Today => "Today",
+            TranscriptRange::Everything => "Everything",
+        }
+    }
+
+    fn start(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            TranscriptRange::LastFifteenMinutes => Some(now - Duration::minutes(15)),
+            TranscriptRange::LastHour => Some(now - Duration::hours(1)),
+            TranscriptRange::Today => Local::now()
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .and_then(|midnight| midnight.and_local_timezone(Local).single())
+                .map(|midnight| midnight.with_timezone(&Utc)),
+            TranscriptRange::Everything => None,
+        }
+    }
+}
+
+/// A markdown transcript of the MCP requests and responses of a project
+pub fn transcript(
+    project_name: &str,
+    events: &[TimestampedEvent],
+    range: TranscriptRange,
+) -> String {
+    let start = range.start(Utc::now());
+    let mut output = format!("# MCP transcript for `{project_name}`\n");
+    for TimestampedEvent(timestamp, event) in events {
+        if start.is_some_and(|start| *timestamp < start) {
+            continue;
+        }
+        let time = timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+        match event {
+            ContextNotification::Mcp(McpNotification::Request { content, .. }, session) => {
+                let arguments = serde_json::to_string_pretty(&content.arguments)
+                    .unwrap_or_else(|e| e.to_string());
+                output.push_str(&format!(
+                    "\n## {time} Request `{}`{}\n\n```json\n{}\n```\n",
+                    content.name,
+                    client(session),
+                    trim_payload(&arguments)
+                ));
+            }
+            ContextNotification::Mcp(McpNotification::Response { content, .. }, session) => {
+                let text = content
+                    .content
+                    .iter()
+                    .map(|content| match content {
+                        ToolResponseContent::Text { text } => text.clone(),
+                        other => serde_json::to_string(other).unwrap_or_else(|e| e.to_string()),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let status = if content.is_error == Some(true) {
+                    "Error"
+                } else {
+                    "Response"
+                };
+                output.push_str(&format!(
+                    "\n## {time} {status}{}\n\n```\n{}\n```\n",
+                    client(session),
+                    trim_payload(&text)
+                ));
+            }
+            _ => {}
+        }
+    }
+    output
+}
+
+/// The client of a tool call, when several clients share the server
+fn client(session: &Option<ClientSession>) -> String {
+    session
+        .as_ref()
+        .map(|session| format!(" ({session})"))
+        .unwrap_or_default()
+}
+
+fn trim_payload(payload: &str) -> String {
+    let Some((index, _)) = payload.char_indices().nth(MAX_PAYLOAD_LENGTH) else {
+        return payload.to_string();
+    };
+    format!(
+        "{}\n... ({} more characters)",
+        &payload[..index],
+        payload[index..].chars().count()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_payload() {
+        assert_eq!(trim_payload("short"), "short");
+        let long = "ä".repeat(MAX_PAYLOAD_LENGTH + 10);
+        let trimmed = trim_payload(&long);
+        assert!(trimmed.starts_with(&"ä".repeat(MAX_PAYLOAD_LENGTH)));
+        assert!(trimmed.ends_with("(10 more characters)"));
+    }
+}